@@ -0,0 +1,126 @@
+//! 架构决策记录 (`new-cli adr "Use Postgres"`)
+//!
+//! 每条记录保存为 ADR 目录（默认 `docs/adr`）中的 `<4 位序号>-<标题 slug>.md`，
+//! 序号取目录中已有记录的最大序号加一。模板目录中有 `adr.md` 时使用该模板，
+//! 否则使用内置的 [`TEMPLATE`]。模板可以使用 `{{number}}`、`{{title}}`、`{{status}}`
+//! 以及所有内置变量。
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoContext, Result};
+use crate::filters;
+use crate::render::Variables;
+use crate::tr;
+use crate::variables;
+
+/// 默认的 ADR 目录，相对于当前目录
+pub const DEFAULT_DIR: &str = "docs/adr";
+
+/// 模板目录中覆盖内置模板的文件名
+pub const TEMPLATE_NAME: &str = "adr.md";
+
+/// 新记录的默认状态
+pub const DEFAULT_STATUS: &str = "Proposed";
+
+/// 内置的 ADR 模板（Michael Nygard 格式）
+pub const TEMPLATE: &str = "\
+# {{number}}. {{title}}
+
+Date: {{date}}
+
+## Status
+
+{{status}}
+
+## Context
+
+## Decision
+
+## Consequences
+";
+
+/// 把标题转换为文件名中使用的 slug，例如 `Use Postgres 15` -> `use-postgres-15`
+pub fn slug(title: &str) -> Result<String> {
+    let slug = filters::apply("kebab", title)?;
+    if slug.is_empty() {
+        return Err(Error::InvalidAdrTitle(title.to_string()));
+    }
+    Ok(slug)
+}
+
+/// 目录中下一个未使用的序号；目录不存在时为 1
+///
+/// 只统计 `<数字>-*.md` 形式的文件。
+pub fn next_number(dir: &Path) -> Result<u32> {
+    if !dir.is_dir() {
+        return Ok(1);
+    }
+    let entries = fs::read_dir(dir)
+        .io_context(|| tr!("无法读取目录 {:?}", "failed to read directory {:?}", dir))?;
+    let max = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?.strip_suffix(".md")?;
+            let (number, _) = name.split_once('-')?;
+            number.parse::<u32>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+    Ok(max + 1)
+}
+
+/// 记录的文件名，例如 `0003-use-postgres.md`
+pub fn file_name(number: u32, slug: &str) -> String {
+    format!("{:04}-{}.md", number, slug)
+}
+
+/// 渲染记录使用的变量：内置变量加上 `number`、`title` 和 `status`
+pub fn variables(number: u32, title: &str, status: &str, slug: &str) -> Variables {
+    let mut vars = variables::builtin_variables(&format!("{:04}-{}", number, slug), "md");
+    vars.insert("number".to_string(), format!("{:04}", number));
+    vars.insert("title".to_string(), title.to_string());
+    vars.insert("status".to_string(), status.to_string());
+    vars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Use Postgres 15").unwrap(), "use-postgres-15");
+        assert_eq!(
+            slug("  Record: GraphQL API?").unwrap(),
+            "record-graph-ql-api"
+        );
+        assert!(matches!(slug("!!"), Err(Error::InvalidAdrTitle(_))));
+    }
+
+    #[test]
+    fn test_next_number() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(next_number(&dir.path().join("missing")).unwrap(), 1);
+        for name in [
+            "0001-record.md",
+            "0012-use-postgres.md",
+            "README.md",
+            "0099.md",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert_eq!(next_number(dir.path()).unwrap(), 13);
+        assert_eq!(file_name(13, "use-redis"), "0013-use-redis.md");
+    }
+
+    #[test]
+    fn test_builtin_template() {
+        let vars = variables(2, "Use Postgres", DEFAULT_STATUS, "use-postgres");
+        let text = render(TEMPLATE, &vars, true).unwrap();
+        assert!(text.starts_with("# 0002. Use Postgres\n\nDate: "));
+        assert!(text.contains("## Status\n\nProposed\n"));
+    }
+}
@@ -0,0 +1,292 @@
+//! 批量生成清单：`new-cli batch spec.csv` 按清单中的每一行创建一个文件
+//!
+//! 清单可以是 CSV（第一行为表头）或 JSON（对象数组），格式按后缀确定。以下列有特殊含义，
+//! 其余的列都是该文件的模板变量：
+//!
+//! | 列 | 说明 |
+//! | --- | --- |
+//! | `filename` | 文件名，必需；可以带后缀 (`app.json`) |
+//! | `extension` | 后缀，文件名中没有后缀时使用 |
+//! | `template` | 使用的模板名 (例如 `web/page.html`)，为空时按文件名查找 |
+//! | `dir` | 相对于目标目录的子目录，不能包含 `..` |
+//!
+//! CSV 中的空单元格视为未提供；JSON 中嵌套的对象与变量文件一样展开为 `a.b`。
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Error, IoContext, Result};
+use crate::render::Variables;
+use crate::tr;
+use crate::vars_file;
+
+/// 清单中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// CSV 中的行号，JSON 中为第几项，均从 1 开始，用于报告错误
+    pub row: usize,
+    pub filename: String,
+    pub extension: Option<String>,
+    pub template: Option<String>,
+    pub dir: Option<PathBuf>,
+    pub variables: Variables,
+}
+
+impl Entry {
+    /// 清单中写明的目标路径（相对于目标目录，不含推断出的后缀），用于显示
+    pub fn path(&self) -> PathBuf {
+        let name = match &self.extension {
+            Some(extension) => format!("{}.{}", self.filename, extension),
+            None => self.filename.clone(),
+        };
+        match &self.dir {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+}
+
+/// 读取批量生成清单
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "无法读取批量生成清单 {:?}",
+            "failed to read batch spec {:?}",
+            path
+        )
+    })?;
+    let invalid = |message: String| Error::BatchSpec {
+        path: path.to_path_buf(),
+        message,
+    };
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let rows = match extension.as_str() {
+        "csv" => csv_rows(&content).map_err(invalid)?,
+        "json" => json_rows(&content).map_err(invalid)?,
+        _ => {
+            return Err(invalid(tr!(
+                "不支持的格式，需要 .csv 或 .json",
+                "unsupported format, expected .csv or .json"
+            )))
+        }
+    };
+    rows.into_iter()
+        .map(|(row, fields)| entry(row, fields).map_err(invalid))
+        .collect()
+}
+
+/// 把一行的各列分为保留列和变量
+fn entry(row: usize, fields: Vec<(String, String)>) -> std::result::Result<Entry, String> {
+    let mut entry = Entry {
+        row,
+        filename: String::new(),
+        extension: None,
+        template: None,
+        dir: None,
+        variables: Variables::new(),
+    };
+    for (key, value) in fields {
+        match key.as_str() {
+            "filename" => entry.filename = value,
+            "extension" => entry.extension = Some(value.trim_start_matches('.').to_string()),
+            "template" => entry.template = Some(value),
+            "dir" => entry.dir = Some(PathBuf::from(value)),
+            _ => {
+                entry.variables.insert(key, value);
+            }
+        }
+    }
+    if entry.filename.is_empty() {
+        return Err(tr!("第 {} 行缺少 filename", "row {} has no filename", row));
+    }
+    if let Some(dir) = &entry.dir {
+        let relative = dir
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !relative {
+            return Err(tr!(
+                "第 {} 行的目录 {:?} 无效，必须是不含 .. 的相对路径",
+                "row {} has invalid dir {:?}, it must be a relative path without ..",
+                row,
+                dir
+            ));
+        }
+    }
+    Ok(entry)
+}
+
+type Rows = Vec<(usize, Vec<(String, String)>)>;
+
+/// 解析 CSV：第一行为表头，支持双引号包围的字段（其中可以有逗号、换行和 `""`）
+fn csv_rows(content: &str) -> std::result::Result<Rows, String> {
+    let mut records = parse_csv(content.strip_prefix('\u{feff}').unwrap_or(content))?;
+    if records.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (_, header) = records.remove(0);
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+    if !header.iter().any(|h| h == "filename") {
+        return Err(tr!(
+            "表头中没有 filename 列",
+            "the header has no filename column"
+        ));
+    }
+    records
+        .into_iter()
+        .filter(|(_, fields)| fields.iter().any(|f| !f.is_empty()))
+        .map(|(line, fields)| {
+            if fields.len() > header.len() {
+                return Err(tr!(
+                    "第 {} 行有 {} 列，多于表头的 {} 列",
+                    "row {} has {} columns, more than the {} in the header",
+                    line,
+                    fields.len(),
+                    header.len()
+                ));
+            }
+            let fields = header
+                .iter()
+                .cloned()
+                .zip(fields)
+                .filter(|(_, value)| !value.is_empty())
+                .collect();
+            Ok((line, fields))
+        })
+        .collect()
+}
+
+/// 拆分 CSV 记录，返回每条记录的起始行号和字段
+fn parse_csv(content: &str) -> std::result::Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(tr!(
+            "第 {} 行的引号没有闭合",
+            "unterminated quote in row {}",
+            start
+        ));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    Ok(records)
+}
+
+/// 解析 JSON：顶层为对象数组，标量值转换为文本
+fn json_rows(content: &str) -> std::result::Result<Rows, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let Value::Array(items) = value else {
+        return Err(tr!(
+            "顶层必须是对象数组",
+            "the top level must be an array of objects"
+        ));
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let row = index + 1;
+            let Value::Object(table) = item else {
+                return Err(tr!("第 {} 项不是对象", "item {} is not an object", row));
+            };
+            let mut vars = Variables::new();
+            for (key, value) in table {
+                vars_file::flatten(&key, value, &mut vars)?;
+            }
+            Ok((row, vars.into_iter().collect()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn load_str(name: &str, content: &str) -> Result<Vec<Entry>> {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        load(&path)
+    }
+
+    #[test]
+    fn test_load_csv() {
+        let entries = load_str(
+            "spec.csv",
+            "filename,extension,template,dir,title\r\n\
+             app.json,,,locales/fr,\"Bonjour, \"\"monde\"\"\"\r\n\
+             \r\n\
+             README,md,docs/readme.md,,\"two\nlines\"\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].row, 2);
+        assert_eq!(entries[0].filename, "app.json");
+        assert_eq!(entries[0].extension, None);
+        assert_eq!(entries[0].dir, Some(PathBuf::from("locales/fr")));
+        assert_eq!(entries[0].variables["title"], "Bonjour, \"monde\"");
+        assert_eq!(entries[1].row, 4);
+        assert_eq!(entries[1].extension.as_deref(), Some("md"));
+        assert_eq!(entries[1].template.as_deref(), Some("docs/readme.md"));
+        assert_eq!(entries[1].variables["title"], "two\nlines");
+    }
+
+    #[test]
+    fn test_load_json() {
+        let entries = load_str(
+            "spec.json",
+            r#"[{"filename": "user", "extension": ".rs", "entity": {"name": "User"}, "id": 7}]"#,
+        )
+        .unwrap();
+        assert_eq!(entries[0].extension.as_deref(), Some("rs"));
+        assert_eq!(entries[0].variables["entity.name"], "User");
+        assert_eq!(entries[0].variables["id"], "7");
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_rows() {
+        assert!(load_str("spec.csv", "name\nx\n").is_err());
+        assert!(load_str("spec.csv", "filename,dir\na.md,../up\n").is_err());
+        assert!(load_str("spec.csv", "filename\n\"a.md\n").is_err());
+        assert!(load_str("spec.json", r#"[{"extension": "md"}]"#).is_err());
+        assert!(load_str("spec.yaml", "").is_err());
+    }
+}
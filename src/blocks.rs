@@ -0,0 +1,422 @@
+//! 模板中的条件块和循环块
+//!
+//! ```text
+//! {% if license %}
+//! // SPDX-License-Identifier: {{license}}
+//! {% endif %}
+//! {% for field in fields %}
+//!     {{field}}: String,
+//! {% endfor %}
+//! ```
+//!
+//! 支持的标签：
+//!
+//! - `{% if name %}`、`{% if not name %}`、`{% if name == "value" %}`、`{% if name != "value" %}`，
+//!   以及 `{% elif ... %}`、`{% else %}`、`{% endif %}`
+//! - `{% for item in list %}` ... `{% endfor %}`：`list` 按逗号拆分（`-D fields=id,name,email`），
+//!   循环体中可以使用 `{{item}}`、`{{loop.index}}`（从 1 开始）、`loop.first` 和 `loop.last`
+//!
+//! 变量未定义、为空或为 `false`/`0`/`no` 时条件不成立。单独占一行的标签连同该行一起移除，
+//! 不会在结果中留下空行。其他 `{% ... %}`（例如 Jinja 模板中的 `{% block %}`）按原文输出，
+//! 需要输出字面量 `{%` 时写作 `\{%`。
+
+use crate::error::{Error, Result};
+use crate::tr;
+
+/// 解析后的模板片段
+#[derive(Debug, PartialEq)]
+pub enum Node<'a> {
+    /// 原样输出的文本
+    Text(String),
+    /// `{{ ... }}` 中的内容（已去除两侧空白）
+    Placeholder(&'a str),
+    /// `{% if %}` 块：按顺序检查每个分支，都不成立时使用 `otherwise`
+    If {
+        branches: Vec<(Condition<'a>, Vec<Node<'a>>)>,
+        otherwise: Vec<Node<'a>>,
+    },
+    /// `{% for item in list %}` 块
+    For {
+        item: &'a str,
+        list: &'a str,
+        body: Vec<Node<'a>>,
+    },
+}
+
+/// `{% if %}` 的条件
+#[derive(Debug, PartialEq)]
+pub enum Condition<'a> {
+    /// 变量有值
+    Truthy(&'a str),
+    /// 变量没有值
+    Not(&'a str),
+    /// 变量等于给定的值
+    Eq(&'a str, &'a str),
+    /// 变量不等于给定的值
+    Ne(&'a str, &'a str),
+}
+
+impl Condition<'_> {
+    /// 使用 `lookup` 查找变量并判断条件是否成立
+    pub fn holds<'v>(&self, lookup: impl Fn(&str) -> Option<&'v str>) -> bool {
+        match self {
+            Condition::Truthy(name) => lookup(name).is_some_and(is_truthy),
+            Condition::Not(name) => !lookup(name).is_some_and(is_truthy),
+            Condition::Eq(name, value) => lookup(name) == Some(*value),
+            Condition::Ne(name, value) => lookup(name) != Some(*value),
+        }
+    }
+}
+
+/// 值是否视为“成立”：非空，且不是 `false`、`0` 或 `no`
+fn is_truthy(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && !["false", "0", "no"].contains(&value.to_lowercase().as_str())
+}
+
+/// 将 `list` 变量的值按逗号拆分为循环项，忽略空项
+pub fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 识别出的块标签
+#[derive(Debug)]
+enum Tag<'a> {
+    If(Condition<'a>),
+    Elif(Condition<'a>),
+    Else,
+    EndIf,
+    For { item: &'a str, list: &'a str },
+    EndFor,
+}
+
+enum Token<'a> {
+    Text(String),
+    Placeholder(&'a str),
+    Tag(Tag<'a>),
+}
+
+/// 将模板解析为片段树
+pub fn parse(template: &str) -> Result<Vec<Node<'_>>> {
+    let tokens = tokenize(template)?;
+    let mut tokens = tokens.into_iter();
+    let (nodes, end) = parse_nodes(&mut tokens)?;
+    match end {
+        None => Ok(nodes),
+        Some(tag) => Err(unexpected(&tag)),
+    }
+}
+
+/// 解析到块结束标签（`elif`/`else`/`endif`/`endfor`）或模板末尾为止，返回遇到的结束标签
+fn parse_nodes<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+) -> Result<(Vec<Node<'a>>, Option<Tag<'a>>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Placeholder(inner) => nodes.push(Node::Placeholder(inner)),
+            Token::Tag(Tag::If(condition)) => {
+                let mut branches = Vec::new();
+                let mut condition = condition;
+                let otherwise = loop {
+                    let (body, end) = parse_nodes(tokens)?;
+                    branches.push((condition, body));
+                    match end {
+                        Some(Tag::Elif(next)) => condition = next,
+                        Some(Tag::Else) => {
+                            let (body, end) = parse_nodes(tokens)?;
+                            match end {
+                                Some(Tag::EndIf) => break body,
+                                Some(tag) => return Err(unexpected(&tag)),
+                                None => return Err(unclosed("if")),
+                            }
+                        }
+                        Some(Tag::EndIf) => break Vec::new(),
+                        Some(tag) => return Err(unexpected(&tag)),
+                        None => return Err(unclosed("if")),
+                    }
+                };
+                nodes.push(Node::If {
+                    branches,
+                    otherwise,
+                });
+            }
+            Token::Tag(Tag::For { item, list }) => {
+                let (body, end) = parse_nodes(tokens)?;
+                match end {
+                    Some(Tag::EndFor) => nodes.push(Node::For { item, list, body }),
+                    Some(tag) => return Err(unexpected(&tag)),
+                    None => return Err(unclosed("for")),
+                }
+            }
+            Token::Tag(tag) => return Ok((nodes, Some(tag))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    // 当前行到目前为止只有空白和块标签
+    let mut line_clean = true;
+
+    let push_text = |text: &mut String, line_clean: &mut bool, chunk: &str| {
+        match chunk.rfind('\n') {
+            Some(i) => *line_clean = chunk[i + 1..].trim().is_empty(),
+            None => *line_clean &= chunk.trim().is_empty(),
+        }
+        text.push_str(chunk);
+    };
+
+    loop {
+        let next = [rest.find("{{"), rest.find("{%")]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(start) = next else {
+            push_text(&mut text, &mut line_clean, rest);
+            break;
+        };
+        let opener = &rest[start..start + 2];
+
+        // `\{{`、`\{%` 转义为字面量
+        if rest[..start].ends_with('\\') {
+            push_text(&mut text, &mut line_clean, &rest[..start - 1]);
+            push_text(&mut text, &mut line_clean, opener);
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        push_text(&mut text, &mut line_clean, &rest[..start]);
+        let after_open = &rest[start + 2..];
+        let closer = if opener == "{{" { "}}" } else { "%}" };
+        let Some(end) = after_open.find(closer) else {
+            // 未闭合的占位符按原文输出
+            push_text(&mut text, &mut line_clean, &rest[start..]);
+            break;
+        };
+        let inner = &after_open[..end];
+        let after_close = &after_open[end + 2..];
+
+        if opener == "{{" {
+            if !text.is_empty() {
+                tokens.push(Token::Text(std::mem::take(&mut text)));
+            }
+            tokens.push(Token::Placeholder(inner.trim()));
+            line_clean = false;
+            rest = after_close;
+            continue;
+        }
+
+        let Some(tag) = parse_tag(inner.trim())? else {
+            push_text(
+                &mut text,
+                &mut line_clean,
+                &rest[start..start + 2 + end + 2],
+            );
+            rest = after_close;
+            continue;
+        };
+
+        // 单独占一行的标签：去掉该行的缩进和换行
+        let line_end = after_close.find('\n');
+        let trailing = &after_close[..line_end.unwrap_or(after_close.len())];
+        if line_clean && trailing.trim().is_empty() {
+            text.truncate(text.rfind('\n').map_or(0, |i| i + 1));
+            rest = &after_close[line_end.map_or(after_close.len(), |i| i + 1)..];
+        } else {
+            rest = after_close;
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(Token::Tag(tag));
+    }
+
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// 解析 `{% ... %}` 的内容；不是支持的标签时返回 None
+fn parse_tag(inner: &str) -> Result<Option<Tag<'_>>> {
+    let (keyword, args) = match inner.split_once(char::is_whitespace) {
+        Some((keyword, args)) => (keyword, args.trim()),
+        None => (inner, ""),
+    };
+    let invalid = || {
+        Error::TemplateSyntax(tr!(
+            "无效的标签 {{% {} %}}",
+            "invalid tag {{% {} %}}",
+            inner
+        ))
+    };
+    let tag = match keyword {
+        "if" => Tag::If(parse_condition(args).ok_or_else(invalid)?),
+        "elif" => Tag::Elif(parse_condition(args).ok_or_else(invalid)?),
+        "else" if args.is_empty() => Tag::Else,
+        "endif" if args.is_empty() => Tag::EndIf,
+        "endfor" if args.is_empty() => Tag::EndFor,
+        "for" => {
+            let words: Vec<&str> = args.split_whitespace().collect();
+            match words.as_slice() {
+                [item, "in", list] => Tag::For { item, list },
+                _ => return Err(invalid()),
+            }
+        }
+        "else" | "endif" | "endfor" => return Err(invalid()),
+        _ => return Ok(None),
+    };
+    Ok(Some(tag))
+}
+
+/// 解析单独的条件表达式（例如模板清单中的 `when = "use_tests"`），语法与 `{% if %}` 相同
+pub fn condition(expr: &str) -> Result<Condition<'_>> {
+    parse_condition(expr.trim()).ok_or_else(|| {
+        Error::TemplateSyntax(tr!("无效的条件 {:?}", "invalid condition {:?}", expr))
+    })
+}
+
+fn parse_condition(args: &str) -> Option<Condition<'_>> {
+    let valid_name = |name: &str| !name.is_empty() && !name.contains(char::is_whitespace);
+    fn literal(value: &str) -> &str {
+        let value = value.trim();
+        ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .unwrap_or(value)
+    }
+
+    if let Some((name, value)) = args.split_once("==") {
+        let name = name.trim();
+        return valid_name(name).then(|| Condition::Eq(name, literal(value)));
+    }
+    if let Some((name, value)) = args.split_once("!=") {
+        let name = name.trim();
+        return valid_name(name).then(|| Condition::Ne(name, literal(value)));
+    }
+    match args.strip_prefix("not ") {
+        Some(name) => valid_name(name.trim()).then(|| Condition::Not(name.trim())),
+        None => valid_name(args).then_some(Condition::Truthy(args)),
+    }
+}
+
+fn unexpected(tag: &Tag) -> Error {
+    let name = match tag {
+        Tag::If(_) => "if",
+        Tag::Elif(_) => "elif",
+        Tag::Else => "else",
+        Tag::EndIf => "endif",
+        Tag::For { .. } => "for",
+        Tag::EndFor => "endfor",
+    };
+    Error::TemplateSyntax(tr!("多余的 {{% {} %}}", "unexpected {{% {} %}}", name))
+}
+
+fn unclosed(name: &str) -> Error {
+    Error::TemplateSyntax(tr!(
+        "{{% {} %}} 没有对应的结束标签",
+        "{{% {} %}} is never closed",
+        name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_standalone_tags_remove_their_lines() {
+        let nodes = parse("a\n  {% if x %}\nb\n{% endif %}\nc").unwrap();
+        assert_eq!(
+            nodes,
+            [
+                Node::Text("a\n".to_string()),
+                Node::If {
+                    branches: vec![(Condition::Truthy("x"), vec![Node::Text("b\n".to_string())])],
+                    otherwise: vec![],
+                },
+                Node::Text("c".to_string()),
+            ]
+        );
+
+        // 与其他内容在同一行的标签不影响换行
+        let nodes = parse("a {% if x %}b{% endif %}\n").unwrap();
+        assert_eq!(nodes.first(), Some(&Node::Text("a ".to_string())));
+        assert_eq!(nodes.last(), Some(&Node::Text("\n".to_string())));
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(parse_condition("x"), Some(Condition::Truthy("x")));
+        assert_eq!(parse_condition("not x"), Some(Condition::Not("x")));
+        assert_eq!(
+            parse_condition("x == \"a b\""),
+            Some(Condition::Eq("x", "a b"))
+        );
+        assert_eq!(parse_condition("x != 'a'"), Some(Condition::Ne("x", "a")));
+        assert_eq!(parse_condition("x==y"), Some(Condition::Eq("x", "y")));
+        assert_eq!(parse_condition(""), None);
+        assert_eq!(parse_condition("a b"), None);
+        assert_eq!(condition(" not x ").unwrap(), Condition::Not("x"));
+        assert!(matches!(condition("a b"), Err(Error::TemplateSyntax(_))));
+    }
+
+    #[test]
+    fn test_condition_holds() {
+        let lookup = |name: &str| match name {
+            "on" => Some("yes"),
+            "off" => Some("False"),
+            "empty" => Some(""),
+            _ => None,
+        };
+        assert!(Condition::Truthy("on").holds(lookup));
+        assert!(!Condition::Truthy("off").holds(lookup));
+        assert!(!Condition::Truthy("empty").holds(lookup));
+        assert!(Condition::Not("missing").holds(lookup));
+        assert!(Condition::Eq("on", "yes").holds(lookup));
+        assert!(Condition::Ne("missing", "yes").holds(lookup));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for template in [
+            "{% if x %}",
+            "{% endif %}",
+            "{% for x in y %}{% endif %}",
+            "{% if x %}{% else %}{% elif y %}{% endif %}",
+            "{% for x %}{% endfor %}",
+            "{% if %}{% endif %}",
+            "{% else x %}",
+        ] {
+            assert!(
+                matches!(parse(template), Err(Error::TemplateSyntax(_))),
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_keeps_unknown_tags() {
+        let nodes = parse("{% block content %}\\{% if %}").unwrap();
+        assert_eq!(
+            nodes,
+            [Node::Text("{% block content %}{% if %}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(split_list("id, name,,email "), ["id", "name", "email"]);
+        assert!(split_list("").is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+//! `adr` 子命令：在 ADR 目录中创建下一条架构决策记录
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use new_cli::adr;
+use new_cli::config::Config;
+use new_cli::render;
+use new_cli::tr;
+use new_cli::{Error, TemplateEngine};
+
+/// `adr` 子命令的参数
+pub struct AdrArgs<'a> {
+    /// 当前目录，相对的 ADR 目录以它为基准
+    pub cwd: &'a Path,
+    pub config: &'a Config,
+    pub title: String,
+    pub status: String,
+    pub dir: Option<PathBuf>,
+    pub stdout: bool,
+}
+
+pub fn run(args: AdrArgs) -> Result<()> {
+    let dir = args.cwd.join(
+        args.dir
+            .or_else(|| args.config.adr_dir.clone())
+            .unwrap_or_else(|| PathBuf::from(adr::DEFAULT_DIR)),
+    );
+    let slug = adr::slug(&args.title)?;
+    let number = adr::next_number(&dir)?;
+    let mut vars = args.config.variables.clone();
+    vars.extend(adr::variables(number, &args.title, &args.status, &slug));
+
+    // 模板目录中的 adr.md 优先于内置模板
+    let engine = TemplateEngine::from_config(args.config)?;
+    let text = match engine.resolve_template(adr::TEMPLATE_NAME) {
+        Ok(template) => engine.render_template(Some(&template), &vars, false)?,
+        Err(Error::TemplateNotFound(_)) => {
+            render::render(adr::TEMPLATE, &vars, false)?.into_bytes()
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if args.stdout {
+        print!("{}", String::from_utf8_lossy(&text));
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dir).with_context(|| {
+        tr!(
+            "无法创建目录 {}",
+            "failed to create directory {}",
+            dir.display()
+        )
+    })?;
+    let path = dir.join(adr::file_name(number, &slug));
+    fs::write(&path, text).with_context(|| tr!("无法写入 {:?}", "failed to write {:?}", path))?;
+    println!("{}", tr!("已创建 {}", "Created {}", path.display()));
+    Ok(())
+}
@@ -0,0 +1,572 @@
+//! `browse` 子命令：浏览模板的全屏终端界面（`tui` feature）
+//!
+//! 左侧按命名空间以树形列出模板，右侧预览选中模板的内容；回车后填写文件名、后缀和
+//! 模板变量，确认后按普通的创建流程（钩子、打开编辑器等）创建文件。
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use new_cli::render::Variables;
+use new_cli::variables::GitCache;
+use new_cli::TemplateEngine;
+
+/// 在界面中确定的创建参数
+pub struct Selection {
+    pub template: PathBuf,
+    pub filename: String,
+    pub extension: String,
+    /// 表单中填写的模板变量
+    pub variables: Vec<(String, String)>,
+}
+
+/// 未启用 `tui` feature 时无法使用
+#[cfg(not(feature = "tui"))]
+pub fn run(
+    _engine: &TemplateEngine,
+    _known: &Variables,
+    _git: &GitCache,
+) -> Result<Option<Selection>> {
+    anyhow::bail!(
+        "{}",
+        new_cli::tr!(
+            "当前版本未启用 tui feature，无法使用 browse",
+            "browse is unavailable, this build does not include the tui feature"
+        )
+    )
+}
+
+/// 打开界面，用户确认创建时返回创建参数，退出时返回 None
+///
+/// `known` 是已经定义的变量，表单中只询问其余的变量；选中的模板引用了 `git.` 变量时
+/// 才通过 `git` 查询当前目录所在仓库的信息。
+#[cfg(feature = "tui")]
+pub fn run(
+    engine: &TemplateEngine,
+    known: &Variables,
+    git: &GitCache,
+) -> Result<Option<Selection>> {
+    let templates = engine.list_templates()?;
+    if templates.is_empty() {
+        anyhow::bail!(
+            "{}",
+            new_cli::tr!(
+                "模板目录中没有模板",
+                "there are no templates in the template directories"
+            )
+        );
+    }
+    let mut app = tui::App::new(engine, known, git, &templates);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(feature = "tui")]
+mod tui {
+    use anyhow::Result;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use ratatui::layout::{Constraint, Flex, Layout, Position, Rect};
+    use ratatui::style::{Style, Stylize};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph};
+    use ratatui::{DefaultTerminal, Frame};
+    use std::fs;
+
+    use super::Selection;
+    use new_cli::render::Variables;
+    use new_cli::template::TemplateInfo;
+    use new_cli::variables::{self, GitCache};
+    use new_cli::{tr, TemplateEngine};
+
+    /// 树中的一行：命名空间或模板
+    enum Row {
+        Group { label: String, depth: usize },
+        Template { index: usize, depth: usize },
+    }
+
+    struct Field {
+        /// 变量名；文件名和后缀为 None
+        variable: Option<String>,
+        label: String,
+        value: String,
+        required: bool,
+    }
+
+    /// 选中模板后填写的表单
+    struct Form {
+        template: usize,
+        fields: Vec<Field>,
+        focus: usize,
+        error: Option<String>,
+    }
+
+    pub(super) enum Action {
+        Continue,
+        Quit,
+        Create(Selection),
+    }
+
+    pub(super) struct App<'a> {
+        engine: &'a TemplateEngine,
+        known: &'a Variables,
+        git: &'a GitCache,
+        templates: &'a [TemplateInfo],
+        rows: Vec<Row>,
+        /// 选中的行，总是模板行
+        selected: usize,
+        scroll: u16,
+        form: Option<Form>,
+        /// 当前预览的模板及其内容
+        preview: Option<(usize, String)>,
+    }
+
+    impl<'a> App<'a> {
+        pub(super) fn new(
+            engine: &'a TemplateEngine,
+            known: &'a Variables,
+            git: &'a GitCache,
+            templates: &'a [TemplateInfo],
+        ) -> App<'a> {
+            let rows = build_rows(templates);
+            let selected = rows
+                .iter()
+                .position(|row| matches!(row, Row::Template { .. }))
+                .unwrap_or(0);
+            App {
+                engine,
+                known,
+                git,
+                templates,
+                rows,
+                selected,
+                scroll: 0,
+                form: None,
+                preview: None,
+            }
+        }
+
+        pub(super) fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<Selection>> {
+            loop {
+                terminal.draw(|frame| self.draw(frame))?;
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match self.handle_key(key)? {
+                    Action::Continue => {}
+                    Action::Quit => return Ok(None),
+                    Action::Create(selection) => return Ok(Some(selection)),
+                }
+            }
+        }
+
+        fn current(&self) -> usize {
+            match self.rows[self.selected] {
+                Row::Template { index, .. } => index,
+                Row::Group { .. } => 0,
+            }
+        }
+
+        pub(super) fn handle_key(&mut self, key: KeyEvent) -> Result<Action> {
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(Action::Quit);
+            }
+            if self.form.is_some() {
+                return Ok(self.handle_form_key(key));
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::Quit),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::Enter => self.open_form()?,
+                _ => {}
+            }
+            Ok(Action::Continue)
+        }
+
+        fn move_selection(&mut self, step: isize) {
+            let mut row = self.selected;
+            loop {
+                let Some(next) = row
+                    .checked_add_signed(step)
+                    .filter(|&r| r < self.rows.len())
+                else {
+                    return;
+                };
+                row = next;
+                if matches!(self.rows[row], Row::Template { .. }) {
+                    self.selected = row;
+                    self.scroll = 0;
+                    return;
+                }
+            }
+        }
+
+        fn open_form(&mut self) -> Result<()> {
+            let index = self.current();
+            let template = &self.templates[index];
+            let mut fields = vec![
+                Field {
+                    variable: None,
+                    label: tr!("文件名", "File name"),
+                    value: template.name.clone(),
+                    required: true,
+                },
+                Field {
+                    variable: None,
+                    label: tr!("后缀", "Extension"),
+                    value: template.extension.clone(),
+                    required: true,
+                },
+            ];
+            let mut known = self.known.clone();
+            if variables::references_git(&template.path) {
+                known.extend(self.git.get(&std::env::current_dir()?));
+            }
+            for prompt in self.engine.variable_prompts(&template.path, &known)? {
+                let label = match &prompt.description {
+                    Some(description) => format!("{} ({})", prompt.name, description),
+                    None => prompt.name.clone(),
+                };
+                fields.push(Field {
+                    variable: Some(prompt.name),
+                    label,
+                    value: prompt.default.unwrap_or_default(),
+                    required: prompt.required,
+                });
+            }
+            self.form = Some(Form {
+                template: index,
+                fields,
+                focus: 0,
+                error: None,
+            });
+            Ok(())
+        }
+
+        fn handle_form_key(&mut self, key: KeyEvent) -> Action {
+            let Some(form) = &mut self.form else {
+                return Action::Continue;
+            };
+            let last = form.fields.len() - 1;
+            match key.code {
+                KeyCode::Esc => self.form = None,
+                KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1).min(last),
+                KeyCode::BackTab | KeyCode::Up => form.focus = form.focus.saturating_sub(1),
+                KeyCode::Backspace => {
+                    form.fields[form.focus].value.pop();
+                }
+                KeyCode::Char(c) => form.fields[form.focus].value.push(c),
+                KeyCode::Enter if form.focus < last => form.focus += 1,
+                KeyCode::Enter => return self.submit(),
+                _ => {}
+            }
+            Action::Continue
+        }
+
+        fn submit(&mut self) -> Action {
+            let Some(form) = &mut self.form else {
+                return Action::Continue;
+            };
+            if let Some(position) = form
+                .fields
+                .iter()
+                .position(|field| field.required && field.value.trim().is_empty())
+            {
+                form.focus = position;
+                form.error = Some(tr!(
+                    "{} 不能为空",
+                    "{} must not be empty",
+                    form.fields[position].label
+                ));
+                return Action::Continue;
+            }
+            let Some(form) = self.form.take() else {
+                return Action::Continue;
+            };
+            let mut fields = form.fields.into_iter();
+            let filename = fields.next().map(|f| f.value).unwrap_or_default();
+            let extension = fields.next().map(|f| f.value).unwrap_or_default();
+            Action::Create(Selection {
+                template: self.templates[form.template].path.clone(),
+                filename: filename.trim().to_string(),
+                extension: extension.trim().to_string(),
+                variables: fields
+                    .filter_map(|field| Some((field.variable?, field.value)))
+                    .collect(),
+            })
+        }
+
+        fn preview_text(&mut self, index: usize) -> &str {
+            if self.preview.as_ref().map(|(i, _)| *i) != Some(index) {
+                let template = &self.templates[index];
+                let text = match fs::read(&template.path) {
+                    Ok(bytes) if bytes.contains(&0) => tr!(
+                        "(二进制文件，{} 字节)",
+                        "(binary file, {} bytes)",
+                        bytes.len()
+                    ),
+                    Ok(bytes) => String::from_utf8_lossy(&bytes).replace('\t', "    "),
+                    Err(e) => tr!("无法读取模板: {}", "failed to read the template: {}", e),
+                };
+                self.preview = Some((index, text));
+            }
+            self.preview
+                .as_ref()
+                .map(|(_, text)| text.as_str())
+                .unwrap_or("")
+        }
+
+        fn draw(&mut self, frame: &mut Frame) {
+            let [main, help] =
+                Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+            let [tree, preview] =
+                Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                    .areas(main);
+
+            let items: Vec<ListItem> = self
+                .rows
+                .iter()
+                .map(|row| match row {
+                    Row::Group { label, depth } => {
+                        ListItem::new(format!("{}{}", "  ".repeat(*depth), label)).bold()
+                    }
+                    Row::Template { index, depth } => ListItem::new(format!(
+                        "{}{}",
+                        "  ".repeat(*depth),
+                        self.templates[*index].file_name()
+                    )),
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::bordered().title(tr!(" 模板 ", " Templates ")))
+                .highlight_style(Style::new().reversed());
+            let mut state = ListState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(list, tree, &mut state);
+
+            let index = self.current();
+            let title = format!(" {} ", self.templates[index].qualified_name());
+            let scroll = self.scroll;
+            let text = self.preview_text(index).to_string();
+            frame.render_widget(
+                Paragraph::new(text)
+                    .block(Block::bordered().title(title))
+                    .scroll((scroll, 0)),
+                preview,
+            );
+
+            let hint = if self.form.is_some() {
+                tr!(
+                    "Tab/↑↓ 切换  Enter 下一项/创建  Esc 返回",
+                    "Tab/↑↓ switch fields  Enter next/create  Esc back"
+                )
+            } else {
+                tr!(
+                    "↑↓/jk 选择  PgUp/PgDn 滚动预览  Enter 创建  q 退出",
+                    "↑↓/jk select  PgUp/PgDn scroll preview  Enter create  q quit"
+                )
+            };
+            frame.render_widget(Paragraph::new(hint).dim(), help);
+
+            if let Some(form) = &self.form {
+                draw_form(frame, form, &self.templates[form.template], main);
+            }
+        }
+    }
+
+    fn draw_form(frame: &mut Frame, form: &Form, template: &TemplateInfo, area: Rect) {
+        let height = form.fields.len() as u16 + 4;
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        let block = Block::bordered().title(tr!(
+            " 使用 {} 创建 ",
+            " Create from {} ",
+            template.qualified_name()
+        ));
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let label_width = form
+            .fields
+            .iter()
+            .map(|field| Span::raw(field.label.as_str()).width())
+            .max()
+            .unwrap_or(0)
+            + 4;
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, field) in form.fields.iter().enumerate() {
+            let marker = if field.required { "*" } else { " " };
+            let label = format!("{}{}", field.label, marker);
+            let padding = label_width.saturating_sub(Span::raw(label.as_str()).width());
+            let label = Span::raw(format!("{}{}", label, " ".repeat(padding)));
+            let label = if i == form.focus {
+                label.reversed()
+            } else {
+                label
+            };
+            lines.push(Line::from(vec![label, Span::raw(field.value.as_str())]));
+            if i == form.focus {
+                let x = inner.x + (label_width + Span::raw(field.value.as_str()).width()) as u16;
+                frame.set_cursor_position(Position::new(x.min(inner.right()), inner.y + i as u16));
+            }
+        }
+        lines.push(Line::default());
+        if let Some(error) = &form.error {
+            lines.push(Line::from(error.as_str()).red());
+        }
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+
+    /// 按命名空间生成树形的行：根目录的模板在前，子命名空间缩进在父命名空间之下
+    fn build_rows(templates: &[TemplateInfo]) -> Vec<Row> {
+        let mut order: Vec<usize> = (0..templates.len()).collect();
+        order.sort_by_key(|&i| {
+            let segments: Vec<&str> = templates[i]
+                .namespace
+                .as_deref()
+                .map(|ns| ns.split('/').collect())
+                .unwrap_or_default();
+            (segments, templates[i].file_name())
+        });
+
+        let mut rows = Vec::new();
+        let mut previous: Vec<&str> = Vec::new();
+        for index in order {
+            let segments: Vec<&str> = templates[index]
+                .namespace
+                .as_deref()
+                .map(|ns| ns.split('/').collect())
+                .unwrap_or_default();
+            let common = previous
+                .iter()
+                .zip(&segments)
+                .take_while(|(a, b)| a == b)
+                .count();
+            for (depth, segment) in segments.iter().enumerate().skip(common) {
+                rows.push(Row::Group {
+                    label: format!("{}/", segment),
+                    depth,
+                });
+            }
+            rows.push(Row::Template {
+                index,
+                depth: segments.len(),
+            });
+            previous = segments;
+        }
+        rows
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::path::Path;
+
+        fn key(code: KeyCode) -> KeyEvent {
+            KeyEvent::new(code, KeyModifiers::NONE)
+        }
+
+        fn type_text(app: &mut App, text: &str) {
+            for c in text.chars() {
+                app.handle_key(key(KeyCode::Char(c))).unwrap();
+            }
+        }
+
+        fn write(dir: &Path, name: &str, content: &str) {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        #[test]
+        fn test_tree_rows() {
+            let dir = tempfile::TempDir::new().unwrap();
+            for name in [
+                "web/react/app.tsx",
+                "web/index.html",
+                "web-x/a.md",
+                "main.rs",
+            ] {
+                write(dir.path(), name, "");
+            }
+            let templates = new_cli::template::list_templates(dir.path()).unwrap();
+            let labels: Vec<String> = build_rows(&templates)
+                .iter()
+                .map(|row| match row {
+                    Row::Group { label, depth } => format!("{}{}", depth, label),
+                    Row::Template { index, depth } => {
+                        format!("{}{}", depth, templates[*index].file_name())
+                    }
+                })
+                .collect();
+            assert_eq!(
+                labels,
+                [
+                    "0main.rs",
+                    "0web/",
+                    "1index.html",
+                    "1react/",
+                    "2app.tsx",
+                    "0web-x/",
+                    "1a.md"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_form_creates_selection() {
+            let dir = tempfile::TempDir::new().unwrap();
+            write(dir.path(), "a.md", "");
+            write(
+                dir.path(),
+                "ui/button.tsx",
+                "+++\n[variables]\nlabel = { required = true }\n+++\n{{label}} {{author}}",
+            );
+            let engine = TemplateEngine::new(dir.path());
+            let templates = engine.list_templates().unwrap();
+            let known: Variables = [("author".to_string(), "me".to_string())].into();
+            let git = GitCache::default();
+            let mut app = App::new(&engine, &known, &git, &templates);
+
+            // 组标题不可选中：从 a.md 向下直接到 button.tsx
+            app.handle_key(key(KeyCode::Down)).unwrap();
+            assert_eq!(templates[app.current()].file_name(), "button.tsx");
+            app.handle_key(key(KeyCode::Down)).unwrap();
+            assert_eq!(templates[app.current()].file_name(), "button.tsx");
+
+            app.handle_key(key(KeyCode::Enter)).unwrap();
+            app.handle_key(key(KeyCode::Backspace)).unwrap();
+            type_text(&mut app, "On");
+            // 跳到最后一项时必填的 label 为空，不能创建
+            app.handle_key(key(KeyCode::Enter)).unwrap();
+            app.handle_key(key(KeyCode::Enter)).unwrap();
+            assert!(matches!(
+                app.handle_key(key(KeyCode::Enter)).unwrap(),
+                Action::Continue
+            ));
+            assert!(app.form.as_ref().unwrap().error.is_some());
+
+            type_text(&mut app, "OK");
+            let Action::Create(selection) = app.handle_key(key(KeyCode::Enter)).unwrap() else {
+                panic!("expected a selection");
+            };
+            assert_eq!(selection.filename, "buttoOn");
+            assert_eq!(selection.extension, "tsx");
+            assert_eq!(
+                selection.variables,
+                [("label".to_string(), "OK".to_string())]
+            );
+            assert!(selection.template.ends_with("ui/button.tsx"));
+        }
+    }
+}
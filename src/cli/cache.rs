@@ -0,0 +1,53 @@
+//! `cache` 子命令：查看和清理缓存目录中下载的远程模板
+
+use anyhow::Result;
+
+use crate::cli::template::{format_modified, format_size};
+use new_cli::remote;
+use new_cli::tr;
+
+/// `cache list`：列出缓存中的模板及其下载地址
+pub fn run_list() -> Result<()> {
+    let dir = remote::cache_dir()?;
+    let files = remote::list_cache(&dir)?;
+    if files.is_empty() {
+        println!("{}", tr!("缓存 {:?} 为空", "The cache {:?} is empty", dir));
+        return Ok(());
+    }
+
+    for file in &files {
+        let name = file.path.strip_prefix(&dir).unwrap_or(&file.path);
+        println!(
+            "{:>10}  {:<16}  {}",
+            format_size(file.size),
+            format_modified(file.modified),
+            file.url.as_deref().unwrap_or(&name.display().to_string())
+        );
+    }
+    println!(
+        "{}",
+        tr!(
+            "共 {} 个文件 ({})，位于 {:?}",
+            "{} files ({}) in {:?}",
+            files.len(),
+            format_size(files.iter().map(|file| file.size).sum()),
+            dir
+        )
+    );
+    Ok(())
+}
+
+/// `cache clean`：删除缓存中的所有文件
+pub fn run_clean() -> Result<()> {
+    let (count, size) = remote::clean_cache(&remote::cache_dir()?)?;
+    println!(
+        "{}",
+        tr!(
+            "已删除 {} 个缓存文件，释放 {}",
+            "Removed {} cached files, freed {}",
+            count,
+            format_size(size)
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! 读写系统剪贴板
+//!
+//! 通过各平台的命令行工具访问剪贴板：macOS 使用 `pbpaste`/`pbcopy`，Windows 使用 PowerShell，
+//! Linux 在 Wayland 下使用 `wl-paste`/`wl-copy`，否则依次尝试 `xclip` 和 `xsel`。
+
+use anyhow::{Context, Result};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use new_cli::tr;
+
+/// 读取剪贴板中的文本
+pub fn read() -> Result<String> {
+    let session = Session::current();
+    for &(program, args) in paste_commands(session) {
+        let output = match Command::new(program).args(args).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| failed(program)),
+        };
+        if !output.status.success() {
+            anyhow::bail!(
+                "{}: {}",
+                failed(program),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        log::debug!(
+            "read {} bytes from the clipboard with {}",
+            output.stdout.len(),
+            program
+        );
+        return String::from_utf8(output.stdout).map_err(|_| {
+            anyhow::anyhow!(
+                "{}",
+                tr!(
+                    "剪贴板中的内容不是文本",
+                    "the clipboard does not contain text"
+                )
+            )
+        });
+    }
+    Err(unavailable(session))
+}
+
+/// 将文本写入剪贴板
+pub fn write(text: &str) -> Result<()> {
+    let session = Session::current();
+    for &(program, args) in copy_commands(session) {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| failed(program)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .with_context(|| failed(program))?;
+        }
+        let status = child.wait().with_context(|| failed(program))?;
+        if !status.success() {
+            anyhow::bail!("{} ({})", failed(program), status);
+        }
+        return Ok(());
+    }
+    Err(unavailable(session))
+}
+
+/// 当前的桌面环境
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Session {
+    MacOs,
+    Windows,
+    Wayland,
+    X11,
+}
+
+impl Session {
+    fn current() -> Session {
+        if cfg!(target_os = "macos") {
+            Session::MacOs
+        } else if cfg!(windows) {
+            Session::Windows
+        } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            Session::Wayland
+        } else {
+            Session::X11
+        }
+    }
+}
+
+type Commands = &'static [(&'static str, &'static [&'static str])];
+
+fn paste_commands(session: Session) -> Commands {
+    match session {
+        Session::MacOs => &[("pbpaste", &[])],
+        Session::Windows => &[(
+            "powershell",
+            &["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+        )],
+        Session::Wayland => &[("wl-paste", &["--no-newline"])],
+        Session::X11 => &[
+            ("xclip", &["-selection", "clipboard", "-out"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ],
+    }
+}
+
+fn copy_commands(session: Session) -> Commands {
+    match session {
+        Session::MacOs => &[("pbcopy", &[])],
+        Session::Windows => &[(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+            ],
+        )],
+        Session::Wayland => &[("wl-copy", &[])],
+        Session::X11 => &[
+            ("xclip", &["-selection", "clipboard", "-in"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ],
+    }
+}
+
+fn failed(program: &str) -> String {
+    tr!(
+        "使用 {} 访问剪贴板失败",
+        "failed to access the clipboard with {}",
+        program
+    )
+}
+
+fn unavailable(session: Session) -> anyhow::Error {
+    let programs: Vec<&str> = paste_commands(session)
+        .iter()
+        .map(|(program, _)| *program)
+        .collect();
+    anyhow::anyhow!(
+        "{}",
+        tr!(
+            "无法访问剪贴板，请安装 {}",
+            "cannot access the clipboard, please install {}",
+            programs.join(tr!(" 或 ", " or ").as_str())
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_commands() {
+        assert_eq!(paste_commands(Session::MacOs)[0].0, "pbpaste");
+        assert_eq!(copy_commands(Session::Wayland)[0].0, "wl-copy");
+        let x11: Vec<_> = copy_commands(Session::X11).iter().map(|c| c.0).collect();
+        assert_eq!(x11, ["xclip", "xsel"]);
+        assert!(unavailable(Session::X11).to_string().contains("xclip"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Shell 补全 (`new-cli completions <shell>`)
+//!
+//! 生成的脚本在每次补全时回调 `COMPLETE=<shell> new-cli`，因此除了子命令和参数外，
+//! 模板名也能根据当前模板目录动态补全。
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use clap_complete::env::Shells;
+use clap_complete::CompletionCandidate;
+use std::ffi::OsStr;
+
+use new_cli::config::Config;
+use new_cli::template::{self, TemplateInfo};
+use new_cli::tr;
+use new_cli::TemplateEngine;
+
+/// 补全脚本回调时使用的环境变量
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// 支持生成补全脚本的 shell
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+impl Shell {
+    fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Powershell => "powershell",
+            Shell::Elvish => "elvish",
+        }
+    }
+}
+
+/// 将指定 shell 的补全脚本输出到标准输出
+pub fn run(shell: Shell) -> Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell.name())
+        .with_context(|| tr!("不支持的 shell: {}", "unsupported shell: {}", shell.name()))?;
+
+    let bin = env!("CARGO_PKG_NAME");
+    completer
+        .write_registration(COMPLETE_VAR, bin, bin, bin, &mut std::io::stdout())
+        .context(tr!(
+            "无法输出补全脚本",
+            "failed to write the completion script"
+        ))?;
+    Ok(())
+}
+
+/// 补全全局模板目录中的模板文件名，例如 `index.html`、`web/index.html`
+pub fn complete_template_file(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete_templates(current, false, |t| Some(t.qualified_name()))
+}
+
+/// 补全所有模板目录中的模板文件名
+pub fn complete_template_file_all(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete_templates(current, true, |t| Some(t.qualified_name()))
+}
+
+/// 补全模板命名空间（模板目录下的子目录），用于 `--from`
+pub fn complete_namespace(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete_templates(current, true, |t| t.namespace.clone())
+}
+
+/// 补全模板名（不含后缀），用于要创建的文件名
+pub fn complete_template_name(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete_templates(current, true, |t| {
+        Some(match &t.namespace {
+            Some(namespace) => format!("{}/{}", namespace, t.name),
+            None => t.name.clone(),
+        })
+    })
+}
+
+/// `all_layers` 为 false 时只补全全局模板目录（模板管理命令只操作该目录）
+fn complete_templates(
+    current: &OsStr,
+    all_layers: bool,
+    key: impl Fn(&TemplateInfo) -> Option<String>,
+) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    let Ok(engine) = TemplateEngine::discover(&config) else {
+        return Vec::new();
+    };
+    let templates = if all_layers {
+        engine.list_templates()
+    } else {
+        template::list_templates(engine.template_dir())
+    };
+    let Ok(templates) = templates else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = templates
+        .iter()
+        .filter_map(key)
+        .filter(|name| name.starts_with(current.as_ref()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().map(CompletionCandidate::new).collect()
+}
@@ -0,0 +1,392 @@
+//! `doctor` 子命令：检查配置、模板目录、模板和编辑器，并给出修复建议
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::cli::editor;
+use new_cli::config::{self, Config};
+use new_cli::layers::LayerSource;
+use new_cli::tr;
+use new_cli::TemplateEngine;
+
+/// 检查结果的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// 一项检查的结果
+#[derive(Debug)]
+struct Check {
+    level: Level,
+    message: String,
+    /// 如何修复问题
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(message: String) -> Check {
+        Check {
+            level: Level::Ok,
+            message,
+            fix: None,
+        }
+    }
+
+    fn warning(message: String, fix: String) -> Check {
+        Check {
+            level: Level::Warning,
+            message,
+            fix: Some(fix),
+        }
+    }
+
+    fn error(message: String, fix: String) -> Check {
+        Check {
+            level: Level::Error,
+            message,
+            fix: Some(fix),
+        }
+    }
+}
+
+/// 执行所有检查并打印结果，存在错误时以退出码 1 结束
+pub fn run() -> Result<()> {
+    let mut checks = Vec::new();
+    let config = check_config(&mut checks)?;
+    let engine = TemplateEngine::discover(&config)?;
+    checks.extend(check_template_dirs(&config, &engine));
+    checks.extend(check_templates(&engine));
+    checks.extend(check_aliases(&config, &engine));
+    checks.extend(check_editors(&config, editor::find_program));
+    checks.extend(check_hooks(&config, editor::find_program));
+
+    for check in &checks {
+        let mark = match check.level {
+            Level::Ok => "✓",
+            Level::Warning => "!",
+            Level::Error => "✗",
+        };
+        println!("{} {}", mark, check.message);
+        if let Some(fix) = &check.fix {
+            println!("  {}", tr!("修复: {}", "Fix: {}", fix));
+        }
+    }
+
+    let count = |level| checks.iter().filter(|c| c.level == level).count();
+    let (errors, warnings) = (count(Level::Error), count(Level::Warning));
+    println!();
+    if errors == 0 && warnings == 0 {
+        println!("{}", tr!("一切正常", "Everything looks good"));
+        return Ok(());
+    }
+    println!(
+        "{}",
+        tr!(
+            "发现 {} 个错误，{} 个警告",
+            "Found {} errors and {} warnings",
+            errors,
+            warnings
+        )
+    );
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// 读取配置文件；格式错误时记录问题并使用默认配置继续检查
+fn check_config(checks: &mut Vec<Check>) -> Result<Config> {
+    let path = config::config_path()?;
+    if !path.exists() {
+        checks.push(Check::ok(tr!(
+            "未找到配置文件 {}，使用默认配置",
+            "No config file at {}, using defaults",
+            path.display()
+        )));
+        return Ok(Config::default());
+    }
+    match Config::load_from(&path) {
+        Ok(config) => {
+            checks.push(Check::ok(tr!(
+                "配置文件: {}",
+                "Config file: {}",
+                path.display()
+            )));
+            Ok(config)
+        }
+        Err(e) => {
+            checks.push(Check::error(
+                format!("{:#}", anyhow::Error::from(e)),
+                tr!(
+                    "修正 {} 中的语法错误，或删除该文件以使用默认配置",
+                    "fix the syntax error in {}, or delete it to use the defaults",
+                    path.display()
+                ),
+            ));
+            Ok(Config::default())
+        }
+    }
+}
+
+fn check_template_dirs(config: &Config, engine: &TemplateEngine) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let dir = engine.template_dir();
+    if dir.is_dir() {
+        checks.push(Check::ok(tr!(
+            "模板目录: {}",
+            "Template directory: {}",
+            dir.display()
+        )));
+    } else if config.template_dir.is_some() {
+        checks.push(Check::error(
+            tr!(
+                "配置的模板目录 {} 不存在",
+                "The configured template directory {} does not exist",
+                dir.display()
+            ),
+            tr!(
+                "创建该目录，或修改配置文件中的 template_dir",
+                "create the directory, or change template_dir in the config file"
+            ),
+        ));
+    } else {
+        checks.push(Check::warning(
+            tr!(
+                "模板目录 {} 不存在",
+                "Template directory {} does not exist",
+                dir.display()
+            ),
+            tr!(
+                "首次创建文件时会自动创建，也可以运行 new-cli template list",
+                "it is created on first use, or run new-cli template list"
+            ),
+        ));
+    }
+    for layer in engine.layers() {
+        if layer.source == LayerSource::Global {
+            continue;
+        }
+        if layer.dir.is_dir() {
+            checks.push(Check::ok(tr!(
+                "{}模板目录: {}",
+                "Template directory ({}): {}",
+                layer.source.label(),
+                layer.dir.display()
+            )));
+        } else {
+            checks.push(Check::warning(
+                tr!(
+                    "{}模板目录 {} 不存在",
+                    "Template directory ({}) {} does not exist",
+                    layer.source.label(),
+                    layer.dir.display()
+                ),
+                tr!(
+                    "创建该目录，或从配置文件的 template_paths 中删除它",
+                    "create the directory, or remove it from template_paths in the config file"
+                ),
+            ));
+        }
+    }
+    checks
+}
+
+/// 逐个检查模板能否渲染
+fn check_templates(engine: &TemplateEngine) -> Vec<Check> {
+    let templates = match engine.list_templates() {
+        Ok(templates) => templates,
+        Err(e) => {
+            return vec![Check::error(
+                e.to_string(),
+                tr!(
+                    "检查模板目录的权限",
+                    "check the permissions of the template directory"
+                ),
+            )]
+        }
+    };
+    let mut checks: Vec<Check> = templates
+        .iter()
+        .filter_map(|template| {
+            let e = engine.check_template(&template.path).err()?;
+            Some(Check::error(
+                format!("{:#}", anyhow::Error::from(e)),
+                tr!(
+                    "编辑 {} 修正错误，或运行 new-cli template remove {}",
+                    "edit {} to fix it, or run new-cli template remove {}",
+                    template.path.display(),
+                    template.qualified_name()
+                ),
+            ))
+        })
+        .collect();
+    let valid = templates.len() - checks.len();
+    if valid > 0 {
+        checks.insert(
+            0,
+            Check::ok(tr!(
+                "{} 个模板可以正常渲染",
+                "{} templates render correctly",
+                valid
+            )),
+        );
+    }
+    checks
+}
+
+fn check_aliases(config: &Config, engine: &TemplateEngine) -> Vec<Check> {
+    let mut aliases: Vec<_> = config.aliases.iter().collect();
+    aliases.sort();
+    aliases
+        .into_iter()
+        .filter(|(_, template)| engine.resolve_template(template).is_err())
+        .map(|(alias, template)| {
+            Check::error(
+                tr!(
+                    "别名 {} 指向的模板 {} 不存在",
+                    "Alias {} points to missing template {}",
+                    alias,
+                    template
+                ),
+                tr!(
+                    "添加模板 {}，或修改配置文件 [aliases] 中的 {}",
+                    "add the template {}, or change {} under [aliases] in the config file",
+                    template,
+                    alias
+                ),
+            )
+        })
+        .collect()
+}
+
+/// 检查默认编辑器和 `[editors]` 中的编辑器是否能找到
+fn check_editors(config: &Config, find: impl Fn(&str) -> Option<PathBuf>) -> Vec<Check> {
+    let default = editor::resolve_editor(None, config, Path::new(""));
+    let mut editors = vec![(None, default)];
+    let mut by_extension: Vec<_> = config.editors.iter().collect();
+    by_extension.sort();
+    editors.extend(
+        by_extension
+            .into_iter()
+            .map(|(ext, editor)| (Some(ext.as_str()), editor.clone())),
+    );
+
+    editors
+        .into_iter()
+        .map(|(ext, command)| {
+            let label = match ext {
+                Some(ext) => tr!("编辑器 ({} 文件)", "Editor (.{} files)", ext),
+                None => tr!("编辑器", "Editor"),
+            };
+            let program = editor::parse_editor_command(&command).map(|(program, _)| program);
+            match program.as_deref().map(|program| (program, find(program))) {
+                Ok((_, Some(path))) => Ok(Check::ok(format!(
+                    "{}: {} ({})",
+                    label,
+                    command,
+                    path.display()
+                ))),
+                Ok((program, None)) => Err(tr!(
+                    "{}: 找不到程序 {}",
+                    "{}: program {} not found",
+                    label,
+                    program
+                )),
+                Err(e) => Err(format!("{}: {}", label, e)),
+            }
+            .unwrap_or_else(|message| {
+                Check::warning(
+                    message,
+                    tr!(
+                        "安装该编辑器或把它加入 PATH，或在配置文件中修改 editor/[editors]",
+                        "install the editor or add it to PATH, or change editor/[editors] in the config file"
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// 检查全局钩子命令的程序是否能找到
+fn check_hooks(config: &Config, find: impl Fn(&str) -> Option<PathBuf>) -> Vec<Check> {
+    config
+        .hooks
+        .post_create
+        .iter()
+        .filter_map(|command| {
+            let program = shell_words::split(command).ok()?.into_iter().next()?;
+            find(&program).is_none().then(|| {
+                Check::warning(
+                    tr!(
+                        "钩子命令 '{}' 的程序 {} 不在 PATH 中",
+                        "Hook '{}': program {} is not on PATH",
+                        command,
+                        program
+                    ),
+                    tr!(
+                        "安装该程序，或修改配置文件中的 [hooks]",
+                        "install the program, or change [hooks] in the config file"
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn find(program: &str) -> Option<PathBuf> {
+        (program == "code").then(|| PathBuf::from("/usr/bin/code"))
+    }
+
+    #[test]
+    fn test_check_templates_and_aliases() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("ok.md"), "# {{title}}").unwrap();
+        fs::write(dir.path().join("bad.md"), "{{title | shout}}").unwrap();
+        let engine = TemplateEngine::new(dir.path());
+
+        let checks = check_templates(&engine);
+        let levels: Vec<_> = checks.iter().map(|c| c.level).collect();
+        assert_eq!(levels, [Level::Ok, Level::Error]);
+        assert!(checks[1].fix.as_deref().unwrap().contains("bad.md"));
+
+        let config = Config {
+            aliases: [("a", "ok.md"), ("b", "gone.md")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Config::default()
+        };
+        let checks = check_aliases(&config, &engine);
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].message.contains("gone.md"));
+    }
+
+    #[test]
+    fn test_check_editors_and_hooks() {
+        let mut config = Config {
+            editor: Some("code --wait".to_string()),
+            ..Config::default()
+        };
+        config
+            .editors
+            .insert("psd".to_string(), "photoshop".to_string());
+        config.hooks.post_create = vec!["cargo fmt".to_string()];
+
+        let checks = check_editors(&config, find);
+        let levels: Vec<_> = checks.iter().map(|c| c.level).collect();
+        assert_eq!(levels, [Level::Ok, Level::Warning]);
+        assert!(checks[1].message.contains("photoshop"));
+
+        let checks = check_hooks(&config, find);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].level, Level::Warning);
+    }
+}
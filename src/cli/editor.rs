@@ -0,0 +1,579 @@
+//! 编辑器选择与启动
+//!
+//! 编辑器的优先级：`--editor`/`--app` 参数 > 配置文件 `[editors]` 中按后缀指定的编辑器 >
+//! `[apps]` 中按后缀指定的 macOS 应用 > 配置文件 `editor` > `$VISUAL` > `$EDITOR` > 平台默认。
+//! 编辑器命令按 shell 规则拆分，因此可以带参数，例如 `code --wait`。文件默认作为最后一个参数；
+//! 参数中含有 `{file}` 时改为替换到该处，同时替换 `{line}` 和 `{column}`，
+//! 例如 `idea --line {line} {file}`。
+//!
+//! Windows 上的平台默认编辑器依次检测：`.txt` 文件关联的程序、`code`、`notepad++`、`notepad`，
+//! 检测结果缓存在缓存目录的 `detected-editor` 中（删除该文件即可重新检测）；
+//! 都找不到时使用 `cmd /C start` 按文件关联打开。
+//!
+//! 指定光标位置时，VS Code、Vim/Neovim、Sublime Text 和 nano 使用各自的语法跳转，
+//! 其他编辑器只打开文件。
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use crate::cli::exit::{ExitCode, Failure};
+use new_cli::config::{self, Config};
+use new_cli::cursor::Position;
+use new_cli::tr;
+
+/// 按优先级确定打开 `path` 要使用的编辑器命令
+pub fn resolve_editor(flag: Option<&str>, config: &Config, path: &Path) -> String {
+    resolve_editor_with(flag, config, path, |key| std::env::var(key).ok())
+}
+
+fn resolve_editor_with(
+    flag: Option<&str>,
+    config: &Config,
+    path: &Path,
+    env: impl Fn(&str) -> Option<String>,
+) -> String {
+    let app = || {
+        cfg!(target_os = "macos")
+            .then(|| app_for_extension(config, path))
+            .flatten()
+    };
+    let editor = [
+        flag.map(str::to_string),
+        editor_for_extension(config, path),
+        app(),
+        config.editor.clone(),
+        env("VISUAL"),
+        env("EDITOR"),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|editor| !editor.trim().is_empty())
+    .unwrap_or_else(platform_default);
+    log::debug!("editor for {}: {}", path.display(), editor);
+    editor
+}
+
+/// 配置文件 `[editors]` 中为该文件后缀指定的编辑器，后缀不区分大小写
+fn editor_for_extension(config: &Config, path: &Path) -> Option<String> {
+    lookup_extension(&config.editors, path).cloned()
+}
+
+/// 配置文件 `[apps]` 中为该文件后缀指定的应用对应的 `open -a` 命令
+fn app_for_extension(config: &Config, path: &Path) -> Option<String> {
+    lookup_extension(&config.apps, path).map(|app| app_command(app))
+}
+
+fn lookup_extension<'a>(map: &'a HashMap<String, String>, path: &Path) -> Option<&'a String> {
+    let extension = path.extension()?.to_str()?;
+    map.iter()
+        .find(|(key, _)| key.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        .map(|(_, value)| value)
+}
+
+/// 用 macOS 应用打开文件的编辑器命令 `open -a <app>`，应用名中的空格和引号会被正确转义
+pub fn app_command(app: &str) -> String {
+    shell_words::join(["open", "-a", app])
+}
+
+fn platform_default() -> String {
+    if cfg!(target_os = "windows") {
+        windows_default()
+    } else if cfg!(target_os = "macos") {
+        "open".to_string() // macOS 使用 open 命令
+    } else {
+        "xdg-open".to_string() // Linux 使用 xdg-open
+    }
+}
+
+/// 检测结果的缓存文件
+fn detected_editor_path() -> Option<PathBuf> {
+    config::cache_dir()
+        .ok()
+        .map(|dir| dir.join("detected-editor"))
+}
+
+/// Windows 上的默认编辑器：优先使用缓存的检测结果，缓存的程序不存在时重新检测
+fn windows_default() -> String {
+    let cache = detected_editor_path();
+    let cached = cache
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|editor| editor.trim().to_string())
+        .filter(|editor| {
+            parse_editor_command(editor).is_ok_and(|(program, _)| find_program(&program).is_some())
+        });
+    if let Some(editor) = cached {
+        return editor;
+    }
+
+    match detect_windows_editor(text_file_association(), find_program) {
+        Some(editor) => {
+            log::debug!("detected editor: {}", editor);
+            if let Some(cache) = cache {
+                if let Err(e) = fs::write(&cache, &editor) {
+                    log::debug!("failed to cache the detected editor: {}", e);
+                }
+            }
+            editor
+        }
+        // 没有可用的编辑器时交给文件关联打开，不缓存以便安装编辑器后重新检测
+        None => r#"cmd /C start """#.to_string(),
+    }
+}
+
+/// 按 `.txt` 文件关联的程序、`code`、`notepad++`、`notepad` 的顺序选择第一个存在的编辑器
+///
+/// 使用 `.txt` 而不是新文件本身的关联，因为 `.html` 等文件通常关联到浏览器而不是编辑器。
+fn detect_windows_editor(
+    association: Option<String>,
+    find: impl Fn(&str) -> Option<PathBuf>,
+) -> Option<String> {
+    if let Some(program) = association.as_deref().and_then(association_program) {
+        if find(&program).is_some() {
+            return Some(shell_words::quote(&program).into_owned());
+        }
+    }
+    ["code", "notepad++", "notepad"]
+        .into_iter()
+        .find(|program| find(program).is_some())
+        .map(str::to_string)
+}
+
+/// 通过 `assoc`/`ftype` 查询 `.txt` 文件的打开命令，例如 `%SystemRoot%\system32\NOTEPAD.EXE %1`
+fn text_file_association() -> Option<String> {
+    let query = |args: &[&str]| {
+        let output = Command::new("cmd").arg("/C").args(args).output().ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // 输出形如 `.txt=txtfile`
+        let (_, value) = stdout.trim().split_once('=')?;
+        output.status.success().then(|| value.to_string())
+    };
+    let file_type = query(&["assoc", ".txt"])?;
+    query(&["ftype", &file_type])
+}
+
+/// 文件关联命令中的程序路径：去掉 `"%1"` 等参数，并展开 `%VAR%` 形式的环境变量
+///
+/// 命令中的路径使用反斜杠，不能按 shell 规则拆分。
+fn association_program(command: &str) -> Option<String> {
+    let command = command.trim();
+    let program = match command.strip_prefix('"') {
+        Some(rest) => rest.split('"').next()?,
+        None => command.split_whitespace().next()?,
+    };
+    let mut expanded = String::new();
+    let mut parts = program.split('%');
+    expanded.push_str(parts.next()?);
+    while let Some(name) = parts.next() {
+        match (parts.next(), std::env::var(name)) {
+            (Some(rest), Ok(value)) => {
+                expanded.push_str(&value);
+                expanded.push_str(rest);
+            }
+            (rest, _) => {
+                expanded.push('%');
+                expanded.push_str(name);
+                if let Some(rest) = rest {
+                    expanded.push('%');
+                    expanded.push_str(rest);
+                }
+            }
+        }
+    }
+    (!expanded.is_empty()).then_some(expanded)
+}
+
+/// 在 `PATH` 中查找程序；包含路径分隔符时直接检查该路径
+pub fn find_program(program: &str) -> Option<std::path::PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    // Windows 上还要尝试 PATHEXT 中的后缀，例如 code.cmd
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{program}{ext}")))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// 将编辑器命令拆分为程序名和参数
+pub fn parse_editor_command(editor: &str) -> Result<(String, Vec<String>)> {
+    let mut words = shell_words::split(editor).with_context(|| {
+        tr!(
+            "无法解析编辑器命令: {}",
+            "failed to parse editor command: {}",
+            editor
+        )
+    })?;
+    if words.is_empty() {
+        anyhow::bail!(
+            "{}",
+            tr!("编辑器命令不能为空", "the editor command must not be empty")
+        );
+    }
+    let program = words.remove(0);
+    Ok((program, words))
+}
+
+/// 使用编辑器打开文件并尽量把光标放在 `position`，不等待编辑器退出
+pub fn open_file(editor: &str, path: &Path, position: Option<Position>) -> Result<()> {
+    command(editor, path, position)
+        .and_then(|mut command| Ok(command.spawn()?))
+        .with_context(|| open_failed(editor))?;
+    Ok(())
+}
+
+/// 使用编辑器打开文件，并等待编辑器退出
+///
+/// 图形界面的编辑器通常会立即返回，需要在命令中加上各自的等待参数，例如 `code --wait`。
+pub fn open_file_and_wait(
+    editor: &str,
+    path: &Path,
+    position: Option<Position>,
+) -> Result<ExitStatus> {
+    command(editor, path, position)
+        .and_then(|mut command| Ok(command.status()?))
+        .with_context(|| open_failed(editor))
+}
+
+/// 编辑器是否运行在终端中，例如 vim、nano
+///
+/// 终端编辑器需要继承当前终端并在前台运行，new-cli 要等待它退出后再结束，否则编辑器会和
+/// shell 争抢终端。Emacs 只有带 `-nw`/`-t` 等参数时才在终端中运行。
+pub fn is_terminal_editor(editor: &str) -> bool {
+    let Ok((program, args)) = parse_editor_command(editor) else {
+        return false;
+    };
+    let name = Path::new(&program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "vi" | "vim" | "nvim" | "view" | "nano" | "pico" | "micro" | "hx" | "helix" | "kak"
+        | "joe" | "jed" | "ne" | "mg" | "ed" | "mcedit" => true,
+        "emacs" | "emacsclient" => args
+            .iter()
+            .any(|arg| matches!(arg.as_str(), "-nw" | "-t" | "--tty" | "--no-window-system")),
+        _ => false,
+    }
+}
+
+/// 启动编辑器失败时以 [`ExitCode::Editor`] 退出
+fn open_failed(editor: &str) -> Failure {
+    Failure::new(
+        ExitCode::Editor,
+        tr!(
+            "无法使用 {} 打开文件",
+            "failed to open the file with {}",
+            editor
+        ),
+    )
+}
+
+/// 编辑器命令中文件路径的占位符
+const FILE_PLACEHOLDER: &str = "{file}";
+
+fn command(editor: &str, path: &Path, position: Option<Position>) -> Result<Command> {
+    let (program, args) = parse_editor_command(editor)?;
+    let mut command = Command::new(&program);
+    if args.iter().any(|arg| arg.contains(FILE_PLACEHOLDER)) {
+        let position = position.unwrap_or(Position::line(1));
+        command.args(args.iter().map(|arg| substitute(arg, path, position)));
+    } else {
+        command
+            .args(&args)
+            .args(file_args(&program, path, position));
+    }
+    log::debug!("editor command: {:?}", command);
+    Ok(command)
+}
+
+/// 替换参数中的 `{file}`、`{line}` 和 `{column}`；路径不一定是 UTF-8，因此拼接 `OsString`
+fn substitute(arg: &str, path: &Path, position: Position) -> OsString {
+    let arg = arg
+        .replace("{line}", &position.line.to_string())
+        .replace("{column}", &position.column.to_string());
+    let mut result = OsString::new();
+    for (i, part) in arg.split(FILE_PLACEHOLDER).enumerate() {
+        if i > 0 {
+            result.push(path);
+        }
+        result.push(part);
+    }
+    result
+}
+
+/// 打开 `path` 的参数，按编辑器的语法附带光标位置
+fn file_args(program: &str, path: &Path, position: Option<Position>) -> Vec<OsString> {
+    let Some(Position { line, column }) = position else {
+        return vec![path.into()];
+    };
+    let with_position = || {
+        let mut arg = path.as_os_str().to_owned();
+        arg.push(format!(":{line}:{column}"));
+        arg
+    };
+    let name = Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["--goto".into(), with_position()]
+        }
+        "subl" | "sublime_text" => vec![with_position()],
+        "vim" | "nvim" | "gvim" | "mvim" => {
+            vec![
+                format!("+call cursor({line}, {column})").into(),
+                path.into(),
+            ]
+        }
+        "vi" => vec![format!("+{line}").into(), path.into()],
+        "nano" => vec![format!("+{line},{column}").into(), path.into()],
+        _ => vec![path.into()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_from(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_resolve_editor_precedence() {
+        let env = env_from(&[("VISUAL", "vim"), ("EDITOR", "nano")]);
+        let config = Config {
+            editor: Some("code".to_string()),
+            ..Config::default()
+        };
+        let path = Path::new("index.html");
+
+        assert_eq!(
+            resolve_editor_with(Some("subl"), &config, path, &env),
+            "subl"
+        );
+        assert_eq!(resolve_editor_with(None, &config, path, &env), "code");
+        assert_eq!(
+            resolve_editor_with(None, &Config::default(), path, &env),
+            "vim"
+        );
+        assert_eq!(
+            resolve_editor_with(
+                None,
+                &Config::default(),
+                path,
+                env_from(&[("EDITOR", "nano")])
+            ),
+            "nano"
+        );
+        assert_eq!(
+            resolve_editor_with(None, &Config::default(), path, env_from(&[])),
+            platform_default()
+        );
+    }
+
+    #[test]
+    fn test_resolve_editor_by_extension() {
+        let env = env_from(&[("VISUAL", "vim")]);
+        let config = Config {
+            editor: Some("code".to_string()),
+            editors: [("md", "typora"), (".psd", "open -a Photoshop")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Config::default()
+        };
+
+        let editor = |path: &str| resolve_editor_with(None, &config, Path::new(path), &env);
+        assert_eq!(editor("notes.md"), "typora");
+        assert_eq!(editor("README.MD"), "typora");
+        assert_eq!(editor("logo.psd"), "open -a Photoshop");
+        assert_eq!(editor("index.html"), "code");
+        assert_eq!(editor("Makefile"), "code");
+        assert_eq!(
+            resolve_editor_with(Some("subl"), &config, Path::new("notes.md"), &env),
+            "subl"
+        );
+    }
+
+    #[test]
+    fn test_is_terminal_editor() {
+        assert!(is_terminal_editor("vim"));
+        assert!(is_terminal_editor("/usr/bin/nvim -u NONE"));
+        assert!(is_terminal_editor("nano"));
+        assert!(is_terminal_editor("emacs -nw"));
+        assert!(is_terminal_editor("emacsclient -t"));
+        assert!(!is_terminal_editor("emacs"));
+        assert!(!is_terminal_editor("gvim"));
+        assert!(!is_terminal_editor("code --wait"));
+        assert!(!is_terminal_editor("xdg-open"));
+        assert!(!is_terminal_editor("vim 'unclosed"));
+    }
+
+    #[test]
+    fn test_app_command() {
+        let command = app_command("Visual Studio Code");
+        assert_eq!(command, "open -a 'Visual Studio Code'");
+        assert_eq!(
+            parse_editor_command(&command).unwrap(),
+            (
+                "open".to_string(),
+                vec!["-a".to_string(), "Visual Studio Code".to_string()]
+            )
+        );
+        assert_eq!(
+            parse_editor_command(&app_command("Bob's Editor"))
+                .unwrap()
+                .1[1],
+            "Bob's Editor"
+        );
+
+        let config = Config {
+            apps: [("HTML".to_string(), "Safari".to_string())].into(),
+            ..Config::default()
+        };
+        assert_eq!(
+            app_for_extension(&config, Path::new("index.html")).as_deref(),
+            Some("open -a Safari")
+        );
+        assert_eq!(app_for_extension(&config, Path::new("main.rs")), None);
+    }
+
+    #[test]
+    fn test_resolve_editor_ignores_blank_values() {
+        let env = env_from(&[("VISUAL", "  "), ("EDITOR", "nano")]);
+        assert_eq!(
+            resolve_editor_with(Some(""), &Config::default(), Path::new("a.txt"), env),
+            "nano"
+        );
+    }
+
+    #[test]
+    fn test_detect_windows_editor() {
+        let installed = |names: &'static [&'static str]| {
+            move |program: &str| names.contains(&program).then(|| PathBuf::from(program))
+        };
+        const NPP: &str = r"C:\Program Files\Notepad++\notepad++.exe";
+        let association = Some(format!(r#""{NPP}" "%1""#));
+
+        assert_eq!(
+            detect_windows_editor(association.clone(), installed(&[NPP, "code"])),
+            Some(format!("'{NPP}'"))
+        );
+        // 关联的程序不存在时按顺序检测
+        assert_eq!(
+            detect_windows_editor(association, installed(&["notepad", "code"])),
+            Some("code".to_string())
+        );
+        assert_eq!(
+            detect_windows_editor(None, installed(&["notepad", "notepad++"])),
+            Some("notepad++".to_string())
+        );
+        assert_eq!(detect_windows_editor(None, installed(&[])), None);
+
+        assert_eq!(parse_editor_command(&format!("'{NPP}'")).unwrap().0, NPP);
+    }
+
+    #[test]
+    fn test_association_program() {
+        assert_eq!(
+            association_program(r#""C:\Tools\ed.exe" "%1""#).as_deref(),
+            Some(r"C:\Tools\ed.exe")
+        );
+        assert_eq!(
+            association_program(r"%NEW_CLI_NO_SUCH_VAR%\NOTEPAD.EXE %1").as_deref(),
+            Some(r"%NEW_CLI_NO_SUCH_VAR%\NOTEPAD.EXE")
+        );
+        assert_eq!(association_program("  "), None);
+    }
+
+    #[test]
+    fn test_file_args_with_position() {
+        let path = Path::new("src/main.rs");
+        let position = Some(Position { line: 3, column: 5 });
+        let args = |program: &str, position| {
+            file_args(program, path, position)
+                .into_iter()
+                .map(|arg| arg.into_string().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(args("code", position), ["--goto", "src/main.rs:3:5"]);
+        assert_eq!(args("/usr/local/bin/subl", position), ["src/main.rs:3:5"]);
+        assert_eq!(
+            args("nvim", position),
+            ["+call cursor(3, 5)", "src/main.rs"]
+        );
+        assert_eq!(args("nano", position), ["+3,5", "src/main.rs"]);
+        assert_eq!(args("xdg-open", position), ["src/main.rs"]);
+        assert_eq!(args("code", None), ["src/main.rs"]);
+    }
+
+    #[test]
+    fn test_parse_editor_command() {
+        assert_eq!(
+            parse_editor_command("code --wait").unwrap(),
+            ("code".to_string(), vec!["--wait".to_string()])
+        );
+        assert_eq!(
+            parse_editor_command(r#""/Applications/Sublime Text.app/subl" -n"#).unwrap(),
+            (
+                "/Applications/Sublime Text.app/subl".to_string(),
+                vec!["-n".to_string()]
+            )
+        );
+        assert!(parse_editor_command("").is_err());
+        assert!(parse_editor_command("code \"unterminated").is_err());
+    }
+
+    #[test]
+    fn test_command_with_placeholders() {
+        let args = |editor: &str, position| -> Vec<OsString> {
+            command(editor, Path::new("/w/my file.rs"), position)
+                .unwrap()
+                .get_args()
+                .map(|arg| arg.to_os_string())
+                .collect()
+        };
+        assert_eq!(
+            args(
+                "idea --line {line} {file}",
+                Some(Position { line: 3, column: 5 })
+            ),
+            ["--line", "3", "/w/my file.rs"]
+        );
+        assert_eq!(
+            args("'my editor' --open={file}:{line}", None),
+            ["--open=/w/my file.rs:1"]
+        );
+        // 没有 {file} 时文件仍是最后一个参数
+        assert_eq!(args("code -r", None), ["-r", "/w/my file.rs"]);
+    }
+}
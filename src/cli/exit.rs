@@ -0,0 +1,86 @@
+//! 退出码：失败时按原因以不同的退出码结束，脚本可以据此分支
+//!
+//! 库中的错误由 [`new_cli::Error::exit_code`] 决定退出码；命令行自己检查出的问题用
+//! [`Failure`] 附带退出码。其余错误以 1 结束。
+
+use std::fmt;
+
+pub use new_cli::error::ExitCode;
+
+/// 附带退出码的错误，也可以作为 `context` 包在其他错误外面
+#[derive(Debug)]
+pub struct Failure {
+    code: ExitCode,
+    message: String,
+}
+
+impl Failure {
+    pub fn new(code: ExitCode, message: impl Into<String>) -> Failure {
+        Failure {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// 以 `code` 失败的错误，用法同 `anyhow::anyhow!`
+pub fn fail(code: ExitCode, message: impl Into<String>) -> anyhow::Error {
+    Failure::new(code, message).into()
+}
+
+/// 错误对应的退出码：最外层的 [`Failure`] 优先，其次是错误链中的库错误
+pub fn code_of(error: &anyhow::Error) -> ExitCode {
+    if let Some(failure) = error.downcast_ref::<Failure>() {
+        return failure.code;
+    }
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<new_cli::Error>())
+        .map_or(ExitCode::Failure, new_cli::Error::exit_code)
+}
+
+/// 多个文件失败时合并退出码：原因相同时保留，不同时为 1
+pub fn merge(current: Option<ExitCode>, code: ExitCode) -> ExitCode {
+    match current {
+        Some(current) if current != code => ExitCode::Failure,
+        _ => code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use new_cli::Error;
+
+    #[test]
+    fn test_code_of_error_chain() {
+        let exists = anyhow::Error::from(Error::TargetExists("a.md".to_string()));
+        assert_eq!(code_of(&exists), ExitCode::TargetExists);
+        let wrapped = exists.context("while creating a.md");
+        assert_eq!(code_of(&wrapped), ExitCode::TargetExists);
+
+        let editor = Err::<(), _>(std::io::Error::other("not found"))
+            .context(Failure::new(ExitCode::Editor, "failed to open"))
+            .unwrap_err();
+        assert_eq!(code_of(&editor), ExitCode::Editor);
+        assert_eq!(code_of(&anyhow::anyhow!("boom")), ExitCode::Failure);
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(merge(None, ExitCode::TargetExists), ExitCode::TargetExists);
+        let same = merge(Some(ExitCode::TargetExists), ExitCode::TargetExists);
+        assert_eq!(same, ExitCode::TargetExists);
+        let mixed = merge(Some(ExitCode::TargetExists), ExitCode::InvalidInput);
+        assert_eq!(mixed, ExitCode::Failure);
+    }
+}
@@ -0,0 +1,151 @@
+//! 调用系统 `git` 命令的辅助函数
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use new_cli::tr;
+
+/// 在 `dir` 中执行 git 命令，git 的输出直接透传到终端
+pub fn run(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .context(tr!(
+            "无法执行 git，请确认已安装 git 并在 PATH 中",
+            "failed to run git, make sure git is installed and on PATH"
+        ))?;
+
+    if !status.success() {
+        anyhow::bail!(
+            "{}",
+            tr!(
+                "git {} 执行失败 ({})",
+                "git {} failed ({})",
+                args.join(" "),
+                status
+            )
+        );
+    }
+    Ok(())
+}
+
+/// [`add`] 的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Added {
+    /// 已添加到暂存区
+    Staged,
+    /// 文件不在 git 工作区中
+    NotInRepository,
+    /// 文件被 `.gitignore` 忽略，没有添加
+    Ignored,
+}
+
+/// 将文件添加到其所在 git 仓库的暂存区
+///
+/// 文件不在工作区中或被忽略时不执行 `git add`；无法执行 git 时返回错误。
+pub fn add(path: &Path) -> Result<Added> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let git = |args: &[&std::ffi::OsStr]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .context(tr!(
+                "无法执行 git，请确认已安装 git 并在 PATH 中",
+                "failed to run git, make sure git is installed and on PATH"
+            ))
+    };
+
+    let inside = git(&["rev-parse".as_ref(), "--is-inside-work-tree".as_ref()])?;
+    if !inside.status.success() || inside.stdout.trim_ascii() != b"true" {
+        return Ok(Added::NotInRepository);
+    }
+    if git(&["check-ignore".as_ref(), "-q".as_ref(), path.as_os_str()])?
+        .status
+        .success()
+    {
+        return Ok(Added::Ignored);
+    }
+    let output = git(&["add".as_ref(), "--".as_ref(), path.as_os_str()])?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{}",
+            tr!(
+                "git add 执行失败: {}",
+                "git add failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+        );
+    }
+    Ok(Added::Staged)
+}
+
+/// `git config user.name` 的值，没有设置或无法执行 git 时返回 None
+pub fn user_name(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "user.name"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// 从仓库地址推断仓库名，例如 `https://github.com/user/templates.git` -> `templates`
+pub fn repo_name_from_url(url: &str) -> Option<String> {
+    let trimmed = url.trim_end_matches('/');
+    let last = trimmed.rsplit(['/', ':']).next()?;
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_name_from_url() {
+        assert_eq!(
+            repo_name_from_url("https://github.com/user/templates.git").as_deref(),
+            Some("templates")
+        );
+        assert_eq!(
+            repo_name_from_url("https://github.com/user/templates/").as_deref(),
+            Some("templates")
+        );
+        assert_eq!(
+            repo_name_from_url("git@github.com:user/web-templates.git").as_deref(),
+            Some("web-templates")
+        );
+        assert_eq!(repo_name_from_url("https://example.com/.git"), None);
+    }
+
+    #[test]
+    fn test_add() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("main.rs");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(add(&file).unwrap(), Added::NotInRepository);
+
+        if run(dir.path(), &["init", "-q"]).is_err() {
+            return; // 没有安装 git
+        }
+        std::fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "").unwrap();
+        assert_eq!(add(&dir.path().join("debug.log")).unwrap(), Added::Ignored);
+        assert_eq!(add(&file).unwrap(), Added::Staged);
+
+        let staged = Command::new("git")
+            .args(["diff", "--cached", "--name-only"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&staged.stdout).trim(), "main.rs");
+    }
+}
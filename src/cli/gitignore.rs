@@ -0,0 +1,77 @@
+//! `gitignore` 子命令：生成或补充当前目录的 `.gitignore`
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use new_cli::gitignore;
+use new_cli::remote::FetchOptions;
+use new_cli::tr;
+
+/// 将 `profiles` 中的规则合并到 `dir/.gitignore`，`stdout` 为 true 时只输出要追加的内容
+pub fn run(dir: &Path, profiles: &[String], list: bool, stdout: bool) -> Result<()> {
+    if list {
+        for name in gitignore::bundled_profiles() {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+
+    let options = FetchOptions::default();
+    let profiles = profiles
+        .iter()
+        .map(|name| Ok((name.clone(), gitignore::profile(name, &options)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let path = dir.join(".gitignore");
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| tr!("无法读取 {:?}", "failed to read {:?}", path)),
+    };
+    let mut addition = gitignore::merge(&existing, &profiles);
+    if stdout {
+        print!("{}", addition);
+        return Ok(());
+    }
+    if addition.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "{} 中已包含所有规则",
+                "{} already contains all the rules",
+                path.display()
+            )
+        );
+        return Ok(());
+    }
+
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        addition.insert(0, '\n');
+    }
+    fs::write(&path, existing.clone() + &addition)
+        .with_context(|| tr!("无法写入 {:?}", "failed to write {:?}", path))?;
+    let added = addition
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .count();
+    println!(
+        "{}",
+        if existing.is_empty() {
+            tr!(
+                "已创建 {}（{} 条规则）",
+                "Created {} ({} rules)",
+                path.display(),
+                added
+            )
+        } else {
+            tr!(
+                "已向 {} 添加 {} 条规则",
+                "Updated {}: added {} rules",
+                path.display(),
+                added
+            )
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! 终端语法高亮（`highlight` feature）
+
+/// 按文件后缀为文本加上终端颜色；无法识别的后缀原样返回
+#[cfg(feature = "highlight")]
+pub fn highlight(text: &str, extension: &str) -> String {
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::ThemeSet;
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let Some(syntax) = syntaxes.find_syntax_by_extension(extension) else {
+        return text.to_string();
+    };
+    let themes = ThemeSet::load_defaults();
+    let mut highlighter = HighlightLines::new(syntax, &themes.themes["base16-ocean.dark"]);
+
+    let mut output = String::with_capacity(text.len() * 2);
+    for line in LinesWithEndings::from(text) {
+        match highlighter.highlight_line(line, &syntaxes) {
+            Ok(ranges) => output.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => return text.to_string(),
+        }
+    }
+    // 恢复终端默认颜色
+    output.push_str("\x1b[0m");
+    output
+}
+
+/// 未启用 `highlight` feature 时不做任何处理
+#[cfg(not(feature = "highlight"))]
+pub fn highlight(text: &str, _extension: &str) -> String {
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_unknown_extension_is_unchanged() {
+        assert_eq!(highlight("plain text\n", "no-such-ext"), "plain text\n");
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_highlight_adds_colors() {
+        let colored = highlight("fn main() {}\n", "rs");
+        assert!(colored.contains("\x1b["));
+        assert!(colored.contains("main"));
+    }
+}
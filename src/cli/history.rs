@@ -0,0 +1,138 @@
+//! `history` 与 `undo` 子命令：查看和撤销创建记录
+
+use anyhow::Result;
+use chrono::Local;
+
+use crate::cli::prompt::confirm;
+use new_cli::history::{self, Action, Filter, Reverted};
+use new_cli::tr;
+
+/// 按时间顺序列出满足条件的创建记录
+pub fn run(today: bool, template: Option<String>) -> Result<()> {
+    let filter = Filter {
+        date: today.then(|| Local::now().date_naive()),
+        template,
+    };
+    let path = history::history_path()?;
+    let entries: Vec<_> = history::load(&path)?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+
+    if entries.is_empty() {
+        println!("{}", tr!("没有创建记录", "No history entries"));
+        return Ok(());
+    }
+
+    for entry in &entries {
+        let template = entry
+            .template
+            .as_ref()
+            .and_then(|t| t.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "-".to_string());
+        let action = match (entry.action, entry.undone) {
+            (_, true) => tr!("已撤销", "undone"),
+            (Action::Created, false) => tr!("创建", "created"),
+            (Action::Inserted, false) => tr!("插入", "inserted"),
+        };
+        println!(
+            "{}  {:<8}  {:<20}  {}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            action,
+            template,
+            entry.path.display()
+        );
+        if !entry.variables.is_empty() {
+            let variables: Vec<String> = entry
+                .variables
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            println!("    {}", variables.join(" "));
+        }
+    }
+    println!(
+        "{}",
+        tr!(
+            "共 {} 条记录，位于 {:?}",
+            "{} entries in {:?}",
+            entries.len(),
+            path
+        )
+    );
+    Ok(())
+}
+
+/// `undo`：撤销最近 `steps` 次新建文件，并在记录中标记为已撤销
+pub fn run_undo(steps: usize, yes: bool) -> Result<()> {
+    let path = history::history_path()?;
+    let mut entries = history::load(&path)?;
+    let indices: Vec<usize> = (0..entries.len())
+        .rev()
+        .filter(|&i| entries[i].can_undo())
+        .take(steps)
+        .collect();
+
+    if indices.is_empty() {
+        println!("{}", tr!("没有可以撤销的创建记录", "Nothing to undo"));
+        return Ok(());
+    }
+    if indices.len() < steps {
+        println!(
+            "{}",
+            tr!(
+                "只有 {} 条可以撤销的记录",
+                "Only {} entries can be undone",
+                indices.len()
+            )
+        );
+    }
+
+    for &i in &indices {
+        let entry = &entries[i];
+        match entry.backup.as_ref().filter(|backup| backup.is_file()) {
+            Some(backup) => println!(
+                "{}",
+                tr!(
+                    "  用备份 {} 恢复 {}",
+                    "  restore {1} from backup {0}",
+                    backup.display(),
+                    entry.path.display()
+                )
+            ),
+            None => println!("{}", tr!("  删除 {}", "  remove {}", entry.path.display())),
+        }
+    }
+    if !yes && !confirm(&tr!("确定要撤销吗？", "Undo these changes?"))? {
+        println!("{}", tr!("已取消", "Cancelled"));
+        return Ok(());
+    }
+
+    for &i in &indices {
+        let reverted = match history::revert(&entries[i]) {
+            Ok(reverted) => reverted,
+            Err(e) => {
+                // 保留已经完成的撤销
+                history::save(&path, &entries)?;
+                return Err(e.into());
+            }
+        };
+        entries[i].undone = true;
+        let file = entries[i].path.display();
+        match reverted {
+            Reverted::Removed => println!("{}", tr!("已删除: {}", "Removed: {}", file)),
+            Reverted::Restored => println!("{}", tr!("已恢复: {}", "Restored: {}", file)),
+            Reverted::Missing => println!(
+                "{}",
+                tr!(
+                    "文件 {} 已不存在，跳过",
+                    "File {} no longer exists, skipped",
+                    file
+                )
+            ),
+        }
+    }
+    history::save(&path, &entries)?;
+    Ok(())
+}
@@ -0,0 +1,724 @@
+//! 命令行帮助信息的本地化
+//!
+//! 帮助文本直接写在参数的文档注释中（中文），英文界面下按下表逐条替换。
+
+use clap::builder::StyledStr;
+use clap::Command;
+
+use new_cli::config::Config;
+use new_cli::i18n::{self, Lang};
+use new_cli::tr;
+use std::ffi::OsStr;
+
+/// 帮助文本对照表：(中文, 英文)
+const HELP: &[(&str, &str)] = &[
+    (
+        "界面语言: zh 或 en (默认: 根据 LANG 等环境变量确定)",
+        "Interface language: zh or en (default: from LANG and related variables)",
+    ),
+    (
+        "不输出提示信息，只输出警告和错误",
+        "Do not print progress messages, only warnings and errors",
+    ),
+    (
+        "输出调试信息，例如模板查找过程、目标路径和编辑器命令；-vv 输出更多细节",
+        "Print debug output such as template lookup, target paths and editor commands; -vv for more detail",
+    ),
+    (
+        "要创建的文件：`[文件名] [后缀]`，或一个或多个 `name.ext` (默认: index html)",
+        "Files to create: `[name] [ext]`, or one or more `name.ext` (default: index html)",
+    ),
+    (
+        "为所有文件指定后缀，此时每个位置参数都是一个文件名",
+        "Extension for all files; every positional argument is then a file name",
+    ),
+    (
+        "定义模板变量，可重复使用 (例如: -D title=\"Q3 Review\")",
+        "Define a template variable, may be repeated (e.g. -D title=\"Q3 Review\")",
+    ),
+    (
+        "从 JSON、TOML 或 YAML 文件读取模板变量，可重复使用，-D 的优先级更高",
+        "Read template variables from a JSON, TOML or YAML file, may be repeated; -D takes precedence",
+    ),
+    (
+        "模板中出现未定义的变量时报错，而不是替换为空",
+        "Fail on undefined template variables instead of replacing them with nothing",
+    ),
+    (
+        "允许模板通过 {{env.NAME}} 读取所有环境变量，而不只是配置中 allow_env 列出的变量",
+        "Let templates read any environment variable via {{env.NAME}}, not just those listed in allow_env",
+    ),
+    (
+        "本次打开文件使用的命令，优先于配置的编辑器；可带参数，{file}、{line}、{column} 会被替换为文件和光标位置 (例如: --open-with \"code -r\" 或 --open-with \"idea --line {line} {file}\")",
+        "Command used to open files this time, overriding the configured editor; may include arguments, and {file}, {line} and {column} are replaced with the file and cursor position (e.g. --open-with \"code -r\" or --open-with \"idea --line {line} {file}\")",
+    ),
+    ("使用剪贴板中的文本作为模板内容", "Use the text in the clipboard as the template"),
+    (
+        "使用从标准输入读取的内容作为模板内容 (例如: curl ... | new-cli page html --from-stdin)",
+        "Use the content read from stdin as the template (e.g. curl ... | new-cli page html --from-stdin)",
+    ),
+    (
+        "在标准错误中输出查找模板的过程：检查过的每个候选模板及其结果",
+        "Print how the template was resolved to stderr: every candidate checked and the outcome",
+    ),
+    (
+        "不经确认直接使用 default.<后缀> 或配置的后备模板；只在配置了 confirm_fallback = true 时有效，默认本来就不需要确认",
+        "Use default.<ext> or the configured fallback template without asking; only has an effect with confirm_fallback = true, since no confirmation is asked by default",
+    ),
+    (
+        "不查找模板，直接创建空文件 (不会用到其他同后缀的模板)",
+        "Skip template lookup and create an empty file (never falls back to another template)",
+    ),
+    (
+        "将渲染结果复制到剪贴板；与 --stdout 一起使用时不创建文件",
+        "Copy the rendered content to the clipboard; with --stdout no file is created",
+    ),
+    (
+        "如果文件在 git 工作区中，创建后将其添加到暂存区",
+        "Stage the new file in git if it is inside a work tree",
+    ),
+    (
+        "不自动 git add，即使配置文件中设置了 git_add = true",
+        "Do not stage new files, even if git_add = true is set in the config file",
+    ),
+    (
+        "在 macOS 上使用指定的应用打开文件，相当于 open -a APP (例如: --app \"Visual Studio Code\")",
+        "Open files with this application on macOS, like open -a APP (e.g. --app \"Visual Studio Code\")",
+    ),
+    (
+        "只创建文件，不使用编辑器打开",
+        "Only create files, do not open them in an editor",
+    ),
+    (
+        "打开文件时把光标放在第 N 行，优先于模板中的 {{cursor}}",
+        "Open the file with the cursor on line N, overriding {{cursor}} in the template",
+    ),
+    (
+        "模板中除 {{cursor}} 外另一个标记光标位置的文本 (例如: --cursor-marker '$0')，写入时删除",
+        "Additional text marking the cursor position in the template (e.g. --cursor-marker '$0'), removed when writing",
+    ),
+    (
+        "结果的输出格式：text 或 json (json 时在结束后输出一个 JSON 文档，便于脚本解析)",
+        "Output format: text or json (json prints one JSON document at the end for scripts to parse)",
+    ),
+    (
+        "等待编辑器退出，并以编辑器的退出码结束",
+        "Wait for the editor to exit and use its exit status",
+    ),
+    (
+        "在前台运行编辑器并等待其退出，用于未被识别的终端编辑器 (vim、nano 等会自动识别)",
+        "Run the editor in the foreground and wait for it to exit, for terminal editors that are not detected (vim, nano and others are detected automatically)",
+    ),
+    (
+        "忽略 .editorconfig，按模板原样写入",
+        "Ignore .editorconfig and write the template as is",
+    ),
+    (
+        "统一新建文件的换行符: lf、crlf 或 native (默认: 保留模板中的换行)",
+        "Normalize line endings of new files: lf, crlf or native (default: keep the template's)",
+    ),
+    (
+        "为新建文件加上执行权限 (仅 Unix)",
+        "Make new files executable (Unix only)",
+    ),
+    (
+        "从所有模板中交互式选择要使用的模板",
+        "Pick the template interactively from all templates",
+    ),
+    (
+        "不进行任何交互：不询问变量、不弹出模板选择列表",
+        "Never interact: no variable prompts and no template picker",
+    ),
+    (
+        "CI 模式：不打开编辑器、不进行任何交互、未定义的变量报错，并输出 JSON；设置了环境变量 CI 时自动启用",
+        "CI mode: no editor, no interaction, strict variables and JSON output; enabled automatically when CI is set",
+    ),
+    (
+        "不执行模板和配置文件中定义的钩子命令",
+        "Do not run hook commands defined in templates or the config file",
+    ),
+    (
+        "在指定目录中创建文件 (默认: 当前目录)",
+        "Create files in this directory (default: current directory)",
+    ),
+    (
+        "配合 --dir 使用，目录不存在时自动创建",
+        "With --dir, create the directory if it does not exist",
+    ),
+    ("目标文件已存在时直接覆盖", "Overwrite existing files"),
+    (
+        "目标文件已存在时跳过，不写入任何内容",
+        "Skip existing files without writing anything",
+    ),
+    (
+        "目标文件已存在时先重命名为 <文件名>.bak 再写入",
+        "Rename existing files to <name>.bak before writing",
+    ),
+    (
+        "在文件名后加上目标目录中下一个未使用的序号 (例如: note -> note-001)",
+        "Append the next free sequence number in the target directory to the name (e.g. note -> note-001)",
+    ),
+    (
+        "管理模板目录中的模板",
+        "Manage templates in the template directory",
+    ),
+    ("列出所有可用模板", "List all available templates"),
+    (
+        "查看模板使用统计：最常用、最近使用和从未使用的模板 (需要配置 track_usage = true)",
+        "Show template usage statistics: most used, recently used and never used templates (requires track_usage = true)",
+    ),
+    ("每一项最多列出的模板数", "Maximum number of templates listed in each section"),
+    (
+        "列出模板中使用的所有标签，以及带有各标签的模板数",
+        "List all tags used by templates and how many templates carry each",
+    ),
+    (
+        "只列出带有该标签的模板集，可重复使用，此时需要带有所有标签",
+        "Only list template sets with this tag, may be repeated to require all of them",
+    ),
+    (
+        "交互式选择模板 (--pick 或未找到模板时) 只列出带有该标签的模板，可重复使用",
+        "Only offer templates with this tag in the template picker (--pick, or when no template is found), may be repeated",
+    ),
+    (
+        "只列出名称匹配该模式的模板，例如 'react/*' 或 '*.md'",
+        "Only list templates whose name matches this pattern, e.g. 'react/*' or '*.md'",
+    ),
+    (
+        "把模式和 --tag 作为正则表达式，而不是通配模式",
+        "Treat the pattern and --tag as regular expressions instead of globs",
+    ),
+    (
+        "只列出这些后缀的模板，可重复使用",
+        "Only list templates with these extensions, may be repeated",
+    ),
+    (
+        "只列出带有该标签的模板，可重复使用，此时需要带有所有标签",
+        "Only list templates with this tag, may be repeated to require all of them",
+    ),
+    (
+        "以 JSON 数组输出，便于脚本和其他工具使用",
+        "Print a JSON array for scripts and other tools",
+    ),
+    (
+        "显示每个模板来自哪个模板目录，以及被它遮蔽的同名模板",
+        "Show which template directory each template comes from and which ones it shadows",
+    ),
+    (
+        "检查模板中的语法错误、未声明的变量和编码问题，存在问题时退出码为 1",
+        "Check templates for syntax errors, undeclared variables and encoding problems; exits with 1 on problems",
+    ),
+    (
+        "要检查的模板文件名 (默认: 所有模板)",
+        "Template file names to check (default: all templates)",
+    ),
+    (
+        "按锁文件 templates.lock 检查已安装模板的内容是否被改动，存在不一致时退出码为 1",
+        "Check installed templates against the templates.lock lock file; exits with 1 if any were changed",
+    ),
+    (
+        "只检查这些模板或模板集 (默认: 锁文件中的所有模板)",
+        "Only check these templates or template sets (default: every template in the lock file)",
+    ),
+    (
+        "接受当前内容，重新记录校验和",
+        "Accept the current contents and record their checksums again",
+    ),
+    (
+        "离线模式：不访问网络，远程模板只使用缓存中已下载的副本",
+        "Offline mode: never access the network, serve remote templates from the download cache only",
+    ),
+    (
+        "查看或清理缓存中下载的远程模板",
+        "List or clean the remote templates downloaded to the cache",
+    ),
+    (
+        "列出缓存中的模板及其下载地址",
+        "List cached templates and the URLs they came from",
+    ),
+    ("删除缓存中的所有模板", "Remove every cached template"),
+    (
+        "分离签名文件的路径或地址 (默认: 模板包旁边的 .minisig、.asc 或 .sig)",
+        "Path or URL of the detached signature (default: .minisig, .asc or .sig next to the pack)",
+    ),
+    ("没有签名时拒绝导入", "Refuse to import unsigned packs"),
+    (
+        "管理校验模板包签名使用的受信任公钥 (minisign 或 GPG)",
+        "Manage the trusted keys used to verify template pack signatures (minisign or GPG)",
+    ),
+    (
+        "信任一个公钥：minisign 公钥、minisign .pub 文件或 GPG 公钥文件",
+        "Trust a key: a minisign public key, a minisign .pub file or a GPG key file",
+    ),
+    ("公钥或公钥文件路径", "Public key or path to a key file"),
+    ("列出受信任的公钥", "List trusted keys"),
+    ("删除受信任的公钥", "Remove a trusted key"),
+    ("minisign 密钥 ID 或 GPG 指纹", "minisign key ID or GPG fingerprint"),
+    (
+        "检查该目录中的模板，而不是配置的模板目录 (例如在模板仓库的 CI 中)",
+        "Check the templates in this directory instead of the configured ones (e.g. in CI for a template repository)",
+    ),
+    (
+        "把已有文件转换为模板：将变量值替换为占位符后保存到模板目录",
+        "Turn an existing file into a template by replacing variable values with placeholders",
+    ),
+    ("要转换的文件", "File to turn into a template"),
+    (
+        "要替换为占位符的值，例如 name=Button 会把 Button 替换为 {{name}}，可重复",
+        "Value to replace with a placeholder, e.g. name=Button replaces Button with {{name}}; repeatable",
+    ),
+    (
+        "同时替换值的其他大小写形式 (例如 myButton、my-button、MY_BUTTON)，只在完整的标识符处替换",
+        "Also replace other case forms of the values (e.g. myButton, my-button, MY_BUTTON), only where they form a whole identifier",
+    ),
+    ("将已有文件添加为模板", "Add an existing file as a template"),
+    ("源文件路径", "Path of the source file"),
+    (
+        "模板名，可带命名空间 (例如: web/page；默认: 源文件名，不含后缀)",
+        "Template name, may include a namespace (e.g. web/page; default: source file name without extension)",
+    ),
+    ("覆盖同名模板", "Overwrite a template with the same name"),
+    (
+        "显示创建某个文件时会使用哪个模板，以及查找的完整过程",
+        "Show which template would be used to create a file, and how it was found",
+    ),
+    (
+        "要创建的文件名，可带命名空间 (例如: report.md 或 web/index.html)",
+        "File name to create, optionally with a namespace (e.g. report.md or web/index.html)",
+    ),
+    (
+        "与创建文件时相同，在该命名空间中查找",
+        "Look up templates in this namespace, as when creating files",
+    ),
+    (
+        "用编辑器打开模板，模板不存在时先创建它",
+        "Open a template in the editor, creating it first if it does not exist",
+    ),
+    (
+        "模板文件名，可带命名空间 (例如: web/page.html)",
+        "Template file name, optionally with a namespace (e.g. web/page.html)",
+    ),
+    (
+        "模板不存在时复制该模板的内容作为起点，而不是创建空模板",
+        "When the template does not exist, start from a copy of this template instead of an empty one",
+    ),
+    (
+        "打开模板使用的编辑器，可带参数 (例如: --editor \"code --wait\")",
+        "Editor used to open the template, may include arguments (e.g. --editor \"code --wait\")",
+    ),
+    ("等待编辑器退出", "Wait for the editor to exit"),
+    ("删除模板", "Remove a template"),
+    (
+        "模板文件名 (例如: index.html)",
+        "Template file name (e.g. index.html)",
+    ),
+    ("跳过确认", "Skip confirmation"),
+    (
+        "在 src/ 中创建 .rs 文件后，在父模块 (lib.rs、main.rs 或 mod.rs) 中添加 mod 声明",
+        "After creating a .rs file in src/, add a mod declaration to its parent module (lib.rs, main.rs or mod.rs)",
+    ),
+    (
+        "不添加 mod 声明，即使配置文件中设置了 add_mod = true",
+        "Do not add a mod declaration even if add_mod = true is set in the config",
+    ),
+    ("重命名模板", "Rename a template"),
+    ("原模板文件名", "Current template file name"),
+    ("新模板文件名", "New template file name"),
+    (
+        "从 git 仓库安装一组模板",
+        "Install a set of templates from a git repository",
+    ),
+    (
+        "git 仓库地址，或模板注册表中的名称",
+        "Git repository URL, or a name in the template registry",
+    ),
+    (
+        "安装到模板目录下的子目录名 (默认: 仓库名)",
+        "Subdirectory of the template directory to install into (default: repository name)",
+    ),
+    (
+        "更新通过 git 安装的模板集",
+        "Update template sets installed with git",
+    ),
+    (
+        "模板集名称 (默认: 更新全部)",
+        "Template set name (default: update all)",
+    ),
+    (
+        "以 .tar.gz 或 .zip 模板包导入、导出模板",
+        "Import and export templates as .tar.gz or .zip packs",
+    ),
+    (
+        "将模板目录中的模板导出为模板包",
+        "Export the templates in the template directory as a pack",
+    ),
+    (
+        "模板包文件路径，按后缀决定格式 (.tar.gz、.tgz 或 .zip)",
+        "Pack file path; the format follows the suffix (.tar.gz, .tgz or .zip)",
+    ),
+    (
+        "只导出该命名空间中的模板",
+        "Only export the templates in this namespace",
+    ),
+    (
+        "从模板包文件或 HTTP(S) 地址导入模板",
+        "Import templates from a pack file or HTTP(S) URL",
+    ),
+    ("模板包文件路径或地址", "Pack file path or URL"),
+    (
+        "导入到该命名空间中 (默认: 模板目录根部)",
+        "Import into this namespace (default: the template directory root)",
+    ),
+    ("覆盖已存在的模板", "Overwrite existing templates"),
+    (
+        "把 VS Code 代码片段文件中的片段转换为模板",
+        "Convert the snippets in a VS Code snippets file into templates",
+    ),
+    (
+        "由模板生成 VS Code 代码片段文件 (或 JetBrains Live Templates)",
+        "Generate a VS Code snippets file (or JetBrains live templates) from the templates",
+    ),
+    (
+        "写入该文件 (默认: 输出到标准输出)",
+        "Write to this file (default: standard output)",
+    ),
+    ("代码片段格式", "Snippet format"),
+    (
+        "代码片段文件 (例如: typescript.json 或 *.code-snippets)",
+        "Snippets file (e.g. typescript.json or *.code-snippets)",
+    ),
+    (
+        "模板的后缀 (默认: 根据片段的 scope 或文件名推断语言)",
+        "Extension of the templates (default: inferred from the snippet scope or the file name)",
+    ),
+    (
+        "根据目录模板创建整个项目",
+        "Create a whole project from a directory template",
+    ),
+    (
+        "项目名，即要创建的目录名",
+        "Project name, i.e. the directory to create",
+    ),
+    (
+        "模板目录下的项目模板子目录名",
+        "Project template subdirectory in the template directory",
+    ),
+    (
+        "定义模板变量，可重复使用",
+        "Define a template variable, may be repeated",
+    ),
+    (
+        "在模板注册表中搜索模板集",
+        "Search the template registry for template sets",
+    ),
+    (
+        "关键字，匹配名称、说明和标签 (不区分大小写，默认: 全部)",
+        "Keyword matched against names, descriptions and tags (case-insensitive, default: all)",
+    ),
+    ("查看创建过的文件记录", "Show the history of created files"),
+    ("只显示今天的记录", "Only show today's entries"),
+    (
+        "只显示使用该模板的记录 (例如: index.html 或 index)",
+        "Only show entries that used this template (e.g. index.html or index)",
+    ),
+    (
+        "撤销最近创建的文件：删除文件，或用备份恢复被覆盖的原文件",
+        "Undo recent creations: remove the files, or restore overwritten files from their backups",
+    ),
+    ("撤销最近的几次创建", "Number of recent creations to undo"),
+    (
+        "在当前目录生成许可证文件 LICENSE (例如: new-cli license MIT)",
+        "Create a LICENSE file in the current directory (e.g. new-cli license MIT)",
+    ),
+    (
+        "许可证的 SPDX 标识符 (不区分大小写)",
+        "SPDX identifier of the license (case-insensitive)",
+    ),
+    (
+        "版权所有者 (默认: 配置中的 author 变量，其次是 git 的 user.name)",
+        "Copyright holder (default: the author variable from the config, then git's user.name)",
+    ),
+    ("版权年份 (默认: 今年)", "Copyright year (default: this year)"),
+    ("列出内置的许可证", "List the bundled licenses"),
+    ("输出到标准输出，不写入文件", "Print to stdout instead of writing the file"),
+    ("覆盖已存在的 LICENSE", "Overwrite an existing LICENSE"),
+    (
+        "创建下一条架构决策记录 (例如: new-cli adr \"Use Postgres\")",
+        "Create the next architecture decision record (e.g. new-cli adr \"Use Postgres\")",
+    ),
+    (
+        "决策的标题，多个参数以空格连接",
+        "Title of the decision; multiple arguments are joined with spaces",
+    ),
+    ("记录的状态", "Status of the record"),
+    (
+        "记录所在的目录 (默认: 配置中的 adr_dir，其次是 docs/adr)",
+        "Directory of the records (default: adr_dir from the config, then docs/adr)",
+    ),
+    (
+        "生成或补充当前目录的 .gitignore，可合并多个规则集 (例如: new-cli gitignore rust node)",
+        "Create or extend .gitignore in the current directory from one or more profiles (e.g. new-cli gitignore rust node)",
+    ),
+    (
+        "规则集名称，内置的规则集之外的名称从 gitignore.io 下载",
+        "Profile names; names that are not bundled are downloaded from gitignore.io",
+    ),
+    ("列出内置的规则集", "List the bundled profiles"),
+    (
+        "输出要追加的规则，不写入文件",
+        "Print the rules that would be added instead of writing the file",
+    ),
+    (
+        "检查配置、模板目录、模板和编辑器，并给出修复建议",
+        "Check the config, template directories, templates and editor, and suggest fixes",
+    ),
+    (
+        "监视队列文件（或目录），为每个新增的行创建文件，行的写法与文件名参数相同 (例如: new-cli --dir notes watch tasks.txt)",
+        "Watch a queue file (or directory) and create a file for every new line, written like the file name arguments (e.g. new-cli --dir notes watch tasks.txt)",
+    ),
+    ("队列文件或目录", "Queue file or directory"),
+    ("检查队列的间隔秒数", "Seconds between checks of the queue"),
+    (
+        "同时处理开始监视前已有的条目",
+        "Also process the entries that exist before watching starts",
+    ),
+    (
+        "列出 PATH 中的插件 (名为 new-cli-<名称> 的程序，通过 new-cli <名称> 运行)",
+        "List plugins in PATH (programs named new-cli-<name>, run with new-cli <name>)",
+    ),
+    (
+        "运行插件 new-cli-<名称>，其余参数原样传给插件 (例如: new-cli plugin gen api v2)",
+        "Run the plugin new-cli-<name>, passing the remaining arguments through (e.g. new-cli plugin gen api v2)",
+    ),
+    ("插件名", "Plugin name"),
+    ("传给插件的参数", "Arguments passed to the plugin"),
+    (
+        "按 CSV 或 JSON 清单批量创建文件，每行一个文件，最后输出汇总表 (例如: new-cli --dir src batch entities.csv)",
+        "Create files in bulk from a CSV or JSON spec, one file per row, then print a summary table (e.g. new-cli --dir src batch entities.csv)",
+    ),
+    (
+        "清单文件 (.csv 或 .json)，列 filename、extension、template、dir 之外的列都是模板变量",
+        "Spec file (.csv or .json); columns other than filename, extension, template and dir are template variables",
+    ),
+    (
+        "同时创建的文件数 (默认: CPU 核数)；大于 1 时不询问变量，-j 1 逐个创建",
+        "Number of files to create at once (default: CPU count); above 1 variables are never prompted for, -j 1 creates them one by one",
+    ),
+    (
+        "某一行失败后继续处理其余的行，而不是立即停止",
+        "Keep processing the remaining rows after one fails instead of stopping",
+    ),
+    (
+        "从 GitHub Releases 更新到最新版本：下载当前平台的可执行文件，校验 SHA-256 后替换自身",
+        "Update to the latest version from GitHub Releases: download the executable for this platform, verify its SHA-256 and replace the running one",
+    ),
+    (
+        "只检查是否有新版本，不下载",
+        "Only check whether a new version is available, do not download it",
+    ),
+    (
+        "在全屏终端界面中浏览、预览模板并创建文件",
+        "Browse and preview templates in a full-screen terminal UI and create files",
+    ),
+    ("生成 shell 补全脚本", "Generate shell completion scripts"),
+    (
+        "输出模板内容，用于在使用前检查模板",
+        "Print a template to check it before use",
+    ),
+    (
+        "输出使用当前默认变量渲染后的结果",
+        "Print the template rendered with the current default variables",
+    ),
+    (
+        "渲染时使用的模板变量，可重复使用",
+        "Template variable used for rendering, may be repeated",
+    ),
+    ("不进行语法高亮", "Disable syntax highlighting"),
+    (
+        "从模板目录的子目录（命名空间）中查找模板，或使用 GitHub 仓库中的模板 (例如: --from web 或 --from gh:acme/templates#react/component.tsx@main)",
+        "Look templates up in a subdirectory (namespace) of the template directory, or use a template from a GitHub repository (e.g. --from web or --from gh:acme/templates#react/component.tsx@main)",
+    ),
+    (
+        "使用通过 HTTP(S) 下载的模板 (例如: --from-url https://example.com/t/invoice.md)",
+        "Use a template downloaded over HTTP(S) (e.g. --from-url https://example.com/t/invoice.md)",
+    ),
+    (
+        "校验远程模板 (--from-url 或 --from gh:...) 的 SHA-256，不一致时拒绝使用",
+        "Verify the SHA-256 of a remote template (--from-url or --from gh:...) and refuse it on mismatch",
+    ),
+    (
+        "远程模板 (--from-url 或 --from gh:...) 优先使用缓存中已下载的版本，不再重新下载",
+        "Prefer the cached copy of a remote template (--from-url or --from gh:...) instead of downloading it again",
+    ),
+    (
+        "将渲染结果输出到标准输出，不创建文件也不打开编辑器",
+        "Print the rendered template to stdout instead of creating a file",
+    ),
+    (
+        "写入前先显示渲染结果的前 N 行 (默认 20 行，用 --preview=N 指定)，确认后才创建文件",
+        "Show the first N rendered lines (20 by default, set with --preview=N) and ask before creating the file",
+    ),
+    (
+        "将渲染结果追加到已存在的目标文件末尾",
+        "Append the rendered template to the end of an existing file",
+    ),
+    (
+        "将渲染结果插入已存在的目标文件：行号表示插入到该行之前，其他内容表示插入到第一个包含该标记的行之前",
+        "Insert the rendered template into an existing file: a line number inserts before that line, anything else inserts before the first line containing it",
+    ),
+    (
+        "使用配置文件中定义的模板别名 (例如: --alias rc)",
+        "Use a template alias defined in the config file (e.g. --alias rc)",
+    ),
+    ("目标 shell", "Target shell"),
+];
+
+/// 解析 `--lang` 参数的值
+pub fn parse_lang(value: &str) -> Result<Lang, String> {
+    Lang::parse(value).ok_or_else(|| {
+        tr!(
+            "不支持的语言 '{}'，可选 zh 或 en",
+            "unsupported language '{}', expected zh or en",
+            value
+        )
+    })
+}
+
+/// 确定并设置界面语言：`--lang` > 配置文件 `lang` > locale 环境变量
+///
+/// 需要在解析命令行之前调用，这样帮助信息也能使用对应的语言；`from_args` 是
+/// [`lang_from_args`] 的结果。
+pub fn init_lang(config: &Config, from_args: Option<Lang>) {
+    let lang = from_args.or(config.lang).unwrap_or_else(Lang::from_env);
+    i18n::set_lang(lang);
+}
+
+/// 在解析命令行之前从原始参数中找出 `--lang` 的值
+///
+/// 参数不一定是 UTF-8（例如 `std::env::args_os()`），无效的字节按 U+FFFD 处理，不会 panic。
+pub fn lang_from_args<S: AsRef<OsStr>>(args: impl IntoIterator<Item = S>) -> Option<Lang> {
+    let mut args = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--lang" {
+            return args.next().and_then(|value| Lang::parse(&value));
+        }
+        if let Some(value) = arg.strip_prefix("--lang=") {
+            return Lang::parse(value);
+        }
+    }
+    None
+}
+
+/// 按当前语言翻译命令及其所有子命令、参数的帮助信息
+pub fn localize(cmd: Command) -> Command {
+    match i18n::lang() {
+        Lang::Zh => cmd,
+        Lang::En => translate(cmd),
+    }
+}
+
+fn translate(mut cmd: Command) -> Command {
+    if let Some(about) = cmd.get_about().and_then(lookup) {
+        cmd = cmd.about(about);
+    }
+    if let Some(long_about) = cmd.get_long_about().and_then(lookup) {
+        cmd = cmd.long_about(long_about);
+    }
+
+    cmd = cmd.mut_args(|mut arg| {
+        if let Some(help) = arg.get_help().and_then(lookup) {
+            arg = arg.help(help);
+        }
+        if let Some(long_help) = arg.get_long_help().and_then(lookup) {
+            arg = arg.long_help(long_help);
+        }
+        arg
+    });
+
+    let names: Vec<String> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        cmd = cmd.mut_subcommand(name, translate);
+    }
+    cmd
+}
+
+fn lookup(text: &StyledStr) -> Option<&'static str> {
+    let text = text.to_string();
+    HELP.iter().find(|(zh, _)| *zh == text).map(|(_, en)| *en)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_lang_from_args() {
+        assert_eq!(
+            lang_from_args(strings(&["new-cli", "--lang", "en"])),
+            Some(Lang::En)
+        );
+        assert_eq!(
+            lang_from_args(strings(&["new-cli", "a.md", "--lang=zh"])),
+            Some(Lang::Zh)
+        );
+        assert_eq!(
+            lang_from_args(strings(&["new-cli", "--", "--lang", "en"])),
+            None
+        );
+        assert_eq!(lang_from_args(strings(&["new-cli", "a.md"])), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lang_from_non_utf8_args() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStringExt;
+
+        let args = [
+            OsString::from("new-cli"),
+            OsString::from_vec(b"\xff\xfe.txt".to_vec()),
+            OsString::from("--lang=en"),
+        ];
+        assert_eq!(lang_from_args(args), Some(Lang::En));
+    }
+
+    /// 所有帮助文本都必须有英文翻译
+    #[test]
+    fn test_all_help_translated() {
+        fn collect(cmd: &Command, missing: &mut Vec<String>) {
+            let texts = cmd
+                .get_about()
+                .into_iter()
+                .chain(cmd.get_long_about())
+                .chain(
+                    cmd.get_arguments()
+                        .flat_map(|arg| arg.get_help().into_iter().chain(arg.get_long_help())),
+                );
+            for text in texts {
+                if lookup(text).is_none() && !text.to_string().is_ascii() {
+                    missing.push(text.to_string());
+                }
+            }
+            for sub in cmd.get_subcommands() {
+                collect(sub, missing);
+            }
+        }
+
+        let mut missing = Vec::new();
+        collect(&crate::Cli::command(), &mut missing);
+        assert!(missing.is_empty(), "缺少英文帮助: {:#?}", missing);
+    }
+}
@@ -0,0 +1,87 @@
+//! `license` 子命令：用内置的 SPDX 许可证文本生成 `LICENSE`
+
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use std::fs;
+use std::path::Path;
+
+use crate::cli::exit::{self, ExitCode};
+use crate::cli::git;
+use new_cli::config::Config;
+use new_cli::license;
+use new_cli::tr;
+use new_cli::variables::builtin_variables;
+
+/// `license` 子命令的参数
+pub struct LicenseArgs<'a> {
+    /// 生成 `LICENSE` 的目录
+    pub dir: &'a Path,
+    pub config: &'a Config,
+    pub id: Option<&'a str>,
+    pub author: Option<String>,
+    pub year: Option<String>,
+    pub list: bool,
+    pub stdout: bool,
+    pub force: bool,
+}
+
+pub fn run(args: LicenseArgs) -> Result<()> {
+    if args.list {
+        for id in license::ids() {
+            println!("{}", id);
+        }
+        return Ok(());
+    }
+    // clap 保证未指定 --list 时有许可证标识符
+    let (id, _) = license::find(args.id.unwrap_or_default())?;
+
+    let author = [args.author, args.config.variables.get("author").cloned()]
+        .into_iter()
+        .flatten()
+        .chain(git::user_name(args.dir))
+        .chain(builtin_variables("", "").remove("author"))
+        .find(|author| !author.trim().is_empty())
+        .unwrap_or_else(|| {
+            eprintln!(
+                "{}",
+                tr!(
+                    "警告: 无法确定版权所有者，可以用 --author 指定",
+                    "Warning: could not determine the copyright holder, use --author to set it"
+                )
+            );
+            String::new()
+        });
+    let year = args
+        .year
+        .unwrap_or_else(|| chrono::Local::now().year().to_string());
+    let text = license::generate(id, &year, &author)?;
+    if args.stdout {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let path = args.dir.join("LICENSE");
+    if path.exists() && !args.force {
+        return Err(exit::fail(
+            ExitCode::TargetExists,
+            tr!(
+                "{} 已存在，使用 --force 覆盖",
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            ),
+        ));
+    }
+    fs::write(&path, text).with_context(|| tr!("无法写入 {:?}", "failed to write {:?}", path))?;
+    println!(
+        "{}",
+        tr!(
+            "已创建 {}（{}，{} {}）",
+            "Created {} ({}, {} {})",
+            path.display(),
+            id,
+            year,
+            author
+        )
+    );
+    Ok(())
+}
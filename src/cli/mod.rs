@@ -0,0 +1,25 @@
+//! 命令行专用的模块：子命令实现、交互和编辑器启动
+
+pub mod adr;
+pub mod browse;
+pub mod cache;
+pub mod clipboard;
+pub mod completions;
+pub mod doctor;
+pub mod editor;
+pub mod exit;
+pub mod git;
+pub mod gitignore;
+pub mod highlight;
+pub mod history;
+pub mod i18n;
+pub mod license;
+pub mod output;
+pub mod picker;
+pub mod plugin;
+pub mod prompt;
+pub mod self_update;
+pub mod stats;
+pub mod template;
+pub mod trust;
+pub mod watch;
@@ -0,0 +1,325 @@
+//! 创建文件时的结果输出
+//!
+//! 默认直接输出给人看的提示，`-q` 时不输出提示；`--output json` 时不输出提示，而是在结束后向标准输出
+//! 写入一个 JSON 文档，供脚本和编辑器插件解析：
+//!
+//! ```json
+//! {
+//!   "files": [
+//!     {
+//!       "path": "/work/index.html",
+//!       "status": "created",
+//!       "template": "/home/me/.local/share/new-cli/templates/index.html",
+//!       "bytes": 118,
+//!       "backup": null,
+//!       "editor": "code",
+//!       "error": null
+//!     }
+//!   ],
+//!   "warnings": []
+//! }
+//! ```
+//!
+//! 还没有处理任何文件就失败时（例如别名无效、目标目录不存在、下载模板失败），
+//! 文档中的 `files` 为空，`error` 为错误信息。
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cli::exit::{self, ExitCode};
+use new_cli::tr;
+
+/// 结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// 单个文件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Created,
+    Inserted,
+    Skipped,
+    Failed,
+}
+
+impl Status {
+    /// 汇总表中显示的文字
+    pub fn label(self) -> String {
+        match self {
+            Status::Created => tr!("已创建", "created"),
+            Status::Inserted => tr!("已插入", "inserted"),
+            Status::Skipped => tr!("已跳过", "skipped"),
+            Status::Failed => tr!("失败", "failed"),
+        }
+    }
+}
+
+/// JSON 输出中的一个文件
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub status: Status,
+    /// 使用的模板，创建空文件时为 null
+    pub template: Option<PathBuf>,
+    /// 写入后的文件大小
+    pub bytes: Option<u64>,
+    pub backup: Option<PathBuf>,
+    /// 成功打开该文件的编辑器命令
+    pub editor: Option<String>,
+    pub error: Option<String>,
+}
+
+impl FileReport {
+    pub fn new(path: PathBuf, status: Status) -> FileReport {
+        FileReport {
+            path,
+            status,
+            template: None,
+            bytes: None,
+            backup: None,
+            editor: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Document {
+    files: Vec<FileReport>,
+    warnings: Vec<String>,
+    /// 整个命令失败时的错误信息，正常结束时不输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// 是否已经输出了 JSON 文档，保证标准输出中只有一个文档
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// `--output json` 时，还没有输出 JSON 文档就失败的命令改为输出一个带 `error` 的文档
+pub fn report_errors(
+    format: OutputFormat,
+    run: impl FnOnce() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let result = run();
+    if let Err(e) = &result {
+        if format == OutputFormat::Json && !FINISHED.load(Ordering::SeqCst) {
+            let mut reporter = Reporter::new(format, false);
+            reporter.document.error = Some(format!("{:#}", e));
+            reporter.finish();
+        }
+    }
+    result
+}
+
+/// 缓冲中的一行文本输出
+#[derive(Debug)]
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// 按输出格式输出提示，或收集结果在结束时输出
+#[derive(Debug, Default)]
+pub struct Reporter {
+    format: OutputFormat,
+    quiet: bool,
+    document: Document,
+    /// 失败的文件合并后的退出码
+    exit_code: Option<ExitCode>,
+    /// 不为 None 时文本输出先缓存起来，由 [`Reporter::absorb`] 按顺序输出
+    buffer: Option<Vec<Line>>,
+}
+
+impl Reporter {
+    /// `quiet` 为 true 时不输出普通提示，警告和错误照常输出
+    pub fn new(format: OutputFormat, quiet: bool) -> Reporter {
+        Reporter {
+            format,
+            quiet,
+            document: Document::default(),
+            exit_code: None,
+            buffer: None,
+        }
+    }
+
+    /// 格式相同、但先缓存输出的 Reporter，用于在其他线程中处理文件
+    pub fn buffered(&self) -> Reporter {
+        Reporter {
+            buffer: Some(Vec::new()),
+            ..Reporter::new(self.format, self.quiet)
+        }
+    }
+
+    /// 输出 `other` 缓存的文本，并合并其收集的结果
+    pub fn absorb(&mut self, other: Reporter) {
+        for line in other.buffer.unwrap_or_default() {
+            self.print(line);
+        }
+        self.document.files.extend(other.document.files);
+        self.document.warnings.extend(other.document.warnings);
+        if let Some(code) = other.exit_code {
+            self.exit_code = Some(exit::merge(self.exit_code, code));
+        }
+    }
+
+    fn print(&mut self, line: Line) {
+        match (&mut self.buffer, line) {
+            (Some(buffer), line) => buffer.push(line),
+            (None, Line::Stdout(text)) => println!("{}", text),
+            (None, Line::Stderr(text)) => eprintln!("{}", text),
+        }
+    }
+
+    /// 是否输出 JSON：此时标准输出只用于最终的 JSON 文档
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// 普通提示，JSON 模式和 `-q` 时不输出
+    pub fn info(&mut self, message: impl Display) {
+        if !self.is_json() && !self.quiet {
+            self.print(Line::Stdout(message.to_string()));
+        }
+    }
+
+    /// 警告：文本模式输出到标准错误，JSON 模式收集到 `warnings`
+    pub fn warn(&mut self, message: impl Display) {
+        match self.format {
+            OutputFormat::Text => self.print(Line::Stderr(message.to_string())),
+            OutputFormat::Json => self.document.warnings.push(message.to_string()),
+        }
+    }
+
+    /// 记录一个文件的处理结果
+    pub fn file(&mut self, report: FileReport) {
+        self.document.files.push(report);
+    }
+
+    /// 处理失败的文件：文本模式输出错误，JSON 模式记录为 `failed`
+    pub fn failed(&mut self, path: PathBuf, error: &anyhow::Error) {
+        self.exit_code = Some(exit::merge(self.exit_code, exit::code_of(error)));
+        match self.format {
+            OutputFormat::Text => self.print(Line::Stderr(format!("{:#}", error))),
+            OutputFormat::Json => self.file(FileReport {
+                error: Some(format!("{:#}", error)),
+                ..FileReport::new(path, Status::Failed)
+            }),
+        }
+    }
+
+    /// 有文件失败时的退出码，各文件失败的原因相同时使用该原因的退出码，否则为 1
+    pub fn exit_code(&self) -> Option<ExitCode> {
+        self.exit_code
+    }
+
+    /// 记录打开 `path` 的编辑器
+    pub fn opened(&mut self, path: &Path, editor: &str) {
+        if let Some(report) = self.document.files.iter_mut().find(|f| f.path == path) {
+            report.editor = Some(editor.to_string());
+        }
+    }
+
+    /// 结束输出：JSON 模式下写出收集到的结果
+    pub fn finish(&self) {
+        if self.is_json() {
+            FINISHED.store(true, Ordering::SeqCst);
+            // 只包含路径和字符串，序列化不会失败
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&self.document).unwrap_or_default()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_document() {
+        let mut reporter = Reporter::new(OutputFormat::Json, false);
+        reporter.info("ignored");
+        reporter.warn("no template");
+        reporter.file(FileReport {
+            bytes: Some(3),
+            ..FileReport::new(PathBuf::from("/w/a.md"), Status::Created)
+        });
+        reporter.failed(PathBuf::from("b.md"), &anyhow::anyhow!("exists"));
+        reporter.opened(Path::new("/w/a.md"), "code");
+
+        let value = serde_json::to_value(&reporter.document).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "files": [
+                    {
+                        "path": "/w/a.md",
+                        "status": "created",
+                        "template": null,
+                        "bytes": 3,
+                        "backup": null,
+                        "editor": "code",
+                        "error": null
+                    },
+                    {
+                        "path": "b.md",
+                        "status": "failed",
+                        "template": null,
+                        "bytes": null,
+                        "backup": null,
+                        "editor": null,
+                        "error": "exists"
+                    }
+                ],
+                "warnings": ["no template"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_document() {
+        let mut reporter = Reporter::new(OutputFormat::Json, false);
+        reporter.document.error = Some("bad alias".to_string());
+        assert_eq!(
+            serde_json::to_value(&reporter.document).unwrap(),
+            serde_json::json!({ "files": [], "warnings": [], "error": "bad alias" })
+        );
+    }
+
+    #[test]
+    fn test_absorb_buffered_reporters_in_order() {
+        let reporter = Reporter::new(OutputFormat::Text, false);
+        let mut first = reporter.buffered();
+        let mut second = reporter.buffered();
+        second.warn("second");
+        second.failed(
+            PathBuf::from("b.md"),
+            &new_cli::Error::TargetExists("b.md".to_string()).into(),
+        );
+        first.info("first");
+        assert!(matches!(&first.buffer.as_deref(), Some([Line::Stdout(text)]) if text == "first"));
+
+        let mut collected = reporter.buffered();
+        collected.absorb(first);
+        collected.absorb(second);
+        let texts: Vec<_> = collected
+            .buffer
+            .unwrap()
+            .into_iter()
+            .map(|line| match line {
+                Line::Stdout(text) | Line::Stderr(text) => text,
+            })
+            .collect();
+        assert_eq!(texts[..2], ["first", "second"]);
+        assert_eq!(collected.exit_code, Some(ExitCode::TargetExists));
+    }
+}
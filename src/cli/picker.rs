@@ -0,0 +1,39 @@
+//! 交互式模糊搜索模板选择器
+
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::FuzzySelect;
+use std::io::IsTerminal;
+
+use new_cli::template::TemplateInfo;
+
+/// 标准输入和标准输出都连接到终端时才允许交互
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// 让用户从模板列表中模糊搜索并选择一个模板，按 Esc 取消时返回 None
+pub fn pick_template<'a>(
+    templates: &'a [TemplateInfo],
+    prompt: &str,
+) -> Result<Option<&'a TemplateInfo>> {
+    if templates.is_empty() {
+        return Ok(None);
+    }
+
+    // 带上 front matter 中的说明，搜索时也会匹配说明
+    let items: Vec<String> = templates
+        .iter()
+        .map(|t| match &t.description {
+            Some(description) => format!("{}  {}", t.qualified_name(), description),
+            None => t.qualified_name(),
+        })
+        .collect();
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+
+    Ok(selection.map(|index| &templates[index]))
+}
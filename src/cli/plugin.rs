@@ -0,0 +1,240 @@
+//! 外部插件：像 cargo 和 git 一样，把未知的子命令交给 `PATH` 中名为 `new-cli-<名称>` 的程序
+//!
+//! `new-cli foo --flag` 会运行 `new-cli-foo --flag`。插件名后面紧跟普通参数时按要创建的文件处理
+//! （`new-cli test rs` 创建 `test.rs`），需要传递这样的参数时使用 `new-cli plugin foo a b`。
+//! 插件通过环境变量获得上下文：
+//!
+//! - `NEW_CLI`：new-cli 可执行文件的路径，插件可以用它回调 new-cli
+//! - `NEW_CLI_VERSION`：new-cli 的版本
+//! - `NEW_CLI_CONFIG`：配置文件路径
+//! - `NEW_CLI_TEMPLATE_DIR`：模板目录
+//! - `NEW_CLI_LANG`：界面语言，`zh` 或 `en`
+//! - `NEW_CLI_VARIABLES`：配置中的 `[variables]`，JSON 对象
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::editor::find_program;
+use crate::cli::exit::{self, ExitCode};
+use new_cli::config::{self, Config};
+use new_cli::i18n::{self, Lang};
+use new_cli::tr;
+
+/// 插件程序名的前缀
+pub const PREFIX: &str = "new-cli-";
+
+/// 若 `args[1]` 是一个插件，运行它并返回其退出码
+///
+/// 只有不是内置子命令、形如插件名（字母、数字、`-` 和 `_`，不含 `.`，因此不会与要创建的
+/// 文件名混淆）、后面没有紧跟普通参数（`new-cli test rs` 是 `[文件名] [后缀]` 的写法）
+/// 且在 `PATH` 中找到对应程序时才视为插件，否则返回 `None` 按普通命令处理。
+pub fn dispatch(
+    args: &[OsString],
+    builtin: &clap::Command,
+    config: &Config,
+) -> Result<Option<i32>> {
+    let Some(name) = candidate(args, builtin) else {
+        return Ok(None);
+    };
+    let Some(program) = find_program(&format!("{}{}", PREFIX, name)) else {
+        return Ok(None);
+    };
+    run_program(&program, &args[2..], config).map(Some)
+}
+
+/// 可能是插件的 `args[1]`
+fn candidate<'a>(args: &'a [OsString], builtin: &clap::Command) -> Option<&'a str> {
+    let name = args
+        .get(1)
+        .and_then(|name| name.to_str())
+        .filter(|name| is_plugin_name(name))?;
+    if name == "help" || builtin.find_subcommand(name).is_some() {
+        return None;
+    }
+    let positional = args
+        .get(2)
+        .is_some_and(|next| !next.to_string_lossy().starts_with('-'));
+    (!positional).then_some(name)
+}
+
+/// `new-cli plugin <名称> [参数...]`：运行插件并返回其退出码，找不到插件时报错
+pub fn run(name: &str, args: &[OsString], config: &Config) -> Result<i32> {
+    let program = Some(name)
+        .filter(|name| is_plugin_name(name))
+        .and_then(|name| find_program(&format!("{}{}", PREFIX, name)));
+    let Some(program) = program else {
+        return Err(exit::fail(
+            ExitCode::InvalidInput,
+            tr!(
+                "PATH 中没有插件 {}{}",
+                "plugin {}{} not found in PATH",
+                PREFIX,
+                name
+            ),
+        ));
+    };
+    run_program(&program, args, config)
+}
+
+fn run_program(program: &Path, args: &[OsString], config: &Config) -> Result<i32> {
+    log::debug!("running plugin {}", program.display());
+    let variables: BTreeMap<_, _> = config.variables.iter().collect();
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .env("NEW_CLI_VERSION", env!("CARGO_PKG_VERSION"))
+        .env("NEW_CLI_TEMPLATE_DIR", config.template_dir()?)
+        .env("NEW_CLI_CONFIG", config::config_path()?)
+        .env(
+            "NEW_CLI_LANG",
+            match i18n::lang() {
+                Lang::Zh => "zh",
+                Lang::En => "en",
+            },
+        )
+        .env("NEW_CLI_VARIABLES", serde_json::to_string(&variables)?);
+    if let Ok(exe) = std::env::current_exe() {
+        command.env("NEW_CLI", exe);
+    }
+    let status = command.status().with_context(|| {
+        tr!(
+            "无法运行插件 {}",
+            "failed to run plugin {}",
+            program.display()
+        )
+    })?;
+    // 被信号终止时没有退出码
+    Ok(status.code().unwrap_or(1))
+}
+
+/// 列出 `PATH` 中的插件
+pub fn run_list() -> Result<()> {
+    let paths = std::env::var_os("PATH").unwrap_or_default();
+    let plugins = discover(std::env::split_paths(&paths));
+    if plugins.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "PATH 中没有插件（名为 {}<名称> 的程序）",
+                "No plugins found in PATH (programs named {}<name>)",
+                PREFIX
+            )
+        );
+    }
+    for (name, path) in plugins {
+        println!("{:<16} {}", name, path.display());
+    }
+    #[cfg(feature = "wasm")]
+    for (filter, path) in new_cli::wasm::installed().filters() {
+        println!("{:<16} {}", format!("| {}", filter), path.display());
+    }
+    Ok(())
+}
+
+/// 在 `dirs` 中查找插件，返回插件名和程序路径；同名插件以先出现的目录为准
+fn discover(dirs: impl IntoIterator<Item = PathBuf>) -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = plugin_of(&path) else {
+                continue;
+            };
+            if path.is_file() {
+                plugins.entry(name).or_insert(path);
+            }
+        }
+    }
+    plugins
+}
+
+/// `new-cli-foo`（Windows 上还有 `new-cli-foo.exe` 等）对应的插件名 `foo`
+fn plugin_of(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = if cfg!(windows) {
+        name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+    } else {
+        name
+    };
+    is_plugin_name(name).then(|| name.to_string())
+}
+
+fn is_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_plugin_name() {
+        for name in ["foo", "gen-api", "db_migrate"] {
+            assert!(is_plugin_name(name), "{name}");
+        }
+        for name in ["", "-v", "--lang", "index.html", "src/foo", "Makefile ok"] {
+            assert!(!is_plugin_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_candidate() {
+        let builtin = clap::Command::new("new-cli").subcommand(clap::Command::new("doctor"));
+        let args = |args: &[&str]| -> Vec<OsString> {
+            std::iter::once("new-cli")
+                .chain(args.iter().copied())
+                .map(OsString::from)
+                .collect()
+        };
+        assert_eq!(candidate(&args(&["deploy"]), &builtin), Some("deploy"));
+        assert_eq!(
+            candidate(&args(&["deploy", "--dry-run", "x"]), &builtin),
+            Some("deploy")
+        );
+        // `[文件名] [后缀]` 的写法不交给插件
+        assert_eq!(candidate(&args(&["test", "rs"]), &builtin), None);
+        assert_eq!(candidate(&args(&["doctor"]), &builtin), None);
+        assert_eq!(candidate(&args(&["index.html"]), &builtin), None);
+        assert_eq!(candidate(&args(&[]), &builtin), None);
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let args = vec![OsString::from("new-cli"), OsString::from_vec(vec![0xff])];
+            assert_eq!(candidate(&args, &builtin), None);
+        }
+    }
+
+    #[test]
+    fn test_discover() {
+        let first = tempfile::TempDir::new().unwrap();
+        let second = tempfile::TempDir::new().unwrap();
+        for (dir, name) in [
+            (&first, "new-cli-foo"),
+            (&second, "new-cli-foo"),
+            (&second, "new-cli-bar"),
+            (&second, "new-cli-"),
+            (&second, "other"),
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        std::fs::create_dir(second.path().join("new-cli-dir")).unwrap();
+
+        let plugins = discover([first.path().to_path_buf(), second.path().to_path_buf()]);
+        assert_eq!(
+            plugins.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["bar", "foo"]
+        );
+        assert_eq!(plugins["foo"], first.path().join("new-cli-foo"));
+    }
+}
@@ -0,0 +1,53 @@
+//! 在终端中询问模板变量的值和确认操作
+
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Input;
+use std::io::{self, BufRead, Write};
+
+use new_cli::render::Variables;
+use new_cli::tr;
+use new_cli::VariablePrompt;
+
+/// 依次询问每个变量的值并写入 `vars`
+///
+/// 有默认值的变量直接回车即使用默认值；必需的变量不接受空值。
+pub fn prompt_variables(prompts: &[VariablePrompt], vars: &mut Variables) -> Result<()> {
+    let theme = ColorfulTheme::default();
+    for prompt in prompts {
+        let label = match &prompt.description {
+            Some(description) => format!("{} ({})", prompt.name, description),
+            None => prompt.name.clone(),
+        };
+
+        let mut input = Input::<String>::with_theme(&theme)
+            .with_prompt(label)
+            .allow_empty(!prompt.required);
+        if let Some(default) = &prompt.default {
+            input = input.default(default.clone());
+        }
+        if prompt.required {
+            input = input.validate_with(|value: &String| {
+                if value.trim().is_empty() {
+                    Err(tr!("该变量不能为空", "this variable must not be empty"))
+                } else {
+                    Ok(())
+                }
+            });
+        }
+
+        let value = input.interact_text()?;
+        vars.insert(prompt.name.clone(), value);
+    }
+    Ok(())
+}
+
+/// 在终端询问用户确认，输入 y/yes 视为确认
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,65 @@
+//! `self-update` 子命令：从 GitHub Releases 更新到最新版本
+
+use anyhow::{Context, Result};
+
+use crate::cli::prompt::confirm;
+use new_cli::tr;
+use new_cli::update::{self, CURRENT_VERSION};
+
+/// 检查最新版本，`check` 为 false 时下载、校验并替换当前的可执行文件
+pub fn run(check: bool, yes: bool) -> Result<()> {
+    let release = update::latest_release()?;
+    if !update::is_newer(release.version(), CURRENT_VERSION) {
+        println!(
+            "{}",
+            tr!(
+                "已是最新版本 {}",
+                "Already up to date ({})",
+                CURRENT_VERSION
+            )
+        );
+        return Ok(());
+    }
+    println!(
+        "{}",
+        tr!(
+            "发现新版本 {}（当前版本 {}）",
+            "New version {} is available (current version {})",
+            release.version(),
+            CURRENT_VERSION
+        )
+    );
+    if check {
+        return Ok(());
+    }
+
+    let platform = update::platform();
+    let asset = release.binary_asset(&platform).with_context(|| {
+        tr!(
+            "版本 {} 没有适用于 {} 的可执行文件，请从源码安装",
+            "release {} has no executable for {}, please install from source",
+            release.tag,
+            platform
+        )
+    })?;
+    let exe = std::env::current_exe().context(tr!(
+        "无法确定当前可执行文件的位置",
+        "cannot determine the location of the current executable"
+    ))?;
+    if !yes
+        && !confirm(&tr!(
+            "用 {} 替换 {}？",
+            "Replace {1} with {0}?",
+            asset.name,
+            exe.display()
+        ))?
+    {
+        println!("{}", tr!("已取消", "Cancelled"));
+        return Ok(());
+    }
+
+    let binary = update::download_binary(&release, asset)?;
+    update::replace_executable(&exe, &binary)?;
+    println!("{}", tr!("已更新到 {}", "Updated to {}", release.version()));
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! `stats` 子命令：模板使用统计
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+
+use new_cli::config::Config;
+use new_cli::tr;
+use new_cli::usage::{self, Usage};
+use new_cli::TemplateEngine;
+
+/// 列出最常用、最近使用和从未使用的模板，每一项最多 `limit` 个
+pub fn run(config: &Config, limit: usize) -> Result<()> {
+    if !config.track_usage {
+        println!(
+            "{}",
+            tr!(
+                "未开启使用统计，在配置文件中设置 track_usage = true 后开始记录",
+                "Usage tracking is off, set track_usage = true in the config file to start recording"
+            )
+        );
+    }
+    let events = usage::load(&usage::usage_path()?)?;
+    let Some(since) = events.iter().map(|event| event.timestamp).min() else {
+        if config.track_usage {
+            println!("{}", tr!("还没有使用记录", "No usage recorded yet"));
+        }
+        return Ok(());
+    };
+    let summary = usage::summarize(&events);
+    let mut used: Vec<(&String, &Usage)> = summary.iter().collect();
+    let width = used
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+
+    println!("{}", tr!("最常用的模板:", "Most used templates:"));
+    used.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+    for (name, usage) in used.iter().take(limit) {
+        println!(
+            "  {:<width$}  {}",
+            name,
+            tr!("{} 次", "{} times", usage.count)
+        );
+    }
+
+    println!("{}", tr!("最近使用的模板:", "Recently used templates:"));
+    used.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.last_used));
+    for (name, usage) in used.iter().take(limit) {
+        println!("  {:<width$}  {}", name, format_time(usage.last_used));
+    }
+
+    // 已删除的模板不算在内，只列出仍然存在的模板
+    let engine = TemplateEngine::discover(config)?;
+    let unused: Vec<String> = engine
+        .list_templates()?
+        .iter()
+        .map(|t| t.qualified_name())
+        .filter(|name| !summary.contains_key(name))
+        .collect();
+    if unused.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "{}",
+        tr!(
+            "自 {} 开始统计以来从未使用的模板 (共 {} 个，可以考虑清理):",
+            "Templates never used since tracking began on {} ({} in total, candidates for cleanup):",
+            since.format("%Y-%m-%d"),
+            unused.len()
+        )
+    );
+    for name in unused.iter().take(limit) {
+        println!("  {}", name);
+    }
+    if unused.len() > limit {
+        println!(
+            "{}",
+            tr!(
+                "  ... 以及另外 {} 个 (用 -n 列出更多)",
+                "  ... and {} more (list more with -n)",
+                unused.len() - limit
+            )
+        );
+    }
+    Ok(())
+}
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.format("%Y-%m-%d %H:%M").to_string()
+}
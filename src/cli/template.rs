@@ -0,0 +1,1488 @@
+//! 模板管理子命令 (`new-cli template ...`)
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cli::exit::{self, ExitCode};
+use crate::cli::prompt::confirm;
+use crate::cli::{editor, git, highlight, picker};
+use new_cli::config::Config;
+use new_cli::engine::{write_atomic, Candidate, Step, Verdict};
+use new_cli::layers::LayerSource;
+use new_cli::lint;
+use new_cli::lock::{self, Drift, Lockfile};
+use new_cli::pack::{self, PackFormat};
+use new_cli::registry::{is_registry_name, Registry};
+use new_cli::remote::{self, FetchOptions};
+use new_cli::render::Variables;
+use new_cli::signature::{trust_dir, Signature, TrustStore, GPG_SUFFIXES, MINISIGN_SUFFIX};
+use new_cli::snippets::{self, Exported};
+use new_cli::template::{resolve_existing_template, Filter, Pattern, TemplateInfo};
+use new_cli::templatize::templatize;
+use new_cli::tr;
+use new_cli::validate::{
+    validate_cli_inputs, validate_namespace, validate_qualified_name, validate_template_name,
+};
+use new_cli::vscode;
+use new_cli::{Error, Lookup, TemplateEngine};
+
+/// 将字节数格式化为便于阅读的大小
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+pub fn format_modified(modified: Option<SystemTime>) -> String {
+    match modified {
+        Some(time) => DateTime::<Local>::from(time)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// `template list --json` 输出的一个模板
+#[derive(Serialize)]
+struct ListedTemplate<'a> {
+    name: String,
+    namespace: Option<&'a str>,
+    extension: &'a str,
+    path: &'a Path,
+    size: u64,
+    modified: Option<DateTime<Local>>,
+    description: Option<&'a str>,
+    tags: &'a [String],
+    source: Option<LayerSource>,
+}
+
+/// `template list`：打印 `filter` 筛选出的模板，存在多个模板目录时标注模板来源
+///
+/// `show_where` 为 true 时改为列出每个模板的完整路径和被它遮蔽的同名模板；
+/// `json` 为 true 时输出 JSON 数组。
+pub fn run_list(
+    engine: &TemplateEngine,
+    filter: &Filter,
+    show_where: bool,
+    json: bool,
+) -> Result<()> {
+    let all = engine.list_templates()?;
+    let total = all.len();
+    let templates: Vec<TemplateInfo> = all.into_iter().filter(|t| filter.matches(t)).collect();
+
+    if json {
+        let listed: Vec<ListedTemplate> = templates
+            .iter()
+            .map(|t| ListedTemplate {
+                name: t.qualified_name(),
+                namespace: t.namespace.as_deref(),
+                extension: &t.extension,
+                path: &t.path,
+                size: t.size,
+                modified: t.modified.map(DateTime::<Local>::from),
+                description: t.description.as_deref(),
+                tags: &t.tags,
+                source: engine.layer_of(&t.path).map(|layer| layer.source),
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+    if templates.is_empty() && total > 0 {
+        println!(
+            "{}",
+            tr!(
+                "没有匹配的模板（共 {} 个模板）",
+                "No matching templates ({} templates in total)",
+                total
+            )
+        );
+        return Ok(());
+    }
+    if templates.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "模板目录 {:?} 中没有任何模板",
+                "No templates in template directory {:?}",
+                engine.template_dir()
+            )
+        );
+        return Ok(());
+    }
+
+    let layered = engine.layers().len() > 1;
+    let name_width = templates
+        .iter()
+        .map(|t| t.qualified_name().chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+
+    if show_where {
+        print_where(engine, &templates, name_width);
+    } else {
+        print_table(engine, &templates, name_width, layered);
+    }
+    if !layered {
+        println!(
+            "{}",
+            tr!(
+                "共 {} 个模板，位于 {:?}",
+                "{} templates in {:?}",
+                templates.len(),
+                engine.template_dir()
+            )
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        tr!(
+            "共 {} 个模板，同名模板以排在前面的模板目录为准",
+            "{} templates, templates in earlier directories take precedence",
+            templates.len()
+        )
+    );
+    for (index, layer) in engine.layers().iter().enumerate() {
+        println!(
+            "{}",
+            tr!(
+                "  {}. {}模板目录: {:?}",
+                "  {}. {} template directory: {:?}",
+                index + 1,
+                layer.source.label(),
+                layer.dir
+            )
+        );
+    }
+
+    Ok(())
+}
+
+/// `template tags`：模板中使用的所有标签及带有各标签的模板数，按标签排序
+pub fn run_tags(engine: &TemplateEngine) -> Result<()> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let templates = engine.list_templates()?;
+    for template in &templates {
+        for tag in &template.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    if counts.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "模板中没有任何标签，可在 front matter 中用 tags = [...] 添加",
+                "No template has tags, add them with tags = [...] in the front matter"
+            )
+        );
+        return Ok(());
+    }
+    let width = counts
+        .keys()
+        .map(|tag| tag.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+    println!(
+        "{:<width$}  {}",
+        tr!("标签", "TAG"),
+        tr!("模板数", "TEMPLATES")
+    );
+    for (tag, count) in &counts {
+        println!("{:<width$}  {}", tag, count);
+    }
+    println!(
+        "{}",
+        tr!(
+            "使用 `new-cli template list --tag <标签>` 查看带有某个标签的模板",
+            "Run `new-cli template list --tag <tag>` to see the templates with a tag"
+        )
+    );
+    Ok(())
+}
+
+fn print_table(
+    engine: &TemplateEngine,
+    templates: &[TemplateInfo],
+    name_width: usize,
+    layered: bool,
+) {
+    if layered {
+        println!(
+            "{:<name_width$}  {:<8}  {:>10}  {:<16}  {}",
+            tr!("模板", "TEMPLATE"),
+            tr!("后缀", "EXT"),
+            tr!("大小", "SIZE"),
+            tr!("修改时间", "MODIFIED"),
+            tr!("来源", "SOURCE")
+        );
+    } else {
+        println!(
+            "{:<name_width$}  {:<8}  {:>10}  {}",
+            tr!("模板", "TEMPLATE"),
+            tr!("后缀", "EXT"),
+            tr!("大小", "SIZE"),
+            tr!("修改时间", "MODIFIED")
+        );
+    }
+    for template in templates {
+        let line = format!(
+            "{:<name_width$}  {:<8}  {:>10}  {}",
+            template.qualified_name(),
+            template.extension,
+            format_size(template.size),
+            format_modified(template.modified),
+        );
+        match engine.layer_of(&template.path) {
+            Some(layer) if layered => println!("{}  {}", line, layer.source.label()),
+            _ => println!("{}", line),
+        }
+    }
+}
+
+/// `template list --where`：模板的来源和路径，被遮蔽的同名模板列在下面
+fn print_where(engine: &TemplateEngine, templates: &[TemplateInfo], name_width: usize) {
+    println!(
+        "{:<name_width$}  {:<8}  {}",
+        tr!("模板", "TEMPLATE"),
+        tr!("来源", "SOURCE"),
+        tr!("路径", "PATH")
+    );
+    for template in templates {
+        let name = template.qualified_name();
+        let layers = engine.layers_with(&name);
+        let Some((used, shadowed)) = layers.split_first() else {
+            continue;
+        };
+        println!(
+            "{:<name_width$}  {:<8}  {}",
+            name,
+            used.source.label(),
+            used.dir.join(&name).display()
+        );
+        for layer in shadowed {
+            println!(
+                "{:<name_width$}  {:<8}  {}",
+                "",
+                layer.source.label(),
+                tr!(
+                    "{} (被遮蔽)",
+                    "{} (shadowed)",
+                    layer.dir.join(&name).display()
+                )
+            );
+        }
+    }
+}
+
+/// 输出模板查找的过程（`template which` 和 `--explain`）：每个候选模板及其结果，最后是查找结果
+pub fn write_explanation(
+    out: &mut dyn Write,
+    label: &str,
+    lookup: &Lookup,
+    candidates: &[Candidate],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        tr!("查找模板 {}:", "Resolving template {}:", label)
+    )?;
+    for candidate in candidates {
+        let step = match candidate.step {
+            Step::Exact => tr!("同名", "exact"),
+            Step::Nested => tr!("子目录", "nested"),
+            Step::Default => tr!("默认", "default"),
+            Step::Fallback => tr!("后备", "fallback"),
+        };
+        let verdict = match &candidate.verdict {
+            Verdict::Used => tr!("使用", "used"),
+            Verdict::Shadowed => tr!("被遮蔽", "shadowed"),
+            Verdict::Missing => tr!("不存在", "missing"),
+            Verdict::WrongExtension => tr!("后缀不同", "wrong extension"),
+            Verdict::NoDirectory => tr!("目录不存在", "no directory"),
+            Verdict::Unresolvable => tr!("无法解析", "unresolvable"),
+            Verdict::OutsideDirectory(_) => tr!("在模板目录外", "outside directory"),
+            Verdict::InvalidNamespace => tr!("无效的命名空间", "invalid namespace"),
+            Verdict::NotConfigured => tr!("未配置", "not configured"),
+        };
+        let mut path = candidate.path.display().to_string();
+        if let Verdict::OutsideDirectory(target) = &candidate.verdict {
+            path = format!("{} -> {}", path, target.display());
+        }
+        let line = format!("  {:<8}  {:<16}  {}", step, verdict, path);
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    let result = match lookup {
+        Lookup::Exact(path) => tr!("{} (同名模板)", "{} (exact match)", path.display()),
+        Lookup::Nested(path) => tr!(
+            "{} (子目录中的同名模板)",
+            "{} (exact match in a subdirectory)",
+            path.display()
+        ),
+        Lookup::Default(path) => tr!("{} (默认模板)", "{} (default template)", path.display()),
+        Lookup::Fallback(path) => tr!("{} (后备模板)", "{} (fallback template)", path.display()),
+        Lookup::Empty => tr!(
+            "没有可用的模板，将创建空文件",
+            "no template, an empty file would be created"
+        ),
+    };
+    writeln!(out, "{}", tr!("结果: {}", "Result: {}", result))
+}
+
+/// `template show`：将模板内容输出到标准输出
+///
+/// 指定 `variables` 时输出渲染后的结果（不含 front matter），否则输出模板原文。
+/// `highlight` 为 true 时按模板后缀进行语法高亮。
+pub fn run_show(
+    engine: &TemplateEngine,
+    name: &str,
+    variables: Option<&Variables>,
+    highlight: bool,
+) -> Result<()> {
+    let path = engine.resolve_template(name)?;
+
+    let bytes = match variables {
+        Some(variables) => engine.render_template(Some(&path), variables, false)?,
+        None => fs::read(&path).with_context(|| {
+            tr!(
+                "无法读取模板文件: {:?}",
+                "failed to read template file: {:?}",
+                path
+            )
+        })?,
+    };
+    let Ok(text) = String::from_utf8(bytes) else {
+        println!(
+            "{}",
+            tr!(
+                "{} 是二进制模板 ({})，不显示内容",
+                "{} is a binary template ({}), not showing its contents",
+                name,
+                format_size(fs::metadata(&path).map(|m| m.len()).unwrap_or(0))
+            )
+        );
+        return Ok(());
+    };
+
+    let extension = path
+        .extension()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let text = if highlight {
+        highlight::highlight(&text, &extension)
+    } else {
+        text
+    };
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// `template lint`：检查模板，打印发现的问题，存在问题时以退出码 1 结束
+///
+/// `dir` 为 Some 时检查该目录中的所有模板；否则检查 `names`，为空时检查所有模板目录中的模板。
+pub fn run_lint(
+    engine: &TemplateEngine,
+    names: &[String],
+    dir: Option<&Path>,
+    defined: &Variables,
+) -> Result<()> {
+    let templates: Vec<(String, PathBuf)> = match dir {
+        Some(dir) => new_cli::template::list_templates(dir)?
+            .into_iter()
+            .map(|t| (t.qualified_name(), t.path))
+            .collect(),
+        None if names.is_empty() => engine
+            .list_templates()?
+            .into_iter()
+            .map(|t| (t.qualified_name(), t.path))
+            .collect(),
+        None => names
+            .iter()
+            .map(|name| Ok((name.clone(), engine.resolve_template(name)?)))
+            .collect::<Result<_>>()?,
+    };
+
+    let mut failed = 0;
+    for (name, path) in &templates {
+        let issues = lint::lint(path, defined)?;
+        if !issues.is_empty() {
+            failed += 1;
+        }
+        for issue in issues {
+            println!("{}: {}", name, issue);
+        }
+    }
+
+    if failed == 0 {
+        println!(
+            "{}",
+            tr!(
+                "检查了 {} 个模板，没有发现问题",
+                "Checked {} templates, no problems found",
+                templates.len()
+            )
+        );
+        return Ok(());
+    }
+    Err(exit::fail(
+        ExitCode::Failure,
+        tr!(
+            "检查了 {} 个模板，其中 {} 个存在问题",
+            "Checked {} templates, {} with problems",
+            templates.len(),
+            failed
+        ),
+    ))
+}
+
+/// `template add`：将已有文件复制到模板目录
+///
+/// 模板名默认取源文件名（不含后缀），后缀始终沿用源文件的后缀。
+/// 若同名模板已存在，除非指定 `force`，否则拒绝覆盖。
+pub fn run_add(template_dir: &Path, source: &Path, name: Option<&str>, force: bool) -> Result<()> {
+    let (target, label) = new_template_path(template_dir, source, name, force)?;
+    let copy_context = || {
+        tr!(
+            "无法复制 {:?} 到模板目录",
+            "failed to copy {:?} into the template directory",
+            source
+        )
+    };
+    let content = fs::read(source).with_context(copy_context)?;
+    write_atomic(&target, &content).with_context(copy_context)?;
+    // 与直接复制一样沿用源文件的权限，例如可执行的脚本模板
+    if let Ok(metadata) = fs::metadata(source) {
+        let _ = fs::set_permissions(&target, metadata.permissions());
+    }
+    repin_if_locked(template_dir, &target)?;
+    println!("{}", tr!("已添加模板: {}", "Added template: {}", label));
+
+    Ok(())
+}
+
+/// `template edit`：用编辑器打开模板；模板不存在时先在全局模板目录中创建它
+///
+/// 新模板默认为空，指定 `based_on` 时复制该模板的原文。`wait` 为 true 时等待编辑器退出。
+/// 只编辑项目和全局模板目录中的模板：`template_paths` 和系统模板目录中的模板通常是共享的
+/// 甚至不可写，在终端中会询问是否先复制到全局模板目录（同名的全局模板优先使用）。
+/// 记录在锁文件中的模板编辑完成后重新记录校验和，否则之后使用它时会因内容变化而被拒绝。
+pub fn run_edit(
+    engine: &TemplateEngine,
+    config: &Config,
+    name: &str,
+    based_on: Option<&str>,
+    editor_flag: Option<&str>,
+    wait: bool,
+) -> Result<()> {
+    validate_qualified_name(name)?;
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    if !file_name.contains('.') || file_name.ends_with('.') {
+        return Err(exit::fail(
+            ExitCode::InvalidInput,
+            tr!(
+                "模板名 {} 需要带后缀，例如 {}.md",
+                "template name {} needs an extension, e.g. {}.md",
+                name,
+                name
+            ),
+        ));
+    }
+
+    let path = match engine.resolve_template(name) {
+        Ok(path) => {
+            if let Some(based_on) = based_on {
+                return Err(exit::fail(
+                    ExitCode::TargetExists,
+                    tr!(
+                        "模板 {} 已存在，不能再基于 {} 创建",
+                        "template {} already exists and cannot be created from {}",
+                        name,
+                        based_on
+                    ),
+                ));
+            }
+            match engine.layer_of(&path).map(|layer| layer.source) {
+                Some(source @ (LayerSource::Extra | LayerSource::System)) => {
+                    match copy_to_global(engine, name, &path, source)? {
+                        Some(copy) => copy,
+                        None => return Ok(()),
+                    }
+                }
+                _ => path,
+            }
+        }
+        Err(Error::TemplateNotFound(_)) => {
+            let content = match based_on {
+                Some(based_on) => {
+                    let source = engine.resolve_template(based_on)?;
+                    fs::read(&source).with_context(|| {
+                        tr!("无法读取模板 {:?}", "failed to read template {:?}", source)
+                    })?
+                }
+                None => Vec::new(),
+            };
+            let path = write_global_template(engine, name, &content)?;
+            println!("{}", tr!("已创建模板: {}", "Created template: {}", name));
+            path
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let editor = editor::resolve_editor(editor_flag, config, &path);
+    let waited = wait || editor::is_terminal_editor(&editor);
+    if waited {
+        let status = editor::open_file_and_wait(&editor, &path, None)?;
+        if wait && !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+    } else {
+        editor::open_file(&editor, &path, None)?;
+    }
+    repin_after_edit(engine, &path, waited)
+}
+
+/// 询问后把共享模板目录中的模板复制到全局模板目录，返回副本的路径；拒绝时返回 None
+fn copy_to_global(
+    engine: &TemplateEngine,
+    name: &str,
+    path: &Path,
+    source: LayerSource,
+) -> Result<Option<PathBuf>> {
+    let message = tr!(
+        "模板 {} 位于{}模板目录 {}，不能直接编辑",
+        "template {} is in the {} template directory {} and cannot be edited in place",
+        name,
+        source.label(),
+        path.display()
+    );
+    if !picker::is_interactive() {
+        return Err(exit::fail(
+            ExitCode::InvalidInput,
+            tr!(
+                "{}；在终端中运行时可以先复制到全局模板目录",
+                "{}; run it in a terminal to copy it into the global template directory first",
+                message
+            ),
+        ));
+    }
+    let question = tr!(
+        "{}，是否复制到全局模板目录后编辑？",
+        "{}. Copy it into the global template directory and edit the copy?",
+        message
+    );
+    if !confirm(&question)? {
+        return Ok(None);
+    }
+    let content = fs::read(path)
+        .with_context(|| tr!("无法读取模板 {:?}", "failed to read template {:?}", path))?;
+    let copy = write_global_template(engine, name, &content)?;
+    println!("{}", tr!("已复制到: {}", "Copied to: {}", copy.display()));
+    Ok(Some(copy))
+}
+
+/// 在全局模板目录中写入模板 `name`，必要时创建所在的命名空间目录
+fn write_global_template(engine: &TemplateEngine, name: &str, content: &[u8]) -> Result<PathBuf> {
+    let path = engine.template_dir().join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            tr!(
+                "无法创建目录 {:?}",
+                "failed to create directory {:?}",
+                parent
+            )
+        })?;
+    }
+    fs::write(&path, content)
+        .with_context(|| tr!("无法写入模板 {:?}", "failed to write template {:?}", path))?;
+    Ok(path)
+}
+
+/// 编辑记录在锁文件中的模板后：等待了编辑器时重新记录校验和，否则提醒需要手动接受修改
+fn repin_after_edit(engine: &TemplateEngine, path: &Path, waited: bool) -> Result<()> {
+    let name = match engine.layer_of(path) {
+        Some(layer) if layer.source == LayerSource::Global => engine.qualified_name(path),
+        _ => None,
+    };
+    let Some(name) = name else {
+        return Ok(());
+    };
+    if !Lockfile::load(&lock::lock_path()?)?
+        .templates
+        .contains_key(&name)
+    {
+        return Ok(());
+    }
+    if waited {
+        update_lockfile(|lockfile| lockfile.pin(engine.template_dir(), &name).map(drop))?;
+        println!(
+            "{}",
+            tr!(
+                "已更新锁文件中 {} 的校验和",
+                "Updated the checksum of {} in the lock file",
+                name
+            )
+        );
+    } else {
+        eprintln!(
+            "{}",
+            tr!(
+                "警告: {} 记录在锁文件中，保存修改后需要运行 new-cli template verify --update {} 接受修改，否则使用时会因内容变化而报错",
+                "warning: {} is recorded in the lock file; after saving, run new-cli template verify --update {} to accept the changes, or using it will fail",
+                name,
+                name
+            )
+        );
+    }
+    Ok(())
+}
+
+/// `template init --from-current`：把 `vars` 中的值（`case_variants` 时包括其大小写形式）替换为
+/// 占位符后，将文件保存为模板
+///
+/// 模板名和覆盖规则与 `template add` 相同。
+pub fn run_init(
+    template_dir: &Path,
+    source: &Path,
+    vars: &[(String, String)],
+    case_variants: bool,
+    name: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    let (target, label) = new_template_path(template_dir, source, name, force)?;
+    let content = fs::read_to_string(source).with_context(|| {
+        tr!(
+            "无法读取 {:?}，只有 UTF-8 文本文件可以转换为模板",
+            "failed to read {:?}, only UTF-8 text files can be turned into templates",
+            source
+        )
+    })?;
+    let result = templatize(&content, vars, case_variants);
+    fs::write(&target, result.content)
+        .with_context(|| tr!("无法写入模板 {:?}", "failed to write template {:?}", target))?;
+    repin_if_locked(template_dir, &target)?;
+    println!(
+        "{}",
+        tr!(
+            "已添加模板: {}（替换了 {} 处）",
+            "Added template: {} ({} replacements)",
+            label,
+            result.replaced
+        )
+    );
+    if result.replaced == 0 && !vars.is_empty() {
+        eprintln!(
+            "{}",
+            tr!(
+                "警告: 文件中没有找到要替换的值",
+                "warning: none of the values were found in the file"
+            )
+        );
+    }
+    Ok(())
+}
+
+/// 新模板在模板目录中的路径和显示名称（`name.ext`），并创建所需的命名空间目录
+fn new_template_path(
+    template_dir: &Path,
+    source: &Path,
+    name: Option<&str>,
+    force: bool,
+) -> Result<(PathBuf, String)> {
+    if !source.is_file() {
+        return Err(exit::fail(
+            ExitCode::InvalidInput,
+            tr!(
+                "源文件 {:?} 不存在或不是文件",
+                "source {:?} does not exist or is not a file",
+                source
+            ),
+        ));
+    }
+
+    let extension = source
+        .extension()
+        .map(|s| s.to_string_lossy().into_owned())
+        .with_context(|| {
+            tr!(
+                "源文件 {:?} 没有后缀，无法作为模板",
+                "source {:?} has no extension and cannot be used as a template",
+                source
+            )
+        })?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => source
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+
+    // `--name web/page` 将模板添加到命名空间 web 中
+    let (namespace, name) = match name.rsplit_once('/') {
+        Some((namespace, name)) => (Some(namespace.to_string()), name.to_string()),
+        None => (None, name),
+    };
+    if let Some(namespace) = &namespace {
+        validate_namespace(namespace)?;
+    }
+    validate_cli_inputs(&name, &extension)?;
+    let name = match namespace {
+        Some(namespace) => format!("{}/{}", namespace, name),
+        None => name,
+    };
+
+    let target = template_path(template_dir, &name, &extension);
+    if target.exists() && !force {
+        return Err(exit::fail(
+            ExitCode::TargetExists,
+            tr!(
+                "模板 {}.{} 已存在，使用 --force 覆盖",
+                "template {}.{} already exists, use --force to overwrite it",
+                name,
+                extension
+            ),
+        ));
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            tr!(
+                "无法创建目录 {:?}",
+                "failed to create directory {:?}",
+                parent
+            )
+        })?;
+    }
+    Ok((target, format!("{}.{}", name, extension)))
+}
+
+/// `template remove`：删除模板目录中的模板
+pub fn run_remove(template_dir: &Path, name: &str, yes: bool) -> Result<()> {
+    let path = resolve_existing_template(template_dir, name)?;
+
+    if !yes && !confirm(&tr!("确定要删除模板 {} 吗？", "Remove template {}?", name))? {
+        println!("{}", tr!("已取消", "Cancelled"));
+        return Ok(());
+    }
+
+    fs::remove_file(&path).with_context(|| {
+        tr!(
+            "无法删除模板: {:?}",
+            "failed to remove template: {:?}",
+            path
+        )
+    })?;
+    if let Some(key) = lock::key(template_dir, &path) {
+        update_lockfile(|lockfile| {
+            lockfile.unpin(&key);
+            Ok(())
+        })?;
+    }
+    println!("{}", tr!("已删除模板: {}", "Removed template: {}", name));
+
+    Ok(())
+}
+
+/// `template rename`：重命名模板目录中的模板
+pub fn run_rename(template_dir: &Path, old: &str, new: &str, yes: bool) -> Result<()> {
+    let old_path = resolve_existing_template(template_dir, old)?;
+
+    validate_qualified_name(new)?;
+    let new_path = template_dir.join(new);
+    if new_path.exists() {
+        return Err(exit::fail(
+            ExitCode::TargetExists,
+            tr!("模板 {} 已存在", "template {} already exists", new),
+        ));
+    }
+
+    if !yes
+        && !confirm(&tr!(
+            "确定要将模板 {} 重命名为 {} 吗？",
+            "Rename template {} to {}?",
+            old,
+            new
+        ))?
+    {
+        println!("{}", tr!("已取消", "Cancelled"));
+        return Ok(());
+    }
+
+    // 重命名到新的命名空间时先创建对应的子目录
+    if let Some(parent) = new_path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            tr!(
+                "无法创建目录 {:?}",
+                "failed to create directory {:?}",
+                parent
+            )
+        })?;
+    }
+    let old_key = lock::key(template_dir, &old_path);
+    fs::rename(&old_path, &new_path).with_context(|| {
+        tr!(
+            "无法重命名模板: {:?} -> {:?}",
+            "failed to rename template: {:?} -> {:?}",
+            old_path,
+            new_path
+        )
+    })?;
+    if let Some(old_key) = old_key {
+        update_lockfile(|lockfile| {
+            lockfile.rename(&old_key, new);
+            Ok(())
+        })?;
+    }
+    println!(
+        "{}",
+        tr!(
+            "已将模板 {} 重命名为 {}",
+            "Renamed template {} to {}",
+            old,
+            new
+        )
+    );
+
+    Ok(())
+}
+
+/// `search`：在模板注册表中按关键字搜索模板集
+pub fn run_search(config: &Config, keyword: &str, tags: &[String]) -> Result<()> {
+    let location = config.registry.as_deref().ok_or(Error::NoRegistry)?;
+    let registry = Registry::load(location)?;
+    let tags: Vec<Pattern> = tags.iter().cloned().map(Pattern::Glob).collect();
+    let found = registry.search(keyword, &tags);
+    if found.is_empty() && keyword.is_empty() {
+        println!("{}", tr!("没有找到匹配的模板集", "No template sets match"));
+        return Ok(());
+    }
+    if found.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "没有找到与 '{}' 匹配的模板集",
+                "No template sets match '{}'",
+                keyword
+            )
+        );
+        return Ok(());
+    }
+
+    let name_width = found
+        .iter()
+        .map(|entry| entry.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+    for entry in &found {
+        println!(
+            "{:<name_width$}  {}",
+            entry.name,
+            entry.description.as_deref().unwrap_or("-")
+        );
+        if !entry.tags.is_empty() {
+            println!("{:<name_width$}  [{}]", "", entry.tags.join(", "));
+        }
+        println!("{:<name_width$}  {}", "", entry.url);
+    }
+    println!(
+        "{}",
+        tr!(
+            "共 {} 个模板集，使用 `new-cli template install <名称>` 安装",
+            "{} template sets, install one with `new-cli template install <name>`",
+            found.len()
+        )
+    );
+    Ok(())
+}
+
+/// `template install`：从 git 仓库浅克隆一组模板到模板目录下的子目录
+///
+/// `url` 不是地址而是一个名称时，从配置的模板注册表中查找对应的仓库地址。
+pub fn run_install(
+    template_dir: &Path,
+    config: &Config,
+    url: &str,
+    name: Option<&str>,
+) -> Result<()> {
+    let (url, name) = if is_registry_name(url) {
+        let location = config.registry.as_deref().ok_or(Error::NoRegistry)?;
+        let entry = Registry::load(location)?.find(url)?.clone();
+        // 注册表中的模板包导入到同名的命名空间中
+        if PackFormat::detect(&entry.url).is_ok() {
+            let options = PackImport {
+                into: Some(name.unwrap_or(url)),
+                force: false,
+                signature: entry.signature.as_deref(),
+                require_signature: false,
+            };
+            return run_pack_import(template_dir, config, &entry.url, &options);
+        }
+        (entry.url, Some(name.unwrap_or(url).to_string()))
+    } else {
+        (url.to_string(), name.map(str::to_string))
+    };
+    let url = url.as_str();
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => git::repo_name_from_url(url).with_context(|| {
+            tr!(
+                "无法从地址 {} 推断模板集名称，请使用 --name 指定",
+                "cannot infer a template set name from {}, use --name",
+                url
+            )
+        })?,
+    };
+    validate_template_name(&name)?;
+    if remote::is_offline() {
+        return Err(Error::Offline(url.to_string()).into());
+    }
+
+    let target = template_dir.join(&name);
+    if target.exists() {
+        return Err(exit::fail(
+            ExitCode::TargetExists,
+            tr!(
+                "模板集 {} 已存在，使用 `new-cli template update {}` 更新",
+                "template set {} already exists, use `new-cli template update {}` to update it",
+                name,
+                name
+            ),
+        ));
+    }
+
+    git::run(
+        template_dir,
+        &["clone", "--depth", "1", "--", url, name.as_str()],
+    )?;
+    update_lockfile(|lockfile| lockfile.pin(template_dir, &name).map(drop))?;
+    println!(
+        "{}",
+        tr!(
+            "已安装模板集 {} 到 {:?}",
+            "Installed template set {} to {:?}",
+            name,
+            target
+        )
+    );
+
+    Ok(())
+}
+
+/// `template update`：拉取已安装模板集的最新内容，未指定名称时更新全部
+pub fn run_update(template_dir: &Path, name: Option<&str>) -> Result<()> {
+    let repos = match name {
+        Some(name) => {
+            validate_template_name(name)?;
+            let repo = template_dir.join(name);
+            if !repo.join(".git").exists() {
+                anyhow::bail!(
+                    "{}",
+                    tr!(
+                        "模板集 {} 不存在或不是通过 git 安装的",
+                        "template set {} does not exist or was not installed with git",
+                        name
+                    )
+                );
+            }
+            vec![repo]
+        }
+        None => installed_repos(template_dir)?,
+    };
+
+    if remote::is_offline() && !repos.is_empty() {
+        return Err(
+            Error::Offline(tr!("模板集的 git 仓库", "the template set repositories")).into(),
+        );
+    }
+    if repos.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "没有通过 git 安装的模板集",
+                "No template sets installed with git"
+            )
+        );
+        return Ok(());
+    }
+
+    for repo in &repos {
+        let repo_name = repo.file_name().unwrap_or_default().to_string_lossy();
+        println!(
+            "{}",
+            tr!(
+                "正在更新模板集 {} ...",
+                "Updating template set {} ...",
+                repo_name
+            )
+        );
+        git::run(repo, &["pull", "--ff-only"])?;
+        // 更新是明确的操作，拉取到的内容成为新的记录
+        update_lockfile(|lockfile| lockfile.pin(template_dir, &repo_name).map(drop))?;
+    }
+    println!(
+        "{}",
+        tr!(
+            "已更新 {} 个模板集",
+            "Updated {} template sets",
+            repos.len()
+        )
+    );
+
+    Ok(())
+}
+
+/// `template pack export`：将模板导出为模板包
+pub fn run_pack_export(template_dir: &Path, file: &Path, namespace: Option<&str>) -> Result<()> {
+    let format = PackFormat::detect(&file.to_string_lossy())?;
+    let count = pack::export(template_dir, namespace, file, format)?;
+    println!(
+        "{}",
+        tr!(
+            "已导出 {} 个模板到 {:?}",
+            "Exported {} templates to {:?}",
+            count,
+            file
+        )
+    );
+    Ok(())
+}
+
+/// `template pack import` 的选项
+pub struct PackImport<'a> {
+    /// 导入到该命名空间中
+    pub into: Option<&'a str>,
+    /// 覆盖已存在的模板
+    pub force: bool,
+    /// 分离签名的地址或路径，默认在模板包旁边查找
+    pub signature: Option<&'a str>,
+    /// 没有签名时拒绝导入
+    pub require_signature: bool,
+}
+
+/// `template pack import`：从本地文件或 HTTP(S) 地址导入模板包
+///
+/// 找到分离签名时必须由受信任的公钥签署；从地址下载的模板包没有签名时，
+/// 指定了 `require_signature` 或配置了 `require_signatures` 则拒绝导入，否则给出警告。
+pub fn run_pack_import(
+    template_dir: &Path,
+    config: &Config,
+    source: &str,
+    options: &PackImport,
+) -> Result<()> {
+    let PackImport { into, force, .. } = *options;
+    let format = PackFormat::detect(source)?;
+    let is_remote = is_url(source);
+    let data = if is_remote {
+        let options = FetchOptions {
+            max_size: pack::MAX_DOWNLOAD_SIZE,
+            ..FetchOptions::default()
+        };
+        remote::download(source, &options)?
+    } else {
+        fs::read(source).with_context(|| {
+            tr!(
+                "无法读取模板包 {}",
+                "failed to read template pack {}",
+                source
+            )
+        })?
+    };
+
+    match find_signature(source, options.signature)? {
+        Some(signature) => {
+            let key = TrustStore::new(trust_dir()?).verify(&data, &signature)?;
+            println!("{}", tr!("签名有效: {}", "Good signature from {}", key));
+        }
+        None if options.require_signature || (is_remote && config.require_signatures) => {
+            return Err(Error::UnsignedPack(source.to_string()).into());
+        }
+        None if is_remote => eprintln!(
+            "{}",
+            tr!(
+                "警告: 模板包 {} 没有签名，无法确认来源",
+                "Warning: template pack {} is not signed, its origin cannot be verified",
+                source
+            )
+        ),
+        None => {}
+    }
+
+    let target = match into {
+        Some(namespace) => {
+            validate_namespace(namespace)?;
+            template_dir.join(namespace)
+        }
+        None => template_dir.to_path_buf(),
+    };
+    let imported = pack::import(&data, format, &target, force, source)?;
+    update_lockfile(|lockfile| {
+        for name in &imported {
+            match into {
+                Some(namespace) => {
+                    lockfile.pin(template_dir, &format!("{}/{}", namespace, name))?
+                }
+                None => lockfile.pin(template_dir, name)?,
+            };
+        }
+        Ok(())
+    })?;
+    for name in &imported {
+        match into {
+            Some(namespace) => println!("  {}/{}", namespace, name),
+            None => println!("  {}", name),
+        }
+    }
+    println!(
+        "{}",
+        tr!("已导入 {} 个模板", "Imported {} templates", imported.len())
+    );
+    Ok(())
+}
+
+/// `template import-vscode`：把 VS Code 代码片段转换为模板
+///
+/// 模板名取片段的触发前缀，后缀依次取 `ext`、片段 `scope` 中的第一种语言或文件名
+/// （`typescript.json`）对应的语言；无法确定后缀或同名模板已存在（未指定 `force`）的片段会被跳过。
+pub fn run_import_vscode(
+    template_dir: &Path,
+    file: &Path,
+    ext: Option<&str>,
+    into: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    if let Some(namespace) = into {
+        validate_namespace(namespace)?;
+    }
+    let file_language = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut imported = 0;
+    for snippet in vscode::load(file)? {
+        let extension = ext.or_else(|| {
+            snippet
+                .scope
+                .iter()
+                .find_map(|language| vscode::extension(language))
+                .or_else(|| vscode::extension(&file_language))
+        });
+        let (Some(extension), Some(name)) = (extension, vscode::template_name(&snippet.prefix))
+        else {
+            eprintln!(
+                "{}",
+                tr!(
+                    "跳过 {:?}：无法确定模板名或后缀，可使用 --ext 指定后缀",
+                    "skipped {:?}: cannot determine the template name or extension, use --ext to set the extension",
+                    snippet.name
+                )
+            );
+            continue;
+        };
+        validate_cli_inputs(&name, extension)?;
+        let name = match into {
+            Some(namespace) => format!("{}/{}", namespace, name),
+            None => name,
+        };
+        let target = template_path(template_dir, &name, extension);
+        if target.exists() && !force {
+            eprintln!(
+                "{}",
+                tr!(
+                    "跳过 {}.{}：模板已存在，使用 --force 覆盖",
+                    "skipped {}.{}: the template already exists, use --force to overwrite it",
+                    name,
+                    extension
+                )
+            );
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                tr!(
+                    "无法创建目录 {:?}",
+                    "failed to create directory {:?}",
+                    parent
+                )
+            })?;
+        }
+        fs::write(&target, vscode::to_template(&snippet))
+            .with_context(|| tr!("无法写入模板 {:?}", "failed to write template {:?}", target))?;
+        repin_if_locked(template_dir, &target)?;
+        println!("  {}.{}", name, extension);
+        imported += 1;
+    }
+    println!(
+        "{}",
+        tr!("已导入 {} 个模板", "Imported {} templates", imported)
+    );
+    Ok(())
+}
+
+/// `template export-vscode` 输出的代码片段格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SnippetFormat {
+    Vscode,
+    Jetbrains,
+}
+
+/// `template export-vscode`：由模板生成编辑器代码片段
+///
+/// 二进制模板和使用了条件、循环的模板无法表示为代码片段，会被跳过并给出提示。
+pub fn run_export_vscode(
+    engine: &TemplateEngine,
+    output: Option<&Path>,
+    namespace: Option<&str>,
+    format: SnippetFormat,
+) -> Result<()> {
+    if let Some(namespace) = namespace {
+        validate_namespace(namespace)?;
+    }
+    let mut snippets = Vec::new();
+    for info in engine.list_templates()? {
+        let in_namespace = match (namespace, info.namespace.as_deref()) {
+            (Some(wanted), Some(actual)) => {
+                actual == wanted || actual.starts_with(&format!("{}/", wanted))
+            }
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        if !in_namespace {
+            continue;
+        }
+        let name = info.qualified_name();
+        let exported = match engine.text_source(&info.path)? {
+            Some((front_matter, body)) => {
+                Exported::new(&name, &info.extension, front_matter.as_ref(), &body)?
+            }
+            None => None,
+        };
+        match exported {
+            Some(snippet) => snippets.push(snippet),
+            None => eprintln!(
+                "{}",
+                tr!(
+                    "跳过 {}：二进制模板或使用了 {{% if %}}/{{% for %}} 的模板无法转换为代码片段",
+                    "skipped {}: binary templates and templates using {{% if %}}/{{% for %}} cannot be turned into snippets",
+                    name
+                )
+            ),
+        }
+    }
+
+    let content = match format {
+        SnippetFormat::Vscode => snippets::to_vscode(&snippets),
+        SnippetFormat::Jetbrains => snippets::to_jetbrains(&snippets, "new-cli"),
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, content)
+                .with_context(|| tr!("无法写入 {:?}", "failed to write {:?}", path))?;
+            println!(
+                "{}",
+                tr!(
+                    "已导出 {} 个代码片段到 {:?}",
+                    "Exported {} snippets to {:?}",
+                    snippets.len(),
+                    path
+                )
+            );
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// `template verify`：按锁文件检查已安装模板的内容，存在不一致时以退出码 1 结束
+///
+/// `update` 为 true 时接受当前内容：重新记录改动过的模板，删除已不存在的模板的记录；
+/// `names` 中的远程模板地址的记录也会被删除，下次下载时重新记录。
+pub fn run_verify(template_dir: &Path, names: &[String], update: bool) -> Result<()> {
+    let path = lock::lock_path()?;
+    let mut lockfile = Lockfile::load(&path)?;
+    let checked = lockfile.names(names).len();
+    let drifts = lockfile.verify_all(template_dir, names)?;
+
+    for drift in &drifts {
+        match drift {
+            Drift::Modified {
+                name,
+                expected,
+                actual,
+            } => println!(
+                "{}",
+                tr!(
+                    "{}: 内容已改变 (期望 {}，实际为 {})",
+                    "{}: modified (expected {}, got {})",
+                    name,
+                    expected,
+                    actual
+                )
+            ),
+            Drift::Missing { name } => {
+                println!("{}", tr!("{}: 已不存在", "{}: missing", name))
+            }
+        }
+    }
+
+    if update {
+        for drift in &drifts {
+            match drift {
+                Drift::Modified { name, .. } => {
+                    lockfile.pin(template_dir, name)?;
+                }
+                Drift::Missing { name } => lockfile.unpin(name),
+            }
+        }
+        for name in names {
+            lockfile.remote.remove(name);
+        }
+        lockfile.save(&path)?;
+        println!(
+            "{}",
+            tr!("已更新锁文件 {:?}", "Updated the lock file {:?}", path)
+        );
+        return Ok(());
+    }
+
+    if drifts.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "检查了 {} 个模板，全部与锁文件一致",
+                "Checked {} templates, all match the lock file",
+                checked
+            )
+        );
+        return Ok(());
+    }
+    Err(exit::fail(
+        ExitCode::Failure,
+        tr!(
+            "检查了 {} 个模板，其中 {} 个与锁文件不一致",
+            "Checked {} templates, {} do not match the lock file",
+            checked,
+            drifts.len()
+        ),
+    ))
+}
+
+/// `--force` 覆盖了记录在锁文件中的模板 `path` 后重新记录校验和，否则之后使用它时会因内容变化而报错
+fn repin_if_locked(template_dir: &Path, path: &Path) -> Result<()> {
+    let Ok(relative) = path.strip_prefix(template_dir) else {
+        return Ok(());
+    };
+    let name = relative.to_string_lossy().replace('\\', "/");
+    if !Lockfile::load(&lock::lock_path()?)?
+        .templates
+        .contains_key(&name)
+    {
+        return Ok(());
+    }
+    update_lockfile(|lockfile| lockfile.pin(template_dir, &name).map(drop))
+}
+
+/// 读取锁文件，调用 `update` 修改后写回
+fn update_lockfile(update: impl FnOnce(&mut Lockfile) -> new_cli::Result<()>) -> Result<()> {
+    let path = lock::lock_path()?;
+    let mut lockfile = Lockfile::load(&path)?;
+    update(&mut lockfile)?;
+    lockfile.save(&path)?;
+    Ok(())
+}
+
+/// 模板包的分离签名：`explicit` 指定的签名，或在模板包旁边依次查找
+/// `.minisig`、`.asc` 和 `.sig`；都不存在时返回 None
+fn find_signature(source: &str, explicit: Option<&str>) -> Result<Option<Signature>> {
+    let candidates: Vec<String> = match explicit {
+        Some(signature) => vec![signature.to_string()],
+        None => std::iter::once(MINISIGN_SUFFIX)
+            .chain(GPG_SUFFIXES.iter().copied())
+            .map(|suffix| format!("{}{}", source, suffix))
+            .collect(),
+    };
+    for candidate in candidates {
+        let bytes = if is_url(&candidate) {
+            match remote::download(&candidate, &FetchOptions::default()) {
+                Ok(bytes) => bytes,
+                Err(e) if explicit.is_none() => {
+                    log::debug!("no signature at {}: {}", candidate, e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        } else if explicit.is_some() || Path::new(&candidate).is_file() {
+            fs::read(&candidate)
+                .with_context(|| tr!("无法读取签名 {}", "failed to read signature {}", candidate))?
+        } else {
+            continue;
+        };
+        log::debug!("verifying signature {}", candidate);
+        return Ok(Some(Signature::from_file_name(&candidate, bytes)?));
+    }
+    Ok(None)
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// 模板目录下所有通过 git 安装的子目录
+fn installed_repos(template_dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(template_dir).with_context(|| {
+        tr!(
+            "无法读取模板目录: {:?}",
+            "failed to read template directory: {:?}",
+            template_dir
+        )
+    })?;
+    let mut repos: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path.join(".git").exists())
+        .collect();
+    repos.sort();
+    Ok(repos)
+}
+
+fn template_path(template_dir: &Path, name: &str, extension: &str) -> PathBuf {
+    template_dir.join(format!("{}.{}", name, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KB");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -0,0 +1,48 @@
+//! `trust` 子命令：管理校验模板包签名使用的受信任公钥
+
+use anyhow::Result;
+
+use new_cli::signature::{trust_dir, TrustStore};
+use new_cli::tr;
+
+/// `trust add`：添加 minisign 公钥或导入 GPG 公钥文件
+pub fn run_add(key: &str) -> Result<()> {
+    let added = TrustStore::new(trust_dir()?).add(key)?;
+    if added.is_empty() {
+        println!(
+            "{}",
+            tr!("公钥已受信任，没有变化", "The key is already trusted")
+        );
+    }
+    for key in added {
+        println!("{}", tr!("已信任公钥: {}", "Trusted key: {}", key));
+    }
+    Ok(())
+}
+
+/// `trust list`：列出所有受信任的公钥
+pub fn run_list() -> Result<()> {
+    let keys = TrustStore::new(trust_dir()?).list()?;
+    if keys.is_empty() {
+        println!(
+            "{}",
+            tr!(
+                "没有受信任的公钥，使用 `new-cli trust add <公钥>` 添加",
+                "No trusted keys, add one with `new-cli trust add <key>`"
+            )
+        );
+    }
+    for key in keys {
+        println!("{}", key);
+    }
+    Ok(())
+}
+
+/// `trust remove`：按密钥 ID 或指纹删除受信任的公钥
+pub fn run_remove(id: &str) -> Result<()> {
+    if !TrustStore::new(trust_dir()?).remove(id)? {
+        anyhow::bail!("{}", tr!("没有受信任的公钥 {}", "no trusted key {}", id));
+    }
+    println!("{}", tr!("已删除公钥: {}", "Removed key: {}", id));
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! `watch` 子命令：监视队列文件或目录，为每个新条目创建文件
+//!
+//! 队列文件中每追加一行就是一个条目；监视目录时，目录中每出现一个新文件，其中的每一行都是一个条目。
+//! 条目的写法与命令行中的文件名参数相同，例如 `standup md` 或 `notes/retro.md`，
+//! 空行和以 `#` 开头的行会被忽略。
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use new_cli::tr;
+
+/// 被监视的队列
+pub struct Queue {
+    path: PathBuf,
+    source: Source,
+}
+
+enum Source {
+    /// 已读到的位置，以及末尾还没有换行的不完整行
+    File { offset: u64, partial: String },
+    /// 已经处理过的文件
+    Dir { seen: HashSet<PathBuf> },
+}
+
+impl Queue {
+    /// 开始监视 `path`；`from_start` 为 false 时只处理之后新增的条目
+    ///
+    /// 队列文件还不存在时视为空文件，之后创建的内容都是新条目。
+    pub fn open(path: &Path, from_start: bool) -> Result<Queue> {
+        let source = if path.is_dir() {
+            let seen = if from_start {
+                HashSet::new()
+            } else {
+                list_files(path)?.into_iter().collect()
+            };
+            Source::Dir { seen }
+        } else {
+            let offset = match fs::metadata(path) {
+                Ok(metadata) if !from_start => metadata.len(),
+                _ => 0,
+            };
+            Source::File {
+                offset,
+                partial: String::new(),
+            }
+        };
+        Ok(Queue {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// 读取上次检查之后新增的行
+    pub fn poll(&mut self) -> Result<Vec<String>> {
+        let context = || tr!("无法读取队列 {:?}", "failed to read queue {:?}", self.path);
+        match &mut self.source {
+            Source::File { offset, partial } => {
+                let mut file = match fs::File::open(&self.path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(e).with_context(context),
+                };
+                let len = file.metadata().with_context(context)?.len();
+                // 文件被截断或替换时从头读取
+                if len < *offset {
+                    *offset = 0;
+                    partial.clear();
+                }
+                file.seek(SeekFrom::Start(*offset)).with_context(context)?;
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes).with_context(context)?;
+                *offset += bytes.len() as u64;
+
+                partial.push_str(&String::from_utf8_lossy(&bytes));
+                let complete = match partial.rfind('\n') {
+                    Some(end) => partial.drain(..=end).collect::<String>(),
+                    None => return Ok(Vec::new()),
+                };
+                Ok(complete.lines().map(str::to_string).collect())
+            }
+            Source::Dir { seen } => {
+                let mut lines = Vec::new();
+                for file in list_files(&self.path)? {
+                    if seen.contains(&file) {
+                        continue;
+                    }
+                    let content = fs::read_to_string(&file).with_context(|| {
+                        tr!(
+                            "无法读取队列条目 {:?}",
+                            "failed to read queue entry {:?}",
+                            file
+                        )
+                    })?;
+                    lines.extend(content.lines().map(str::to_string));
+                    seen.insert(file);
+                }
+                Ok(lines)
+            }
+        }
+    }
+}
+
+/// 目录中的文件（不含隐藏文件，以免读到正在写入的临时文件），按名称排序
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let context = || {
+        tr!(
+            "无法读取队列目录 {:?}",
+            "failed to read queue directory {:?}",
+            dir
+        )
+    };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(context)? {
+        let path = entry.with_context(context)?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 把一行拆分为文件名参数，空行和注释返回 `None`
+pub fn parse_entry(line: &str) -> Result<Option<Vec<String>>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let args = shell_words::split(line).with_context(|| {
+        tr!(
+            "无法解析队列条目: {}",
+            "failed to parse queue entry: {}",
+            line
+        )
+    })?;
+    Ok(Some(args))
+}
+
+/// 每隔 `interval` 检查一次队列，对每个新条目调用 `handle`，直到进程被中断
+pub fn run(
+    path: &Path,
+    interval: Duration,
+    from_start: bool,
+    mut handle: impl FnMut(&str, Vec<String>),
+) -> Result<()> {
+    let mut queue = Queue::open(path, from_start)?;
+    loop {
+        for line in queue.poll()? {
+            match parse_entry(&line) {
+                Ok(Some(args)) => handle(&line, args),
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", tr!("警告: {:#}", "Warning: {:#}", e)),
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_file_queue() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("tasks.txt");
+        fs::write(&path, "old md\n").unwrap();
+
+        let mut queue = Queue::open(&path, false).unwrap();
+        assert!(queue.poll().unwrap().is_empty());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "standup md\nretro").unwrap();
+        assert_eq!(queue.poll().unwrap(), ["standup md"]);
+        // 不完整的行等写完换行后再处理
+        writeln!(file, " md").unwrap();
+        assert_eq!(queue.poll().unwrap(), ["retro md"]);
+
+        // 截断后从头读取
+        fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(queue.poll().unwrap(), ["fresh"]);
+
+        let mut queue = Queue::open(&path, true).unwrap();
+        assert_eq!(queue.poll().unwrap(), ["fresh"]);
+        let mut queue = Queue::open(&dir.path().join("missing.txt"), false).unwrap();
+        assert!(queue.poll().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dir_queue() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("1.txt"), "old\n").unwrap();
+        let mut queue = Queue::open(dir.path(), false).unwrap();
+        assert!(queue.poll().unwrap().is_empty());
+
+        fs::write(dir.path().join("2.txt"), "a md\nb md\n").unwrap();
+        fs::write(dir.path().join(".3.txt.tmp"), "partial").unwrap();
+        assert_eq!(queue.poll().unwrap(), ["a md", "b md"]);
+        assert!(queue.poll().unwrap().is_empty());
+
+        let mut queue = Queue::open(dir.path(), true).unwrap();
+        assert_eq!(queue.poll().unwrap(), ["old", "a md", "b md"]);
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(
+            parse_entry("  'Q3 review' md ").unwrap(),
+            Some(vec!["Q3 review".to_string(), "md".to_string()])
+        );
+        assert_eq!(parse_entry("# comment").unwrap(), None);
+        assert_eq!(parse_entry("   ").unwrap(), None);
+        assert!(parse_entry("'unclosed").is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! 模板组合：引用片段和继承基础模板
+//!
+//! ```text
+//! {% include "partials/license-header.txt" %}
+//! ```
+//!
+//! 把另一个模板的正文插入到标签所在的位置，常用于许可证头、meta 标签等公共片段。
+//!
+//! ```text
+//! {% extends "layouts/base.html" %}
+//! {% block title %}Home{% endblock %}
+//! ```
+//!
+//! 以 `{% extends %}` 开头的模板继承基础模板：基础模板中的 `{% block 名称 %}默认内容{% endblock %}`
+//! 被子模板中的同名块替换，子模板中块之外的内容被忽略。基础模板本身也可以继承其他模板。
+//!
+//! 模板名与 `template show` 等命令中的写法相同（例如 `partials/header.html`），在所有模板层中查找。
+//! 组合在渲染之前完成，被引用的模板与当前模板使用同一组变量；它们的 front matter 不会输出，
+//! 其中声明的变量对当前模板同样生效。循环引用会报错。
+//! 不参与继承的模板中的 `{% block %}` 按原文输出；需要输出字面量
+//! `{% include` 时写作 `\{% include`。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::front_matter::FrontMatter;
+use crate::tr;
+
+/// 被引用的模板
+pub struct Part {
+    pub path: PathBuf,
+    pub front_matter: Option<FrontMatter>,
+    /// 不含 front matter 的正文
+    pub body: String,
+}
+
+/// 展开模板 `root` 的正文 `body` 中的引用和继承
+///
+/// `load` 按模板名读取被引用的模板。返回展开后的正文，以及参与组合的其他模板的
+/// front matter（按引用的先后顺序）。
+pub fn compose(
+    root: &Path,
+    body: &str,
+    load: &mut dyn FnMut(&str) -> Result<Part>,
+) -> Result<(String, Vec<FrontMatter>)> {
+    let mut composer = Composer {
+        load,
+        stack: vec![root.to_path_buf()],
+        inherited: Vec::new(),
+    };
+    let body = composer.expand(body, None)?;
+    Ok((body, composer.inherited))
+}
+
+/// 以下标签会被识别；其他 `{% ... %}` 留给渲染时处理
+enum Tag<'a> {
+    Include(&'a str),
+    Extends(&'a str),
+    Block(&'a str),
+    EndBlock,
+}
+
+/// 标签在文本中的位置
+struct Span<'a> {
+    tag: Tag<'a>,
+    start: usize,
+    end: usize,
+    /// 标签单独占一行时，连同缩进和换行在内的范围
+    line: Option<(usize, usize)>,
+}
+
+impl Span<'_> {
+    /// 替换标签时覆盖的范围
+    fn outer(&self) -> (usize, usize) {
+        self.line.unwrap_or((self.start, self.end))
+    }
+}
+
+struct Composer<'l> {
+    load: &'l mut dyn FnMut(&str) -> Result<Part>,
+    /// 正在展开的模板，用于检测循环引用
+    stack: Vec<PathBuf>,
+    inherited: Vec<FrontMatter>,
+}
+
+impl Composer<'_> {
+    /// 展开 `body`；`blocks` 为 Some 时 `body` 是基础模板，其中的块按 `blocks` 替换
+    fn expand(&mut self, body: &str, blocks: Option<&HashMap<String, String>>) -> Result<String> {
+        let spans = scan(body)?;
+        if let Some(Span {
+            tag: Tag::Extends(base),
+            start,
+            ..
+        }) = spans.first()
+        {
+            if body[..*start].trim().is_empty() {
+                return self.extend(body, &spans, base, blocks);
+            }
+        }
+
+        let mut output = String::with_capacity(body.len());
+        let mut position = 0;
+        let mut i = 0;
+        while i < spans.len() {
+            let span = &spans[i];
+            i += 1;
+            let (mut from, mut to) = span.outer();
+            let content = match &span.tag {
+                Tag::Include(name) => fit_line(self.include(name)?, body, span.line),
+                Tag::Block(name) => {
+                    let Some(blocks) = blocks else {
+                        continue;
+                    };
+                    let close = matching_end(&spans, i - 1)?;
+                    let default = block_content(body, span, &spans[close]);
+                    to = spans[close].outer().1;
+                    // 块中的标签随默认内容一起处理
+                    i = close + 1;
+                    // 开始和结束标签在同一行且这一行没有其他内容时，按单独占一行处理
+                    let line = match (span.line, spans[close].line) {
+                        (None, None) => own_line(body, span.start, spans[close].end),
+                        _ => None,
+                    };
+                    if let Some((start, end)) = line {
+                        (from, to) = (start, end);
+                    }
+                    let content = match blocks.get(*name) {
+                        Some(content) => {
+                            // 替换内容中的同名块不再替换，避免无限展开
+                            let mut rest = blocks.clone();
+                            rest.remove(*name);
+                            self.expand(content, Some(&rest))?
+                        }
+                        None => self.expand(default, Some(blocks))?,
+                    };
+                    match line {
+                        Some(_) => fit_line(content, body, line),
+                        None => content,
+                    }
+                }
+                Tag::Extends(_) => return Err(misplaced("extends")),
+                Tag::EndBlock if blocks.is_some() => return Err(misplaced("endblock")),
+                Tag::EndBlock => continue,
+            };
+            output.push_str(&body[position..from]);
+            output.push_str(&content);
+            position = to;
+        }
+        output.push_str(&body[position..]);
+        Ok(output)
+    }
+
+    /// 展开继承了 `base` 的模板：收集其中的块，再用它们展开基础模板
+    fn extend(
+        &mut self,
+        body: &str,
+        spans: &[Span],
+        base: &str,
+        inherited: Option<&HashMap<String, String>>,
+    ) -> Result<String> {
+        let mut blocks = HashMap::new();
+        let mut i = 1;
+        while i < spans.len() {
+            match &spans[i].tag {
+                Tag::Block(name) => {
+                    let close = matching_end(spans, i)?;
+                    // 块的内容在替换到基础模板中时再展开，其中可以定义更深一层的块
+                    let content = block_content(body, &spans[i], &spans[close]);
+                    blocks.insert(name.to_string(), content.to_string());
+                    i = close + 1;
+                }
+                Tag::Extends(_) => return Err(misplaced("extends")),
+                Tag::EndBlock => return Err(misplaced("endblock")),
+                Tag::Include(_) => i += 1,
+            }
+        }
+        // 更下层的子模板中的块优先
+        if let Some(inherited) = inherited {
+            blocks.extend(inherited.clone());
+        }
+        let part = self.enter(base)?;
+        let result = self.expand(&part.body, Some(&blocks));
+        self.stack.pop();
+        result
+    }
+
+    fn include(&mut self, name: &str) -> Result<String> {
+        let part = self.enter(name)?;
+        let result = self.expand(&part.body, None);
+        self.stack.pop();
+        result
+    }
+
+    /// 读取被引用的模板并压入引用栈
+    fn enter(&mut self, name: &str) -> Result<Part> {
+        let mut part = (self.load)(name)?;
+        if self.stack.contains(&part.path) {
+            let mut chain: Vec<String> = self
+                .stack
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            chain.push(part.path.display().to_string());
+            return Err(Error::IncludeCycle(chain));
+        }
+        self.stack.push(part.path.clone());
+        if let Some(front_matter) = part.front_matter.take() {
+            self.inherited.push(front_matter);
+        }
+        Ok(part)
+    }
+}
+
+/// 块的内容：单独占一行的开始和结束标签所在的行不计入内容
+fn block_content<'b>(body: &'b str, open: &Span, close: &Span) -> &'b str {
+    let from = open.line.map_or(open.end, |(_, end)| end);
+    let to = close.line.map_or(close.start, |(start, _)| start);
+    &body[from..to.max(from)]
+}
+
+/// 与第 `open` 个标签（`{% block %}`）配对的 `{% endblock %}`
+fn matching_end(spans: &[Span], open: usize) -> Result<usize> {
+    let mut depth = 0;
+    for (i, span) in spans.iter().enumerate().skip(open) {
+        match span.tag {
+            Tag::Block(_) => depth += 1,
+            Tag::EndBlock => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(Error::TemplateSyntax(tr!(
+        "{{% block %}} 没有对应的结束标签",
+        "{{% block %}} is never closed"
+    )))
+}
+
+fn misplaced(name: &str) -> Error {
+    Error::TemplateSyntax(tr!("{{% {} %}} 的位置不正确", "misplaced {{% {} %}}", name))
+}
+
+/// 找出文本中的组合标签，跳过转义的 `\{%`
+fn scan(body: &str) -> Result<Vec<Span<'_>>> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = body[from..].find("{%") {
+        let start = from + offset;
+        let Some(len) = body[start + 2..].find("%}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        from = end;
+        if body[..start].ends_with('\\') {
+            from = start + 2;
+            continue;
+        }
+        let Some(tag) = parse_tag(body[start + 2..end - 2].trim())? else {
+            continue;
+        };
+        spans.push(Span {
+            tag,
+            start,
+            end,
+            line: own_line(body, start, end),
+        });
+    }
+    Ok(spans)
+}
+
+/// `body[start..end]` 单独占一行时，返回包括缩进和换行在内的整行范围
+fn own_line(body: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let line_start = body[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = body[end..].find('\n').map_or(body.len(), |i| end + i + 1);
+    let standalone =
+        body[line_start..start].trim().is_empty() && body[end..line_end].trim().is_empty();
+    standalone.then_some((line_start, line_end))
+}
+
+/// 替换单独占一行的标签时保留该行的换行，行内替换时去掉内容末尾的换行
+fn fit_line(mut content: String, body: &str, line: Option<(usize, usize)>) -> String {
+    match line {
+        Some((_, end)) => {
+            if !content.is_empty() && !content.ends_with('\n') && body[..end].ends_with('\n') {
+                content.push('\n');
+            }
+        }
+        None => {
+            if content.ends_with('\n') {
+                content.pop();
+            }
+        }
+    }
+    content
+}
+
+fn parse_tag(inner: &str) -> Result<Option<Tag<'_>>> {
+    let (keyword, argument) = inner
+        .split_once(char::is_whitespace)
+        .map_or((inner, ""), |(k, a)| (k, a.trim()));
+    Ok(Some(match keyword {
+        "include" => Tag::Include(quoted(keyword, argument)?),
+        "extends" => Tag::Extends(quoted(keyword, argument)?),
+        "block" if is_identifier(argument) => Tag::Block(argument),
+        "endblock" if argument.is_empty() || is_identifier(argument) => Tag::EndBlock,
+        _ => return Ok(None),
+    }))
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 解析带引号的模板名
+fn quoted<'a>(keyword: &str, argument: &'a str) -> Result<&'a str> {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| {
+            argument
+                .strip_prefix(quote)?
+                .strip_suffix(quote)
+                .filter(|name| !name.is_empty())
+        })
+        .ok_or_else(|| {
+            Error::TemplateSyntax(tr!(
+                "{{% {} %}} 需要带引号的模板名，例如 {{% {} \"partials/header.html\" %}}",
+                "{{% {} %}} needs a quoted template name, e.g. {{% {} \"partials/header.html\" %}}",
+                keyword,
+                keyword
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose_with(parts: &[(&str, &str)], body: &str) -> Result<String> {
+        let parts: HashMap<String, String> = parts
+            .iter()
+            .map(|(name, body)| (name.to_string(), body.to_string()))
+            .collect();
+        let mut load = |name: &str| match parts.get(name) {
+            Some(body) => Ok(Part {
+                path: PathBuf::from(name),
+                front_matter: None,
+                body: body.clone(),
+            }),
+            None => Err(Error::TemplateNotFound(name.to_string())),
+        };
+        compose(Path::new("root"), body, &mut load).map(|(body, _)| body)
+    }
+
+    #[test]
+    fn test_include() {
+        let parts = [
+            ("header.txt", "// Copyright {{year}}\n"),
+            ("name.txt", "{{name}}\n"),
+        ];
+        assert_eq!(
+            compose_with(&parts, "  {% include \"header.txt\" %}\nfn {{name}}() {}").unwrap(),
+            "// Copyright {{year}}\nfn {{name}}() {}"
+        );
+        assert_eq!(
+            compose_with(
+                &parts,
+                "<b>{% include 'name.txt' %}</b> \\{% include \"x\" %}"
+            )
+            .unwrap(),
+            "<b>{{name}}</b> \\{% include \"x\" %}"
+        );
+        assert!(matches!(
+            compose_with(&parts, "{% include \"missing.txt\" %}"),
+            Err(Error::TemplateNotFound(_))
+        ));
+        assert!(matches!(
+            compose_with(&parts, "{% include header.txt %}"),
+            Err(Error::TemplateSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn test_extends() {
+        let parts = [
+            (
+                "base.html",
+                "<title>{% block title %}Site{% endblock %}</title>\n<body>\n{% block body %}\n<p>empty</p>\n{% endblock %}\n</body>\n",
+            ),
+            (
+                "page.html",
+                "{% extends \"base.html\" %}\n{% block body %}\n<main>{% block main %}{% endblock %}</main>\n{% endblock %}\n",
+            ),
+            ("nav.html", "<nav></nav>\n"),
+        ];
+        let child = "{% extends \"page.html\" %}\nignored\n{% block title %}Home{% endblock %}\n{% block main %}{% include \"nav.html\" %}{{content}}{% endblock %}\n";
+        assert_eq!(
+            compose_with(&parts, child).unwrap(),
+            "<title>Home</title>\n<body>\n<main><nav></nav>{{content}}</main>\n</body>\n"
+        );
+
+        // 不参与继承时 block 按原文输出
+        let jinja = "{% block content %}{{x}}{% endblock %}";
+        assert_eq!(compose_with(&parts, jinja).unwrap(), jinja);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let parts = [
+            ("a.txt", "{% include \"b.txt\" %}"),
+            ("b.txt", "{% include \"a.txt\" %}"),
+            (
+                "twice.txt",
+                "{% include \"leaf.txt\" %}{% include \"leaf.txt\" %}",
+            ),
+            ("leaf.txt", "x"),
+        ];
+        match compose_with(&parts, "{% include \"a.txt\" %}") {
+            Err(Error::IncludeCycle(chain)) => {
+                assert_eq!(chain, ["root", "a.txt", "b.txt", "a.txt"])
+            }
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        // 同一个片段被引用多次不是循环
+        assert_eq!(
+            compose_with(&parts, "{% include \"twice.txt\" %}").unwrap(),
+            "xx"
+        );
+        assert!(matches!(
+            compose_with(
+                &[("self.txt", "{% extends \"self.txt\" %}")],
+                "{% extends \"self.txt\" %}"
+            ),
+            Err(Error::IncludeCycle(_))
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! 配置文件 `config.toml` 及 new-cli 使用的各个目录
+//!
+//! 配置文件是可选的，不存在时所有配置项均使用内置默认值。
+//! 命令行参数的优先级始终高于配置文件。
+//!
+//! 目录遵循各平台的约定：
+//!
+//! | 用途 | Linux | macOS | Windows |
+//! | --- | --- | --- | --- |
+//! | 配置 | `$XDG_CONFIG_HOME/new-cli` | `~/Library/Application Support/new-cli` | `%APPDATA%\new-cli` |
+//! | 数据 | `$XDG_DATA_HOME/new-cli` | `~/Library/Application Support/new-cli` | `%APPDATA%\new-cli` |
+//! | 缓存 | `$XDG_CACHE_HOME/new-cli` | `~/Library/Caches/new-cli` | `%LOCALAPPDATA%\new-cli` |
+//!
+//! 设置环境变量 `NEW_CLI_HOME` 后三者都改为该目录（缓存位于其中的 `cache/`）。
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::eol::Eol;
+use crate::error::{Error, IoContext, Result};
+use crate::hooks::Hooks;
+use crate::i18n::Lang;
+use crate::project_type::ProjectDefaults;
+use crate::tr;
+
+/// 配置文件中可设置的全部选项
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// 未指定文件名时使用的默认文件名
+    pub default_filename: Option<String>,
+
+    /// 未指定后缀时使用的默认后缀
+    pub default_extension: Option<String>,
+
+    /// 打开新文件使用的编辑器
+    pub editor: Option<String>,
+
+    /// 按文件后缀指定编辑器：后缀 -> 编辑器命令，例如 `md = "typora"`，优先于 `editor`
+    pub editors: HashMap<String, String>,
+
+    /// 按文件后缀指定 macOS 上用 `open -a` 打开的应用，例如 `html = "Visual Studio Code"`，
+    /// 优先级低于 `[editors]`，其他平台上忽略
+    pub apps: HashMap<String, String>,
+
+    /// 创建文件后是否用编辑器打开 (默认: true)
+    pub open: Option<bool>,
+
+    /// 创建文件后是否自动 `git add` (默认: false)，可被 `--git-add`/`--no-git-add` 覆盖
+    pub git_add: bool,
+
+    /// 在 `src/` 中创建 `.rs` 文件后是否在父模块中添加 `mod` 声明 (默认: false)，
+    /// 可被 `--add-mod`/`--no-add-mod` 覆盖
+    pub add_mod: bool,
+
+    /// 模板目录，覆盖默认的 `<数据目录>/templates`
+    pub template_dir: Option<PathBuf>,
+
+    /// 在模板目录之后依次查找的其他模板目录，例如团队共享的目录
+    pub template_paths: Vec<PathBuf>,
+
+    /// 在子目录中查找同名模板的最大深度 (默认: 3)，0 表示只查找指定的目录
+    pub template_depth: Option<usize>,
+
+    /// 首次创建模板目录时不写入内置的默认模板
+    pub no_defaults: bool,
+
+    /// `adr` 子命令写入记录的目录，相对于当前目录 (默认: `docs/adr`)
+    pub adr_dir: Option<PathBuf>,
+
+    /// 默认模板变量，可被 `-D` 覆盖
+    pub variables: HashMap<String, String>,
+
+    /// 模板中可以通过 `{{env.NAME}}` 读取的环境变量，支持 `CI_*` 形式的前缀
+    pub allow_env: Vec<String>,
+
+    /// 按后缀指定的后备模板：后缀 -> 模板名，例如 `md = "notes/page.md"`，
+    /// 在没有同名模板和 `default.<后缀>` 时使用
+    pub fallbacks: HashMap<String, String>,
+
+    /// 使用 `default.<后缀>` 或后备模板前先在终端中确认，非交互时需要 `--allow-fallback`；
+    /// 关闭时直接使用并给出提示，`--allow-fallback` 不起作用 (默认: false)
+    pub confirm_fallback: bool,
+
+    /// 模板别名：别名 -> 模板文件名，例如 `rc = "react-component.tsx"`
+    pub aliases: HashMap<String, String>,
+
+    /// 界面语言，覆盖根据环境变量确定的语言
+    pub lang: Option<Lang>,
+
+    /// 每次创建文件后都执行的钩子命令
+    pub hooks: Hooks,
+
+    /// 新建文件的换行风格：lf、crlf 或 native，可被 `--eol` 覆盖
+    pub eol: Option<Eol>,
+
+    /// 按项目类型（`rust`、`node`、`go`、`python`）指定的默认后缀和模板命名空间
+    pub projects: HashMap<String, ProjectDefaults>,
+
+    /// 模板注册表清单的地址（HTTP(S) 或本地文件），用于 `search` 和 `template install <名称>`
+    pub registry: Option<String>,
+
+    /// 从地址或注册表导入模板包时要求有受信任公钥签署的签名 (默认: false)
+    pub require_signatures: bool,
+
+    /// 记录模板的使用次数，用于 `stats` 和交互式选择时把最近用过的模板排在前面 (默认: false)
+    pub track_usage: bool,
+}
+
+impl Config {
+    /// 读取配置目录中的 `config.toml`，文件不存在时返回默认配置
+    pub fn load() -> Result<Config> {
+        Config::load_from(&config_path()?)
+    }
+
+    /// 从指定路径读取配置，文件不存在时返回默认配置
+    pub fn load_from(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(path).io_context(|| {
+            tr!(
+                "无法读取配置文件: {:?}",
+                "failed to read config file: {:?}",
+                path
+            )
+        })?;
+        Config::parse(&content).map_err(|source| Error::Config {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn parse(content: &str) -> std::result::Result<Config, toml::de::Error> {
+        let mut config: Config = toml::from_str(content)?;
+        config.template_dir = config.template_dir.map(|dir| expand_tilde(&dir));
+        config.template_paths = config
+            .template_paths
+            .iter()
+            .map(|dir| expand_tilde(dir))
+            .collect();
+        Ok(config)
+    }
+
+    /// 模板目录路径：优先使用配置中的 `template_dir`，否则为 `<数据目录>/templates`
+    pub fn template_dir(&self) -> Result<PathBuf> {
+        match &self.template_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(data_dir()?.join("templates")),
+        }
+    }
+}
+
+/// 覆盖配置、数据和缓存目录的环境变量
+pub const HOME_VAR: &str = "NEW_CLI_HOME";
+
+/// 各目录都在 `NEW_CLI_HOME` 中时返回该目录
+pub fn home_override() -> Option<PathBuf> {
+    std::env::var_os(HOME_VAR)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 配置目录，存放 `config.toml` 和受信任的公钥
+pub fn config_dir() -> Result<PathBuf> {
+    platform_dir(dirs::config_dir)
+}
+
+/// 数据目录，存放模板、插件、创建记录和锁文件
+pub fn data_dir() -> Result<PathBuf> {
+    platform_dir(dirs::data_dir)
+}
+
+/// 缓存目录，存放下载的远程模板等可以随时删除的文件
+pub fn cache_dir() -> Result<PathBuf> {
+    match home_override() {
+        Some(dir) => Ok(dir.join("cache")),
+        None => platform_dir(dirs::cache_dir),
+    }
+}
+
+/// 旧版本使用的目录 `~/.new-cli`，启动时会迁移到新的位置
+pub fn legacy_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir().ok_or(Error::NoHomeDir)?.join(".new-cli"))
+}
+
+fn platform_dir(base: fn() -> Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(dir) = home_override() {
+        return Ok(dir);
+    }
+    Ok(base().ok_or(Error::NoHomeDir)?.join("new-cli"))
+}
+
+/// 配置文件路径 `<配置目录>/config.toml`
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// 将以 `~` 开头的路径展开为主目录下的路径
+fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_config() {
+        let config = Config::parse("").unwrap();
+        assert!(config.default_filename.is_none());
+        assert!(config.default_extension.is_none());
+        assert!(config.editor.is_none());
+        assert!(config.open.is_none());
+        assert!(config.template_dir.is_none());
+        assert!(config.variables.is_empty());
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = Config::parse(
+            r#"
+            default_filename = "main"
+            default_extension = "rs"
+            editor = "code"
+            open = false
+            template_dir = "/opt/templates"
+            template_paths = ["/srv/team-templates", "~/shared"]
+            registry = "https://example.com/registry.json"
+            eol = "crlf"
+            allow_env = ["USER", "CI_*"]
+            confirm_fallback = true
+
+            [variables]
+            author = "jaff"
+
+            [aliases]
+            rc = "react-component.tsx"
+
+            [fallbacks]
+            md = "notes/page.md"
+
+            [editors]
+            md = "typora"
+
+            [hooks]
+            post_create = ["cargo fmt"]
+
+            [projects.rust]
+            extension = "rs"
+            namespace = "rust"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.default_filename.as_deref(), Some("main"));
+        assert_eq!(config.default_extension.as_deref(), Some("rs"));
+        assert_eq!(config.editor.as_deref(), Some("code"));
+        assert_eq!(config.open, Some(false));
+        assert_eq!(config.template_dir, Some(PathBuf::from("/opt/templates")));
+        assert_eq!(
+            config.template_paths,
+            [
+                PathBuf::from("/srv/team-templates"),
+                dirs::home_dir().unwrap().join("shared")
+            ]
+        );
+        assert_eq!(config.variables["author"], "jaff");
+        assert_eq!(config.aliases["rc"], "react-component.tsx");
+        assert_eq!(config.fallbacks["md"], "notes/page.md");
+        assert!(config.confirm_fallback);
+        assert_eq!(config.editors["md"], "typora");
+        assert_eq!(config.hooks.post_create, ["cargo fmt"]);
+        assert_eq!(config.eol, Some(Eol::Crlf));
+        assert_eq!(config.allow_env, ["USER", "CI_*"]);
+        assert_eq!(config.projects["rust"].extension.as_deref(), Some("rs"));
+        assert_eq!(config.projects["rust"].namespace.as_deref(), Some("rust"));
+        assert_eq!(
+            config.registry.as_deref(),
+            Some("https://example.com/registry.json")
+        );
+    }
+
+    #[test]
+    fn test_parse_expands_tilde() {
+        let config = Config::parse(r#"template_dir = "~/templates""#).unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(config.template_dir, Some(home.join("templates")));
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        assert!(Config::parse("default_extension = 1").is_err());
+    }
+}
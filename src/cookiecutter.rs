@@ -0,0 +1,369 @@
+//! cookiecutter 模板兼容 (`new-cli project my-app --template cookiecutter-pypackage`)
+//!
+//! 项目模板目录中有 `cookiecutter.json` 时按 cookiecutter 的布局处理：
+//!
+//! - `cookiecutter.json` 中的每一项成为 `{{cookiecutter.<key>}}` 变量。字符串默认值中可以引用
+//!   之前的变量；列表取第一项（cookiecutter 的默认选项）；以 `_` 开头的私有项和字典会被忽略。
+//!   `-D key=value` 或 `-D cookiecutter.key=value` 覆盖默认值。
+//! - 生成的是名称含有 `{{cookiecutter.` 的那个子目录的内容，目标目录仍由命令行中的项目名决定；
+//!   `hooks/` 等其他文件不会被复制。
+//! - 渲染前先做少量转换：去掉 `{%-`、`-%}` 等空白控制符，`{% raw %}` 块中的内容原样输出，
+//!   `{{ cookiecutter.name.lower().replace(' ', '_') }}` 这类字符串方法调用直接求值
+//!   （支持 `lower`、`upper`、`title`、`capitalize`、`strip` 和 `replace`）。
+//!
+//! 其余部分（条件、循环、`| lower` 等过滤器）使用 new-cli 自己的模板语法，见 [`blocks`](crate::blocks)。
+
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, IoContext, Result};
+use crate::render::{self, Variables};
+use crate::tr;
+
+/// cookiecutter 的变量文件名
+pub const CONFIG_FILE: &str = "cookiecutter.json";
+
+/// 变量名前缀
+const PREFIX: &str = "cookiecutter.";
+
+/// 一个 cookiecutter 模板
+#[derive(Debug)]
+pub struct Cookiecutter {
+    /// 要生成的目录，例如 `{{cookiecutter.project_slug}}`
+    pub root: PathBuf,
+
+    /// `cookiecutter.json` 中的项，保持文件中的顺序
+    entries: Vec<(String, Value)>,
+}
+
+/// 按文件中的顺序读取 JSON 对象
+struct Entries(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+impl Cookiecutter {
+    /// 目录 `dir` 是 cookiecutter 模板时读取它，否则返回 None
+    pub fn detect(dir: &Path) -> Result<Option<Cookiecutter>> {
+        let path = dir.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let invalid = |message: String| Error::Cookiecutter {
+            path: dir.to_path_buf(),
+            message,
+        };
+
+        let content = fs::read_to_string(&path)
+            .io_context(|| tr!("无法读取 {:?}", "failed to read {:?}", path))?;
+        let Entries(entries) =
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+        let mut roots: Vec<PathBuf> = fs::read_dir(dir)
+            .io_context(|| tr!("无法读取目录 {:?}", "failed to read directory {:?}", dir))?
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter(|entry| entry.file_name().to_string_lossy().contains("{{"))
+            .map(|entry| entry.path())
+            .collect();
+        if roots.len() != 1 {
+            return Err(invalid(tr!(
+                "需要恰好一个名称含有 {{{{cookiecutter. 的子目录，找到 {} 个",
+                "expected exactly one subdirectory named like {{{{cookiecutter.…}}}}, found {}",
+                roots.len()
+            )));
+        }
+        Ok(Some(Cookiecutter {
+            root: roots.remove(0),
+            entries,
+        }))
+    }
+
+    /// 求出所有 `cookiecutter.<key>` 变量，`vars` 中已有的值优先
+    ///
+    /// 返回的变量包含 `vars` 中的全部内容。
+    pub fn variables(&self, vars: &Variables) -> Result<Variables> {
+        let mut vars = vars.clone();
+        for (key, value) in &self.entries {
+            if key.starts_with('_') {
+                continue;
+            }
+            let name = format!("{}{}", PREFIX, key);
+            if let Some(value) = vars.get(key).or_else(|| vars.get(&name)) {
+                vars.insert(name, value.clone());
+                continue;
+            }
+            let value = match value {
+                Value::String(text) => render::render(&translate(text, &vars), &vars, false)?,
+                Value::Array(choices) => match choices.first() {
+                    Some(Value::String(text)) => {
+                        render::render(&translate(text, &vars), &vars, false)?
+                    }
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                },
+                Value::Bool(value) => value.to_string(),
+                Value::Number(number) => number.to_string(),
+                Value::Null => String::new(),
+                Value::Object(_) => continue,
+            };
+            vars.insert(name, value);
+        }
+        Ok(vars)
+    }
+}
+
+/// 把 cookiecutter（Jinja）模板文本转换为 new-cli 的模板语法
+pub fn translate(text: &str, vars: &Variables) -> String {
+    let text = text
+        .replace("{%-", "{%")
+        .replace("-%}", "%}")
+        .replace("{{-", "{{")
+        .replace("-}}", "}}");
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("{{").into_iter().chain(rest.find("{%")).min() {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some((body, after)) = raw_block(rest) {
+            output.push_str(&escape(body));
+            rest = after;
+            continue;
+        }
+        let end = match rest.find(if rest.starts_with("{{") { "}}" } else { "%}" }) {
+            Some(end) => end + 2,
+            None => rest.len(),
+        };
+        let tag = &rest[..end];
+        match tag
+            .strip_prefix("{{")
+            .and_then(|tag| tag.strip_suffix("}}"))
+            .and_then(|expr| evaluate(expr.trim(), vars))
+        {
+            Some(value) => output.push_str(&escape(&value)),
+            None => output.push_str(tag),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// `rest` 以 `{% raw %}` 开头时返回块中的内容和块之后的文本
+fn raw_block(rest: &str) -> Option<(&str, &str)> {
+    let open_end = rest.find("%}")? + 2;
+    if rest[2..open_end - 2].trim() != "raw" {
+        return None;
+    }
+    let body = &rest[open_end..];
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find("{%") {
+        let start = offset + start;
+        let end = start + body[start..].find("%}")? + 2;
+        if body[start + 2..end - 2].trim() == "endraw" {
+            return Some((&body[..start], &body[end..]));
+        }
+        offset = end;
+    }
+    None
+}
+
+/// 转义文本中的 `{{` 和 `{%`，使其原样输出
+fn escape(text: &str) -> String {
+    text.replace("{{", "\\{{").replace("{%", "\\{%")
+}
+
+/// 求出带字符串方法调用或带参数过滤器的表达式，例如
+/// `cookiecutter.name.lower().replace(' ', '_')` 或 `cookiecutter.name | replace('-', '_')`
+///
+/// 普通的变量和过滤器交给渲染器处理，不支持的写法也返回 None，保持原样。
+fn evaluate(expr: &str, vars: &Variables) -> Option<String> {
+    let rest = expr.strip_prefix(PREFIX)?;
+    let key_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let (key, mut rest) = rest.split_at(key_len);
+    if !rest.contains('(') {
+        return None;
+    }
+    let mut value = vars.get(&format!("{}{}", PREFIX, key))?.clone();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some(value);
+        }
+        rest = rest.strip_prefix('.').or_else(|| rest.strip_prefix('|'))?;
+        rest = rest.trim_start();
+        let name_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, after) = rest.split_at(name_len);
+        let (args, after) = match after.trim_start().strip_prefix('(') {
+            Some(after) => {
+                let close = after.find(')')?;
+                (arguments(&after[..close])?, &after[close + 1..])
+            }
+            None => (Vec::new(), after),
+        };
+        value = match (name, args.as_slice()) {
+            ("lower", []) => value.to_lowercase(),
+            ("upper", []) => value.to_uppercase(),
+            ("strip" | "trim", []) => value.trim().to_string(),
+            ("capitalize", []) => capitalize(&value),
+            ("title", []) => value
+                .split(' ')
+                .map(capitalize)
+                .collect::<Vec<_>>()
+                .join(" "),
+            ("replace", [from, to]) => value.replace(from, to),
+            _ => return None,
+        };
+        rest = after;
+    }
+}
+
+/// 首字母大写，其余小写（Python 的 `str.capitalize`）
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// 解析方法参数列表，只支持带引号的字符串，例如 `' ', '_'`
+fn arguments(args: &str) -> Option<Vec<String>> {
+    let mut result = Vec::new();
+    let mut rest = args.trim();
+    while !rest.is_empty() {
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+        let body = &rest[1..];
+        let end = body.find(quote)?;
+        result.push(body[..end].to_string());
+        rest = body[end + 1..].trim_start();
+        rest = match rest.strip_prefix(',') {
+            Some(after) => after.trim_start(),
+            None if rest.is_empty() => rest,
+            None => return None,
+        };
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_translate() {
+        let vars = vars(&[("cookiecutter.project_name", "My Cool-App")]);
+        let translate = |text: &str| translate(text, &vars);
+        assert_eq!(
+            translate(
+                "{{ cookiecutter.project_name.lower().replace(' ', '_').replace('-', '_') }}"
+            ),
+            "my_cool_app"
+        );
+        assert_eq!(
+            translate("{{cookiecutter.project_name|replace(\"-\", \"\")|upper}}"),
+            "MY COOLAPP"
+        );
+        // 不需要求值的占位符和不支持的写法保持原样
+        assert_eq!(
+            translate("{{ cookiecutter.project_name | lower }}"),
+            "{{ cookiecutter.project_name | lower }}"
+        );
+        assert_eq!(
+            translate("{{ cookiecutter.project_name.split(',') }}"),
+            "{{ cookiecutter.project_name.split(',') }}"
+        );
+        assert_eq!(
+            translate("{%- if cookiecutter.x == 'y' -%}a{% endif %}"),
+            "{% if cookiecutter.x == 'y' %}a{% endif %}"
+        );
+        assert_eq!(
+            translate("run: {% raw %}${{ github.sha }}{% endraw %} done"),
+            "run: $\\{{ github.sha }} done"
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("{{cookiecutter.project_slug}}")).unwrap();
+        fs::create_dir(dir.path().join("hooks")).unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"{
+                "project_name": "Hello World",
+                "project_slug": "{{ cookiecutter.project_name.lower().replace(' ', '_') }}",
+                "license": ["MIT", "BSD-3-Clause"],
+                "use_docker": true,
+                "version": "0.1.0",
+                "_copy_without_render": ["*.html"]
+            }"#,
+        )
+        .unwrap();
+        let template = Cookiecutter::detect(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            template.root,
+            dir.path().join("{{cookiecutter.project_slug}}")
+        );
+
+        let result = template.variables(&vars(&[("version", "2.0")])).unwrap();
+        assert_eq!(result["cookiecutter.project_slug"], "hello_world");
+        assert_eq!(result["cookiecutter.license"], "MIT");
+        assert_eq!(result["cookiecutter.use_docker"], "true");
+        assert_eq!(result["cookiecutter.version"], "2.0");
+        assert!(!result.contains_key("cookiecutter._copy_without_render"));
+
+        let result = template
+            .variables(&vars(&[("cookiecutter.project_name", "Other App")]))
+            .unwrap();
+        assert_eq!(result["cookiecutter.project_slug"], "other_app");
+
+        assert!(Cookiecutter::detect(&dir.path().join("hooks"))
+            .unwrap()
+            .is_none());
+    }
+}
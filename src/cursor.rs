@@ -0,0 +1,85 @@
+//! 新建文件打开后的光标位置
+//!
+//! 模板中的 `{{cursor}}` 标记打开文件后光标所在的位置，写入的文件中不会保留该标记。
+//! 也可以额外指定一个字面量标记（例如代码片段中常见的 `$0`）。
+
+/// 标记光标位置的占位符名称，`{{cursor}}` 在普通渲染中替换为空
+pub const PLACEHOLDER: &str = "cursor";
+
+/// 创建文件时 `{{cursor}}` 先渲染为该字符串，定位后再删除
+pub(crate) const SENTINEL: &str = "\u{E000}new-cli:cursor\u{E000}";
+
+/// 文件中的位置，行和列都从 1 开始，列按字符计算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// 指定行的行首
+    pub fn line(line: usize) -> Position {
+        Position { line, column: 1 }
+    }
+}
+
+/// 删除内容中所有的 `markers`，返回删除后的内容和第一个标记所在的位置
+///
+/// 二进制内容原样返回。
+pub fn take(content: Vec<u8>, markers: &[&str]) -> (Vec<u8>, Option<Position>) {
+    let markers: Vec<&str> = markers.iter().copied().filter(|m| !m.is_empty()).collect();
+    let text = match String::from_utf8(content) {
+        Ok(text) => text,
+        Err(err) => return (err.into_bytes(), None),
+    };
+    let first = markers
+        .iter()
+        .filter_map(|marker| text.find(marker))
+        .min()
+        .map(|offset| position_of(&text[..offset]));
+    if first.is_none() {
+        return (text.into_bytes(), None);
+    }
+    let stripped = markers
+        .iter()
+        .fold(text, |text, marker| text.replace(marker, ""));
+    (stripped.into_bytes(), first)
+}
+
+/// `before` 之后的位置；开头的 BOM 不算一列
+fn position_of(before: &str) -> Position {
+    let before = before.strip_prefix('\u{feff}').unwrap_or(before);
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take() {
+        let content = format!("fn main() {{\n    {SENTINEL}\n}}\n$0");
+        let (content, position) = take(content.into_bytes(), &[SENTINEL, "$0"]);
+        assert_eq!(content, b"fn main() {\n    \n}\n");
+        assert_eq!(position, Some(Position { line: 2, column: 5 }));
+
+        // 取第一个出现的标记，列按字符计算
+        let (content, position) = take("# 标题 $0\n$0".as_bytes().to_vec(), &["$0", ""]);
+        assert_eq!(content, "# 标题 \n".as_bytes());
+        assert_eq!(position, Some(Position { line: 1, column: 6 }));
+
+        assert_eq!(
+            take(b"plain".to_vec(), &[SENTINEL]),
+            (b"plain".to_vec(), None)
+        );
+        let (_, position) = take("\u{feff}$0".as_bytes().to_vec(), &["$0"]);
+        assert_eq!(position, Some(Position { line: 1, column: 1 }));
+
+        let binary = vec![0xff, b'$', b'0'];
+        assert_eq!(take(binary.clone(), &["$0"]), (binary, None));
+    }
+}
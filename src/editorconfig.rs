@@ -0,0 +1,366 @@
+//! 读取 `.editorconfig` 并应用到新建文件的内容
+//!
+//! 从目标文件所在目录开始逐级向上查找 `.editorconfig`，直到遇到 `root = true`，
+//! 离目标文件越近的文件、越靠后的小节优先级越高。支持的属性：
+//! `indent_style`、`indent_size`、`tab_width`、`insert_final_newline` 和 `charset`，
+//! 其他属性会被忽略。
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{IoContext, Result};
+use crate::glob;
+use crate::render;
+use crate::tr;
+
+/// `.editorconfig` 文件名
+pub const FILE_NAME: &str = ".editorconfig";
+
+/// 缩进方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Space,
+    Tab,
+}
+
+/// 文件编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    Utf8Bom,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// 对某个文件生效的 `.editorconfig` 属性，未设置的属性为 None
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    /// 每级缩进的空格数；`indent_size = tab` 时使用 `tab_width`
+    pub indent_size: Option<usize>,
+    pub tab_width: Option<usize>,
+    pub insert_final_newline: Option<bool>,
+    pub charset: Option<Charset>,
+}
+
+impl EditorConfig {
+    /// 查找对 `path` 生效的 `.editorconfig` 属性
+    pub fn lookup(path: &Path) -> Result<EditorConfig> {
+        let Some(file_name) = path.file_name() else {
+            return Ok(EditorConfig::default());
+        };
+        let file_name = file_name.to_string_lossy();
+
+        // 从近到远收集，遇到 root = true 时停止
+        let mut files = Vec::new();
+        let mut relative = file_name.to_string();
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            let config = current.join(FILE_NAME);
+            if config.is_file() {
+                log::debug!("reading {}", config.display());
+                let content = fs::read_to_string(&config)
+                    .io_context(|| tr!("无法读取 {:?}", "failed to read {:?}", config))?;
+                let root = is_root(&content);
+                files.push((content, relative.clone()));
+                if root {
+                    break;
+                }
+            }
+            if let Some(name) = current.file_name() {
+                relative = format!("{}/{}", name.to_string_lossy(), relative);
+            }
+            dir = current.parent();
+        }
+
+        let mut properties = EditorConfig::default();
+        for (content, relative) in files.iter().rev() {
+            properties.apply_file(content, relative);
+        }
+        if properties.indent_size.is_none() && properties.indent_style == Some(IndentStyle::Tab) {
+            properties.indent_size = properties.tab_width;
+        }
+        log::debug!("editorconfig for {}: {:?}", path.display(), properties);
+        Ok(properties)
+    }
+
+    /// 按顺序应用文件中与 `relative`（相对于该文件所在目录的路径）匹配的小节
+    fn apply_file(&mut self, content: &str, relative: &str) {
+        let mut matched = false;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                matched = glob::matches(section, relative);
+                continue;
+            }
+            if !matched {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                self.set(&key.trim().to_lowercase(), &value.trim().to_lowercase());
+            }
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let unset = value == "unset";
+        match key {
+            "indent_style" => {
+                self.indent_style = match value {
+                    "space" => Some(IndentStyle::Space),
+                    "tab" => Some(IndentStyle::Tab),
+                    _ if unset => None,
+                    _ => self.indent_style,
+                }
+            }
+            "indent_size" => {
+                self.indent_size = match value {
+                    "tab" => self.tab_width.or(self.indent_size),
+                    _ if unset => None,
+                    _ => value.parse().ok().or(self.indent_size),
+                }
+            }
+            "tab_width" => {
+                self.tab_width = match value {
+                    _ if unset => None,
+                    _ => value.parse().ok().or(self.tab_width),
+                }
+            }
+            "insert_final_newline" => {
+                self.insert_final_newline = match value {
+                    "true" => Some(true),
+                    "false" => Some(false),
+                    _ if unset => None,
+                    _ => self.insert_final_newline,
+                }
+            }
+            "charset" => {
+                self.charset = match value {
+                    "utf-8" => Some(Charset::Utf8),
+                    "utf-8-bom" => Some(Charset::Utf8Bom),
+                    "latin1" => Some(Charset::Latin1),
+                    "utf-16le" => Some(Charset::Utf16Le),
+                    "utf-16be" => Some(Charset::Utf16Be),
+                    _ if unset => None,
+                    _ => self.charset,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 未设置任何支持的属性
+    pub fn is_empty(&self) -> bool {
+        *self == EditorConfig::default()
+    }
+
+    /// 将属性应用到文件内容；二进制内容原样返回
+    pub fn apply(&self, content: Vec<u8>) -> Vec<u8> {
+        if self.is_empty() || render::is_binary(&content) {
+            return content;
+        }
+        let mut text = match String::from_utf8(content) {
+            Ok(text) => text,
+            Err(err) => return err.into_bytes(),
+        };
+
+        if self.indent_style.is_some() || self.indent_size.is_some() {
+            text = reindent(&text, self.indent_style, self.indent_size);
+        }
+        match self.insert_final_newline {
+            Some(true) if !text.is_empty() && !text.ends_with('\n') => {
+                text.push_str(if text.contains("\r\n") { "\r\n" } else { "\n" });
+            }
+            Some(false) => {
+                let trimmed = text.trim_end_matches(['\r', '\n']).len();
+                text.truncate(trimmed);
+            }
+            _ => {}
+        }
+        encode(&text, self.charset)
+    }
+}
+
+/// 文件中是否声明了 `root = true`（只看第一个小节之前的部分）
+fn is_root(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| {
+            key.trim().eq_ignore_ascii_case("root") && value.trim().eq_ignore_ascii_case("true")
+        })
+}
+
+/// 按目标缩进方式和宽度重新生成每行开头的缩进
+///
+/// 模板原有的缩进宽度取所有以空格缩进的行中最小的空格数，制表符算作一级缩进；
+/// 不足一级的空格原样保留。
+fn reindent(text: &str, style: Option<IndentStyle>, size: Option<usize>) -> String {
+    let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let unit = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..leading(line)])
+        .filter(|indent| !indent.is_empty() && !indent.contains('\t'))
+        .map(str::len)
+        .min();
+
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let indent_len = leading(line);
+        let (indent, rest) = line.split_at(indent_len);
+        if indent.is_empty() || rest.trim().is_empty() {
+            output.push_str(line);
+            continue;
+        }
+        let tabs = indent.matches('\t').count();
+        let spaces = indent.len() - tabs;
+        let (levels, remainder) = match unit {
+            Some(unit) => (tabs + spaces / unit, spaces % unit),
+            None => (tabs, spaces),
+        };
+        match style.unwrap_or(if tabs > 0 && spaces == 0 {
+            IndentStyle::Tab
+        } else {
+            IndentStyle::Space
+        }) {
+            IndentStyle::Tab => output.push_str(&"\t".repeat(levels)),
+            IndentStyle::Space => {
+                let width = size.or(unit).unwrap_or(4);
+                output.push_str(&" ".repeat(levels * width));
+            }
+        }
+        output.push_str(&" ".repeat(remainder));
+        output.push_str(rest);
+    }
+    output
+}
+
+/// 按 `charset` 编码文本；latin1 无法表示的内容保持 UTF-8
+fn encode(text: &str, charset: Option<Charset>) -> Vec<u8> {
+    match charset {
+        None | Some(Charset::Utf8) => text.as_bytes().to_vec(),
+        Some(Charset::Utf8Bom) => {
+            let mut bytes = b"\xEF\xBB\xBF".to_vec();
+            bytes.extend(text.as_bytes());
+            bytes
+        }
+        Some(Charset::Latin1) => {
+            if text.chars().all(|c| (c as u32) <= 0xFF) {
+                text.chars().map(|c| c as u8).collect()
+            } else {
+                text.as_bytes().to_vec()
+            }
+        }
+        Some(Charset::Utf16Le) => {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        }
+        Some(Charset::Utf16Be) => {
+            let mut bytes = vec![0xFE, 0xFF];
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lookup_merges_nested_files() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("project/src")).unwrap();
+        fs::write(dir.path().join(".editorconfig"), "[*]\ncharset = latin1\n").unwrap();
+        fs::write(
+            dir.path().join("project/.editorconfig"),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\ninsert_final_newline = true\n\n[*.md]\nindent_size = 2\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("project/src/.editorconfig"),
+            "[*.rs]\ninsert_final_newline = false\n",
+        )
+        .unwrap();
+
+        let rs = EditorConfig::lookup(&dir.path().join("project/src/main.rs")).unwrap();
+        assert_eq!(rs.indent_style, Some(IndentStyle::Space));
+        assert_eq!(rs.indent_size, Some(4));
+        assert_eq!(rs.insert_final_newline, Some(false));
+        // 上层的 .editorconfig 在 root = true 之外，不生效
+        assert_eq!(rs.charset, None);
+
+        let md = EditorConfig::lookup(&dir.path().join("project/README.md")).unwrap();
+        assert_eq!(md.indent_size, Some(2));
+        assert_eq!(md.insert_final_newline, Some(true));
+    }
+
+    #[test]
+    fn test_apply_indent() {
+        let template = "fn main() {\n    if x {\n        y();\n    }\n}"
+            .as_bytes()
+            .to_vec();
+        let tabs = EditorConfig {
+            indent_style: Some(IndentStyle::Tab),
+            insert_final_newline: Some(true),
+            ..EditorConfig::default()
+        };
+        assert_eq!(
+            String::from_utf8(tabs.apply(template.clone())).unwrap(),
+            "fn main() {\n\tif x {\n\t\ty();\n\t}\n}\n"
+        );
+
+        let two = EditorConfig {
+            indent_style: Some(IndentStyle::Space),
+            indent_size: Some(2),
+            ..EditorConfig::default()
+        };
+        assert_eq!(
+            String::from_utf8(two.apply(template)).unwrap(),
+            "fn main() {\n  if x {\n    y();\n  }\n}"
+        );
+        assert_eq!(
+            String::from_utf8(two.apply(b"a\n\tb\n".to_vec())).unwrap(),
+            "a\n  b\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_final_newline_and_charset() {
+        let strip = EditorConfig {
+            insert_final_newline: Some(false),
+            ..EditorConfig::default()
+        };
+        assert_eq!(strip.apply(b"a\r\n\r\n".to_vec()), b"a");
+
+        let bom = EditorConfig {
+            charset: Some(Charset::Utf8Bom),
+            ..EditorConfig::default()
+        };
+        assert_eq!(bom.apply(b"a".to_vec()), b"\xEF\xBB\xBFa");
+
+        let latin1 = EditorConfig {
+            charset: Some(Charset::Latin1),
+            ..EditorConfig::default()
+        };
+        assert_eq!(latin1.apply("é".as_bytes().to_vec()), [0xE9]);
+
+        let utf16 = EditorConfig {
+            charset: Some(Charset::Utf16Le),
+            ..EditorConfig::default()
+        };
+        assert_eq!(utf16.apply(b"a".to_vec()), [0xFF, 0xFE, b'a', 0]);
+
+        // 二进制内容原样返回
+        assert_eq!(utf16.apply(vec![0, 1, 2]), [0, 1, 2]);
+    }
+}
@@ -0,0 +1,1241 @@
+//! 模板引擎：模板查找、渲染与目标文件创建
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use include_dir::{include_dir, Dir};
+
+use crate::compose::{self, Part};
+use crate::config::{self, Config};
+use crate::cursor::{self, Position};
+use crate::editorconfig::EditorConfig;
+use crate::eol::Eol;
+use crate::error::{Error, IoContext, Result};
+use crate::front_matter::{self, FrontMatter};
+use crate::index::{self, Index};
+use crate::insert::{self, Insertion};
+use crate::layers::{find_project_template_dir, system_template_dir, Layer, LayerSource};
+use crate::lock::{self, Lockfile};
+use crate::manifest;
+use crate::permissions;
+use crate::render::{self, Variables};
+use crate::template::{self, TemplateInfo};
+use crate::tr;
+use crate::validate::{validate_cli_inputs, validate_namespace, validate_qualified_name};
+
+/// 内置的默认模板（html、css、js、md、sh、py、rs 各一个），首次运行时写入模板目录
+static DEFAULT_TEMPLATES: Dir = include_dir!("$CARGO_MANIFEST_DIR/template");
+
+/// 各后缀的默认模板的文件名（不含后缀），例如 `default.md`
+pub const DEFAULT_TEMPLATE_NAME: &str = "default";
+
+/// 在子目录中查找同名模板的默认最大深度，可用配置 `template_depth` 修改
+pub const DEFAULT_TEMPLATE_DEPTH: usize = 3;
+
+/// 模板查找的结果，标明模板来自查找顺序中的哪一步
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup {
+    /// 与目标文件同名的模板
+    Exact(PathBuf),
+    /// 子目录中与目标文件同名的模板，例如 `web/pages/index.html`
+    Nested(PathBuf),
+    /// 该后缀的默认模板 `default.<后缀>`
+    Default(PathBuf),
+    /// 配置 `[fallbacks]` 中为该后缀指定的模板
+    Fallback(PathBuf),
+    /// 没有可用的模板
+    Empty,
+}
+
+impl Lookup {
+    /// 找到的模板路径
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Lookup::Exact(path)
+            | Lookup::Nested(path)
+            | Lookup::Default(path)
+            | Lookup::Fallback(path) => Some(path),
+            Lookup::Empty => None,
+        }
+    }
+}
+
+/// 模板查找顺序中的一步
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// 同名模板
+    Exact,
+    /// 子目录中的同名模板
+    Nested,
+    /// `default.<后缀>`
+    Default,
+    /// 配置 `[fallbacks]` 中的后备模板
+    Fallback,
+}
+
+/// 一个候选模板的检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// 使用该模板
+    Used,
+    /// 模板可用，但已经使用了排在前面的模板
+    Shadowed,
+    /// 文件不存在
+    Missing,
+    /// 同名但后缀不同的模板
+    WrongExtension,
+    /// 模板目录（或命名空间目录）不存在或无法访问
+    NoDirectory,
+    /// 无法解析真实路径，例如失效的符号链接
+    Unresolvable,
+    /// 真实路径（附带）在模板目录之外，例如指向别处的符号链接
+    OutsideDirectory(PathBuf),
+    /// 命名空间无效，没有查找
+    InvalidNamespace,
+    /// 配置中没有为该后缀指定后备模板
+    NotConfigured,
+}
+
+/// 查找模板时检查过的一个候选
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub step: Step,
+    /// 候选模板的路径；[`Verdict::NoDirectory`] 时为目录，[`Verdict::InvalidNamespace`]
+    /// 时为命名空间，[`Verdict::NotConfigured`] 时为空
+    pub path: PathBuf,
+    pub verdict: Verdict,
+}
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overwrite {
+    /// 拒绝覆盖，返回 [`Error::TargetExists`]
+    #[default]
+    Refuse,
+    /// 直接覆盖
+    Force,
+    /// 跳过，不写入任何内容
+    Skip,
+    /// 先将原文件重命名为 `<文件名>.bak` 再写入
+    Backup,
+}
+
+/// 创建文件的选项
+#[derive(Debug, Default)]
+pub struct CreateOptions {
+    /// 目标目录，文件会直接创建在该目录下
+    pub target_dir: PathBuf,
+    /// 渲染模板时可用的变量
+    pub variables: Variables,
+    /// 模板中出现未定义的变量时报错
+    pub strict_vars: bool,
+    /// 目标文件已存在时的处理方式
+    pub overwrite: Overwrite,
+    /// 设置后不创建新文件，而是把内容插入已存在的目标文件，此时忽略 `overwrite`
+    pub insert: Option<Insertion>,
+    /// 不读取目标目录的 `.editorconfig`，按模板原样写入
+    pub ignore_editorconfig: bool,
+    /// 将新建文件中的换行统一为该风格，None 时保留模板中的换行
+    pub eol: Option<Eol>,
+    /// 为新建文件加上执行权限（只在 Unix 上生效）
+    pub executable: bool,
+    /// 除 `{{cursor}}` 外另一个标记光标位置的字面量，例如 `$0`，写入时删除
+    pub cursor_marker: Option<String>,
+}
+
+/// 创建单个文件的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// 文件已写入；若原文件被备份，`backup` 为备份文件路径；
+    /// `cursor` 为模板中第一个光标标记的位置
+    Created {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+        cursor: Option<Position>,
+    },
+    /// 目标文件已存在且选择了跳过
+    Skipped { path: PathBuf },
+    /// 内容已插入已存在的文件
+    Inserted { path: PathBuf },
+}
+
+/// 渲染模板前需要向用户询问的变量
+#[derive(Debug, PartialEq, Eq)]
+pub struct VariablePrompt {
+    pub name: String,
+    /// front matter 中的变量说明
+    pub description: Option<String>,
+    /// front matter 中的默认值
+    pub default: Option<String>,
+    /// 是否必须提供非空的值
+    pub required: bool,
+}
+
+/// 基于分层的模板目录查找、渲染模板并创建文件
+pub struct TemplateEngine {
+    /// 按优先级从高到低排列，总是包含一个全局模板目录
+    layers: Vec<Layer>,
+    /// 使用全局模板目录中的模板前按其校验和检查内容
+    lockfile: Lockfile,
+    /// 后缀 -> 找不到同名模板和默认模板时使用的模板
+    fallbacks: HashMap<String, String>,
+    /// 列出模板时使用的索引文件，None 时每次都完整扫描
+    index: Option<PathBuf>,
+    /// 在子目录中查找同名模板的最大深度，0 表示不查找
+    depth: usize,
+}
+
+impl TemplateEngine {
+    /// 只使用指定的全局模板目录，不会检查或创建该目录
+    pub fn new(template_dir: impl Into<PathBuf>) -> TemplateEngine {
+        TemplateEngine {
+            layers: vec![Layer {
+                source: LayerSource::Global,
+                dir: template_dir.into(),
+            }],
+            lockfile: Lockfile::default(),
+            fallbacks: HashMap::new(),
+            index: None,
+            depth: DEFAULT_TEMPLATE_DEPTH,
+        }
+    }
+
+    /// 在子目录中查找同名模板的最大深度，0 表示只查找指定的目录
+    pub fn with_depth(mut self, depth: usize) -> TemplateEngine {
+        self.depth = depth;
+        self
+    }
+
+    /// 列出模板时使用并更新索引文件 `path`（见 [`crate::index`]）
+    pub fn with_index(mut self, path: impl Into<PathBuf>) -> TemplateEngine {
+        self.index = Some(path.into());
+        self
+    }
+
+    /// 按后缀指定找不到同名模板和 `default.<后缀>` 时使用的模板（带命名空间的模板名）
+    pub fn with_fallbacks(mut self, fallbacks: HashMap<String, String>) -> TemplateEngine {
+        self.fallbacks = fallbacks;
+        self
+    }
+
+    /// 使用 `lockfile` 中记录的校验和检查全局模板目录中的模板
+    pub fn with_lockfile(mut self, lockfile: Lockfile) -> TemplateEngine {
+        self.lockfile = lockfile;
+        self
+    }
+
+    /// 添加优先级高于全局模板目录的项目模板目录
+    pub fn with_project_dir(mut self, dir: impl Into<PathBuf>) -> TemplateEngine {
+        self.layers
+            .retain(|layer| layer.source != LayerSource::Project);
+        self.layers.insert(
+            0,
+            Layer {
+                source: LayerSource::Project,
+                dir: dir.into(),
+            },
+        );
+        self
+    }
+
+    /// 添加优先级低于全局模板目录的附加模板目录，多个附加目录按添加的顺序查找
+    pub fn with_extra_dir(mut self, dir: impl Into<PathBuf>) -> TemplateEngine {
+        self.add_layer(LayerSource::Extra, dir.into());
+        self
+    }
+
+    /// 使用优先级最低的系统模板目录
+    pub fn with_system_dir(mut self, dir: impl Into<PathBuf>) -> TemplateEngine {
+        self.layers
+            .retain(|layer| layer.source != LayerSource::System);
+        self.add_layer(LayerSource::System, dir.into());
+        self
+    }
+
+    /// 按来源的优先级插入一层，已经在其他层中的目录会被忽略
+    fn add_layer(&mut self, source: LayerSource, dir: PathBuf) {
+        if self.layers.iter().any(|layer| same_dir(&layer.dir, &dir)) {
+            log::debug!("skipping duplicate template directory {}", dir.display());
+            return;
+        }
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| layer.source > source)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(index, Layer { source, dir });
+    }
+
+    /// 使用配置中的模板目录，目录不存在时创建它并写入默认模板（配置 `no_defaults` 时不写入）；
+    /// 同时从当前目录向上查找项目模板目录
+    pub fn from_config(config: &Config) -> Result<TemplateEngine> {
+        let engine = TemplateEngine::discover(config)?;
+        engine.ensure_template_dir(!config.no_defaults)?;
+        Ok(engine)
+    }
+
+    /// 与 [`TemplateEngine::from_config`] 相同，但不创建任何目录
+    ///
+    /// 配置中 `template_paths` 列出的目录和存在的系统模板目录排在全局模板目录之后。
+    pub fn discover(config: &Config) -> Result<TemplateEngine> {
+        let mut engine = TemplateEngine::new(config.template_dir()?)
+            .with_lockfile(Lockfile::load(&lock::lock_path()?)?)
+            .with_fallbacks(config.fallbacks.clone());
+        log::debug!(
+            "global template directory: {}",
+            engine.template_dir().display()
+        );
+        if let Ok(dir) = config::cache_dir() {
+            engine = engine.with_index(dir.join(index::FILE_NAME));
+        }
+        if let Some(depth) = config.template_depth {
+            engine = engine.with_depth(depth);
+        }
+        for dir in &config.template_paths {
+            engine = engine.with_extra_dir(dir);
+        }
+        if let Some(dir) = system_template_dir().filter(|dir| dir.is_dir()) {
+            log::debug!("system template directory: {}", dir.display());
+            engine = engine.with_system_dir(dir);
+        }
+        let Ok(current_dir) = std::env::current_dir() else {
+            return Ok(engine);
+        };
+        Ok(
+            match find_project_template_dir(&current_dir, engine.template_dir()) {
+                Some(dir) => {
+                    log::debug!("project template directory: {}", dir.display());
+                    engine.with_project_dir(dir)
+                }
+                None => engine,
+            },
+        )
+    }
+
+    /// 全局模板目录路径，模板管理命令操作的是该目录
+    pub fn template_dir(&self) -> &Path {
+        self.layers
+            .iter()
+            .find(|layer| layer.source == LayerSource::Global)
+            .map(|layer| layer.dir.as_path())
+            .expect("the global template directory is always a layer")
+    }
+
+    /// 所有模板目录，按优先级从高到低排列
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// 路径所在的模板目录层
+    pub fn layer_of(&self, path: &Path) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .find(|layer| path.starts_with(&layer.dir))
+    }
+
+    /// 模板文件 `path` 在所在层中的带命名空间的模板名（例如 `web/index.html`），
+    /// 不在任何层中（例如远程模板）时为 None
+    pub fn qualified_name(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.layer_of(path)?.dir).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        Some(parts.join("/"))
+    }
+
+    /// 含有模板 `name`（例如 `web/index.html`）的所有层，按优先级从高到低排列；
+    /// 第一层中的模板会被使用，其余的被它遮蔽
+    pub fn layers_with(&self, name: &str) -> Vec<&Layer> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.dir.join(name).is_file())
+            .collect()
+    }
+
+    /// 如果全局模板目录不存在，创建它；`defaults` 为 true 时同时写入内置的默认模板
+    ///
+    /// 多个进程同时运行时（例如并行的 make 目标），由模板目录旁的锁文件
+    /// `.<目录名>.lock` 保证只有一个进程创建目录。默认模板先写入临时目录，
+    /// 完成后整体重命名为模板目录，因此其他进程不会看到写了一半的模板。
+    pub fn ensure_template_dir(&self, defaults: bool) -> Result<()> {
+        let template_dir = self.template_dir();
+        if template_dir.exists() {
+            return Ok(());
+        }
+        let context = || {
+            tr!(
+                "无法创建模板目录",
+                "failed to create the template directory"
+            )
+        };
+        let parent = match template_dir.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let name = template_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        fs::create_dir_all(parent).io_context(context)?;
+
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(parent.join(format!(".{}.lock", name)))
+            .io_context(context)?;
+        lock.lock().io_context(context)?;
+        // 等待锁期间其他进程可能已经创建好了模板目录
+        if template_dir.exists() {
+            return Ok(());
+        }
+
+        let staging = parent.join(format!(".{}.tmp-{}", name, std::process::id()));
+        let result = fs::create_dir(&staging).io_context(context).and_then(|()| {
+            if defaults {
+                DEFAULT_TEMPLATES.extract(&staging).io_context(|| {
+                    tr!(
+                        "无法写入默认模板到用户目录",
+                        "failed to write the default template to the user directory"
+                    )
+                })?;
+            }
+            fs::rename(&staging, template_dir).io_context(context)
+        });
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        result
+    }
+
+    /// 列出所有层中的模板（包括各命名空间），同名模板只保留优先级最高的一个
+    pub fn list_templates(&self) -> Result<Vec<TemplateInfo>> {
+        let mut index = self.index.as_deref().map(Index::load);
+        let mut templates: Vec<TemplateInfo> = Vec::new();
+        for layer in &self.layers {
+            if !layer.dir.is_dir() {
+                continue;
+            }
+            let listed = match &mut index {
+                Some(index) => index.list(&layer.dir)?,
+                None => template::list_templates(&layer.dir)?,
+            };
+            for info in listed {
+                if !templates
+                    .iter()
+                    .any(|t| t.qualified_name() == info.qualified_name())
+                {
+                    templates.push(info);
+                }
+            }
+        }
+        templates.sort_by_key(|t| t.qualified_name());
+        // 索引只是缓存，写入失败不影响结果
+        if let (Some(index), Some(path)) = (index, &self.index) {
+            if let Err(e) = index.save(path) {
+                log::debug!("{}", e);
+            }
+        }
+        Ok(templates)
+    }
+
+    /// 查找模板文件，返回找到的模板路径，查找顺序见 [`lookup_among`](Self::lookup_among)
+    pub fn find_template(&self, filename: &str, extension: &str) -> Option<PathBuf> {
+        self.find_template_in(None, filename, extension)
+    }
+
+    /// 与 [`find_template`](Self::find_template) 相同，但从命名空间 `namespace`
+    /// （各层模板目录下的同名子目录，例如 `web`）开始查找；为 None 时从模板目录根部开始
+    pub fn find_template_in(
+        &self,
+        namespace: Option<&str>,
+        filename: &str,
+        extension: &str,
+    ) -> Option<PathBuf> {
+        self.find_template_among(&[namespace], filename, extension)
+    }
+
+    /// 与 [`lookup_among`](Self::lookup_among) 相同，只返回找到的模板路径
+    pub fn find_template_among(
+        &self,
+        namespaces: &[Option<&str>],
+        filename: &str,
+        extension: &str,
+    ) -> Option<PathBuf> {
+        self.lookup_among(namespaces, filename, extension)
+            .path()
+            .map(Path::to_path_buf)
+    }
+
+    /// 依次在多个命名空间中查找模板，无效的命名空间会被跳过：
+    ///
+    /// 1. 所有命名空间中的同名模板 `<文件名>.<后缀>`
+    /// 2. 各命名空间的子目录中（不超过 [`with_depth`](Self::with_depth) 层）的同名模板，
+    ///    浅的优先，深度相同时按名称排序；以 `.` 开头的文件和目录（例如 `.git`）被忽略
+    /// 3. 所有命名空间中该后缀的默认模板 `default.<后缀>`
+    /// 4. 配置 `[fallbacks]` 中为该后缀指定的模板
+    ///
+    /// 都没有时返回 [`Lookup::Empty`]。
+    pub fn lookup_among(
+        &self,
+        namespaces: &[Option<&str>],
+        filename: &str,
+        extension: &str,
+    ) -> Lookup {
+        self.lookup_traced(namespaces, filename, extension, None)
+    }
+
+    /// 与 [`lookup_among`](Self::lookup_among) 相同，同时返回检查过的所有候选及其结果，
+    /// 用于解释为什么使用了某个模板；找到模板后仍会检查其余的候选，标记为被遮蔽
+    pub fn explain_among(
+        &self,
+        namespaces: &[Option<&str>],
+        filename: &str,
+        extension: &str,
+    ) -> (Lookup, Vec<Candidate>) {
+        let mut trace = Vec::new();
+        let lookup = self.lookup_traced(namespaces, filename, extension, Some(&mut trace));
+        (lookup, trace)
+    }
+
+    fn lookup_traced(
+        &self,
+        namespaces: &[Option<&str>],
+        filename: &str,
+        extension: &str,
+        mut trace: Option<&mut Vec<Candidate>>,
+    ) -> Lookup {
+        let tracing = trace.is_some();
+        let mut record = |step: Step, path: PathBuf, verdict: Verdict| {
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(Candidate {
+                    step,
+                    path,
+                    verdict,
+                });
+            }
+        };
+
+        let mut dirs = Vec::new();
+        for namespace in namespaces {
+            if let Some(namespace) = namespace {
+                if validate_namespace(namespace).is_err() {
+                    record(
+                        Step::Exact,
+                        PathBuf::from(namespace),
+                        Verdict::InvalidNamespace,
+                    );
+                    continue;
+                }
+            }
+            dirs.extend(self.layers.iter().map(|layer| match namespace {
+                Some(namespace) => layer.dir.join(namespace),
+                None => layer.dir.clone(),
+            }));
+        }
+
+        let specified_template_name = format!("{}.{}", filename, extension);
+        let default_name = format!("{}.{}", DEFAULT_TEMPLATE_NAME, extension);
+        let mut found = Lookup::Empty;
+        for (step, name) in [
+            (Step::Exact, &specified_template_name),
+            (Step::Nested, &specified_template_name),
+            (Step::Default, &default_name),
+        ] {
+            if step == Step::Nested {
+                if found.path().is_some() && !tracing {
+                    continue;
+                }
+                for template in self.nested_templates(namespaces, name) {
+                    let Some(layer) = self.layer_of(&template.path) else {
+                        continue;
+                    };
+                    let verdict = match check_candidate(&layer.dir, &template.qualified_name()) {
+                        Verdict::Used if found.path().is_some() => Verdict::Shadowed,
+                        Verdict::Used => {
+                            found = Lookup::Nested(template.path.clone());
+                            Verdict::Used
+                        }
+                        verdict => verdict,
+                    };
+                    record(step, template.path, verdict);
+                }
+                continue;
+            }
+            for dir in &dirs {
+                if found.path().is_some() && !tracing {
+                    break;
+                }
+                let path = dir.join(name);
+                let verdict = match check_candidate(dir, name) {
+                    Verdict::Used if found.path().is_some() => Verdict::Shadowed,
+                    Verdict::Used => {
+                        found = match step {
+                            Step::Exact => Lookup::Exact(path.clone()),
+                            _ => Lookup::Default(path.clone()),
+                        };
+                        Verdict::Used
+                    }
+                    // 每个目录只报告一次不存在
+                    Verdict::NoDirectory if step == Step::Default => continue,
+                    Verdict::NoDirectory => {
+                        record(step, dir.clone(), Verdict::NoDirectory);
+                        continue;
+                    }
+                    verdict => verdict,
+                };
+                record(step, path, verdict);
+                if step == Step::Exact && tracing {
+                    for other in other_extensions(dir, filename, extension) {
+                        record(step, other, Verdict::WrongExtension);
+                    }
+                }
+            }
+        }
+
+        match self.fallbacks.get(extension) {
+            None => record(Step::Fallback, PathBuf::new(), Verdict::NotConfigured),
+            Some(name) if found.path().is_none() || tracing => match self.resolve_template(name) {
+                Ok(path) if found.path().is_some() => {
+                    record(Step::Fallback, path, Verdict::Shadowed)
+                }
+                Ok(path) => {
+                    found = Lookup::Fallback(path.clone());
+                    record(Step::Fallback, path, Verdict::Used);
+                }
+                Err(e) => {
+                    if found.path().is_none() {
+                        log::warn!(
+                            "{}",
+                            tr!(
+                                "无法使用 .{} 的后备模板: {}",
+                                "cannot use the fallback template for .{}: {}",
+                                extension,
+                                e
+                            )
+                        );
+                    }
+                    record(Step::Fallback, PathBuf::from(name), Verdict::Missing);
+                }
+            },
+            Some(_) => {}
+        }
+
+        match found.path() {
+            Some(path) => log::debug!("using template {}", path.display()),
+            None => log::debug!("no template found for {}", specified_template_name),
+        }
+        found
+    }
+
+    /// 名为 `name`（不含后缀，例如 `report`）的模板，用于推断未指定的后缀
+    ///
+    /// 依次在各命名空间中查找，返回第一个有同名模板的命名空间中的全部模板（按后缀排序）；
+    /// 无效的命名空间会被跳过。都没有时与 [`lookup_among`](Self::lookup_among) 一样
+    /// 在子目录中查找，返回最浅的有同名模板的子目录中的全部模板。
+    pub fn templates_named(
+        &self,
+        namespaces: &[Option<&str>],
+        name: &str,
+    ) -> Result<Vec<TemplateInfo>> {
+        let templates = self.list_templates()?;
+        for namespace in namespaces {
+            if namespace.is_some_and(|namespace| validate_namespace(namespace).is_err()) {
+                continue;
+            }
+            let matches: Vec<TemplateInfo> = templates
+                .iter()
+                .filter(|t| t.namespace.as_deref() == *namespace && t.name == name)
+                .filter(|t| !t.extension.is_empty())
+                .cloned()
+                .collect();
+            if !matches.is_empty() {
+                return Ok(matches);
+            }
+        }
+        let nested = templates
+            .iter()
+            .filter(|t| t.name == name && !t.extension.is_empty())
+            .filter_map(|t| Some((self.nested_depth(namespaces, t)?, t)))
+            .min_by_key(|(depth, t)| (*depth, t.namespace.clone()));
+        let Some((_, first)) = nested else {
+            return Ok(Vec::new());
+        };
+        Ok(templates
+            .iter()
+            .filter(|t| t.namespace == first.namespace && t.name == name)
+            .filter(|t| !t.extension.is_empty())
+            .cloned()
+            .collect())
+    }
+
+    /// 各命名空间的子目录中文件名为 `file_name` 的模板，浅的在前
+    fn nested_templates(&self, namespaces: &[Option<&str>], file_name: &str) -> Vec<TemplateInfo> {
+        if self.depth == 0 {
+            return Vec::new();
+        }
+        let templates = match self.list_templates() {
+            Ok(templates) => templates,
+            Err(e) => {
+                log::debug!("{}", e);
+                return Vec::new();
+            }
+        };
+        let mut nested: Vec<(usize, TemplateInfo)> = templates
+            .into_iter()
+            .filter(|t| t.file_name() == file_name)
+            .filter_map(|t| Some((self.nested_depth(namespaces, &t)?, t)))
+            .collect();
+        nested.sort_by_key(|(depth, t)| (*depth, t.qualified_name()));
+        nested.into_iter().map(|(_, t)| t).collect()
+    }
+
+    /// 模板位于某个命名空间的子目录中时，返回相对于该命名空间的最小深度
+    ///
+    /// 超过深度限制、位于以 `.` 开头的目录中或文件名以 `.` 开头的模板不参与查找。
+    fn nested_depth(&self, namespaces: &[Option<&str>], template: &TemplateInfo) -> Option<usize> {
+        let namespace = template.namespace.as_deref()?;
+        if template.file_name().starts_with('.') {
+            return None;
+        }
+        namespaces
+            .iter()
+            .filter(|base| base.is_none_or(|base| validate_namespace(base).is_ok()))
+            .filter_map(|base| match base {
+                Some(base) => namespace.strip_prefix(base)?.strip_prefix('/'),
+                None => Some(namespace),
+            })
+            .filter(|rest| !rest.split('/').any(|dir| dir.starts_with('.')))
+            .map(|rest| rest.split('/').count())
+            .filter(|depth| *depth <= self.depth)
+            .min()
+    }
+
+    /// 按完整文件名（例如 `index.html` 或 `web/index.html`）在所有层中查找模板，
+    /// 返回优先级最高的那个
+    pub fn resolve_template(&self, name: &str) -> Result<PathBuf> {
+        validate_qualified_name(name)?;
+        match self
+            .layers
+            .iter()
+            .find(|layer| layer.dir.join(name).is_file())
+        {
+            Some(layer) => template::resolve_existing_template(&layer.dir, name),
+            None => Err(Error::TemplateNotFound(name.to_string())),
+        }
+    }
+
+    /// 查找名为 `name` 的模板清单（含有 `template.toml` 的子目录，见 [`manifest`]），
+    /// 返回优先级最高的清单目录
+    pub fn find_manifest(&self, name: &str) -> Option<PathBuf> {
+        validate_namespace(name).ok()?;
+        self.layers
+            .iter()
+            .map(|layer| layer.dir.join(name))
+            .find(|dir| dir.join(manifest::FILE_NAME).is_file())
+    }
+
+    /// 查找项目模板（子目录）所在的模板目录，未找到时返回全局模板目录
+    pub fn project_template_root(&self, name: &str) -> &Path {
+        self.layers
+            .iter()
+            .map(|layer| layer.dir.as_path())
+            .find(|dir| dir.join(name).is_dir())
+            .unwrap_or_else(|| self.template_dir())
+    }
+
+    /// 读取模板头部的 front matter，没有 front matter 或是二进制模板时返回 None
+    pub fn front_matter(&self, template: &Path) -> Result<Option<FrontMatter>> {
+        Ok(match self.load(template)? {
+            Loaded::Text { front_matter, .. } => front_matter,
+            Loaded::Binary(_) => None,
+        })
+    }
+
+    /// 渲染模板前需要用户提供的变量
+    ///
+    /// 包括 front matter 中声明但尚未定义的变量，以及正文中引用了但既未声明、
+    /// 也未定义的变量。二进制模板不需要任何变量。
+    pub fn variable_prompts(
+        &self,
+        template: &Path,
+        variables: &Variables,
+    ) -> Result<Vec<VariablePrompt>> {
+        let Loaded::Text { front_matter, body } = self.load(template)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut prompts: Vec<VariablePrompt> = Vec::new();
+        let mut known = variables.clone();
+        if let Some(front_matter) = &front_matter {
+            for (name, spec) in &front_matter.variables {
+                if !variables.contains_key(name) {
+                    prompts.push(VariablePrompt {
+                        name: name.clone(),
+                        description: spec.description.clone(),
+                        default: spec.default.clone(),
+                        required: spec.required,
+                    });
+                }
+                known.entry(name.clone()).or_default();
+            }
+        }
+
+        // 严格模式渲染时报告的未定义变量即为正文中未解析的占位符
+        if let Err(Error::UndefinedVariables(undefined)) = render::render(&body, &known, true) {
+            prompts.extend(undefined.into_iter().map(|name| VariablePrompt {
+                name,
+                description: None,
+                default: None,
+                required: false,
+            }));
+        }
+        Ok(prompts)
+    }
+
+    /// 检查模板能否渲染：front matter、块语法、过滤器和日期格式是否有误
+    ///
+    /// 与 [`render_template`](Self::render_template) 不同，不要求变量已定义，
+    /// 未进入的条件分支也不会被检查。二进制模板总是通过检查。
+    pub fn check_template(&self, template: &Path) -> Result<()> {
+        let Loaded::Text { front_matter, body } = self.load(template)? else {
+            return Ok(());
+        };
+        let mut variables = Variables::new();
+        if let Some(front_matter) = front_matter {
+            for name in front_matter.variables.keys() {
+                variables.insert(name.clone(), String::new());
+            }
+        }
+        render::render(&body, &variables, false)
+            .map(drop)
+            .map_err(|source| Error::Render {
+                name: template.display().to_string(),
+                source: Box::new(source),
+            })
+    }
+
+    /// 读取并渲染模板；`template` 为 None 时渲染结果为空
+    ///
+    /// 二进制模板不做变量替换，按字节原样返回。文本模板的 front matter
+    /// 不会出现在结果中，其中声明的默认值和必需变量会在渲染前生效。
+    pub fn render_template(
+        &self,
+        template: Option<&Path>,
+        variables: &Variables,
+        strict: bool,
+    ) -> Result<Vec<u8>> {
+        let Some(template) = template else {
+            return Ok(Vec::new());
+        };
+
+        let (front_matter, body) = match self.load(template)? {
+            Loaded::Binary(bytes) => return Ok(bytes),
+            Loaded::Text { front_matter, body } => (front_matter, body),
+        };
+        let with_name = |source| Error::Render {
+            name: template.display().to_string(),
+            source: Box::new(source),
+        };
+
+        let mut variables = variables.clone();
+        if let Some(front_matter) = front_matter {
+            front_matter.apply_defaults(&mut variables);
+            let missing = front_matter.missing_variables(&variables);
+            if !missing.is_empty() {
+                return Err(with_name(Error::MissingVariables(missing)));
+            }
+        }
+        render::render(&body, &variables, strict)
+            .map(String::into_bytes)
+            .map_err(with_name)
+    }
+
+    /// 文本模板展开引用和继承后的 front matter 和正文；二进制模板返回 None
+    pub fn text_source(&self, template: &Path) -> Result<Option<(Option<FrontMatter>, String)>> {
+        match self.load(template)? {
+            Loaded::Text { front_matter, body } => Ok(Some((front_matter, body))),
+            Loaded::Binary(_) => Ok(None),
+        }
+    }
+
+    /// 读取模板，并展开文本模板中的引用和继承（见 [`compose`]）
+    ///
+    /// 被引用的模板中声明的变量合并到当前模板的 front matter 中，当前模板中的声明优先。
+    fn load(&self, template: &Path) -> Result<Loaded> {
+        let (mut front_matter, body) = match self.read(template)? {
+            Loaded::Text { front_matter, body } => (front_matter, body),
+            binary => return Ok(binary),
+        };
+        let mut load_part = |name: &str| {
+            let path = self.resolve_template(name)?;
+            let path = fs::canonicalize(&path).unwrap_or(path);
+            match self.read(&path)? {
+                Loaded::Text { front_matter, body } => Ok(Part {
+                    path,
+                    front_matter,
+                    body,
+                }),
+                Loaded::Binary(_) => Err(Error::TemplateSyntax(tr!(
+                    "不能引用二进制模板 {}",
+                    "cannot include the binary template {}",
+                    name
+                ))),
+            }
+        };
+        let root = fs::canonicalize(template).unwrap_or_else(|_| template.to_path_buf());
+        let (body, inherited) =
+            compose::compose(&root, &body, &mut load_part).map_err(|source| Error::Render {
+                name: template.display().to_string(),
+                source: Box::new(source),
+            })?;
+        if !inherited.is_empty() {
+            let front_matter = front_matter.get_or_insert_with(FrontMatter::default);
+            for other in inherited {
+                for (name, spec) in other.variables {
+                    front_matter.variables.entry(name).or_insert(spec);
+                }
+            }
+        }
+        Ok(Loaded::Text { front_matter, body })
+    }
+
+    /// 读取模板文件，内容与锁文件中的记录不一致时返回 [`Error::TemplateDrifted`]
+    fn read(&self, template: &Path) -> Result<Loaded> {
+        let bytes = fs::read(template).io_context(|| {
+            tr!(
+                "无法读取模板文件: {:?}",
+                "failed to read template file: {:?}",
+                template
+            )
+        })?;
+        self.lockfile
+            .verify_template(self.template_dir(), template, &bytes)?;
+        parse(template, bytes)
+    }
+
+    /// [`create`](Self::create) 将要写入 `filename.extension` 的内容：渲染后同样统一换行、
+    /// 按 `.editorconfig` 调整并删除光标标记，用于写入前的预览；插入时为要插入的内容
+    pub fn output_content(
+        &self,
+        filename: &str,
+        extension: &str,
+        template: Option<&Path>,
+        options: &CreateOptions,
+    ) -> Result<Vec<u8>> {
+        validate_cli_inputs(filename, extension)?;
+        let content = self.render_with_cursor(template, options)?;
+        if options.insert.is_some() {
+            return Ok(cursor::take(content, &cursor_markers(options)).0);
+        }
+        // 与 create 一样按规范化后的路径查找 .editorconfig
+        let target_dir = options
+            .target_dir
+            .canonicalize()
+            .unwrap_or_else(|_| options.target_dir.clone());
+        let path = target_dir.join(format!("{}.{}", filename, extension));
+        Ok(normalize(content, &path, options)?.0)
+    }
+
+    /// 渲染模板，`{{cursor}}` 先渲染为占位字符串，定位后连同自定义标记一起删除
+    fn render_with_cursor(
+        &self,
+        template: Option<&Path>,
+        options: &CreateOptions,
+    ) -> Result<Vec<u8>> {
+        let mut variables = options.variables.clone();
+        variables
+            .entry(cursor::PLACEHOLDER.to_string())
+            .or_insert_with(|| cursor::SENTINEL.to_string());
+        self.render_template(template, &variables, options.strict_vars)
+    }
+
+    /// 使用 `template` 渲染出的内容在目标目录中创建 `filename.extension`
+    ///
+    /// `template` 为 None 时创建空文件。`options.target_dir` 必须已存在。
+    /// 写入前会统一换行（`options.eol`），并按目标位置生效的 `.editorconfig`
+    /// 调整缩进、结尾换行和编码；写入后按 front matter 的 `mode` 和
+    /// `options.executable` 设置权限。
+    pub fn create(
+        &self,
+        filename: &str,
+        extension: &str,
+        template: Option<&Path>,
+        options: &CreateOptions,
+    ) -> Result<Outcome> {
+        validate_cli_inputs(filename, extension)?;
+        let content = self.render_with_cursor(template, options)?;
+        let markers = cursor_markers(options);
+
+        // 创建目标文件名
+        let target_filename = format!("{}.{}", filename, extension);
+
+        // --- Path validation for target file ---
+        let canonical_target_dir = options.target_dir.canonicalize().io_context(|| {
+            tr!(
+                "无法规范化目标目录路径",
+                "failed to canonicalize the target directory"
+            )
+        })?;
+        let absolute_target_path = canonical_target_dir.join(&target_filename);
+        log::debug!(
+            "target directory {} canonicalized to {}",
+            options.target_dir.display(),
+            canonical_target_dir.display()
+        );
+
+        // Ensure the target path is directly within the canonical target directory
+        if absolute_target_path.parent() != Some(canonical_target_dir.as_path()) {
+            return Err(Error::OutsideTargetDir(absolute_target_path));
+        }
+        // --- End of path validation ---
+
+        if let Some(at) = &options.insert {
+            let (content, _) = cursor::take(content, &markers);
+            return insert_into(&absolute_target_path, &target_filename, content, at);
+        }
+
+        let mode = match template {
+            Some(template) => self.front_matter(template)?.and_then(|fm| fm.mode),
+            None => None,
+        };
+        let (content, cursor) = normalize(content, &absolute_target_path, options)?;
+
+        // 目标文件已存在时的处理：默认拒绝覆盖
+        let mut backup = None;
+        if absolute_target_path.exists() {
+            log::debug!(
+                "{} exists, overwrite policy {:?}",
+                absolute_target_path.display(),
+                options.overwrite
+            );
+            match options.overwrite {
+                Overwrite::Skip => {
+                    return Ok(Outcome::Skipped {
+                        path: absolute_target_path,
+                    })
+                }
+                Overwrite::Backup => {
+                    backup = Some(
+                        absolute_target_path.with_file_name(format!("{}.bak", target_filename)),
+                    );
+                }
+                Overwrite::Force => {}
+                Overwrite::Refuse => return Err(Error::TargetExists(target_filename)),
+            }
+        }
+
+        // 先完整写入临时文件，再备份原文件并重命名到位，中断时不会留下写了一半的文件
+        let create_context = || {
+            tr!(
+                "无法创建文件 {}",
+                "failed to create file {}",
+                target_filename
+            )
+        };
+        let temp = write_temp(&absolute_target_path, &content).io_context(create_context)?;
+        if let Some(backup_path) = &backup {
+            if let Err(e) = fs::rename(&absolute_target_path, backup_path) {
+                let _ = fs::remove_file(&temp);
+                return Err(e).io_context(|| {
+                    tr!(
+                        "无法备份文件 {}",
+                        "failed to back up file {}",
+                        target_filename
+                    )
+                });
+            }
+        }
+        persist(&temp, &absolute_target_path).io_context(create_context)?;
+        permissions::apply(&absolute_target_path, mode, options.executable)?;
+
+        Ok(Outcome::Created {
+            path: absolute_target_path,
+            backup,
+            cursor,
+        })
+    }
+}
+
+/// 读取后的模板内容
+enum Loaded {
+    /// 二进制模板，按字节原样输出
+    Binary(Vec<u8>),
+    /// 文本模板，已拆分出 front matter
+    Text {
+        front_matter: Option<FrontMatter>,
+        body: String,
+    },
+}
+
+/// 解析模板文件的内容，文本模板会拆分出 front matter
+fn parse(template: &Path, bytes: Vec<u8>) -> Result<Loaded> {
+    if render::is_binary(&bytes) {
+        return Ok(Loaded::Binary(bytes));
+    }
+
+    let content = String::from_utf8_lossy(&bytes);
+    let (front_matter, body) = front_matter::split(&content).map_err(|source| Error::Render {
+        name: template.display().to_string(),
+        source: Box::new(Error::FrontMatter(source)),
+    })?;
+    Ok(Loaded::Text {
+        front_matter,
+        body: body.to_string(),
+    })
+}
+
+/// 两个路径是否指向同一个目录，目录不存在时直接比较路径
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 检查模板目录 `template_dir` 中的候选模板 `template_name`
+///
+/// 模板的真实路径必须仍在模板目录中，指向别处的符号链接不会被使用。
+fn check_candidate(template_dir: &Path, template_name: &str) -> Verdict {
+    let Ok(canonical_template_dir) = fs::canonicalize(template_dir) else {
+        return Verdict::NoDirectory;
+    };
+    let path = template_dir.join(template_name);
+    log::trace!("checking {}", path.display());
+    if !path.exists() {
+        // 失效的符号链接本身存在，但无法解析
+        return match fs::symlink_metadata(&path) {
+            Ok(_) => Verdict::Unresolvable,
+            Err(_) => Verdict::Missing,
+        };
+    }
+    match fs::canonicalize(&path) {
+        Ok(canonical) if canonical.starts_with(&canonical_template_dir) => Verdict::Used,
+        Ok(canonical) => {
+            log::debug!(
+                "ignoring {}: it resolves to {} outside {}",
+                path.display(),
+                canonical.display(),
+                canonical_template_dir.display()
+            );
+            Verdict::OutsideDirectory(canonical)
+        }
+        Err(_) => Verdict::Unresolvable,
+    }
+}
+
+/// 模板目录中与 `filename` 同名但后缀不是 `extension` 的模板
+fn other_extensions(template_dir: &Path, filename: &str, extension: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.", filename);
+    let Ok(entries) = fs::read_dir(template_dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(std::result::Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.strip_prefix(&prefix)
+                .is_some_and(|rest| !rest.is_empty() && rest != extension)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// 将渲染结果插入已存在的目标文件
+fn insert_into(
+    path: &Path,
+    target_filename: &str,
+    content: Vec<u8>,
+    at: &Insertion,
+) -> Result<Outcome> {
+    if !path.is_file() {
+        return Err(Error::InsertTargetMissing(target_filename.to_string()));
+    }
+    let existing = fs::read(path)
+        .io_context(|| tr!("无法读取文件 {}", "failed to read file {}", target_filename))?;
+    let (Ok(existing), Ok(content)) = (String::from_utf8(existing), String::from_utf8(content))
+    else {
+        return Err(Error::InsertNotText(target_filename.to_string()));
+    };
+
+    let updated = insert::insert(&existing, &content, at, target_filename)?;
+    write_atomic(path, updated.as_bytes()).io_context(|| {
+        tr!(
+            "无法写入文件 {}",
+            "failed to write file {}",
+            target_filename
+        )
+    })?;
+    Ok(Outcome::Inserted {
+        path: path.to_path_buf(),
+    })
+}
+
+/// 先写入临时文件再重命名为 `path`，中途失败时不会留下写了一半的文件
+pub fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    write_temp(path, content).and_then(|temp| persist(&temp, path))
+}
+
+/// 光标占位字符串和 `options.cursor_marker`
+fn cursor_markers(options: &CreateOptions) -> Vec<&str> {
+    let mut markers = vec![cursor::SENTINEL];
+    markers.extend(options.cursor_marker.as_deref());
+    markers
+}
+
+/// 写入 `path` 前统一换行并按 `.editorconfig` 调整，然后删除光标标记并返回其位置
+fn normalize(
+    content: Vec<u8>,
+    path: &Path,
+    options: &CreateOptions,
+) -> Result<(Vec<u8>, Option<cursor::Position>)> {
+    let content = match options.eol {
+        Some(eol) => eol.normalize(content),
+        None => content,
+    };
+    let content = if options.ignore_editorconfig {
+        content
+    } else {
+        EditorConfig::lookup(path)?.apply(content)
+    };
+    // 换行、缩进和 BOM 都会改变标记的位置，因此在所有转换之后再定位
+    Ok(cursor::take(content, &cursor_markers(options)))
+}
+
+/// 把 `content` 写入 `path` 所在目录中的临时文件并同步到磁盘，返回临时文件的路径
+///
+/// `path` 已存在时临时文件沿用它的权限，替换后与原文件一致。
+fn write_temp(path: &Path, content: &[u8]) -> std::io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+    let written = fs::File::create_new(&temp).and_then(|mut file| {
+        file.write_all(content)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()
+    });
+    match written {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// 将 [`write_temp`] 写好的临时文件重命名为 `path`，失败时删除临时文件
+fn persist(temp: &Path, path: &Path) -> std::io::Result<()> {
+    fs::rename(temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(temp);
+    })
+}
@@ -0,0 +1,87 @@
+//! 统一新建文件的换行符
+//!
+//! 在 Windows 上编写的模板常带有 CRLF，在 Linux 上编写的则是 LF；
+//! 指定换行风格后，写入的文件中所有换行都会被转换为同一种。
+
+use serde::Deserialize;
+
+use crate::render;
+use crate::tr;
+
+/// 换行风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Eol {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// 当前平台的换行：Windows 上为 CRLF，其他平台为 LF
+    Native,
+}
+
+impl Eol {
+    /// 解析 `--eol` 的值：`lf`、`crlf` 或 `native`
+    pub fn parse(value: &str) -> std::result::Result<Eol, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "lf" => Ok(Eol::Lf),
+            "crlf" => Ok(Eol::Crlf),
+            "native" => Ok(Eol::Native),
+            _ => Err(tr!(
+                "不支持的换行风格 '{}'，可选 lf、crlf 或 native",
+                "unsupported line ending '{}', expected lf, crlf or native",
+                value
+            )),
+        }
+    }
+
+    /// 实际使用的换行符
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Eol::Lf => "\n",
+            Eol::Crlf => "\r\n",
+            Eol::Native if cfg!(windows) => "\r\n",
+            Eol::Native => "\n",
+        }
+    }
+
+    /// 将文本内容中的换行统一为该风格；二进制内容原样返回
+    pub fn normalize(self, content: Vec<u8>) -> Vec<u8> {
+        if render::is_binary(&content) {
+            return content;
+        }
+        let text = match String::from_utf8(content) {
+            Ok(text) => text,
+            Err(err) => return err.into_bytes(),
+        };
+        let lf = text.replace("\r\n", "\n");
+        match self.as_str() {
+            "\n" => lf.into_bytes(),
+            eol => lf.replace('\n', eol).into_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Eol::parse("lf"), Ok(Eol::Lf));
+        assert_eq!(Eol::parse("CRLF"), Ok(Eol::Crlf));
+        assert_eq!(Eol::parse("native"), Ok(Eol::Native));
+        assert!(Eol::parse("cr").is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mixed = b"a\r\nb\nc\r\n".to_vec();
+        assert_eq!(Eol::Lf.normalize(mixed.clone()), b"a\nb\nc\n");
+        assert_eq!(Eol::Crlf.normalize(mixed), b"a\r\nb\r\nc\r\n");
+        assert_eq!(Eol::Crlf.normalize(b"a\r\n".to_vec()), b"a\r\n");
+
+        let binary = vec![0, b'\n', 1];
+        assert_eq!(Eol::Crlf.normalize(binary.clone()), binary);
+    }
+}
@@ -0,0 +1,466 @@
+//! 库中使用的错误类型
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::tr;
+
+/// new-cli 库的结果类型
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// new-cli 库可能返回的所有错误
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{}", tr!("错误：文件名不能为空。", "Error: the filename must not be empty."))]
+    EmptyFilename,
+
+    #[error("{}", tr!("错误：文件后缀不能为空。", "Error: the extension must not be empty."))]
+    EmptyExtension,
+
+    #[error("{}", tr!(
+        "错误：文件名 '{}' 包含无效字符 '{}'。",
+        "Error: filename '{}' contains invalid characters '{}'.",
+        .name,
+        .invalid
+    ))]
+    InvalidFilename { name: String, invalid: &'static str },
+
+    #[error("{}", tr!(
+        "错误：文件后缀 '{}' 包含无效字符 '{}'。",
+        "Error: extension '{}' contains invalid characters '{}'.",
+        .extension,
+        .invalid
+    ))]
+    InvalidExtension {
+        extension: String,
+        invalid: &'static str,
+    },
+
+    #[error("{}", tr!("错误：模板名不能为空。", "Error: the template name must not be empty."))]
+    EmptyTemplateName,
+
+    #[error("{}", tr!(
+        "错误：模板名 '{}' 包含无效字符 '{}'。",
+        "Error: template name '{}' contains invalid characters '{}'.",
+        .name,
+        .invalid
+    ))]
+    InvalidTemplateName { name: String, invalid: &'static str },
+
+    #[error("{}", tr!("模板 {} 不存在", "template {} does not exist", .0))]
+    TemplateNotFound(String),
+
+    #[error("{}", tr!(
+        "错误：模板 {} 不在模板目录内，拒绝操作。",
+        "Error: template {} is outside the template directory, refusing to continue.",
+        .0
+    ))]
+    OutsideTemplateDir(String),
+
+    #[error("{}", tr!(
+        "模板中存在未定义的变量: {}",
+        "the template uses undefined variables: {}",
+        .0.join(", ")
+    ))]
+    UndefinedVariables(Vec<String>),
+
+    #[error("{}", tr!(
+        "缺少必需的变量: {}",
+        "missing required variables: {}",
+        .0.join(", ")
+    ))]
+    MissingVariables(Vec<String>),
+
+    #[error("{}", tr!("模板头部 front matter 格式错误", "invalid template front matter"))]
+    FrontMatter(#[source] toml::de::Error),
+
+    #[error("{}", tr!(
+        "模板建议的输出目录 {:?} 无效，必须是不含 .. 的相对路径",
+        "invalid output directory {:?} in template, it must be a relative path without ..",
+        .0
+    ))]
+    InvalidOutputDir(PathBuf),
+
+    #[error("{}", tr!(
+        "模板循环引用: {}",
+        "templates include each other in a cycle: {}",
+        .0.join(" -> ")
+    ))]
+    IncludeCycle(Vec<String>),
+
+    #[error("{}", tr!("无效的日期格式: {}", "invalid date format: {}", .0))]
+    InvalidDateFormat(String),
+
+    #[error("{}", tr!(
+        "无效的文件名模式 {}，序号位数应为 1 到 20，例如 {{n:4}}",
+        "invalid filename pattern {}, the sequence width must be 1 to 20, e.g. {{n:4}}",
+        .0
+    ))]
+    InvalidFilenamePattern(String),
+
+    #[error("{}", tr!(
+        "无效的正则表达式 {}: {}",
+        "invalid regular expression {}: {}",
+        pattern,
+        message
+    ))]
+    InvalidRegex { pattern: String, message: String },
+
+    #[error("{}", tr!(
+        "未知的过滤器 '{}'，可用的过滤器: {}",
+        "unknown filter '{}', available filters: {}",
+        .0,
+        crate::filters::NAMES.join(", ")
+    ))]
+    UnknownFilter(String),
+
+    #[error("{}", tr!("模板语法错误: {}", "template syntax error: {}", .0))]
+    TemplateSyntax(String),
+
+    #[error("{}", tr!("无法渲染模板 {}", "failed to render template {}", .name))]
+    Render {
+        name: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}", tr!("项目模板 {} 不存在", "project template {} does not exist", .0))]
+    ProjectTemplateNotFound(String),
+
+    #[error("{}", tr!("目录 {} 已存在", "directory {} already exists", .0))]
+    DirectoryExists(String),
+
+    #[error("{}", tr!(
+        "文件名 {} 渲染为无效路径 '{}'",
+        "file name {} renders to invalid path '{}'",
+        .name,
+        .rendered
+    ))]
+    InvalidRenderedName { name: String, rendered: String },
+
+    #[error("{}", tr!(
+        "错误：目标文件路径 '{:?}' 不在目标目录内。",
+        "Error: target path '{:?}' is outside the target directory.",
+        .0
+    ))]
+    OutsideTargetDir(PathBuf),
+
+    #[error("{}", tr!("错误：文件 {} 已存在。", "Error: file {} already exists.", .0))]
+    TargetExists(String),
+
+    #[error("{}", tr!(
+        "文件 {} 不存在，无法插入内容",
+        "file {} does not exist, nothing to insert into",
+        .0
+    ))]
+    InsertTargetMissing(String),
+
+    #[error("{}", tr!(
+        "无法向文件 {} 插入内容：模板或目标文件不是文本文件",
+        "cannot insert into {}: the template or the target is not a text file",
+        .0
+    ))]
+    InsertNotText(String),
+
+    #[error("{}", tr!(
+        "文件 {} 只有 {} 行，无法插入到第 {} 行",
+        "file {} has only {} lines, cannot insert at line {}",
+        .file,
+        .lines,
+        .line
+    ))]
+    LineOutOfRange {
+        file: String,
+        line: usize,
+        lines: usize,
+    },
+
+    #[error("{}", tr!(
+        "文件 {} 中没有找到标记 '{}'",
+        "marker '{1}' not found in file {0}",
+        .file,
+        .marker
+    ))]
+    MarkerNotFound { file: String, marker: String },
+
+    #[error("{}", tr!("钩子命令 '{}' 格式错误", "invalid hook command '{}'", .0))]
+    InvalidHook(String),
+
+    #[error("{}", match .status {
+        Some(code) => tr!(
+            "钩子命令 '{}' 执行失败 (退出码 {})",
+            "hook command '{}' failed (exit code {})",
+            .command,
+            code
+        ),
+        None => tr!("钩子命令 '{}' 执行失败", "hook command '{}' failed", .command),
+    })]
+    HookFailed {
+        command: String,
+        status: Option<i32>,
+    },
+
+    #[error("{}", tr!(
+        "无效的模板地址 '{}'，需要以 http:// 或 https:// 开头并指向一个文件",
+        "invalid template URL '{}', it must start with http:// or https:// and point to a file",
+        .0
+    ))]
+    InvalidUrl(String),
+
+    #[error("{}", tr!(
+        "无效的 GitHub 模板来源 '{}'，格式为 gh:owner/repo#path/to/file@ref",
+        "invalid GitHub template source '{}', expected gh:owner/repo#path/to/file@ref",
+        .0
+    ))]
+    InvalidGithubSource(String),
+
+    #[error("{}", tr!("无法下载 {}: {}", "failed to download {}: {}", .url, .message))]
+    Download { url: String, message: String },
+
+    #[error("{}", tr!(
+        "离线模式下不能访问 {}",
+        "cannot access {} in offline mode",
+        .0
+    ))]
+    Offline(String),
+
+    #[error("{}", tr!(
+        "{} 超过了下载大小限制 {} 字节",
+        "{} exceeds the download size limit of {} bytes",
+        .url,
+        .limit
+    ))]
+    DownloadTooLarge { url: String, limit: u64 },
+
+    #[error("{}", tr!(
+        "{} 的 SHA-256 校验失败：期望 {}，实际为 {}",
+        "SHA-256 mismatch for {}: expected {}, got {}",
+        .url,
+        .expected,
+        .actual
+    ))]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{}", tr!(
+        "模板 {} 的内容与锁文件中的记录不一致：期望 SHA-256 {}，实际为 {}；确认改动后运行 new-cli template verify --update",
+        "template {} does not match the lock file: expected SHA-256 {}, got {}; run new-cli template verify --update after reviewing the change",
+        .name,
+        .expected,
+        .actual
+    ))]
+    TemplateDrifted {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{}", tr!("签名校验失败: {}", "signature verification failed: {}", .0))]
+    Signature(String),
+
+    #[error("{}", tr!(
+        "模板包 {} 没有签名，当前配置要求模板包必须签名",
+        "template pack {} is not signed, but signatures are required",
+        .0
+    ))]
+    UnsignedPack(String),
+
+    #[error("{}", tr!("锁文件 {:?} 无效: {}", "invalid lock file {:?}: {}", .path, .message))]
+    Lockfile { path: PathBuf, message: String },
+
+    #[error("{}", tr!(
+        "未知的 .gitignore 规则集 '{}'，内置的规则集可以用 new-cli gitignore --list 查看",
+        "unknown .gitignore profile '{}', run new-cli gitignore --list to see the bundled ones",
+        .0
+    ))]
+    UnknownGitignore(String),
+
+    #[error("{}", tr!(
+        "未知的许可证 '{}'，内置的许可证可以用 new-cli license --list 查看",
+        "unknown license '{}', run new-cli license --list to see the bundled ones",
+        .0
+    ))]
+    UnknownLicense(String),
+
+    #[error("{}", tr!(
+        "无法从标题 {:?} 生成文件名，标题中至少需要一个字母或数字",
+        "cannot derive a file name from the title {:?}, it needs at least one letter or digit",
+        .0
+    ))]
+    InvalidAdrTitle(String),
+
+    #[error("{}", tr!(
+        "没有配置模板注册表，请在配置文件中设置 registry",
+        "no template registry configured, set registry in the config file"
+    ))]
+    NoRegistry,
+
+    #[error("{}", tr!("模板注册表 {} 格式错误", "invalid template registry {}", .location))]
+    Registry {
+        location: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("{}", tr!(
+        "模板注册表中没有 {}",
+        "{} is not in the template registry",
+        .0
+    ))]
+    RegistryEntryNotFound(String),
+
+    #[error("{}", tr!(
+        "不支持的模板包格式 '{}'，需要 .tar.gz、.tgz 或 .zip",
+        "unsupported template pack '{}', expected .tar.gz, .tgz or .zip",
+        .0
+    ))]
+    UnsupportedPack(String),
+
+    #[error("{}", tr!("模板包 {:?} 无效: {}", "invalid template pack {:?}: {}", .path, .message))]
+    Pack { path: PathBuf, message: String },
+
+    #[error("{}", tr!(
+        "模板包 {} 解压后超过了大小限制 {} 字节",
+        "template pack {} exceeds the unpacked size limit of {} bytes",
+        .pack,
+        .limit
+    ))]
+    PackTooLarge { pack: String, limit: u64 },
+
+    #[error("{}", tr!(
+        "模板 '{}' 已存在，使用 --force 覆盖",
+        "template '{}' already exists, use --force to overwrite",
+        .0
+    ))]
+    PackConflict(String),
+
+    #[error("{}", tr!("变量文件 {:?} 无效: {}", "invalid variables file {:?}: {}", .path, .message))]
+    VarsFile { path: PathBuf, message: String },
+
+    #[error("{}", tr!("批量生成清单 {:?} 无效: {}", "invalid batch spec {:?}: {}", .path, .message))]
+    BatchSpec { path: PathBuf, message: String },
+
+    #[error("{}", tr!("cookiecutter 模板 {:?} 无效: {}", "invalid cookiecutter template {:?}: {}", .path, .message))]
+    Cookiecutter { path: PathBuf, message: String },
+
+    #[error("{}", tr!("代码片段文件 {:?} 无效: {}", "invalid snippets file {:?}: {}", .path, .message))]
+    Snippets { path: PathBuf, message: String },
+
+    #[error("{}", tr!("WASM 插件 {:?} 无效: {}", "invalid WASM plugin {:?}: {}", .path, .message))]
+    WasmPlugin { path: PathBuf, message: String },
+
+    #[error("{}", tr!("自更新失败: {}", "self-update failed: {}", .0))]
+    SelfUpdate(String),
+
+    #[error("{}", tr!("无法获取主目录", "cannot determine the home directory"))]
+    NoHomeDir,
+
+    #[error("{}", tr!("配置文件格式错误: {:?}", "invalid config file: {:?}", .path))]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("{}", tr!("模板清单格式错误: {:?}", "invalid template manifest: {:?}", .path))]
+    Manifest {
+        path: PathBuf,
+        #[source]
+        source: Box<toml::de::Error>,
+    },
+
+    #[error("{}", tr!(
+        "模板清单 {:?} 中没有任何 [[files]]",
+        "template manifest {:?} does not list any [[files]]",
+        .0
+    ))]
+    EmptyManifest(PathBuf),
+
+    #[error("{}", tr!(
+        "模板清单中的输出路径 {:?} 无效，必须是不含 .. 的相对路径",
+        "invalid output path {:?} in template manifest, it must be a relative path without ..",
+        .0
+    ))]
+    InvalidManifestPath(PathBuf),
+
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// 命令行程序的退出码，脚本可以据此区分失败的原因
+///
+/// 参数解析错误由 clap 以 2 退出，与 [`ExitCode::InvalidInput`] 一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// 其他错误
+    Failure = 1,
+    /// 文件名、后缀、模板名等输入无效
+    InvalidInput = 2,
+    /// 找不到模板
+    TemplateNotFound = 3,
+    /// 目标文件已存在
+    TargetExists = 4,
+    /// 无法启动编辑器
+    Editor = 5,
+}
+
+impl ExitCode {
+    /// 传给 [`std::process::exit`] 的数值
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+impl Error {
+    /// 该错误对应的退出码
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Error::EmptyFilename
+            | Error::EmptyExtension
+            | Error::InvalidFilename { .. }
+            | Error::InvalidExtension { .. }
+            | Error::EmptyTemplateName
+            | Error::InvalidTemplateName { .. }
+            | Error::UndefinedVariables(_)
+            | Error::MissingVariables(_)
+            | Error::InvalidDateFormat(_)
+            | Error::InvalidFilenamePattern(_)
+            | Error::InvalidRegex { .. }
+            | Error::InvalidRenderedName { .. }
+            | Error::OutsideTargetDir(_)
+            | Error::InvalidUrl(_)
+            | Error::InvalidGithubSource(_)
+            | Error::UnknownGitignore(_)
+            | Error::UnknownLicense(_)
+            | Error::InvalidAdrTitle(_)
+            | Error::BatchSpec { .. } => ExitCode::InvalidInput,
+            Error::TemplateNotFound(_)
+            | Error::ProjectTemplateNotFound(_)
+            | Error::RegistryEntryNotFound(_) => ExitCode::TemplateNotFound,
+            Error::TargetExists(_) | Error::DirectoryExists(_) => ExitCode::TargetExists,
+            Error::Render { source, .. } => source.exit_code(),
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// 为 IO 错误附加说明文字，转换为 [`Error::Io`]
+pub trait IoContext<T> {
+    fn io_context(self, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn io_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| Error::Io {
+            context: context(),
+            source,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+//! 文件名模式
+//!
+//! 要创建的文件名中可以使用以下占位符：
+//!
+//! - `{date}`、`{time}`、`{datetime}`、`{year}`、`{timestamp}`，以及 `{date:%Y%m%d}` 形式的
+//!   带格式日期，格式与模板中的日期变量相同
+//! - `{n}`：目标目录中下一个未使用的序号，默认补零到 3 位（`note-{n}` -> `note-001`），
+//!   `{n:4}` 指定位数
+//!
+//! 其他花括号内容原样保留。
+
+use chrono::{DateTime, Local};
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoContext, Result};
+use crate::tr;
+use crate::variables;
+
+/// 序号的默认位数
+pub const DEFAULT_WIDTH: usize = 3;
+
+/// 文件名中是否有序号占位符 `{n}`
+pub fn has_number(pattern: &str) -> bool {
+    split_number(pattern).is_some()
+}
+
+/// 替换文件名中的日期占位符，`{n}` 保留
+pub fn expand(pattern: &str) -> Result<String> {
+    expand_at(pattern, Local::now())
+}
+
+fn expand_at(pattern: &str, now: DateTime<Local>) -> Result<String> {
+    let mut output = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(len) = rest.find('}') else {
+            break;
+        };
+        let token = &rest[1..len];
+        let value = match token {
+            "date" | "time" | "datetime" | "year" | "timestamp" => {
+                let format = match token {
+                    "date" => "%Y-%m-%d",
+                    "time" => "%H-%M-%S",
+                    "datetime" => "%Y-%m-%d-%H%M%S",
+                    "year" => "%Y",
+                    _ => "%s",
+                };
+                Some(now.format(format).to_string())
+            }
+            _ if token.starts_with("date:")
+                || token.starts_with("datetime:")
+                || token.starts_with("time:") =>
+            {
+                variables::resolve_formatted_at(token, now)?
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[..=len]),
+        }
+        rest = &rest[len + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// 按目标目录中已有的文件，把 `{n}` 替换为下一个未使用的序号
+///
+/// 例如目录中已有 `note-001.md` 和 `note-002.md` 时，`note-{n}` 得到 `note-003`。
+/// 没有 `{n}` 时原样返回。
+pub fn next_free(dir: &Path, pattern: &str, extension: &str) -> Result<String> {
+    let Some((prefix, width, suffix)) = split_number(pattern) else {
+        return Ok(pattern.to_string());
+    };
+    let width = width?;
+    let tail = format!("{}.{}", suffix, extension);
+    let mut max = 0;
+    if dir.is_dir() {
+        let entries = fs::read_dir(dir)
+            .io_context(|| tr!("无法读取目录 {:?}", "failed to read directory {:?}", dir))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(digits) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix))
+                .and_then(|name| name.strip_suffix(&tail))
+            else {
+                continue;
+            };
+            if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                max = max.max(digits.parse::<u64>().unwrap_or(0));
+            }
+        }
+    }
+    Ok(format!(
+        "{}{:0width$}{}",
+        prefix,
+        max + 1,
+        suffix,
+        width = width
+    ))
+}
+
+/// 拆分出 `{n}` 前后的部分和序号位数；只认 `{n}` 和 `{n:位数}`，`{name}` 等其他占位符原样保留
+fn split_number(pattern: &str) -> Option<(&str, Result<usize>, &str)> {
+    let start = ["{n}", "{n:"]
+        .into_iter()
+        .filter_map(|token| pattern.find(token))
+        .min()?;
+    let len = pattern[start..].find('}')?;
+    let token = &pattern[start + 1..start + len];
+    let width = match token {
+        "n" => Ok(DEFAULT_WIDTH),
+        _ => {
+            let width = token.strip_prefix("n:")?;
+            width
+                .parse::<usize>()
+                .ok()
+                .filter(|width| (1..=20).contains(width))
+                .ok_or_else(|| Error::InvalidFilenamePattern(pattern.to_string()))
+        }
+    };
+    Some((&pattern[..start], width, &pattern[start + len + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_expand() {
+        let now = Local.with_ymd_and_hms(2025, 3, 7, 9, 5, 30).unwrap();
+        assert_eq!(expand_at("note-{date}", now).unwrap(), "note-2025-03-07");
+        assert_eq!(
+            expand_at("{date:%Y%m%d}-{n}-{other}", now).unwrap(),
+            "20250307-{n}-{other}"
+        );
+        assert_eq!(expand_at("log-{time}", now).unwrap(), "log-09-05-30");
+        assert_eq!(expand_at("open{", now).unwrap(), "open{");
+        assert!(expand_at("{date:%Q}", now).is_err());
+    }
+
+    #[test]
+    fn test_next_free() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(next_free(dir.path(), "note-{n}", "md").unwrap(), "note-001");
+
+        for name in ["note-001.md", "note-007.md", "note-9.txt", "note-x.md"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert_eq!(next_free(dir.path(), "note-{n}", "md").unwrap(), "note-008");
+        assert_eq!(
+            next_free(dir.path(), "note-{n:2}", "txt").unwrap(),
+            "note-10"
+        );
+        assert_eq!(
+            next_free(dir.path(), "{n:4}-adr", "md").unwrap(),
+            "0001-adr"
+        );
+        assert_eq!(next_free(dir.path(), "plain", "md").unwrap(), "plain");
+        assert!(next_free(dir.path(), "note-{n:x}", "md").is_err());
+        assert!(!has_number("note-{name}"));
+        assert_eq!(
+            next_free(dir.path(), "{name}-{n}", "md").unwrap(),
+            "{name}-001"
+        );
+        assert_eq!(
+            next_free(dir.path(), "{nope}-{n:2}", "md").unwrap(),
+            "{nope}-01"
+        );
+    }
+}
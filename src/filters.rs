@@ -0,0 +1,156 @@
+//! 变量过滤器：`{{name | pascal}}` 这类大小写转换
+//!
+//! 过滤器按从左到右的顺序依次应用，例如 `{{name | snake | upper}}`。
+//! 转换前会把值拆分为单词：空白、`-`、`_` 等非字母数字字符以及
+//! 小写到大写的变化处都视为单词边界，因此 `my widget`、`my-widget`
+//! 和 `myWidget` 的转换结果相同。
+//!
+//! 启用 `wasm` 功能时，内置过滤器之外的名称由 [`crate::wasm`] 中的插件提供。
+
+use crate::error::{Error, Result};
+
+/// 所有可用的过滤器名称
+pub const NAMES: &[&str] = &[
+    "upper", "lower", "pascal", "camel", "snake", "kebab", "constant", "title",
+];
+
+/// 对 `value` 应用名为 `filter` 的过滤器
+pub fn apply(filter: &str, value: &str) -> Result<String> {
+    let words = || split_words(value);
+    let capitalized = || words().iter().map(|w| capitalize(w)).collect::<Vec<_>>();
+    Ok(match filter {
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "pascal" => capitalized().concat(),
+        "camel" => {
+            let mut words = capitalized();
+            if let Some(first) = words.first_mut() {
+                *first = first.to_lowercase();
+            }
+            words.concat()
+        }
+        "snake" => lower_words(&words()).join("_"),
+        "kebab" => lower_words(&words()).join("-"),
+        "constant" => words()
+            .iter()
+            .map(|w| w.to_uppercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+        "title" => capitalized().join(" "),
+        _ => return plugin_filter(filter, value),
+    })
+}
+
+#[cfg(feature = "wasm")]
+fn plugin_filter(filter: &str, value: &str) -> Result<String> {
+    crate::wasm::installed()
+        .apply(filter, value)
+        .unwrap_or_else(|| Err(Error::UnknownFilter(filter.to_string())))
+}
+
+#[cfg(not(feature = "wasm"))]
+fn plugin_filter(filter: &str, _value: &str) -> Result<String> {
+    Err(Error::UnknownFilter(filter.to_string()))
+}
+
+/// 确认过滤器存在，不存在时返回 [`Error::UnknownFilter`]
+pub fn check(filter: &str) -> Result<()> {
+    if NAMES.contains(&filter) || has_plugin_filter(filter) {
+        Ok(())
+    } else {
+        Err(Error::UnknownFilter(filter.to_string()))
+    }
+}
+
+fn has_plugin_filter(filter: &str) -> bool {
+    #[cfg(feature = "wasm")]
+    return crate::wasm::installed().has_filter(filter);
+    #[cfg(not(feature = "wasm"))]
+    {
+        let _ = filter;
+        false
+    }
+}
+
+fn lower_words(words: &[String]) -> Vec<String> {
+    words.iter().map(|w| w.to_lowercase()).collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// 将值拆分为单词，例如 `HTMLParser v2` 拆分为 `HTML`、`Parser`、`v2`
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = value.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if let Some(&prev) = current.chars().last().as_ref() {
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // myWidget -> my|Widget，HTMLParser -> HTML|Parser
+            if c.is_uppercase() && (prev.is_lowercase() || (prev.is_uppercase() && next_is_lower)) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("my widget"), ["my", "widget"]);
+        assert_eq!(split_words("my-widget_name"), ["my", "widget", "name"]);
+        assert_eq!(split_words("myWidget"), ["my", "Widget"]);
+        assert_eq!(split_words("HTMLParser v2"), ["HTML", "Parser", "v2"]);
+        assert!(split_words(" - ").is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let cases = [
+            ("upper", "MY WIDGET"),
+            ("lower", "my widget"),
+            ("pascal", "MyWidget"),
+            ("camel", "myWidget"),
+            ("snake", "my_widget"),
+            ("kebab", "my-widget"),
+            ("constant", "MY_WIDGET"),
+            ("title", "My Widget"),
+        ];
+        for (filter, expected) in cases {
+            assert_eq!(apply(filter, "my Widget").unwrap(), expected, "{}", filter);
+        }
+        assert_eq!(
+            apply("snake", "UserProfileCard").unwrap(),
+            "user_profile_card"
+        );
+        assert_eq!(apply("pascal", "").unwrap(), "");
+        assert!(matches!(
+            apply("reverse", "x"),
+            Err(Error::UnknownFilter(name)) if name == "reverse"
+        ));
+        assert_eq!(NAMES.len(), cases.len());
+    }
+}
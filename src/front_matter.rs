@@ -0,0 +1,214 @@
+//! 模板头部的 front matter
+//!
+//! 模板可以在开头用 `+++` 包裹一段 TOML，声明模板的说明、变量和输出建议：
+//!
+//! ```text
+//! +++
+//! description = "Vue 单文件组件"
+//! tags = ["web", "vue"]
+//!
+//! [variables]
+//! title = { description = "组件标题", default = "Untitled" }
+//! owner = { required = true }
+//!
+//! [output]
+//! dir = "src/components"
+//!
+//! [hooks]
+//! post_create = ["npx prettier --write {{file}}"]
+//! +++
+//! <template>{{title}}</template>
+//! ```
+//!
+//! front matter 本身不会写入生成的文件。
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::hooks::Hooks;
+use crate::permissions::Mode;
+use crate::render::Variables;
+
+/// front matter 的起止分隔行
+const DELIMITER: &str = "+++";
+
+/// 模板声明的元数据
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    /// 模板说明
+    pub description: Option<String>,
+
+    /// 模板的分类标签，用于筛选模板
+    pub tags: Vec<String>,
+
+    /// 模板使用的变量
+    pub variables: BTreeMap<String, VariableSpec>,
+
+    /// 输出建议
+    pub output: OutputHints,
+
+    /// 创建文件后执行的钩子命令
+    pub hooks: Hooks,
+
+    /// 生成文件的权限（八进制，例如 `"755"`），只在 Unix 上生效
+    pub mode: Option<Mode>,
+}
+
+/// 模板中声明的单个变量
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct VariableSpec {
+    /// 变量说明
+    pub description: Option<String>,
+
+    /// 未通过内置变量、配置文件或 `-D` 提供时使用的默认值
+    pub default: Option<String>,
+
+    /// 必须提供该变量，否则拒绝创建文件
+    pub required: bool,
+}
+
+/// 模板对输出位置的建议
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputHints {
+    /// 未指定 `--dir` 时创建文件的目录（相对于当前目录）
+    pub dir: Option<PathBuf>,
+}
+
+impl OutputHints {
+    /// 建议的输出目录拼接到 `base` 之后的路径
+    ///
+    /// 建议目录必须是不含 `..` 的相对路径，防止模板把文件写到 `base` 之外。
+    pub fn dir_under(&self, base: &Path) -> Result<Option<PathBuf>> {
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        if !dir
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(Error::InvalidOutputDir(dir.clone()));
+        }
+        Ok(Some(base.join(dir)))
+    }
+}
+
+impl FrontMatter {
+    /// 将声明的默认值填入尚未定义的变量
+    pub fn apply_defaults(&self, vars: &mut Variables) {
+        for (name, spec) in &self.variables {
+            if let Some(default) = &spec.default {
+                vars.entry(name.clone()).or_insert_with(|| default.clone());
+            }
+        }
+    }
+
+    /// 声明为必需但尚未定义的变量
+    pub fn missing_variables(&self, vars: &Variables) -> Vec<String> {
+        self.variables
+            .iter()
+            .filter(|(name, spec)| spec.required && !vars.contains_key(*name))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// 将模板拆分为 front matter 和正文
+///
+/// 模板的第一行不是 `+++` 时没有 front matter，原样返回全部内容；
+/// 找不到结束的 `+++` 时同样视为没有 front matter。
+pub fn split(content: &str) -> std::result::Result<(Option<FrontMatter>, &str), toml::de::Error> {
+    let Some(rest) = strip_delimiter_line(content) else {
+        return Ok((None, content));
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == DELIMITER {
+            let front_matter = toml::from_str(&rest[..offset])?;
+            return Ok((Some(front_matter), &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    Ok((None, content))
+}
+
+/// 如果 `content` 以 `+++` 分隔行开头，返回该行之后的内容
+fn strip_delimiter_line(content: &str) -> Option<&str> {
+    let rest = content.strip_prefix(DELIMITER)?;
+    rest.strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_without_front_matter() {
+        let (front_matter, body) = split("<h1>{{title}}</h1>\n").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "<h1>{{title}}</h1>\n");
+
+        // 没有结束分隔行
+        let (front_matter, body) = split("+++\ntitle = 1\n").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "+++\ntitle = 1\n");
+    }
+
+    #[test]
+    fn test_split_with_front_matter() {
+        let content = "+++\r\ndescription = \"页面\"\r\n[variables]\r\ntitle = { default = \"Home\" }\r\nowner = { required = true }\r\n[output]\r\ndir = \"pages\"\r\n+++\r\n<h1>{{title}}</h1>\r\n";
+        let (front_matter, body) = split(content).unwrap();
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(body, "<h1>{{title}}</h1>\r\n");
+        assert_eq!(front_matter.description.as_deref(), Some("页面"));
+        assert_eq!(front_matter.output.dir, Some(PathBuf::from("pages")));
+
+        let mut vars = Variables::new();
+        assert_eq!(front_matter.missing_variables(&vars), ["owner"]);
+        front_matter.apply_defaults(&mut vars);
+        assert_eq!(vars["title"], "Home");
+
+        vars.insert("title".to_string(), "About".to_string());
+        vars.insert("owner".to_string(), "me".to_string());
+        front_matter.apply_defaults(&mut vars);
+        assert_eq!(vars["title"], "About");
+        assert!(front_matter.missing_variables(&vars).is_empty());
+    }
+
+    #[test]
+    fn test_split_parses_mode() {
+        let (front_matter, _) = split("+++\nmode = \"755\"\n+++\n#!/bin/sh\n").unwrap();
+        assert_eq!(front_matter.unwrap().mode.map(Mode::bits), Some(0o755));
+        assert!(split("+++\nmode = \"abc\"\n+++\n").is_err());
+    }
+
+    #[test]
+    fn test_output_dir_under() {
+        let base = Path::new("/work");
+        let hints = |dir: &str| OutputHints {
+            dir: Some(PathBuf::from(dir)),
+        };
+
+        assert_eq!(OutputHints::default().dir_under(base).unwrap(), None);
+        assert_eq!(
+            hints("src/components").dir_under(base).unwrap(),
+            Some(PathBuf::from("/work/src/components"))
+        );
+        assert!(hints("../outside").dir_under(base).is_err());
+        assert!(hints("/etc").dir_under(base).is_err());
+    }
+
+    #[test]
+    fn test_split_rejects_invalid_front_matter() {
+        assert!(split("+++\nunknown = true\n+++\nbody").is_err());
+        assert!(split("+++\nnot toml\n+++\nbody").is_err());
+    }
+}
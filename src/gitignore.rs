@@ -0,0 +1,149 @@
+//! 生成 `.gitignore` (`new-cli gitignore rust node`)
+//!
+//! 常用语言和环境的规则内置在程序中（见 [`bundled_profiles`]），其他名称从
+//! [gitignore.io](https://www.toptal.com/developers/gitignore) 下载。
+//! 多个规则集合并时，已经出现过的规则（包括已有 `.gitignore` 中的规则）不会重复写入。
+
+use include_dir::{include_dir, Dir};
+use std::collections::HashSet;
+
+use crate::error::{Error, Result};
+use crate::remote::{self, FetchOptions};
+
+/// 内置的规则集，每个文件为 `<名称>.gitignore`
+static PROFILES: Dir = include_dir!("$CARGO_MANIFEST_DIR/gitignore");
+
+/// gitignore.io 的接口地址，后接规则集名称
+pub const GITIGNORE_IO_URL: &str = "https://www.toptal.com/developers/gitignore/api/";
+
+/// 内置规则集的名称，按名称排序
+pub fn bundled_profiles() -> Vec<&'static str> {
+    let mut names: Vec<&str> = PROFILES
+        .files()
+        .filter_map(|file| file.path().to_str()?.strip_suffix(".gitignore"))
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// 内置的规则集，名称不区分大小写
+pub fn bundled(name: &str) -> Option<&'static str> {
+    PROFILES
+        .get_file(format!("{}.gitignore", name.to_lowercase()))
+        .and_then(|file| file.contents_utf8())
+}
+
+/// 获取规则集：优先使用内置的规则集，否则从 gitignore.io 下载
+pub fn profile(name: &str, options: &FetchOptions) -> Result<String> {
+    if let Some(rules) = bundled(name) {
+        return Ok(rules.to_string());
+    }
+    let url = format!("{}{}", GITIGNORE_IO_URL, name.to_lowercase());
+    log::debug!("fetching gitignore profile {} from {}", name, url);
+    let bytes = remote::download(&url, options).map_err(|e| match e {
+        // 未知的名称返回 404
+        Error::Download { message, .. } if message == "HTTP 404" => {
+            Error::UnknownGitignore(name.to_string())
+        }
+        e => e,
+    })?;
+    let rules = String::from_utf8_lossy(&bytes).into_owned();
+    if rules.contains("#!! ERROR") {
+        return Err(Error::UnknownGitignore(name.to_string()));
+    }
+    Ok(rules)
+}
+
+/// 合并规则集，返回需要追加到 `existing` 之后的内容
+///
+/// 每个规则集前加上 `# <名称>` 标题；`existing` 或前面的规则集中已有的规则会被跳过，
+/// 没有新规则的规则集整个省略。注释和空行不参与去重。
+pub fn merge(existing: &str, profiles: &[(String, String)]) -> String {
+    let mut seen: HashSet<&str> = existing
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_rule(line))
+        .collect();
+    let mut output = String::new();
+    for (name, rules) in profiles {
+        let mut section = String::new();
+        let mut has_rules = false;
+        for line in rules.lines().map(str::trim_end) {
+            if is_rule(line) {
+                if !seen.insert(line.trim()) {
+                    continue;
+                }
+                has_rules = true;
+            }
+            // 连续的空行只保留一个
+            if line.is_empty() && (section.is_empty() || section.ends_with("\n\n")) {
+                continue;
+            }
+            section.push_str(line);
+            section.push('\n');
+        }
+        if !has_rules {
+            continue;
+        }
+        if !output.is_empty() || !existing.trim().is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("# {}\n", name));
+        output.push_str(section.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bundled_profiles() {
+        let names = bundled_profiles();
+        for name in ["go", "macos", "node", "python", "rust"] {
+            assert!(names.contains(&name), "{name}");
+        }
+        assert!(bundled("Rust").unwrap().contains("/target/"));
+        assert_eq!(bundled("cobol"), None);
+    }
+
+    #[test]
+    fn test_merge_deduplicates() {
+        let merged = merge(
+            "",
+            &profiles(&[
+                ("node", "node_modules/\ndist/\n.env\n"),
+                ("python", "# 虚拟环境\n.venv/\n\n\n.env\ndist/\n"),
+                ("empty", "# 只有注释\n.env\n"),
+            ]),
+        );
+        assert_eq!(
+            merged,
+            "# node\nnode_modules/\ndist/\n.env\n\n# python\n# 虚拟环境\n.venv/\n"
+        );
+
+        let appended = merge(
+            "/target/\n*.log\n",
+            &profiles(&[("rust", "/target/\n**/*.rs.bk\n")]),
+        );
+        assert_eq!(appended, "\n# rust\n**/*.rs.bk\n");
+        assert_eq!(
+            merge("/target/\n", &profiles(&[("rust", "/target/\n")])),
+            ""
+        );
+    }
+}
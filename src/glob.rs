@@ -0,0 +1,136 @@
+//! 通配模式匹配，用于 `.editorconfig` 的小节和 `template list` 的过滤
+
+/// 判断通配模式 `pattern` 是否匹配以 `/` 分隔的相对路径 `relative`
+///
+/// 不含 `/` 的模式（如 `*.md`）匹配任意目录下的文件名；含 `/` 的模式匹配整个路径，
+/// 开头的 `/` 会被忽略。支持 `*`、`**`、`?`、`[...]` 和 `{a,b}`。
+pub fn matches(pattern: &str, relative: &str) -> bool {
+    let (pattern, subject) = if pattern.contains('/') {
+        (pattern.trim_start_matches('/'), relative)
+    } else {
+        (pattern, relative.rsplit('/').next().unwrap_or(relative))
+    };
+    expand_braces(pattern).iter().any(|pattern| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let subject: Vec<char> = subject.chars().collect();
+        glob_match(&pattern, &subject)
+    })
+}
+
+/// 展开 `{a,b}`，例如 `*.{js,ts}` 展开为 `*.js` 和 `*.ts`
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let mut close = None;
+    let mut splits = Vec::new();
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => splits.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return vec![pattern.to_string()];
+    };
+
+    let prefix = &pattern[..open];
+    let suffix = &pattern[close + 1..];
+    let mut bounds = vec![open];
+    bounds.extend(&splits);
+    bounds.push(close);
+    let mut expanded = Vec::new();
+    for pair in bounds.windows(2) {
+        let alternative = &pattern[pair[0] + 1..pair[1]];
+        expanded.extend(expand_braces(&format!(
+            "{}{}{}",
+            prefix, alternative, suffix
+        )));
+    }
+    expanded
+}
+
+fn glob_match(pattern: &[char], subject: &[char]) -> bool {
+    match pattern.first() {
+        None => subject.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` 也可以匹配零级目录
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], subject) {
+                return true;
+            }
+            (0..=subject.len()).any(|i| glob_match(rest, &subject[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=subject.len() {
+                if glob_match(rest, &subject[i..]) {
+                    return true;
+                }
+                if subject.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(subject.first(), Some(c) if *c != '/')
+                && glob_match(&pattern[1..], &subject[1..])
+        }
+        Some('[') => match pattern.iter().position(|&c| c == ']') {
+            Some(end) if end > 1 => {
+                let Some(&c) = subject.first() else {
+                    return false;
+                };
+                let class = &pattern[1..end];
+                let (negated, class) = match class.first() {
+                    Some('!') | Some('^') => (true, &class[1..]),
+                    _ => (false, class),
+                };
+                let mut found = false;
+                let mut i = 0;
+                while i < class.len() {
+                    if i + 2 < class.len() && class[i + 1] == '-' {
+                        found |= class[i] <= c && c <= class[i + 2];
+                        i += 3;
+                    } else {
+                        found |= class[i] == c;
+                        i += 1;
+                    }
+                }
+                found != negated && glob_match(&pattern[end + 1..], &subject[1..])
+            }
+            _ => subject.first() == Some(&'[') && glob_match(&pattern[1..], &subject[1..]),
+        },
+        Some(&c) => subject.first() == Some(&c) && glob_match(&pattern[1..], &subject[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*", "src/main.rs"));
+        assert!(matches("*.{js,ts}", "web/app.ts"));
+        assert!(!matches("*.{js,ts}", "web/app.rs"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/cli/mod.rs"));
+        assert!(matches("src/**.rs", "src/cli/mod.rs"));
+        assert!(matches("/docs/**/*.md", "docs/guide.md"));
+        assert!(matches("Makefile", "sub/Makefile"));
+        assert!(matches("[Mm]akefile", "makefile"));
+        assert!(matches("?.md", "a.md"));
+        assert!(!matches("[!a].md", "a.md"));
+    }
+}
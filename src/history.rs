@@ -0,0 +1,279 @@
+//! 创建记录 `<数据目录>/history.jsonl`
+//!
+//! 每创建（或插入）一个文件就追加一行 JSON，记录时间、使用的模板、
+//! 目标路径和变量，用于 `new-cli history` 查看。
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::data_dir;
+use crate::error::{IoContext, Result};
+use crate::tr;
+
+/// 对目标文件进行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// 创建了新文件
+    Created,
+    /// 向已有文件插入了内容
+    Inserted,
+}
+
+/// 一条创建记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: DateTime<Local>,
+    pub action: Action,
+    /// 使用的模板，没有模板（创建空文件）时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PathBuf>,
+    /// 目标文件的绝对路径
+    pub path: PathBuf,
+    /// 覆盖前备份的原文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+    /// 渲染时使用的变量（不含未被覆盖的内置变量）
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// 是否已被 `new-cli undo` 撤销
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undone: bool,
+}
+
+impl Entry {
+    /// 能否被撤销：只有尚未撤销的新建文件可以撤销，插入的内容需要手动处理
+    pub fn can_undo(&self) -> bool {
+        self.action == Action::Created && !self.undone
+    }
+}
+
+/// 撤销一条记录的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reverted {
+    /// 删除了创建的文件
+    Removed,
+    /// 用备份恢复了被覆盖的原文件
+    Restored,
+    /// 文件已不存在，无需处理
+    Missing,
+}
+
+/// 筛选记录的条件
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// 只保留这一天的记录
+    pub date: Option<NaiveDate>,
+    /// 只保留使用该模板的记录，可以是模板文件名 (`index.html`) 或模板名 (`index`)
+    pub template: Option<String>,
+}
+
+impl Filter {
+    /// 记录是否满足所有条件
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if let Some(date) = self.date {
+            if entry.timestamp.date_naive() != date {
+                return false;
+            }
+        }
+        if let Some(name) = &self.template {
+            let Some(template) = &entry.template else {
+                return false;
+            };
+            let file_name = template.file_name().and_then(|n| n.to_str());
+            let stem = template.file_stem().and_then(|n| n.to_str());
+            if file_name != Some(name.as_str()) && stem != Some(name.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 记录文件路径 `<数据目录>/history.jsonl`
+pub fn history_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("history.jsonl"))
+}
+
+/// 在记录文件末尾追加一条记录
+pub fn append(path: &Path, entry: &Entry) -> Result<()> {
+    let context = || {
+        tr!(
+            "无法写入创建记录 {}",
+            "failed to write history file {}",
+            path.display()
+        )
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).io_context(context)?;
+    }
+    let mut line = serde_json::to_string(entry)
+        .map_err(io::Error::from)
+        .io_context(context)?;
+    // 一次写入整行，同时创建多个文件时各行不会交错
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .io_context(context)?;
+    file.write_all(line.as_bytes()).io_context(context)
+}
+
+/// 用 `entries` 重写整个记录文件
+pub fn save(path: &Path, entries: &[Entry]) -> Result<()> {
+    let context = || {
+        tr!(
+            "无法写入创建记录 {}",
+            "failed to write history file {}",
+            path.display()
+        )
+    };
+    let mut content = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(io::Error::from)
+            .io_context(context)?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    fs::write(path, content).io_context(context)
+}
+
+/// 撤销一条新建文件的记录：有备份时用备份恢复原文件，否则删除该文件
+pub fn revert(entry: &Entry) -> Result<Reverted> {
+    if let Some(backup) = entry.backup.as_ref().filter(|backup| backup.is_file()) {
+        fs::rename(backup, &entry.path).io_context(|| {
+            tr!(
+                "无法用备份 {} 恢复文件",
+                "failed to restore the file from backup {}",
+                backup.display()
+            )
+        })?;
+        return Ok(Reverted::Restored);
+    }
+    if !entry.path.exists() {
+        return Ok(Reverted::Missing);
+    }
+    fs::remove_file(&entry.path).io_context(|| {
+        tr!(
+            "无法删除文件 {}",
+            "failed to remove file {}",
+            entry.path.display()
+        )
+    })?;
+    Ok(Reverted::Removed)
+}
+
+/// 读取所有记录，按写入顺序（从旧到新）排列
+///
+/// 记录文件不存在时返回空列表；无法解析的行会被忽略。
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "无法读取创建记录 {}",
+            "failed to read history file {}",
+            path.display()
+        )
+    })?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn entry(template: Option<&str>, day: u32) -> Entry {
+        Entry {
+            timestamp: Local.with_ymd_and_hms(2024, 5, day, 10, 0, 0).unwrap(),
+            action: Action::Created,
+            template: template.map(|t| PathBuf::from("/templates").join(t)),
+            path: PathBuf::from("/work/a.html"),
+            backup: None,
+            variables: BTreeMap::from([("title".to_string(), "Home".to_string())]),
+            undone: false,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested/history.jsonl");
+        assert!(load(&path).unwrap().is_empty());
+
+        let first = entry(Some("index.html"), 1);
+        let second = entry(None, 2);
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+
+        assert_eq!(load(&path).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter {
+            date: NaiveDate::from_ymd_opt(2024, 5, 1),
+            template: None,
+        };
+        assert!(filter.matches(&entry(Some("index.html"), 1)));
+        assert!(!filter.matches(&entry(Some("index.html"), 2)));
+
+        for name in ["index.html", "index"] {
+            let filter = Filter {
+                date: None,
+                template: Some(name.to_string()),
+            };
+            assert!(filter.matches(&entry(Some("index.html"), 1)));
+            assert!(!filter.matches(&entry(Some("main.rs"), 1)));
+            assert!(!filter.matches(&entry(None, 1)));
+        }
+    }
+
+    #[test]
+    fn test_revert() {
+        let dir = TempDir::new().unwrap();
+        let mut created = entry(None, 1);
+        created.path = dir.path().join("a.txt");
+        fs::write(&created.path, "new").unwrap();
+
+        assert_eq!(revert(&created).unwrap(), Reverted::Removed);
+        assert!(!created.path.exists());
+        assert_eq!(revert(&created).unwrap(), Reverted::Missing);
+
+        let backup = dir.path().join("a.txt.bak");
+        fs::write(&created.path, "new").unwrap();
+        fs::write(&backup, "old").unwrap();
+        created.backup = Some(backup.clone());
+        assert_eq!(revert(&created).unwrap(), Reverted::Restored);
+        assert_eq!(fs::read_to_string(&created.path).unwrap(), "old");
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn test_save_marks_undone() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let mut entries = vec![entry(None, 1), entry(None, 2)];
+        entries[1].undone = true;
+        save(&path, &entries).unwrap();
+
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded, entries);
+        assert!(loaded[0].can_undo());
+        assert!(!loaded[1].can_undo());
+    }
+}
@@ -0,0 +1,115 @@
+//! 创建文件后执行的钩子命令
+//!
+//! 钩子可以在配置文件或模板 front matter 的 `[hooks]` 中定义：
+//!
+//! ```toml
+//! [hooks]
+//! post_create = ["chmod +x {{file}}", "cargo fmt"]
+//! ```
+//!
+//! 命令按 shell 规则拆分为参数后，每个参数再单独渲染模板变量，因此
+//! 包含空格的路径无需额外加引号。命令不经过 shell 执行，需要管道或
+//! 重定向时请写成 `sh -c "..."`。
+
+use serde::Deserialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::{Error, IoContext, Result};
+use crate::render::{self, Variables};
+use crate::tr;
+
+/// 钩子命令列表
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hooks {
+    /// 文件写入后执行的命令
+    pub post_create: Vec<String>,
+}
+
+/// 执行一条钩子命令，命令以非零状态退出时返回错误
+///
+/// 命令在 `cwd` 中执行，标准输出和标准错误直接继承自当前进程。
+pub fn run(command: &str, vars: &Variables, cwd: &Path) -> Result<()> {
+    run_with_stdout(command, vars, cwd, Stdio::inherit())
+}
+
+/// 与 [`run`] 相同，但命令的标准输出写入 `stdout`，
+/// 例如在标准输出被机器可读的结果占用时转到标准错误
+pub fn run_with_stdout(command: &str, vars: &Variables, cwd: &Path, stdout: Stdio) -> Result<()> {
+    let args = parse_command(command, vars)?;
+    let Some((program, args)) = args.split_first() else {
+        return Ok(());
+    };
+
+    log::debug!(
+        "running hook {:?} with arguments {:?} in {}",
+        program,
+        args,
+        cwd.display()
+    );
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdout(stdout)
+        .status()
+        .io_context(|| {
+            tr!(
+                "无法执行钩子命令: {}",
+                "failed to run hook command: {}",
+                command
+            )
+        })?;
+    if !status.success() {
+        return Err(Error::HookFailed {
+            command: command.to_string(),
+            status: status.code(),
+        });
+    }
+    Ok(())
+}
+
+/// 将钩子命令拆分为参数并渲染每个参数中的变量
+fn parse_command(command: &str, vars: &Variables) -> Result<Vec<String>> {
+    let words = shell_words::split(command).map_err(|_| Error::InvalidHook(command.to_string()))?;
+    words
+        .iter()
+        .map(|word| render::render(word, vars, false))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let mut vars = Variables::new();
+        vars.insert("file".to_string(), "/tmp/my file.sh".to_string());
+
+        assert_eq!(
+            parse_command("chmod +x {{file}}", &vars).unwrap(),
+            ["chmod", "+x", "/tmp/my file.sh"]
+        );
+        assert_eq!(
+            parse_command("sh -c 'echo hi'", &vars).unwrap(),
+            ["sh", "-c", "echo hi"]
+        );
+        assert!(parse_command("echo 'unclosed", &vars).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_reports_failure() {
+        let vars = Variables::new();
+        let cwd = std::env::temp_dir();
+        assert!(run("true", &vars, &cwd).is_ok());
+        assert!(matches!(
+            run("sh -c 'exit 3'", &vars, &cwd),
+            Err(Error::HookFailed {
+                status: Some(3),
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+//! 界面语言
+//!
+//! 所有面向用户的消息都通过 [`tr!`](crate::tr) 同时给出中文和英文版本，
+//! 运行时按当前语言选择其一。语言按以下优先级确定：
+//!
+//! 1. 命令行参数 `--lang`
+//! 2. 配置文件中的 `lang`
+//! 3. 环境变量 `LC_ALL`、`LC_MESSAGES`、`LANG`（依次检查第一个非空的值）
+//! 4. 默认使用中文
+
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 支持的界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    /// 简体中文
+    Zh,
+    /// English
+    En,
+}
+
+/// 当前语言：0 表示尚未确定，否则为 `Lang as u8 + 1`
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+impl Lang {
+    /// 解析 `zh`、`en` 或 `zh_CN.UTF-8`、`en_US` 这类 locale 名称
+    pub fn parse(value: &str) -> Option<Lang> {
+        let language = value
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "zh" => Some(Lang::Zh),
+            "en" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    /// 根据 locale 环境变量确定语言，无法识别时为中文
+    pub fn from_env() -> Lang {
+        Lang::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// 与 [`Lang::from_env`] 相同，但环境变量由 `env` 提供，便于测试
+    pub fn from_env_with(env: impl Fn(&str) -> Option<String>) -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(&env)
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::parse(&value))
+            .unwrap_or(Lang::Zh)
+    }
+}
+
+/// 设置当前语言
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang as u8 + 1, Ordering::Relaxed);
+}
+
+/// 当前语言，未调用 [`set_lang`] 时根据环境变量确定
+pub fn lang() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Lang::Zh,
+        2 => Lang::En,
+        _ => {
+            let lang = Lang::from_env();
+            set_lang(lang);
+            lang
+        }
+    }
+}
+
+/// 按当前语言格式化消息：`tr!("中文 {}", "English {}", arg)`
+///
+/// 两种语言的格式字符串必须使用相同的位置参数。
+#[macro_export]
+macro_rules! tr {
+    ($zh:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Zh => format!($zh $(, $arg)*),
+            $crate::i18n::Lang::En => format!($en $(, $arg)*),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Lang::parse("zh"), Some(Lang::Zh));
+        assert_eq!(Lang::parse("zh_CN.UTF-8"), Some(Lang::Zh));
+        assert_eq!(Lang::parse("en-US"), Some(Lang::En));
+        assert_eq!(Lang::parse("EN"), Some(Lang::En));
+        assert_eq!(Lang::parse("C"), None);
+    }
+
+    #[test]
+    fn test_from_env_with() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+
+        assert_eq!(Lang::from_env_with(env(&[])), Lang::Zh);
+        assert_eq!(
+            Lang::from_env_with(env(&[("LANG", "en_US.UTF-8")])),
+            Lang::En
+        );
+        assert_eq!(
+            Lang::from_env_with(env(&[("LC_ALL", "zh_CN.UTF-8"), ("LANG", "en_US")])),
+            Lang::Zh
+        );
+        assert_eq!(
+            Lang::from_env_with(env(&[("LC_ALL", ""), ("LANG", "en_US")])),
+            Lang::En
+        );
+        assert_eq!(Lang::from_env_with(env(&[("LANG", "C")])), Lang::Zh);
+    }
+}
@@ -0,0 +1,313 @@
+//! 模板目录索引
+//!
+//! 列出模板需要遍历各层模板目录，并读取每个模板的 front matter 得到说明和标签。模板很多时每次运行
+//! 都这样做会明显变慢，因此结果缓存在缓存目录的 `index.json` 中，之后的运行中：
+//!
+//! - 修改时间没有变化的目录直接使用记录的文件列表，不再读取目录；
+//! - 大小和修改时间都没有变化的模板直接使用记录的说明和标签，不再读取文件。
+//!
+//! 新增、删除或重命名文件会更新所在目录的修改时间，编辑模板会更新模板自身的修改时间，
+//! 因此索引总是与磁盘上的内容一致。索引损坏或格式版本不同时直接重建，删除该文件也没有影响。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::{IoContext, Result};
+use crate::front_matter;
+use crate::manifest;
+use crate::template::TemplateInfo;
+use crate::tr;
+
+/// 索引在缓存目录中的文件名
+pub const FILE_NAME: &str = "index.json";
+
+/// 索引格式的版本，格式变化后旧的索引会被丢弃
+const VERSION: u32 = 2;
+
+/// 各模板目录的索引
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    /// 模板目录 -> 该目录树的记录
+    roots: BTreeMap<PathBuf, Tree>,
+    /// 与读取时相比是否有变化，没有变化时不需要写回
+    #[serde(skip)]
+    dirty: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Tree {
+    /// 命名空间（根目录为空字符串）-> 目录记录
+    dirs: BTreeMap<String, DirRecord>,
+    /// 带命名空间的模板名 -> 模板记录
+    files: BTreeMap<String, FileRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DirRecord {
+    modified: Option<SystemTime>,
+    subdirs: Vec<String>,
+    files: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileRecord {
+    size: u64,
+    modified: Option<SystemTime>,
+    description: Option<String>,
+    tags: Vec<String>,
+}
+
+impl Index {
+    /// 读取索引，文件不存在或无法使用时返回空索引
+    pub fn load(path: &Path) -> Index {
+        let index = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Index>(&bytes).ok())
+            .filter(|index| index.version == VERSION);
+        match index {
+            Some(index) => index,
+            None => {
+                log::debug!("rebuilding template index {}", path.display());
+                Index {
+                    version: VERSION,
+                    ..Index::default()
+                }
+            }
+        }
+    }
+
+    /// 有变化时写回索引，已不存在的模板目录的记录会被删除
+    pub fn save(mut self, path: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.roots.retain(|dir, _| dir.is_dir());
+        let context = || {
+            tr!(
+                "无法写入模板索引 {:?}",
+                "failed to write template index {:?}",
+                path
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).io_context(context)?;
+        }
+        let json = serde_json::to_vec(&self).map_err(std::io::Error::from);
+        // 先写入临时文件再重命名，同时运行的多个进程不会读到写了一半的索引
+        let temp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        let result = json
+            .and_then(|json| fs::write(&temp, json))
+            .and_then(|()| fs::rename(&temp, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result.io_context(context)
+    }
+
+    /// 扫描模板目录及其子目录，返回按带命名空间的模板名排序的模板列表；
+    /// 没有变化的目录和模板使用索引中的记录
+    pub fn list(&mut self, template_dir: &Path) -> Result<Vec<TemplateInfo>> {
+        let old = self.roots.remove(template_dir).unwrap_or_default();
+        let mut new = Tree::default();
+        let mut templates = Vec::new();
+        scan(template_dir, None, &old, &mut new, &mut templates)?;
+        if new != old {
+            self.dirty = true;
+        }
+        self.roots.insert(template_dir.to_path_buf(), new);
+        templates.sort_by_key(|t| t.qualified_name());
+        Ok(templates)
+    }
+}
+
+fn scan(
+    dir: &Path,
+    namespace: Option<&str>,
+    old: &Tree,
+    new: &mut Tree,
+    templates: &mut Vec<TemplateInfo>,
+) -> Result<()> {
+    let read_error = || {
+        tr!(
+            "无法读取模板目录: {:?}",
+            "failed to read template directory: {:?}",
+            dir
+        )
+    };
+    let key = namespace.unwrap_or_default();
+    let modified = fs::metadata(dir).io_context(read_error)?.modified().ok();
+    let record = match old.dirs.get(key) {
+        Some(record) if modified.is_some() && record.modified == modified => record.clone(),
+        _ => read_dir_record(dir, namespace, modified).io_context(read_error)?,
+    };
+
+    for file in &record.files {
+        let path = dir.join(file);
+        let metadata = fs::metadata(&path).io_context(|| {
+            tr!(
+                "无法读取模板文件信息: {:?}",
+                "failed to read template metadata: {:?}",
+                path
+            )
+        })?;
+        let qualified = match namespace {
+            Some(namespace) => format!("{}/{}", namespace, file),
+            None => file.clone(),
+        };
+        let size = metadata.len();
+        let modified = metadata.modified().ok();
+        let (description, tags) = match old.files.get(&qualified) {
+            Some(cached)
+                if modified.is_some() && cached.size == size && cached.modified == modified =>
+            {
+                (cached.description.clone(), cached.tags.clone())
+            }
+            _ => read_metadata(&path),
+        };
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        templates.push(TemplateInfo {
+            path,
+            namespace: namespace.map(str::to_string),
+            name,
+            extension,
+            size,
+            modified,
+            description: description.clone(),
+            tags: tags.clone(),
+        });
+        new.files.insert(
+            qualified,
+            FileRecord {
+                size,
+                modified,
+                description,
+                tags,
+            },
+        );
+    }
+
+    for subdir in &record.subdirs {
+        let child = match namespace {
+            Some(namespace) => format!("{}/{}", namespace, subdir),
+            None => subdir.clone(),
+        };
+        scan(&dir.join(subdir), Some(&child), old, new, templates)?;
+    }
+    new.dirs.insert(key.to_string(), record);
+    Ok(())
+}
+
+/// 读取目录中的子目录和模板文件；以 `.` 开头的子目录和命名空间中的模板清单不是模板
+fn read_dir_record(
+    dir: &Path,
+    namespace: Option<&str>,
+    modified: Option<SystemTime>,
+) -> std::io::Result<DirRecord> {
+    let mut record = DirRecord {
+        modified,
+        subdirs: Vec::new(),
+        files: Vec::new(),
+    };
+    for entry in fs::read_dir(dir)?.filter_map(std::result::Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                record.subdirs.push(name);
+            }
+        } else if path.is_file() && !(namespace.is_some() && name == manifest::FILE_NAME) {
+            record.files.push(name);
+        }
+    }
+    record.subdirs.sort();
+    record.files.sort();
+    Ok(record)
+}
+
+/// 模板 front matter 中的说明和标签，没有 front matter 或无法解析时为空
+fn read_metadata(path: &Path) -> (Option<String>, Vec<String>) {
+    let front_matter = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| front_matter::split(&content).ok()?.0);
+    match front_matter {
+        Some(front_matter) => (front_matter.description, front_matter.tags),
+        None => (None, Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn names(templates: &[TemplateInfo]) -> Vec<String> {
+        templates.iter().map(TemplateInfo::qualified_name).collect()
+    }
+
+    #[test]
+    fn test_index_follows_changes() {
+        let dir = TempDir::new().unwrap();
+        let index_path = dir.path().join("cache/index.json");
+        let templates = dir.path().join("templates");
+        fs::create_dir_all(templates.join("web")).unwrap();
+        fs::write(
+            templates.join("web/page.html"),
+            "+++\ndescription = \"A page\"\ntags = [\"web\"]\n+++\n<p></p>\n",
+        )
+        .unwrap();
+        fs::write(templates.join("main.rs"), "fn main() {}").unwrap();
+
+        let mut index = Index::load(&index_path);
+        let listed = index.list(&templates).unwrap();
+        assert_eq!(names(&listed), ["main.rs", "web/page.html"]);
+        assert_eq!(listed[1].description.as_deref(), Some("A page"));
+        assert_eq!(listed[1].tags, ["web"]);
+        index.save(&index_path).unwrap();
+        assert!(index_path.is_file());
+
+        // 没有变化时得到相同的结果，不需要写回
+        let mut index = Index::load(&index_path);
+        let cached = index.list(&templates).unwrap();
+        assert_eq!(names(&cached), names(&listed));
+        assert_eq!(cached[1].description.as_deref(), Some("A page"));
+        assert_eq!(cached[1].tags, ["web"]);
+        assert!(!index.dirty);
+
+        // 新增文件和修改说明都会反映出来
+        fs::write(templates.join("web/form.html"), "").unwrap();
+        let page = templates.join("web/page.html");
+        fs::write(&page, "+++\ndescription = \"A longer page\"\n+++\n").unwrap();
+        let file = fs::File::options().write(true).open(&page).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        let mut index = Index::load(&index_path);
+        let updated = index.list(&templates).unwrap();
+        assert_eq!(
+            names(&updated),
+            ["main.rs", "web/form.html", "web/page.html"]
+        );
+        assert_eq!(updated[2].description.as_deref(), Some("A longer page"));
+        assert!(index.dirty);
+    }
+
+    #[test]
+    fn test_load_ignores_corrupt_index() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        fs::write(&path, "not json").unwrap();
+        let index = Index::load(&path);
+        assert_eq!(index.version, VERSION);
+        assert!(index.roots.is_empty());
+    }
+}
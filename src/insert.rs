@@ -0,0 +1,169 @@
+//! 向已有文件中追加或插入渲染后的模板内容
+//!
+//! 适合片段式的模板，例如向 `routes.rs` 中加入一个新的路由块。
+
+use crate::error::{Error, Result};
+use crate::tr;
+
+/// 插入内容的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Insertion {
+    /// 追加到文件末尾
+    Append,
+    /// 插入到第 N 行之前（从 1 开始，N 为总行数 + 1 时等同于追加）
+    Line(usize),
+    /// 插入到第一个包含该标记的行之前
+    Marker(String),
+}
+
+impl Insertion {
+    /// 解析 `--insert-at` 的值：正整数为行号，其他内容为标记
+    pub fn parse(value: &str) -> std::result::Result<Insertion, String> {
+        if value.is_empty() {
+            return Err(tr!(
+                "插入位置不能为空",
+                "the insertion point must not be empty"
+            ));
+        }
+        match value.parse::<usize>() {
+            Ok(0) => Err(tr!("行号从 1 开始", "line numbers start at 1")),
+            Ok(line) => Ok(Insertion::Line(line)),
+            Err(_) => Ok(Insertion::Marker(value.to_string())),
+        }
+    }
+}
+
+/// 将 `content` 按 `at` 指定的位置插入 `existing`，返回新的文件内容
+///
+/// 插入的内容总是以换行结尾，且不会与前一行连在一起。`file` 只用于错误信息。
+pub fn insert(existing: &str, content: &str, at: &Insertion, file: &str) -> Result<String> {
+    let mut block = content.to_string();
+    if !block.is_empty() && !block.ends_with('\n') {
+        block.push('\n');
+    }
+
+    let offset = match at {
+        Insertion::Append => existing.len(),
+        Insertion::Line(line) => {
+            line_offset(existing, *line).ok_or_else(|| Error::LineOutOfRange {
+                file: file.to_string(),
+                line: *line,
+                lines: existing.lines().count(),
+            })?
+        }
+        Insertion::Marker(marker) => {
+            marker_offset(existing, marker).ok_or_else(|| Error::MarkerNotFound {
+                file: file.to_string(),
+                marker: marker.clone(),
+            })?
+        }
+    };
+
+    let (before, after) = existing.split_at(offset);
+    let mut result = String::with_capacity(existing.len() + block.len() + 1);
+    result.push_str(before);
+    if !before.is_empty() && !before.ends_with('\n') {
+        result.push('\n');
+    }
+    result.push_str(&block);
+    result.push_str(after);
+    Ok(result)
+}
+
+/// 第 `line` 行开头的字节偏移；`line` 为总行数 + 1 时为文件末尾
+fn line_offset(text: &str, line: usize) -> Option<usize> {
+    if line == 1 {
+        return Some(0);
+    }
+    let mut current = 1;
+    for (index, _) in text.match_indices('\n') {
+        current += 1;
+        if current == line {
+            return Some(index + 1);
+        }
+    }
+    // 最后一行没有换行符时，它之后的位置也可以插入
+    if current + 1 == line && !text.is_empty() && !text.ends_with('\n') {
+        return Some(text.len());
+    }
+    None
+}
+
+/// 第一个包含 `marker` 的行开头的字节偏移
+fn marker_offset(text: &str, marker: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.contains(marker) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Insertion::parse("3"), Ok(Insertion::Line(3)));
+        assert_eq!(
+            Insertion::parse("// routes"),
+            Ok(Insertion::Marker("// routes".to_string()))
+        );
+        assert!(Insertion::parse("0").is_err());
+        assert!(Insertion::parse("").is_err());
+    }
+
+    #[test]
+    fn test_append() {
+        let at = Insertion::Append;
+        assert_eq!(insert("a\n", "b", &at, "f").unwrap(), "a\nb\n");
+        assert_eq!(insert("a", "b\n", &at, "f").unwrap(), "a\nb\n");
+        assert_eq!(insert("", "b\n", &at, "f").unwrap(), "b\n");
+    }
+
+    #[test]
+    fn test_insert_at_line() {
+        let text = "one\ntwo\nthree\n";
+        assert_eq!(
+            insert(text, "new", &Insertion::Line(1), "f").unwrap(),
+            "new\none\ntwo\nthree\n"
+        );
+        assert_eq!(
+            insert(text, "new", &Insertion::Line(3), "f").unwrap(),
+            "one\ntwo\nnew\nthree\n"
+        );
+        assert_eq!(
+            insert(text, "new", &Insertion::Line(4), "f").unwrap(),
+            "one\ntwo\nthree\nnew\n"
+        );
+        assert_eq!(
+            insert("one\ntwo", "new", &Insertion::Line(3), "f").unwrap(),
+            "one\ntwo\nnew\n"
+        );
+        assert!(matches!(
+            insert(text, "new", &Insertion::Line(5), "f"),
+            Err(Error::LineOutOfRange {
+                line: 5,
+                lines: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_insert_at_marker() {
+        let text = "fn routes() {\n    // new-cli: routes\n}\n";
+        let at = Insertion::Marker("new-cli: routes".to_string());
+        assert_eq!(
+            insert(text, "    route(\"/a\");", &at, "f").unwrap(),
+            "fn routes() {\n    route(\"/a\");\n    // new-cli: routes\n}\n"
+        );
+        assert!(matches!(
+            insert(text, "x", &Insertion::Marker("missing".to_string()), "f"),
+            Err(Error::MarkerNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+//! 分层的模板目录
+//!
+//! 模板按层查找，排在前面的层优先级更高：
+//!
+//! 1. 项目层：从当前目录向上找到的第一个 `.new-cli/template/`，可随仓库提交
+//! 2. 全局层：配置中的 `template_dir`，默认为数据目录中的 `templates/`
+//! 3. 附加层：配置中的 `template_paths`，按列出的顺序
+//! 4. 系统层：[`system_template_dir`]，由系统管理员或软件包安装，存在时才使用
+//!
+//! 同名模板以高优先级的层为准；模板管理命令只修改全局层。
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::tr;
+
+/// 项目模板目录相对于项目根目录的路径
+pub const PROJECT_TEMPLATE_DIR: &str = ".new-cli/template";
+
+/// 模板目录的来源，按优先级从高到低排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerSource {
+    /// 当前项目中的 `.new-cli/template/`
+    Project,
+    /// 用户的全局模板目录
+    Global,
+    /// 配置中 `template_paths` 列出的目录
+    Extra,
+    /// 所有用户共享的系统模板目录
+    System,
+}
+
+impl LayerSource {
+    /// 用于展示的名称
+    pub fn label(self) -> String {
+        match self {
+            LayerSource::Project => tr!("项目", "project"),
+            LayerSource::Global => tr!("全局", "global"),
+            LayerSource::Extra => tr!("附加", "extra"),
+            LayerSource::System => tr!("系统", "system"),
+        }
+    }
+}
+
+/// 一层模板目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    pub source: LayerSource,
+    pub dir: PathBuf,
+}
+
+/// 系统模板目录：Windows 上为 `%ProgramData%\new-cli\templates`，
+/// 其他平台为 `/usr/share/new-cli/templates`
+pub fn system_template_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("ProgramData")
+            .map(|dir| PathBuf::from(dir).join("new-cli").join("templates"))
+    } else {
+        Some(PathBuf::from("/usr/share/new-cli/templates"))
+    }
+}
+
+/// 从 `start` 开始逐级向上查找 `.new-cli/template/` 目录
+///
+/// 与全局模板目录 `global` 相同的目录会被忽略（例如 `template_dir` 设为
+/// `~/.new-cli/template` 时在主目录下运行），避免同一目录出现在两层中。
+pub fn find_project_template_dir(start: &Path, global: &Path) -> Option<PathBuf> {
+    let global = fs::canonicalize(global).ok();
+
+    for dir in start.ancestors() {
+        let candidate = dir.join(PROJECT_TEMPLATE_DIR);
+        if !candidate.is_dir() {
+            continue;
+        }
+        let canonical = fs::canonicalize(&candidate).ok();
+        if canonical.is_some() && canonical == global {
+            continue;
+        }
+        return Some(candidate);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_project_template_dir() {
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("repo");
+        let nested = project.join("src/components");
+        fs::create_dir_all(&nested).unwrap();
+        let global = root.path().join("global");
+        fs::create_dir_all(&global).unwrap();
+
+        assert_eq!(find_project_template_dir(&nested, &global), None);
+
+        fs::create_dir_all(project.join(PROJECT_TEMPLATE_DIR)).unwrap();
+        assert_eq!(
+            find_project_template_dir(&nested, &global),
+            Some(project.join(PROJECT_TEMPLATE_DIR))
+        );
+
+        // 找到的目录就是全局目录时跳过
+        assert_eq!(
+            find_project_template_dir(&nested, &project.join(PROJECT_TEMPLATE_DIR)),
+            None
+        );
+    }
+}
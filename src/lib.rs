@@ -0,0 +1,56 @@
+#![deny(unsafe_code)]
+//! new-cli 的核心逻辑：模板查找、输入校验、变量渲染与文件创建。
+//!
+//! 命令行程序只负责参数解析、交互和输出，其余功能都通过 [`TemplateEngine`]
+//! 提供，其他工具和集成测试可以直接调用，无需启动二进制。
+
+pub mod adr;
+pub mod batch;
+pub mod blocks;
+pub mod compose;
+pub mod config;
+pub mod cookiecutter;
+pub mod cursor;
+pub mod editorconfig;
+pub mod engine;
+pub mod eol;
+pub mod error;
+pub mod filename;
+pub mod filters;
+pub mod front_matter;
+pub mod gitignore;
+pub mod glob;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod index;
+pub mod insert;
+pub mod layers;
+pub mod license;
+pub mod lint;
+pub mod lock;
+pub mod manifest;
+pub mod migrate;
+pub mod pack;
+pub mod permissions;
+pub mod project;
+pub mod project_type;
+pub mod registry;
+pub mod remote;
+pub mod render;
+pub mod rust_mod;
+pub mod signature;
+pub mod snippets;
+pub mod template;
+pub mod templatize;
+pub mod update;
+pub mod usage;
+pub mod validate;
+pub mod variables;
+pub mod vars_file;
+pub mod vscode;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use engine::{CreateOptions, Lookup, Outcome, Overwrite, TemplateEngine, VariablePrompt};
+pub use error::{Error, Result};
@@ -0,0 +1,74 @@
+//! 生成许可证文件 (`new-cli license MIT`)
+//!
+//! 常用的 SPDX 许可证文本内置在程序中，与用户的模板目录无关。文本中的 `{{year}}` 和
+//! `{{author}}` 会被替换为年份和版权所有者。
+
+use include_dir::{include_dir, Dir};
+
+use crate::error::{Error, Result};
+use crate::render::{self, Variables};
+
+/// 内置的许可证文本，每个文件为 `<SPDX 标识符>.txt`
+static LICENSES: Dir = include_dir!("$CARGO_MANIFEST_DIR/licenses");
+
+/// 内置许可证的 SPDX 标识符，按标识符排序
+pub fn ids() -> Vec<&'static str> {
+    let mut ids: Vec<&str> = LICENSES
+        .files()
+        .filter_map(|file| file.path().to_str()?.strip_suffix(".txt"))
+        .collect();
+    ids.sort_unstable_by_key(|id| id.to_lowercase());
+    ids
+}
+
+/// 按 SPDX 标识符查找许可证（不区分大小写），返回规范的标识符和许可证文本
+pub fn find(id: &str) -> Result<(&'static str, &'static str)> {
+    ids()
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(id))
+        .and_then(|known| {
+            let text = LICENSES
+                .get_file(format!("{}.txt", known))?
+                .contents_utf8()?;
+            Some((known, text))
+        })
+        .ok_or_else(|| Error::UnknownLicense(id.to_string()))
+}
+
+/// 生成许可证文本，替换其中的年份和版权所有者
+pub fn generate(id: &str, year: &str, author: &str) -> Result<String> {
+    let (_, text) = find(id)?;
+    let vars: Variables = [
+        ("year".to_string(), year.to_string()),
+        ("author".to_string(), author.to_string()),
+    ]
+    .into();
+    render::render(text, &vars, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids() {
+        let ids = ids();
+        for id in ["Apache-2.0", "GPL-3.0", "MIT"] {
+            assert!(ids.contains(&id), "{id}");
+        }
+        assert_eq!(find("apache-2.0").unwrap().0, "Apache-2.0");
+        assert!(matches!(find("WTFPL"), Err(Error::UnknownLicense(_))));
+    }
+
+    #[test]
+    fn test_generate() {
+        let mit = generate("mit", "2025", "Jaff Liang").unwrap();
+        assert!(mit.starts_with("MIT License\n\nCopyright (c) 2025 Jaff Liang\n"));
+
+        // 所有内置许可证都能渲染
+        for id in ids() {
+            let text = generate(id, "2025", "me").unwrap();
+            assert!(!text.contains("{{"), "{id}");
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! 模板检查 (`template lint`)
+//!
+//! 在不创建文件的情况下找出模板中的问题：front matter 格式错误、块未闭合等语法错误、
+//! 未知的过滤器和日期格式、既不是内置变量也没有在 front matter 中声明的占位符，
+//! 以及不是合法 UTF-8 的文本模板（这类文件会被当作二进制按原样复制，变量不会被替换）。
+//!
+//! 出现在 `{% if name %}` 块中的 `{{name}}` 视为可选变量，不要求声明；
+//! 条件和循环列表中的变量本身也可以不定义。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::blocks::{self, Condition, Node};
+use crate::cursor;
+use crate::error::{IoContext, Result};
+use crate::filters;
+use crate::front_matter;
+use crate::render::Variables;
+use crate::tr;
+use crate::variables;
+
+/// 模板中的一个问题
+#[derive(Debug, PartialEq)]
+pub enum Issue {
+    /// 文件中没有 NUL 字节，但不是合法的 UTF-8
+    InvalidUtf8,
+    /// front matter 无法解析
+    FrontMatter(String),
+    /// 块语法、过滤器或日期格式错误
+    Syntax(String),
+    /// 未声明的变量
+    Undeclared(String),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Issue::InvalidUtf8 => tr!(
+                "文件不是合法的 UTF-8，将被当作二进制文件原样复制，变量不会被替换",
+                "the file is not valid UTF-8, it will be copied as binary without substituting variables"
+            ),
+            Issue::FrontMatter(message) => {
+                tr!("front matter 格式错误: {}", "invalid front matter: {}", message)
+            }
+            Issue::Syntax(message) => message.clone(),
+            Issue::Undeclared(name) => tr!(
+                "变量 {} 既不是内置变量，也没有在 front matter 的 [variables] 中声明",
+                "variable {} is neither built in nor declared under [variables] in the front matter",
+                name
+            ),
+        };
+        f.write_str(&message)
+    }
+}
+
+/// 检查一个模板文件；`defined` 是创建文件时总会定义的变量（例如配置文件中的变量）
+///
+/// 二进制模板没有问题。读取文件失败时返回错误。
+pub fn lint(path: &Path, defined: &Variables) -> Result<Vec<Issue>> {
+    let bytes = fs::read(path).io_context(|| {
+        tr!(
+            "无法读取模板文件: {:?}",
+            "failed to read template file: {:?}",
+            path
+        )
+    })?;
+    let content = match String::from_utf8(bytes) {
+        Ok(content) if !content.contains('\0') => content,
+        Ok(_) => return Ok(Vec::new()),
+        Err(err) if err.as_bytes().iter().take(8000).all(|&b| b != 0) => {
+            return Ok(vec![Issue::InvalidUtf8])
+        }
+        Err(_) => return Ok(Vec::new()),
+    };
+    Ok(lint_str(&content, defined))
+}
+
+fn lint_str(content: &str, defined: &Variables) -> Vec<Issue> {
+    let (front_matter, body) = match front_matter::split(content) {
+        Ok(split) => split,
+        Err(e) => return vec![Issue::FrontMatter(e.message().trim().to_string())],
+    };
+    let nodes = match blocks::parse(body) {
+        Ok(nodes) => nodes,
+        Err(e) => return vec![Issue::Syntax(e.to_string())],
+    };
+
+    let mut known: HashSet<String> = variables::builtin_variables("", "").into_keys().collect();
+    known.insert(cursor::PLACEHOLDER.to_string());
+    known.extend(defined.keys().cloned());
+    if let Some(front_matter) = front_matter {
+        known.extend(front_matter.variables.into_keys());
+    }
+
+    let mut issues = Vec::new();
+    check_nodes(&nodes, &known, &mut issues);
+    issues
+}
+
+fn check_nodes(nodes: &[Node], known: &HashSet<String>, issues: &mut Vec<Issue>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Placeholder(inner) => {
+                if let Some(issue) = check_placeholder(inner, known) {
+                    if !issues.contains(&issue) {
+                        issues.push(issue);
+                    }
+                }
+            }
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                for (condition, body) in branches {
+                    // 条件成立说明变量有值，分支中可以直接使用
+                    let mut scope = known.clone();
+                    if let Condition::Truthy(name) | Condition::Eq(name, _) = condition {
+                        scope.insert(name.to_string());
+                    }
+                    check_nodes(body, &scope, issues);
+                }
+                check_nodes(otherwise, known, issues);
+            }
+            Node::For { item, body, .. } => {
+                let mut scope = known.clone();
+                scope.insert(item.to_string());
+                for name in ["loop.index", "loop.first", "loop.last"] {
+                    scope.insert(name.to_string());
+                }
+                check_nodes(body, &scope, issues);
+            }
+        }
+    }
+}
+
+fn check_placeholder(inner: &str, known: &HashSet<String>) -> Option<Issue> {
+    let mut parts = inner.split('|').map(str::trim);
+    let key = parts.next().unwrap_or_default();
+    for filter in parts {
+        if let Err(e) = filters::check(filter) {
+            return Some(Issue::Syntax(e.to_string()));
+        }
+    }
+    if known.contains(key) || key.starts_with("env.") || key.starts_with("git.") {
+        return None;
+    }
+    match variables::resolve_formatted(key) {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(Issue::Undeclared(key.to_string())),
+        Err(e) => Some(Issue::Syntax(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(content: &str) -> Vec<Issue> {
+        lint_str(content, &Variables::new())
+    }
+
+    #[test]
+    fn test_lint_clean_template() {
+        let template = "\
++++
+[variables]
+title = { required = true }
++++
+# {{title | upper}} by {{author}} on {{date:%Y}}
+{% if license %}License: {{license}}{% endif %}
+{% for field in fields %}{{loop.index}}. {{field}}{% endfor %}
+{{env.CI_COMMIT}}{{cursor}}
+";
+        assert_eq!(lint(template), []);
+    }
+
+    #[test]
+    fn test_lint_reports_problems() {
+        assert_eq!(
+            lint("{{title}} {{ title }} {% if x %}{{y}}{% endif %}"),
+            [
+                Issue::Undeclared("title".to_string()),
+                Issue::Undeclared("y".to_string())
+            ]
+        );
+        assert!(matches!(lint("{{name | shout}}")[..], [Issue::Syntax(_)]));
+        assert!(matches!(lint("{{date:%Q}}")[..], [Issue::Syntax(_)]));
+        assert!(matches!(
+            lint("{% for x in xs %}open")[..],
+            [Issue::Syntax(_)]
+        ));
+        assert!(matches!(
+            lint("+++\nmode = 1\n+++\nbody")[..],
+            [Issue::FrontMatter(_)]
+        ));
+
+        let defined: Variables = [("company".to_string(), "Acme".to_string())].into();
+        assert_eq!(lint_str("{{company}}", &defined), []);
+    }
+
+    #[test]
+    fn test_lint_file_encoding() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let latin1 = dir.path().join("latin1.txt");
+        fs::write(&latin1, b"caf\xe9 {{name}}").unwrap();
+        assert_eq!(
+            super::lint(&latin1, &Variables::new()).unwrap(),
+            [Issue::InvalidUtf8]
+        );
+
+        let png = dir.path().join("logo.png");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        assert_eq!(super::lint(&png, &Variables::new()).unwrap(), []);
+    }
+}
@@ -0,0 +1,293 @@
+//! 模板校验和锁文件 `<数据目录>/templates.lock`
+//!
+//! 通过 `template install`、`template pack import` 安装的模板和下载过的远程模板，
+//! 其内容的 SHA-256 会记录在锁文件中：
+//!
+//! ```toml
+//! [templates]
+//! "react-kit/component.tsx" = "9f86d08..."
+//!
+//! [remote]
+//! "https://example.com/t/invoice.md" = "2cf24db..."
+//! ```
+//!
+//! 使用这些模板前会重新计算校验和，与记录不一致时拒绝使用；
+//! `template verify` 检查所有记录，`template verify --update` 接受当前内容。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::data_dir;
+use crate::error::{Error, IoContext, Result};
+use crate::remote::sha256_hex;
+use crate::tr;
+
+/// 锁文件中的全部记录
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// 模板目录中的模板：相对于模板目录的路径（以 `/` 分隔）-> SHA-256
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+    /// 远程模板：下载地址 -> SHA-256
+    #[serde(default)]
+    pub remote: BTreeMap<String, String>,
+}
+
+/// 一条与锁文件不一致的记录
+#[derive(Debug, PartialEq, Eq)]
+pub enum Drift {
+    /// 模板内容与记录的校验和不同
+    Modified {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    /// 记录的模板已不存在
+    Missing { name: String },
+}
+
+/// 锁文件路径 `<数据目录>/templates.lock`
+pub fn lock_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("templates.lock"))
+}
+
+impl Lockfile {
+    /// 读取锁文件，文件不存在时返回空的锁文件
+    pub fn load(path: &Path) -> Result<Lockfile> {
+        if !path.exists() {
+            return Ok(Lockfile::default());
+        }
+        let content = fs::read_to_string(path)
+            .io_context(|| tr!("无法读取锁文件 {:?}", "failed to read lock file {:?}", path))?;
+        toml::from_str(&content).map_err(|e| Error::Lockfile {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// 写入锁文件，必要时创建所在目录
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let context = || {
+            tr!(
+                "无法写入锁文件 {:?}",
+                "failed to write lock file {:?}",
+                path
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).io_context(context)?;
+        }
+        let content = toml::to_string(self).map_err(|e| Error::Lockfile {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        fs::write(path, content).io_context(context)
+    }
+
+    /// 记录模板目录中 `name`（文件或子目录）下所有文件的校验和，返回记录的文件数
+    ///
+    /// 子目录中的 `.git` 会被跳过；`name` 下原有的记录会被替换。
+    pub fn pin(&mut self, template_dir: &Path, name: &str) -> Result<usize> {
+        self.unpin(name);
+        let mut count = 0;
+        let mut pending = vec![template_dir.join(name)];
+        while let Some(path) = pending.pop() {
+            if path.is_dir() {
+                let entries = fs::read_dir(&path).io_context(|| {
+                    tr!("无法读取目录 {:?}", "failed to read directory {:?}", path)
+                })?;
+                for entry in entries.filter_map(std::result::Result::ok) {
+                    if entry.file_name() != ".git" {
+                        pending.push(entry.path());
+                    }
+                }
+            } else if let Some(key) = key(template_dir, &path) {
+                let bytes = read(&path)?;
+                self.templates.insert(key, sha256_hex(&bytes));
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 删除 `name`（文件或子目录）及其下所有模板的记录
+    pub fn unpin(&mut self, name: &str) {
+        let prefix = format!("{}/", name.trim_end_matches('/'));
+        self.templates
+            .retain(|key, _| key != name && !key.starts_with(&prefix));
+    }
+
+    /// 把 `old` 的记录移到 `new` 下，用于重命名模板
+    pub fn rename(&mut self, old: &str, new: &str) {
+        if let Some(checksum) = self.templates.remove(old) {
+            self.templates.insert(new.to_string(), checksum);
+        }
+    }
+
+    /// 校验模板目录中的 `path`，其内容为 `bytes`；没有记录的模板总是通过
+    pub fn verify_template(&self, template_dir: &Path, path: &Path, bytes: &[u8]) -> Result<()> {
+        let Some(name) = key(template_dir, path) else {
+            return Ok(());
+        };
+        let Some(expected) = self.templates.get(&name) else {
+            return Ok(());
+        };
+        let actual = sha256_hex(bytes);
+        if actual != *expected {
+            return Err(Error::TemplateDrifted {
+                name,
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// 有记录的模板名；`filter` 不为空时只包括这些模板及其子目录中的模板
+    pub fn names(&self, filter: &[String]) -> Vec<&String> {
+        self.templates
+            .keys()
+            .filter(|name| {
+                filter.is_empty()
+                    || filter.iter().any(|selected| {
+                        *name == selected || name.starts_with(&format!("{}/", selected))
+                    })
+            })
+            .collect()
+    }
+
+    /// 检查模板目录中的记录，`filter` 的含义与 [`names`](Self::names) 相同
+    pub fn verify_all(&self, template_dir: &Path, filter: &[String]) -> Result<Vec<Drift>> {
+        let mut drifts = Vec::new();
+        for name in self.names(filter) {
+            let expected = &self.templates[name];
+            let path = template_dir.join(name);
+            if !path.is_file() {
+                drifts.push(Drift::Missing { name: name.clone() });
+                continue;
+            }
+            let actual = sha256_hex(&read(&path)?);
+            if actual != *expected {
+                drifts.push(Drift::Modified {
+                    name: name.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(drifts)
+    }
+}
+
+/// `path` 相对于模板目录的记录名，不在模板目录中时返回 None
+pub fn key(template_dir: &Path, path: &Path) -> Option<String> {
+    let relative = match path.strip_prefix(template_dir) {
+        Ok(relative) => relative.to_path_buf(),
+        // 引用的模板可能是规范化后的路径
+        Err(_) => fs::canonicalize(path)
+            .ok()?
+            .strip_prefix(fs::canonicalize(template_dir).ok()?)
+            .ok()?
+            .to_path_buf(),
+    };
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).io_context(|| {
+        tr!(
+            "无法读取模板文件: {:?}",
+            "failed to read template file: {:?}",
+            path
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn templates() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("kit/.git")).unwrap();
+        fs::write(dir.path().join("kit/page.html"), "<p></p>").unwrap();
+        fs::write(dir.path().join("kit/.git/HEAD"), "ref").unwrap();
+        fs::write(dir.path().join("index.md"), "# {{name}}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_pin_and_verify() {
+        let dir = templates();
+        let mut lock = Lockfile::default();
+        assert_eq!(lock.pin(dir.path(), "kit").unwrap(), 1);
+        assert_eq!(lock.templates.keys().collect::<Vec<_>>(), ["kit/page.html"]);
+
+        let page = dir.path().join("kit/page.html");
+        assert!(lock.verify_template(dir.path(), &page, b"<p></p>").is_ok());
+        assert!(matches!(
+            lock.verify_template(dir.path(), &page, b"<p>changed</p>"),
+            Err(Error::TemplateDrifted { .. })
+        ));
+        // 没有记录的模板不校验
+        let index = dir.path().join("index.md");
+        assert!(lock.verify_template(dir.path(), &index, b"").is_ok());
+
+        assert!(lock.verify_all(dir.path(), &[]).unwrap().is_empty());
+        fs::write(&page, "<p>changed</p>").unwrap();
+        assert!(matches!(
+            &lock.verify_all(dir.path(), &[]).unwrap()[..],
+            [Drift::Modified { name, .. }] if name == "kit/page.html"
+        ));
+        assert!(lock
+            .verify_all(dir.path(), &["index.md".to_string()])
+            .unwrap()
+            .is_empty());
+        fs::remove_file(&page).unwrap();
+        assert_eq!(
+            lock.verify_all(dir.path(), &[]).unwrap(),
+            [Drift::Missing {
+                name: "kit/page.html".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unpin_and_rename() {
+        let dir = templates();
+        let mut lock = Lockfile::default();
+        lock.pin(dir.path(), "kit").unwrap();
+        lock.pin(dir.path(), "index.md").unwrap();
+        lock.rename("index.md", "notes/index.md");
+        assert!(lock.templates.contains_key("notes/index.md"));
+        lock.unpin("kit");
+        assert_eq!(
+            lock.templates.keys().collect::<Vec<_>>(),
+            ["notes/index.md"]
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state/templates.lock");
+        assert_eq!(Lockfile::load(&path).unwrap(), Lockfile::default());
+
+        let mut lock = Lockfile::default();
+        lock.templates.insert("a.md".to_string(), "00".to_string());
+        lock.remote
+            .insert("https://example.com/a.md".to_string(), "11".to_string());
+        lock.save(&path).unwrap();
+        assert_eq!(Lockfile::load(&path).unwrap(), lock);
+
+        fs::write(&path, "templates = 1").unwrap();
+        assert!(matches!(Lockfile::load(&path), Err(Error::Lockfile { .. })));
+    }
+}
@@ -1,262 +1,2876 @@
 #![deny(unsafe_code)]
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+use clap_complete::CompleteEnv;
+use new_cli::config::Config;
+use new_cli::cursor::{self, Position};
+use new_cli::eol::Eol;
+use new_cli::i18n::Lang;
+use new_cli::insert::Insertion;
+use new_cli::lock::{self, Lockfile};
+use new_cli::manifest::Manifest;
+use new_cli::project_type::ProjectDefaults;
+use new_cli::render::Variables;
+use new_cli::template::{Filter, Pattern, TemplateInfo};
+use new_cli::variables::GitCache;
+use new_cli::{adr, filename as pattern, history, hooks, project, remote, render, rust_mod, tr};
+use new_cli::{usage, variables, vars_file};
+use new_cli::{CreateOptions, Error, Lookup, Outcome, Overwrite, TemplateEngine, VariablePrompt};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+mod cli;
+
+use cli::exit::{self, ExitCode};
+use cli::output::{self, FileReport, OutputFormat, Reporter, Status};
+use cli::template::SnippetFormat;
+use cli::{clipboard, completions, editor, git, picker, prompt, template};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// 文件名 (默认: index)
-    #[arg(default_value = "index")]
-    filename: String,
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// 界面语言: zh 或 en (默认: 根据 LANG 等环境变量确定)
+    #[arg(long, global = true, value_name = "LANG", value_parser = cli::i18n::parse_lang)]
+    lang: Option<Lang>,
+
+    /// 不输出提示信息，只输出警告和错误
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// 输出调试信息，例如模板查找过程、目标路径和编辑器命令；-vv 输出更多细节
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// 离线模式：不访问网络，远程模板只使用缓存中已下载的副本
+    #[arg(long, global = true)]
+    offline: bool,
+
+    #[command(flatten)]
+    create: CreateArgs,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// 管理模板目录中的模板
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
+    /// 根据目录模板创建整个项目
+    Project {
+        /// 项目名，即要创建的目录名
+        name: String,
+
+        /// 模板目录下的项目模板子目录名
+        #[arg(short, long)]
+        template: String,
+
+        /// 定义模板变量，可重复使用
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = render::parse_define)]
+        defines: Vec<(String, String)>,
+
+        /// 从 JSON、TOML 或 YAML 文件读取模板变量，可重复使用，-D 的优先级更高
+        #[arg(long, value_name = "FILE")]
+        vars_file: Vec<PathBuf>,
+
+        /// 模板中出现未定义的变量时报错，而不是替换为空
+        #[arg(long)]
+        strict_vars: bool,
+
+        /// 允许模板通过 {{env.NAME}} 读取所有环境变量，而不只是配置中 allow_env 列出的变量
+        #[arg(long)]
+        allow_env: bool,
+    },
+
+    /// 在模板注册表中搜索模板集
+    Search {
+        /// 关键字，匹配名称、说明和标签 (不区分大小写，默认: 全部)
+        keyword: Option<String>,
+
+        /// 只列出带有该标签的模板集，可重复使用，此时需要带有所有标签
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// 查看创建过的文件记录
+    History {
+        /// 只显示今天的记录
+        #[arg(long)]
+        today: bool,
+
+        /// 只显示使用该模板的记录 (例如: index.html 或 index)
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+    },
+
+    /// 撤销最近创建的文件：删除文件，或用备份恢复被覆盖的原文件
+    Undo {
+        /// 撤销最近的几次创建
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        steps: u64,
+
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// 查看模板使用统计：最常用、最近使用和从未使用的模板 (需要配置 track_usage = true)
+    Stats {
+        /// 每一项最多列出的模板数
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 10)]
+        limit: usize,
+    },
+
+    /// 检查配置、模板目录、模板和编辑器，并给出修复建议
+    Doctor,
+
+    /// 列出 PATH 中的插件 (名为 new-cli-<名称> 的程序，通过 new-cli <名称> 运行)
+    Plugins,
+
+    /// 运行插件 new-cli-<名称>，其余参数原样传给插件 (例如: new-cli plugin gen api v2)
+    Plugin {
+        /// 插件名
+        name: String,
+
+        /// 传给插件的参数
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<std::ffi::OsString>,
+    },
+
+    /// 监视队列文件（或目录），为每个新增的行创建文件，行的写法与文件名参数相同 (例如: new-cli --dir notes watch tasks.txt)
+    Watch {
+        /// 队列文件或目录
+        path: PathBuf,
+
+        /// 检查队列的间隔秒数
+        #[arg(long, value_name = "SECS", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// 同时处理开始监视前已有的条目
+        #[arg(long)]
+        from_start: bool,
+    },
+
+    /// 按 CSV 或 JSON 清单批量创建文件，每行一个文件，最后输出汇总表 (例如: new-cli --dir src batch entities.csv)
+    Batch {
+        /// 清单文件 (.csv 或 .json)，列 filename、extension、template、dir 之外的列都是模板变量
+        spec: PathBuf,
+
+        /// 某一行失败后继续处理其余的行，而不是立即停止
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// 同时创建的文件数 (默认: CPU 核数)；大于 1 时不询问变量，-j 1 逐个创建
+        #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+    },
+
+    /// 从 GitHub Releases 更新到最新版本：下载当前平台的可执行文件，校验 SHA-256 后替换自身
+    SelfUpdate {
+        /// 只检查是否有新版本，不下载
+        #[arg(long)]
+        check: bool,
+
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// 在全屏终端界面中浏览、预览模板并创建文件
+    Browse,
+
+    /// 在当前目录生成许可证文件 LICENSE (例如: new-cli license MIT)
+    License {
+        /// 许可证的 SPDX 标识符 (不区分大小写)
+        #[arg(required_unless_present = "list")]
+        id: Option<String>,
+
+        /// 版权所有者 (默认: 配置中的 author 变量，其次是 git 的 user.name)
+        #[arg(long)]
+        author: Option<String>,
+
+        /// 版权年份 (默认: 今年)
+        #[arg(long)]
+        year: Option<String>,
+
+        /// 列出内置的许可证
+        #[arg(long, conflicts_with = "id")]
+        list: bool,
+
+        /// 输出到标准输出，不写入文件
+        #[arg(long)]
+        stdout: bool,
+
+        /// 覆盖已存在的 LICENSE
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// 创建下一条架构决策记录 (例如: new-cli adr "Use Postgres")
+    Adr {
+        /// 决策的标题，多个参数以空格连接
+        #[arg(required = true)]
+        title: Vec<String>,
+
+        /// 记录的状态
+        #[arg(long, default_value = adr::DEFAULT_STATUS)]
+        status: String,
+
+        /// 记录所在的目录 (默认: 配置中的 adr_dir，其次是 docs/adr)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// 输出到标准输出，不写入文件
+        #[arg(long)]
+        stdout: bool,
+    },
+
+    /// 生成或补充当前目录的 .gitignore，可合并多个规则集 (例如: new-cli gitignore rust node)
+    Gitignore {
+        /// 规则集名称，内置的规则集之外的名称从 gitignore.io 下载
+        #[arg(required_unless_present = "list")]
+        profiles: Vec<String>,
+
+        /// 列出内置的规则集
+        #[arg(long, conflicts_with = "profiles")]
+        list: bool,
+
+        /// 输出要追加的规则，不写入文件
+        #[arg(long)]
+        stdout: bool,
+    },
+
+    /// 查看或清理缓存中下载的远程模板
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// 管理校验模板包签名使用的受信任公钥 (minisign 或 GPG)
+    Trust {
+        #[command(subcommand)]
+        action: TrustAction,
+    },
+
+    /// 生成 shell 补全脚本
+    Completions {
+        /// 目标 shell
+        shell: completions::Shell,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// 列出所有可用模板
+    List {
+        /// 只列出名称匹配该模式的模板，例如 'react/*' 或 '*.md'
+        pattern: Option<String>,
+
+        /// 把模式和 --tag 作为正则表达式，而不是通配模式
+        #[arg(long)]
+        regex: bool,
+
+        /// 只列出这些后缀的模板，可重复使用
+        #[arg(long = "ext", value_name = "EXT")]
+        extensions: Vec<String>,
+
+        /// 只列出带有该标签的模板，可重复使用，此时需要带有所有标签
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// 以 JSON 数组输出，便于脚本和其他工具使用
+        #[arg(long, conflicts_with = "show_where")]
+        json: bool,
+
+        /// 显示每个模板来自哪个模板目录，以及被它遮蔽的同名模板
+        #[arg(long = "where")]
+        show_where: bool,
+    },
+
+    /// 输出模板内容，用于在使用前检查模板
+    Show {
+        /// 模板文件名 (例如: index.html)
+        #[arg(add = ArgValueCompleter::new(completions::complete_template_file_all))]
+        name: String,
+
+        /// 输出使用当前默认变量渲染后的结果
+        #[arg(long)]
+        rendered: bool,
+
+        /// 渲染时使用的模板变量，可重复使用
+        #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = render::parse_define, requires = "rendered")]
+        defines: Vec<(String, String)>,
+
+        /// 不进行语法高亮
+        #[arg(long)]
+        no_highlight: bool,
+    },
+
+    /// 列出模板中使用的所有标签，以及带有各标签的模板数
+    Tags,
+
+    /// 检查模板中的语法错误、未声明的变量和编码问题，存在问题时退出码为 1
+    Lint {
+        /// 要检查的模板文件名 (默认: 所有模板)
+        #[arg(add = ArgValueCompleter::new(completions::complete_template_file_all))]
+        names: Vec<String>,
+
+        /// 检查该目录中的模板，而不是配置的模板目录 (例如在模板仓库的 CI 中)
+        #[arg(long, value_name = "DIR", conflicts_with = "names")]
+        dir: Option<PathBuf>,
+    },
+
+    /// 显示创建某个文件时会使用哪个模板，以及查找的完整过程
+    Which {
+        /// 要创建的文件名，可带命名空间 (例如: report.md 或 web/index.html)
+        name: String,
+
+        /// 与创建文件时相同，在该命名空间中查找
+        #[arg(long, value_name = "NAMESPACE", add = ArgValueCompleter::new(completions::complete_namespace))]
+        from: Option<String>,
+    },
+
+    /// 用编辑器打开模板，模板不存在时先创建它
+    Edit {
+        /// 模板文件名，可带命名空间 (例如: web/page.html)
+        #[arg(add = ArgValueCompleter::new(completions::complete_template_file_all))]
+        name: String,
+
+        /// 模板不存在时复制该模板的内容作为起点，而不是创建空模板
+        #[arg(long, value_name = "TEMPLATE", add = ArgValueCompleter::new(completions::complete_template_file_all))]
+        from: Option<String>,
+
+        /// 打开模板使用的编辑器，可带参数 (例如: --editor "code --wait")
+        #[arg(long, value_name = "CMD")]
+        editor: Option<String>,
+
+        /// 等待编辑器退出
+        #[arg(long)]
+        wait: bool,
+    },
+
+    /// 将已有文件添加为模板
+    Add {
+        /// 源文件路径
+        file: PathBuf,
+
+        /// 模板名，可带命名空间 (例如: web/page；默认: 源文件名，不含后缀)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// 覆盖同名模板
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 把已有文件转换为模板：将变量值替换为占位符后保存到模板目录
+    Init {
+        /// 要转换的文件
+        #[arg(long, value_name = "FILE")]
+        from_current: PathBuf,
+
+        /// 要替换为占位符的值，例如 name=Button 会把 Button 替换为 {{name}}，可重复
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = render::parse_define)]
+        vars: Vec<(String, String)>,
+
+        /// 同时替换值的其他大小写形式 (例如 myButton、my-button、MY_BUTTON)，只在完整的标识符处替换
+        #[arg(long)]
+        case_variants: bool,
+
+        /// 模板名，可带命名空间 (例如: web/page；默认: 源文件名，不含后缀)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// 覆盖同名模板
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 删除模板
+    Remove {
+        /// 模板文件名 (例如: index.html)
+        #[arg(add = ArgValueCompleter::new(completions::complete_template_file))]
+        name: String,
+
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// 重命名模板
+    Rename {
+        /// 原模板文件名
+        #[arg(add = ArgValueCompleter::new(completions::complete_template_file))]
+        old: String,
+
+        /// 新模板文件名
+        new: String,
+
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// 从 git 仓库安装一组模板
+    Install {
+        /// git 仓库地址，或模板注册表中的名称
+        url: String,
+
+        /// 安装到模板目录下的子目录名 (默认: 仓库名)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// 更新通过 git 安装的模板集
+    Update {
+        /// 模板集名称 (默认: 更新全部)
+        name: Option<String>,
+    },
+
+    /// 把 VS Code 代码片段文件中的片段转换为模板
+    ImportVscode {
+        /// 代码片段文件 (例如: typescript.json 或 *.code-snippets)
+        file: PathBuf,
+
+        /// 模板的后缀 (默认: 根据片段的 scope 或文件名推断语言)
+        #[arg(long, value_name = "EXT")]
+        ext: Option<String>,
+
+        /// 导入到该命名空间中 (默认: 模板目录根部)
+        #[arg(long, value_name = "NAMESPACE")]
+        into: Option<String>,
+
+        /// 覆盖已存在的模板
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 由模板生成 VS Code 代码片段文件 (或 JetBrains Live Templates)
+    ExportVscode {
+        /// 写入该文件 (默认: 输出到标准输出)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// 只导出该命名空间中的模板
+        #[arg(long, value_name = "NAMESPACE", add = ArgValueCompleter::new(completions::complete_namespace))]
+        namespace: Option<String>,
+
+        /// 代码片段格式
+        #[arg(long, value_enum, default_value_t = SnippetFormat::Vscode)]
+        format: SnippetFormat,
+    },
+
+    /// 按锁文件 templates.lock 检查已安装模板的内容是否被改动，存在不一致时退出码为 1
+    Verify {
+        /// 只检查这些模板或模板集 (默认: 锁文件中的所有模板)
+        names: Vec<String>,
+
+        /// 接受当前内容，重新记录校验和
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// 以 .tar.gz 或 .zip 模板包导入、导出模板
+    Pack {
+        #[command(subcommand)]
+        action: PackAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// 列出缓存中的模板及其下载地址
+    List,
+
+    /// 删除缓存中的所有模板
+    Clean,
+}
+
+#[derive(Subcommand)]
+enum TrustAction {
+    /// 信任一个公钥：minisign 公钥、minisign .pub 文件或 GPG 公钥文件
+    Add {
+        /// 公钥或公钥文件路径
+        key: String,
+    },
+
+    /// 列出受信任的公钥
+    List,
+
+    /// 删除受信任的公钥
+    Remove {
+        /// minisign 密钥 ID 或 GPG 指纹
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PackAction {
+    /// 将模板目录中的模板导出为模板包
+    Export {
+        /// 模板包文件路径，按后缀决定格式 (.tar.gz、.tgz 或 .zip)
+        file: PathBuf,
+
+        /// 只导出该命名空间中的模板
+        #[arg(long, value_name = "NAMESPACE", add = ArgValueCompleter::new(completions::complete_namespace))]
+        namespace: Option<String>,
+    },
+
+    /// 从模板包文件或 HTTP(S) 地址导入模板
+    Import {
+        /// 模板包文件路径或地址
+        source: String,
+
+        /// 导入到该命名空间中 (默认: 模板目录根部)
+        #[arg(long, value_name = "NAMESPACE")]
+        into: Option<String>,
+
+        /// 覆盖已存在的模板
+        #[arg(long)]
+        force: bool,
+
+        /// 分离签名文件的路径或地址 (默认: 模板包旁边的 .minisig、.asc 或 .sig)
+        #[arg(long, value_name = "FILE|URL")]
+        signature: Option<String>,
+
+        /// 没有签名时拒绝导入
+        #[arg(long)]
+        require_signature: bool,
+    },
+}
+
+#[derive(Args, Clone)]
+struct CreateArgs {
+    /// 要创建的文件：`[文件名] [后缀]`，或一个或多个 `name.ext` (默认: index html)
+    #[arg(value_name = "FILE", add = ArgValueCompleter::new(completions::complete_template_name))]
+    targets: Vec<String>,
+
+    /// 为所有文件指定后缀，此时每个位置参数都是一个文件名
+    #[arg(long, value_name = "EXT")]
+    ext: Option<String>,
+
+    /// 定义模板变量，可重复使用 (例如: -D title="Q3 Review")
+    #[arg(short = 'D', long = "define", value_name = "KEY=VALUE", value_parser = render::parse_define)]
+    defines: Vec<(String, String)>,
+
+    /// 从 JSON、TOML 或 YAML 文件读取模板变量，可重复使用，-D 的优先级更高
+    #[arg(long, value_name = "FILE")]
+    vars_file: Vec<PathBuf>,
+
+    /// 模板中出现未定义的变量时报错，而不是替换为空
+    #[arg(long)]
+    strict_vars: bool,
+
+    /// 允许模板通过 {{env.NAME}} 读取所有环境变量，而不只是配置中 allow_env 列出的变量
+    #[arg(long)]
+    allow_env: bool,
 
-    /// 文件后缀 (默认: html)
-    #[arg(default_value = "html")]
-    extension: String,
+    /// 本次打开文件使用的命令，优先于配置的编辑器；可带参数，{file}、{line}、{column} 会被替换为文件和光标位置 (例如: --open-with "code -r" 或 --open-with "idea --line {line} {file}")
+    #[arg(long, visible_alias = "open-with", value_name = "CMD")]
+    editor: Option<String>,
+
+    /// 在 macOS 上使用指定的应用打开文件，相当于 open -a APP (例如: --app "Visual Studio Code")
+    #[arg(long, value_name = "APP", conflicts_with = "editor")]
+    app: Option<String>,
+
+    /// 只创建文件，不使用编辑器打开
+    #[arg(long)]
+    no_open: bool,
+
+    /// 如果文件在 git 工作区中，创建后将其添加到暂存区
+    #[arg(long, conflicts_with = "no_git_add")]
+    git_add: bool,
+
+    /// 不自动 git add，即使配置文件中设置了 git_add = true
+    #[arg(long)]
+    no_git_add: bool,
+
+    /// 在 src/ 中创建 .rs 文件后，在父模块 (lib.rs、main.rs 或 mod.rs) 中添加 mod 声明
+    #[arg(long, conflicts_with = "no_add_mod")]
+    add_mod: bool,
+
+    /// 不添加 mod 声明，即使配置文件中设置了 add_mod = true
+    #[arg(long)]
+    no_add_mod: bool,
+
+    /// 打开文件时把光标放在第 N 行，优先于模板中的 {{cursor}}
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    line: Option<u64>,
+
+    /// 模板中除 {{cursor}} 外另一个标记光标位置的文本 (例如: --cursor-marker '$0')，写入时删除
+    #[arg(long, value_name = "TEXT")]
+    cursor_marker: Option<String>,
+
+    /// 结果的输出格式：text 或 json (json 时在结束后输出一个 JSON 文档，便于脚本解析)
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t = OutputFormat::Text, conflicts_with = "stdout")]
+    output: OutputFormat,
+
+    /// 等待编辑器退出，并以编辑器的退出码结束
+    #[arg(long, conflicts_with_all = ["no_open", "stdout"])]
+    wait: bool,
+
+    /// 在前台运行编辑器并等待其退出，用于未被识别的终端编辑器 (vim、nano 等会自动识别)
+    #[arg(long, conflicts_with_all = ["no_open", "stdout"])]
+    terminal_editor: bool,
+
+    /// 忽略 .editorconfig，按模板原样写入
+    #[arg(long)]
+    no_editorconfig: bool,
+
+    /// 统一新建文件的换行符: lf、crlf 或 native (默认: 保留模板中的换行)
+    #[arg(long, value_name = "EOL", value_parser = Eol::parse)]
+    eol: Option<Eol>,
+
+    /// 为新建文件加上执行权限 (仅 Unix)
+    #[arg(long)]
+    exec: bool,
+
+    /// 从所有模板中交互式选择要使用的模板
+    #[arg(long, conflicts_with = "no_input")]
+    pick: bool,
+
+    /// 交互式选择模板 (--pick 或未找到模板时) 只列出带有该标签的模板，可重复使用
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// 从模板目录的子目录（命名空间）中查找模板，或使用 GitHub 仓库中的模板 (例如: --from web 或 --from gh:acme/templates#react/component.tsx@main)
+    #[arg(long, value_name = "NAMESPACE", add = ArgValueCompleter::new(completions::complete_namespace))]
+    from: Option<String>,
+
+    /// 使用通过 HTTP(S) 下载的模板 (例如: --from-url https://example.com/t/invoice.md)
+    #[arg(long, value_name = "URL", conflicts_with_all = ["pick", "alias", "from"])]
+    from_url: Option<String>,
+
+    /// 使用剪贴板中的文本作为模板内容
+    #[arg(long, conflicts_with_all = ["pick", "alias", "from", "from_url"])]
+    from_clipboard: bool,
+
+    /// 使用从标准输入读取的内容作为模板内容 (例如: curl ... | new-cli page html --from-stdin)
+    #[arg(long, conflicts_with_all = ["pick", "alias", "from", "from_url", "from_clipboard"])]
+    from_stdin: bool,
+
+    /// 在标准错误中输出查找模板的过程：检查过的每个候选模板及其结果
+    #[arg(long)]
+    explain: bool,
+
+    /// 不经确认直接使用 default.<后缀> 或配置的后备模板；只在配置了 confirm_fallback = true 时有效，默认本来就不需要确认
+    #[arg(long, conflicts_with = "empty")]
+    allow_fallback: bool,
+
+    /// 不查找模板，直接创建空文件 (不会用到其他同后缀的模板)
+    #[arg(long, conflicts_with_all = ["pick", "alias", "from", "from_url", "from_clipboard", "from_stdin", "sha256", "cache"])]
+    empty: bool,
+
+    /// 将渲染结果复制到剪贴板；与 --stdout 一起使用时不创建文件
+    #[arg(long, conflicts_with_all = ["append", "insert_at"])]
+    to_clipboard: bool,
+
+    /// 校验远程模板 (--from-url 或 --from gh:...) 的 SHA-256，不一致时拒绝使用
+    #[arg(long, value_name = "HEX")]
+    sha256: Option<String>,
+
+    /// 远程模板 (--from-url 或 --from gh:...) 优先使用缓存中已下载的版本，不再重新下载
+    #[arg(long)]
+    cache: bool,
+
+    /// 将渲染结果输出到标准输出，不创建文件也不打开编辑器
+    #[arg(long, conflicts_with_all = ["dir", "force", "skip", "backup"])]
+    stdout: bool,
+
+    /// 写入前先显示渲染结果的前 N 行 (默认 20 行，用 --preview=N 指定)，确认后才创建文件
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["stdout", "no_input", "ci"]
+    )]
+    preview: Option<u64>,
+
+    /// 将渲染结果追加到已存在的目标文件末尾
+    #[arg(long, conflicts_with_all = ["insert_at", "stdout", "force", "skip", "backup"])]
+    append: bool,
+
+    /// 将渲染结果插入已存在的目标文件：行号表示插入到该行之前，其他内容表示插入到第一个包含该标记的行之前
+    #[arg(
+        long,
+        value_name = "LINE|MARKER",
+        value_parser = Insertion::parse,
+        conflicts_with_all = ["stdout", "force", "skip", "backup"]
+    )]
+    insert_at: Option<Insertion>,
+
+    /// 使用配置文件中定义的模板别名 (例如: --alias rc)
+    #[arg(long, value_name = "NAME", conflicts_with = "pick")]
+    alias: Option<String>,
+
+    /// 不进行任何交互：不询问变量、不弹出模板选择列表
+    #[arg(long)]
+    no_input: bool,
+
+    /// CI 模式：不打开编辑器、不进行任何交互、未定义的变量报错，并输出 JSON；设置了环境变量 CI 时自动启用
+    #[arg(long, conflicts_with_all = ["wait", "pick", "stdout"])]
+    ci: bool,
+
+    /// 不执行模板和配置文件中定义的钩子命令
+    #[arg(long)]
+    no_hooks: bool,
+
+    /// 在指定目录中创建文件 (默认: 当前目录)
+    #[arg(long, value_name = "PATH")]
+    dir: Option<PathBuf>,
+
+    /// 配合 --dir 使用，目录不存在时自动创建
+    #[arg(long, requires = "dir")]
+    create_dirs: bool,
+
+    /// 目标文件已存在时直接覆盖
+    #[arg(long, conflicts_with_all = ["skip", "backup"])]
+    force: bool,
+
+    /// 目标文件已存在时跳过，不写入任何内容
+    #[arg(long, conflicts_with = "backup")]
+    skip: bool,
+
+    /// 目标文件已存在时先重命名为 <文件名>.bak 再写入
+    #[arg(long)]
+    backup: bool,
+
+    /// 在文件名后加上目标目录中下一个未使用的序号 (例如: note -> note-001)
+    #[arg(long)]
+    auto_number: bool,
 }
 
-fn ensure_template_dir() -> Result<PathBuf> {
-    // 获取用户主目录下的模板目录路径
-    let template_dir = dirs::home_dir()
-        .context("无法获取主目录")?
-        .join(".new-cli")
-        .join("template");
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit::code_of(&e).code());
+    }
+}
 
-    // 如果模板目录不存在，创建它
-    if !template_dir.exists() {
-        fs::create_dir_all(&template_dir).context("无法创建模板目录")?;
+fn run() -> Result<()> {
+    CompleteEnv::with_factory(|| cli::i18n::localize(Cli::command()))
+        .var(completions::COMPLETE_VAR)
+        .complete();
+
+    // 先确定语言，再解析命令行，这样帮助信息和错误提示都使用同一种语言
+    // 参数不一定是 UTF-8，std::env::args() 遇到这样的参数会 panic，clap 则能正确报告
+    let arg_lang = cli::i18n::lang_from_args(std::env::args_os());
+    if let Some(lang) = arg_lang {
+        new_cli::i18n::set_lang(lang);
+    }
+    migrate_legacy_dir();
+    // 配置文件有误时 doctor 仍然要运行，由它报告问题
+    let (config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    cli::i18n::init_lang(&config, arg_lang);
+    // 未知的子命令交给 PATH 中的 new-cli-<名称> 插件
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    if let Some(code) = cli::plugin::dispatch(&args, &Cli::command(), &config)? {
+        std::process::exit(code);
+    }
+    let matches = cli::i18n::localize(Cli::command()).get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(e) = config_error {
+        if !matches!(cli.command, Some(Commands::Doctor)) {
+            return Err(e.into());
+        }
+    }
+    init_logging(cli.quiet, cli.verbose);
+    remote::set_offline(cli.offline);
+    apply_ci_mode(&mut cli.create, &matches, |key| std::env::var(key).ok());
 
-        // Embed the default template content from the project's template/index.html at compile time
-        let template_content = include_str!("../template/index.html");
+    match cli.command {
+        Some(Commands::Template { action }) => run_template(action, &config),
+        Some(Commands::Project {
+            name,
+            template,
+            defines,
+            vars_file,
+            strict_vars,
+            allow_env,
+        }) => {
+            let engine = TemplateEngine::from_config(&config)?;
+            // 先校验名称再查看其中的文件
+            new_cli::validate::validate_template_name(&template)?;
+            let root = engine.project_template_root(&template);
+            let mut vars = variables::builtin_variables(&name, "");
+            vars.extend(variables::env_variables(&config.allow_env, allow_env));
+            if variables::references_git(&root.join(&template)) {
+                vars.extend(variables::git_variables(&std::env::current_dir()?));
+            }
+            vars.insert("name".to_string(), name.clone());
+            vars.extend(config.variables.clone());
+            for path in &vars_file {
+                vars.extend(vars_file::load(path)?);
+            }
+            vars.extend(defines);
 
-        let target_template = template_dir.join("index.html");
-        fs::write(&target_template, template_content).context("无法写入默认模板到用户目录")?;
+            new_cli::validate::validate_template_name(&name)?;
+            let target = std::env::current_dir()
+                .context(tr!(
+                    "无法获取当前目录",
+                    "failed to get the current directory"
+                ))?
+                .join(&name);
+            let count = project::create_project(root, &target, &template, &vars, strict_vars)?;
+            if !cli.quiet {
+                println!(
+                    "{}",
+                    tr!(
+                        "成功创建项目: {} (共 {} 个文件)",
+                        "Created project: {} ({} files)",
+                        name,
+                        count
+                    )
+                );
+            }
+            Ok(())
+        }
+        Some(Commands::Search { keyword, tags }) => {
+            template::run_search(&config, keyword.as_deref().unwrap_or_default(), &tags)
+        }
+        Some(Commands::History { today, template }) => cli::history::run(today, template),
+        Some(Commands::Undo { steps, yes }) => cli::history::run_undo(steps as usize, yes),
+        Some(Commands::Stats { limit }) => cli::stats::run(&config, limit),
+        Some(Commands::Doctor) => cli::doctor::run(),
+        Some(Commands::Plugins) => cli::plugin::run_list(),
+        Some(Commands::Plugin { name, args }) => {
+            std::process::exit(cli::plugin::run(&name, &args, &config)?)
+        }
+        Some(Commands::Watch {
+            path,
+            interval,
+            from_start,
+        }) => run_watch(
+            cli.create,
+            &config,
+            cli.quiet,
+            &path,
+            std::time::Duration::from_secs(interval),
+            from_start,
+        ),
+        Some(Commands::Batch {
+            spec,
+            continue_on_error,
+            jobs,
+        }) => output::report_errors(cli.create.output, || {
+            run_batch(
+                cli.create,
+                &config,
+                cli.quiet,
+                &spec,
+                continue_on_error,
+                jobs.map(|jobs| jobs as usize),
+            )
+        }),
+        Some(Commands::SelfUpdate { check, yes }) => cli::self_update::run(check, yes),
+        Some(Commands::Browse) => {
+            let engine = TemplateEngine::from_config(&config)?;
+            let mut known = variables::builtin_variables("", "");
+            known.extend(config.variables.clone());
+            known.extend(cli.create.defines.iter().cloned());
+            let Some(selection) = cli::browse::run(&engine, &known, &GitCache::default())? else {
+                return Ok(());
+            };
+            // 按普通的创建流程处理界面中确定的模板、文件名和变量
+            let mut args = cli.create;
+            args.targets = vec![format!("{}.{}", selection.filename, selection.extension)];
+            args.defines.extend(selection.variables);
+            output::report_errors(args.output, || {
+                create_file(args, &config, cli.quiet, Some(selection.template))
+            })
+        }
+        Some(Commands::License {
+            id,
+            author,
+            year,
+            list,
+            stdout,
+            force,
+        }) => cli::license::run(cli::license::LicenseArgs {
+            dir: &std::env::current_dir()?,
+            config: &config,
+            id: id.as_deref(),
+            author,
+            year,
+            list,
+            stdout,
+            force,
+        }),
+        Some(Commands::Adr {
+            title,
+            status,
+            dir,
+            stdout,
+        }) => cli::adr::run(cli::adr::AdrArgs {
+            cwd: &std::env::current_dir()?,
+            config: &config,
+            title: title.join(" "),
+            status,
+            dir,
+            stdout,
+        }),
+        Some(Commands::Gitignore {
+            profiles,
+            list,
+            stdout,
+        }) => cli::gitignore::run(&std::env::current_dir()?, &profiles, list, stdout),
+        Some(Commands::Cache { action }) => match action {
+            CacheAction::List => cli::cache::run_list(),
+            CacheAction::Clean => cli::cache::run_clean(),
+        },
+        Some(Commands::Trust { action }) => match action {
+            TrustAction::Add { key } => cli::trust::run_add(&key),
+            TrustAction::List => cli::trust::run_list(),
+            TrustAction::Remove { id } => cli::trust::run_remove(&id),
+        },
+        Some(Commands::Completions { shell }) => completions::run(shell),
+        None => output::report_errors(cli.create.output, || {
+            create_file(cli.create, &config, cli.quiet, None)
+        }),
     }
+}
 
-    Ok(template_dir)
+/// 把旧版本的 `~/.new-cli` 迁移到新的目录，失败时只给出警告
+fn migrate_legacy_dir() {
+    match new_cli::migrate::migrate_legacy() {
+        Ok(moved) => {
+            for item in moved {
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "已迁移: {} -> {}",
+                        "Migrated: {} -> {}",
+                        item.from.display(),
+                        item.to.display()
+                    )
+                );
+            }
+        }
+        Err(e) => eprintln!("{}", tr!("警告: {:#}", "Warning: {:#}", e)),
+    }
 }
 
-fn get_default_editor() -> &'static str {
-    if cfg!(target_os = "windows") {
-        "notepad3" // Windows 默认使用 notepad
-    } else if cfg!(target_os = "macos") {
-        "open" // macOS 使用 open 命令
-    } else {
-        "xdg-open" // Linux 使用 xdg-open
+/// 按 `-q`/`-v` 设置本程序的日志级别，依赖库只输出警告；
+/// 环境变量 `NEW_CLI_LOG`（例如 `ureq=debug`）可以进一步调整
+fn init_logging(quiet: bool, verbose: u8) {
+    use log::LevelFilter;
+
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level.min(LevelFilter::Warn))
+        .filter_module("new_cli", level)
+        .parse_env(env_logger::Env::new().filter("NEW_CLI_LOG"))
+        .format_timestamp(None)
+        .format_target(false)
+        .init();
+}
+
+fn run_template(action: TemplateAction, config: &Config) -> Result<()> {
+    let engine = TemplateEngine::from_config(config)?;
+    let template_dir = engine.template_dir();
+
+    match action {
+        TemplateAction::List {
+            pattern,
+            regex,
+            extensions,
+            tags,
+            json,
+            show_where,
+        } => {
+            let filter = Filter {
+                pattern: pattern
+                    .map(|pattern| Pattern::new(&pattern, regex))
+                    .transpose()?,
+                extensions,
+                tags: tags
+                    .iter()
+                    .map(|tag| Pattern::new(tag, regex))
+                    .collect::<new_cli::Result<_>>()?,
+            };
+            template::run_list(&engine, &filter, show_where, json)
+        }
+        TemplateAction::Show {
+            name,
+            rendered,
+            defines,
+            no_highlight,
+        } => {
+            let variables = rendered.then(|| {
+                let (filename, extension) = split_name_ext(&name);
+                let mut variables = variables::builtin_variables(
+                    filename.as_deref().unwrap_or_default(),
+                    extension.as_deref().unwrap_or_default(),
+                );
+                let uses_git = engine
+                    .resolve_template(&name)
+                    .is_ok_and(|path| variables::references_git(&path));
+                if let (true, Ok(dir)) = (uses_git, std::env::current_dir()) {
+                    variables.extend(variables::git_variables(&dir));
+                }
+                variables.extend(config.variables.clone());
+                variables.extend(defines);
+                variables
+            });
+            let highlight = !no_highlight && std::io::stdout().is_terminal();
+            template::run_show(&engine, &name, variables.as_ref(), highlight)
+        }
+        TemplateAction::Lint { names, dir } => {
+            template::run_lint(&engine, &names, dir.as_deref(), &config.variables)
+        }
+        TemplateAction::Tags => template::run_tags(&engine),
+        TemplateAction::Which { name, from } => {
+            let current_dir = std::env::current_dir()?;
+            let project = new_cli::project_type::detect(&current_dir)
+                .and_then(|kind| config.projects.get(kind));
+            let (_, file_name, namespaces) = lookup_namespaces(from.as_deref(), project, &name);
+            let (Some(filename), Some(extension)) = split_name_ext(file_name) else {
+                return Err(exit::fail(
+                    ExitCode::InvalidInput,
+                    tr!(
+                        "{} 需要带后缀，例如 {}.md",
+                        "{} needs an extension, e.g. {}.md",
+                        name,
+                        name
+                    ),
+                ));
+            };
+            let (lookup, candidates) = engine.explain_among(&namespaces, &filename, &extension);
+            template::write_explanation(&mut std::io::stdout(), &name, &lookup, &candidates)?;
+            Ok(())
+        }
+        TemplateAction::Edit {
+            name,
+            from,
+            editor,
+            wait,
+        } => template::run_edit(
+            &engine,
+            config,
+            &name,
+            from.as_deref(),
+            editor.as_deref(),
+            wait,
+        ),
+        TemplateAction::Add { file, name, force } => {
+            template::run_add(template_dir, &file, name.as_deref(), force)
+        }
+        TemplateAction::Init {
+            from_current,
+            vars,
+            case_variants,
+            name,
+            force,
+        } => template::run_init(
+            template_dir,
+            &from_current,
+            &vars,
+            case_variants,
+            name.as_deref(),
+            force,
+        ),
+        TemplateAction::Remove { name, yes } => template::run_remove(template_dir, &name, yes),
+        TemplateAction::Rename { old, new, yes } => {
+            template::run_rename(template_dir, &old, &new, yes)
+        }
+        TemplateAction::Install { url, name } => {
+            template::run_install(template_dir, config, &url, name.as_deref())
+        }
+        TemplateAction::Update { name } => template::run_update(template_dir, name.as_deref()),
+        TemplateAction::ImportVscode {
+            file,
+            ext,
+            into,
+            force,
+        } => {
+            template::run_import_vscode(template_dir, &file, ext.as_deref(), into.as_deref(), force)
+        }
+        TemplateAction::ExportVscode {
+            output,
+            namespace,
+            format,
+        } => template::run_export_vscode(&engine, output.as_deref(), namespace.as_deref(), format),
+        TemplateAction::Verify { names, update } => {
+            template::run_verify(template_dir, &names, update)
+        }
+        TemplateAction::Pack { action } => match action {
+            PackAction::Export { file, namespace } => {
+                template::run_pack_export(template_dir, &file, namespace.as_deref())
+            }
+            PackAction::Import {
+                source,
+                into,
+                force,
+                signature,
+                require_signature,
+            } => template::run_pack_import(
+                template_dir,
+                config,
+                &source,
+                &template::PackImport {
+                    into: into.as_deref(),
+                    force,
+                    signature: signature.as_deref(),
+                    require_signature,
+                },
+            ),
+        },
     }
 }
 
-/// 查找模板文件
-/// 如果指定的模板文件存在，则返回该文件路径
-/// 如果不存在，尝试查找相同后缀的其他模板文件
-/// 如果仍未找到，返回None
-fn find_template_file(template_dir: &PathBuf, filename: &str, extension: &str) -> Option<PathBuf> {
-    let canonical_template_dir = match fs::canonicalize(template_dir) {
-        Ok(path) => path,
-        Err(_) => return None, // Cannot canonicalize template_dir, unsafe to proceed
+/// 要创建的文件：(文件名, 后缀)，None 表示使用默认值
+pub type Target = (Option<String>, Option<String>);
+
+/// 将 `name.ext` 形式的参数按最后一个 `.` 拆分为文件名和后缀
+///
+/// 不含 `.`，或拆分后文件名/后缀为空（如 `.gitignore`、`notes.`）时，整个参数都视为文件名。
+pub fn split_name_ext(arg: &str) -> Target {
+    match arg.rsplit_once('.') {
+        Some((name, ext)) if !name.is_empty() && !ext.is_empty() => {
+            (Some(name.to_string()), Some(ext.to_string()))
+        }
+        _ => (Some(arg.to_string()), None),
+    }
+}
+
+/// 解析位置参数得到要创建的文件列表
+///
+/// - 指定了 `--ext` 时，每个参数都是一个文件名（`name.ext` 形式的参数沿用自己的后缀）
+/// - 只有一个参数时，`name.ext` 形式会被拆分为文件名和后缀
+/// - 两个参数且第二个不含 `.` 时，兼容旧写法 `new-cli [文件名] [后缀]`
+/// - 其余情况下每个参数都必须是 `name.ext` 形式
+pub fn parse_targets(args: &[String], ext: Option<&str>) -> Result<Vec<Target>, String> {
+    if let Some(ext) = ext {
+        if args.is_empty() {
+            return Ok(vec![(None, Some(ext.to_string()))]);
+        }
+        return Ok(args
+            .iter()
+            .map(|arg| match split_name_ext(arg) {
+                (name, None) => (name, Some(ext.to_string())),
+                target => target,
+            })
+            .collect());
+    }
+
+    match args {
+        [] => Ok(vec![(None, None)]),
+        [arg] => Ok(vec![split_name_ext(arg)]),
+        [filename, extension] if !extension.contains('.') => {
+            Ok(vec![(Some(filename.clone()), Some(extension.clone()))])
+        }
+        _ => {
+            let targets: Vec<Target> = args.iter().map(|arg| split_name_ext(arg)).collect();
+            if targets.iter().any(|(_, ext)| ext.is_none()) {
+                return Err(
+                    tr!("错误：创建多个文件时，请使用 --ext 指定后缀或使用 name.ext 形式。", "Error: when creating multiple files, use --ext or write each file as name.ext."),
+                );
+            }
+            Ok(targets)
+        }
+    }
+}
+
+/// 解析模板别名：`--alias rc`，或第一个位置参数为配置中的别名（`new-cli rc Button`）
+///
+/// 返回别名对应的模板文件名和剩余的位置参数；没有使用别名时返回 None。
+/// 指定了 `--ext` 时不会把第一个参数当作别名。
+pub fn resolve_alias(
+    alias: Option<&str>,
+    args: &[String],
+    ext: Option<&str>,
+    aliases: &HashMap<String, String>,
+) -> Result<Option<(String, Vec<String>)>, String> {
+    if let Some(alias) = alias {
+        return match aliases.get(alias) {
+            Some(template) => Ok(Some((template.clone(), args.to_vec()))),
+            None => Err(tr!(
+                "错误：配置文件中没有定义别名 '{}'。",
+                "Error: alias '{}' is not defined in the config file.",
+                alias
+            )),
+        };
+    }
+
+    match args.split_first() {
+        Some((first, rest)) if ext.is_none() => Ok(aliases
+            .get(first)
+            .map(|template| (template.clone(), rest.to_vec()))),
+        _ => Ok(None),
+    }
+}
+
+/// `--sha256` 和 `--cache` 只对远程模板有效，没有 `--from-url` 或 `gh:` 来源时报错
+fn check_remote_options(cli: &CreateArgs) -> Result<()> {
+    let remote = cli.from_url.is_some()
+        || cli
+            .from
+            .as_deref()
+            .is_some_and(|from| from.starts_with(remote::GITHUB_PREFIX));
+    if remote || (cli.sha256.is_none() && !cli.cache) {
+        return Ok(());
+    }
+    Err(exit::fail(
+        ExitCode::InvalidInput,
+        tr!(
+            "--sha256 和 --cache 只能与 --from-url 或 --from gh:... 一起使用",
+            "--sha256 and --cache require --from-url or --from gh:..."
+        ),
+    ))
+}
+
+/// 创建 `cli` 指定的文件；`preset` 为已经选定的模板，例如 `browse` 中选择的模板
+fn create_file(
+    cli: CreateArgs,
+    config: &Config,
+    quiet: bool,
+    preset: Option<PathBuf>,
+) -> Result<()> {
+    if cli.app.is_some() && !cfg!(target_os = "macos") {
+        return Err(exit::fail(
+            ExitCode::InvalidInput,
+            tr!(
+                "--app 只能在 macOS 上使用",
+                "--app is only supported on macOS"
+            ),
+        ));
+    }
+    let alias = match resolve_alias(
+        cli.alias.as_deref(),
+        &cli.targets,
+        cli.ext.as_deref(),
+        &config.aliases,
+    ) {
+        Ok(alias) => alias,
+        Err(e) => return Err(exit::fail(ExitCode::InvalidInput, e)),
     };
+    check_remote_options(&cli)?;
 
-    // 首先检查指定的模板文件是否存在并进行路径验证
-    let specified_template_name = format!("{}.{}", filename, extension);
-    let specified_template_path = template_dir.join(&specified_template_name);
+    // 确保模板目录存在
+    let engine = TemplateEngine::from_config(config)?;
 
-    if specified_template_path.exists() {
-        if let Ok(canonical_specified_path) = fs::canonicalize(&specified_template_path) {
-            if canonical_specified_path.starts_with(&canonical_template_dir) {
-                return Some(specified_template_path); // Return original path, not canonicalized one
+    // 第一个参数是模板清单时，其余参数都是名称，每个名称创建清单中的一组文件
+    if alias.is_none() && cli.ext.is_none() && preset.is_none() && !cli.empty {
+        if let Some((first, names)) = cli.targets.split_first() {
+            if let Some(dir) = engine.find_manifest(first).filter(|_| !names.is_empty()) {
+                return create_from_manifest(&cli, config, &engine, quiet, &dir, names);
             }
         }
-        // If canonicalization fails or path is not within template_dir,
-        // proceed to search other files (treat as if specific template not found securely)
     }
 
-    // 如果指定模板不存在或不安全，查找相同后缀的任意文件
-    if let Ok(entries) = fs::read_dir(template_dir) {
-        for entry in entries.filter_map(Result::ok) {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == extension {
-                        // Verify that this path is also within the template_dir
-                        if let Ok(canonical_entry_path) = fs::canonicalize(&path) {
-                            if canonical_entry_path.starts_with(&canonical_template_dir) {
-                                return Some(path); // Return original path
-                            }
-                        }
-                        // If canonicalization fails or path is not within template_dir, skip
+    // 使用别名时，所有位置参数都是文件名，后缀默认取自别名对应的模板
+    let parsed = match &alias {
+        Some((template, args)) => {
+            let (_, alias_ext) = split_name_ext(template);
+            parse_targets(args, cli.ext.as_deref().or(alias_ext.as_deref()))
+        }
+        None => parse_targets(&cli.targets, cli.ext.as_deref()),
+    };
+    let targets = match parsed {
+        Ok(targets) => targets,
+        Err(e) => return Err(exit::fail(ExitCode::InvalidInput, e)),
+    };
+
+    let mut reporter = Reporter::new(cli.output, quiet);
+
+    // 远程模板、--pick 或别名：先确定模板，未指定的文件名/后缀取自该模板
+    let remote_source = match (&cli.from_url, cli.from.as_deref()) {
+        (Some(url), _) => Some((url.clone(), Vec::new())),
+        (None, Some(from)) if from.starts_with(remote::GITHUB_PREFIX) => {
+            let source = remote::GithubSource::parse(from)?;
+            Some((source.raw_url(), source.auth_headers()))
+        }
+        _ => None,
+    };
+    let picked = if let Some(path) = preset {
+        Some((path, None, None))
+    } else if let Some((url, headers)) = remote_source {
+        // 第一次使用时记录校验和，之后的下载必须与之一致；--sha256 优先
+        let lock_path = lock::lock_path()?;
+        let mut lockfile = Lockfile::load(&lock_path)?;
+        let pinned = lockfile.remote.get(&url).cloned();
+        let options = remote::FetchOptions {
+            sha256: cli.sha256.clone().or(pinned.clone()),
+            use_cache: cli.cache,
+            headers,
+            ..remote::FetchOptions::default()
+        };
+        let path = remote::fetch(&url, &remote::cache_dir()?, &options)?;
+        let actual = remote::sha256_hex(&fs::read(&path)?);
+        if pinned.as_ref() != Some(&actual) {
+            lockfile.remote.insert(url.clone(), actual);
+            lockfile.save(&lock_path)?;
+        }
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (name, extension) = split_name_ext(&file_name);
+        Some((path, name, extension))
+    } else if cli.from_clipboard {
+        let text = clipboard::read()?;
+        Some((
+            save_input_template("clipboard", text.as_bytes())?,
+            None,
+            None,
+        ))
+    } else if cli.from_stdin {
+        let mut content = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut content)
+            .with_context(|| {
+                tr!(
+                    "无法从标准输入读取模板",
+                    "failed to read the template from stdin"
+                )
+            })?;
+        Some((save_input_template("stdin", &content)?, None, None))
+    } else if let Some((template, _)) = &alias {
+        let path = engine.resolve_template(template)?;
+        let (name, extension) = split_name_ext(template);
+        Some((path, name, extension))
+    } else if cli.pick {
+        let templates = pickable_templates(&engine, &cli, config)?;
+        if templates.is_empty() && !cli.tags.is_empty() {
+            return Err(exit::fail(
+                ExitCode::TemplateNotFound,
+                tr!(
+                    "没有带有标签 {} 的模板",
+                    "No templates are tagged {}",
+                    cli.tags.join(", ")
+                ),
+            ));
+        }
+        match picker::pick_template(&templates, &tr!("选择模板", "Select a template"))? {
+            Some(picked) => Some((
+                picked.path.clone(),
+                Some(picked.name.clone()),
+                Some(picked.extension.clone()),
+            )),
+            None => {
+                reporter.info(tr!("已取消", "Cancelled"));
+                reporter.finish();
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
+
+    let target_dir = resolve_target_dir(&cli)?;
+    let project = new_cli::project_type::detect(&target_dir).and_then(|kind| {
+        log::debug!("detected {} project", kind);
+        config.projects.get(kind)
+    });
+
+    let total = targets.len();
+    // 成功的文件及打开时的光标位置，--line 优先于模板中的标记
+    let line_position = cli.line.map(|line| Position::line(line as usize));
+    let mut created = Vec::new();
+    let mut skipped = 0;
+    let mut failed = 0;
+    let git = GitCache::default();
+    let context = CreateContext {
+        cli: &cli,
+        config,
+        engine: &engine,
+        target_dir: &target_dir,
+        project,
+        git: &git,
+    };
+    for (filename, extension) in targets {
+        // 命令行参数优先，其次是所选模板、同名模板的后缀、配置文件，最后是内置默认值
+        let filename = filename.or_else(|| picked.as_ref().and_then(|(_, name, _)| name.clone()));
+        let mut extension =
+            extension.or_else(|| picked.as_ref().and_then(|(_, _, ext)| ext.clone()));
+        if let (None, Some(name), false) = (&extension, &filename, cli.empty) {
+            extension = infer_extension(&context, name)?;
+        }
+        let (filename, extension) = fill_target(config, project, filename, extension);
+        let picked_path = picked.as_ref().map(|(path, _, _)| path.clone());
+
+        match create_one(&context, &mut reporter, picked_path, &filename, &extension) {
+            Ok(Some(Outcome::Created { path, cursor, .. })) => {
+                created.push((path, line_position.or(cursor)))
+            }
+            Ok(Some(Outcome::Inserted { path })) => created.push((path, line_position)),
+            Ok(Some(Outcome::Skipped { .. })) => skipped += 1,
+            Ok(None) => {}
+            Err(e) => {
+                let name = filename.rsplit('/').next().unwrap_or_default();
+                reporter.failed(target_dir.join(format!("{}.{}", name, extension)), &e);
+                failed += 1;
+            }
+        }
+    }
+
+    finish_creation(&cli, config, reporter, created, skipped, failed, total)
+}
+
+/// 补全未指定的文件名和后缀：依次取自项目类型的默认值、配置文件和内置默认值
+fn fill_target(
+    config: &Config,
+    project: Option<&ProjectDefaults>,
+    filename: Option<String>,
+    extension: Option<String>,
+) -> (String, String) {
+    let filename = filename
+        .or_else(|| config.default_filename.clone())
+        .unwrap_or_else(|| "index".to_string());
+    let extension = extension
+        .or_else(|| project.and_then(|project| project.extension.clone()))
+        .or_else(|| config.default_extension.clone())
+        .unwrap_or_else(|| "html".to_string());
+    (filename, extension)
+}
+
+/// 监视队列 `queue`，按普通的创建流程为每个新条目创建文件
+///
+/// 条目在后台无人值守地处理，因此不询问变量、不打开编辑器；单个条目失败只报告错误，继续监视。
+fn run_watch(
+    mut cli: CreateArgs,
+    config: &Config,
+    quiet: bool,
+    queue: &Path,
+    interval: std::time::Duration,
+    from_start: bool,
+) -> Result<()> {
+    cli.no_input = true;
+    cli.no_open = true;
+    let engine = TemplateEngine::from_config(config)?;
+    let target_dir = resolve_target_dir(&cli)?;
+    let project =
+        new_cli::project_type::detect(&target_dir).and_then(|kind| config.projects.get(kind));
+    let git = GitCache::default();
+    let context = CreateContext {
+        cli: &cli,
+        config,
+        engine: &engine,
+        target_dir: &target_dir,
+        project,
+        git: &git,
+    };
+    Reporter::new(cli.output, quiet).info(tr!(
+        "正在监视 {}，按 Ctrl-C 停止",
+        "Watching {}, press Ctrl-C to stop",
+        queue.display()
+    ));
+    cli::watch::run(queue, interval, from_start, |line, args| {
+        // 监视可能持续很久，每个条目重新查询 git 信息
+        let git = GitCache::default();
+        let context = CreateContext {
+            git: &git,
+            ..context
+        };
+        let mut reporter = Reporter::new(cli.output, quiet);
+        match parse_targets(&args, cli.ext.as_deref()) {
+            Ok(targets) => {
+                for (filename, extension) in targets {
+                    // 列出模板失败时 create_one 会报告同样的错误
+                    let extension = match (extension, &filename) {
+                        (None, Some(name)) => infer_extension(&context, name).ok().flatten(),
+                        (extension, _) => extension,
+                    };
+                    let (filename, extension) = fill_target(config, project, filename, extension);
+                    if let Err(e) = create_one(&context, &mut reporter, None, &filename, &extension)
+                    {
+                        let name = filename.rsplit('/').next().unwrap_or_default();
+                        reporter.failed(target_dir.join(format!("{}.{}", name, extension)), &e);
                     }
                 }
             }
+            Err(e) => reporter.warn(tr!(
+                "警告: 跳过队列条目 {}: {}",
+                "Warning: skipped queue entry {}: {}",
+                line,
+                e
+            )),
         }
+        reporter.finish();
+    })
+}
+
+/// 按批量生成清单（见 [`new_cli::batch`]）为每一行创建一个文件，最后输出汇总表
+///
+/// 一次生成很多文件，因此不打开编辑器；默认在第一个失败的行停止，`continue_on_error` 时处理所有行。
+/// 各行由 `jobs` 个线程同时创建（见 [`batch_jobs`]），结果仍按清单中的顺序输出。
+fn run_batch(
+    mut cli: CreateArgs,
+    config: &Config,
+    quiet: bool,
+    spec: &Path,
+    continue_on_error: bool,
+    jobs: Option<usize>,
+) -> Result<()> {
+    check_remote_options(&cli)?;
+    let entries = new_cli::batch::load(spec)?;
+    cli.no_open = true;
+    let jobs = batch_jobs(&cli, config, &entries, jobs);
+    if jobs > 1 {
+        // 多个线程不能同时询问，变量只来自清单、-D 和默认值
+        cli.no_input = true;
+    }
+    log::debug!("creating {} files with {} jobs", entries.len(), jobs);
+    let engine = TemplateEngine::from_config(config)?;
+    let target_dir = resolve_target_dir(&cli)?;
+    let git = GitCache::default();
+    let mut reporter = Reporter::new(cli.output, quiet);
+    let mut created = Vec::new();
+    let mut skipped = 0;
+    let mut failed = 0;
+    // 汇总表的每一行：清单中的行号、文件和结果，未处理的行没有结果
+    let mut rows: Vec<(usize, PathBuf, Option<Status>)> = Vec::new();
+    let mut record = |reporter: &mut Reporter, path: PathBuf, result: Result<Option<Outcome>>| {
+        let status = match result {
+            Ok(Some(Outcome::Created { path, .. })) => {
+                created.push((path, None));
+                Status::Created
+            }
+            Ok(Some(Outcome::Inserted { path })) => {
+                created.push((path, None));
+                Status::Inserted
+            }
+            Ok(Some(Outcome::Skipped { .. })) => {
+                skipped += 1;
+                Status::Skipped
+            }
+            Ok(None) => Status::Created,
+            Err(e) => {
+                reporter.failed(path.clone(), &e);
+                failed += 1;
+                Status::Failed
+            }
+        };
+        let shown = path
+            .strip_prefix(&target_dir)
+            .unwrap_or(&path)
+            .to_path_buf();
+        (shown, status)
+    };
+
+    if jobs <= 1 {
+        for entry in &entries {
+            let (path, result) = create_batch_entry(
+                &cli,
+                config,
+                &engine,
+                &target_dir,
+                &git,
+                &mut reporter,
+                entry,
+            );
+            let stop = result.is_err() && !continue_on_error;
+            let (shown, status) = record(&mut reporter, path, result);
+            rows.push((entry.row, shown, Some(status)));
+            if stop {
+                break;
+            }
+        }
+    } else {
+        // 工作线程按顺序领取各行，领取前检查是否已经停止，因此处理过的行总是连续的；
+        // 结果可能乱序到达，先暂存，按行的顺序输出
+        let next = AtomicUsize::new(0);
+        let stopped = AtomicBool::new(false);
+        let prototype = Reporter::new(cli.output, quiet);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            for _ in 0..jobs {
+                let sender = sender.clone();
+                let (cli, engine, target_dir, entries) = (&cli, &engine, &target_dir, &entries);
+                let (next, stopped, prototype, git) = (&next, &stopped, &prototype, &git);
+                scope.spawn(move || {
+                    while !stopped.load(Ordering::SeqCst) {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(entry) = entries.get(index) else {
+                            break;
+                        };
+                        let mut buffer = prototype.buffered();
+                        let (path, result) = create_batch_entry(
+                            cli,
+                            config,
+                            engine,
+                            target_dir,
+                            git,
+                            &mut buffer,
+                            entry,
+                        );
+                        if result.is_err() && !continue_on_error {
+                            stopped.store(true, Ordering::SeqCst);
+                        }
+                        if sender.send((index, buffer, path, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+            let mut arrived = BTreeMap::new();
+            for (index, buffer, path, result) in receiver {
+                arrived.insert(index, (buffer, path, result));
+                while let Some((buffer, path, result)) = arrived.remove(&rows.len()) {
+                    reporter.absorb(buffer);
+                    let (shown, status) = record(&mut reporter, path, result);
+                    rows.push((entries[rows.len()].row, shown, Some(status)));
+                }
+            }
+        });
+    }
+    for entry in &entries[rows.len()..] {
+        rows.push((entry.row, entry.path(), None));
     }
 
-    // 没有找到任何匹配后缀的模板
-    None
+    if !reporter.is_json() && !quiet && !cli.stdout {
+        print_batch_table(&rows);
+    }
+    let pending = rows
+        .iter()
+        .filter(|(_, _, status)| status.is_none())
+        .count();
+    if pending > 0 {
+        reporter.warn(tr!(
+            "已停止，剩余 {} 行未处理；使用 --continue-on-error 处理所有行",
+            "Stopped, {} rows were not processed; use --continue-on-error to process every row",
+            pending
+        ));
+    }
+    let total = entries.len() - pending;
+    finish_creation(&cli, config, reporter, created, skipped, failed, total)
 }
 
-// Public function for validating CLI inputs
-pub fn validate_cli_inputs(filename: &str, extension: &str) -> Result<(), String> {
-    let invalid_chars = ["/", "\\", ".."];
-    for &char_set in &invalid_chars {
-        if filename.contains(char_set) {
-            return Err(format!(
-                "错误：文件名 '{}' 包含无效字符 '{}'。",
-                filename, char_set
-            ));
+/// 批量生成使用的线程数：默认为 CPU 核数，不超过行数
+///
+/// 按序号命名、git add、添加 mod 声明、输出到标准输出或剪贴板，以及清单中有重复的目标时，
+/// 各行之间会相互影响，此时总是逐个创建。
+fn batch_jobs(
+    cli: &CreateArgs,
+    config: &Config,
+    entries: &[new_cli::batch::Entry],
+    jobs: Option<usize>,
+) -> usize {
+    let mut targets = std::collections::HashSet::new();
+    let serial = cli.stdout
+        || cli.to_clipboard
+        || cli.auto_number
+        || cli.git_add
+        || (config.git_add && !cli.no_git_add)
+        || cli.add_mod
+        || (config.add_mod && !cli.no_add_mod)
+        || entries
+            .iter()
+            .any(|entry| pattern::has_number(&entry.filename))
+        || !entries.iter().all(|entry| targets.insert(entry.path()));
+    if serial {
+        return 1;
+    }
+    let jobs = jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+    jobs.min(entries.len()).max(1)
+}
+
+/// 创建批量生成清单中的一个文件，返回目标路径（用于报告）和结果
+fn create_batch_entry(
+    cli: &CreateArgs,
+    config: &Config,
+    engine: &TemplateEngine,
+    target_dir: &Path,
+    git: &GitCache,
+    reporter: &mut Reporter,
+    entry: &new_cli::batch::Entry,
+) -> (PathBuf, Result<Option<Outcome>>) {
+    let dir = match &entry.dir {
+        Some(dir) => target_dir.join(dir),
+        None => target_dir.to_path_buf(),
+    };
+    let (filename, extension) = match &entry.extension {
+        Some(extension) => (Some(entry.filename.clone()), Some(extension.clone())),
+        None => split_name_ext(&entry.filename),
+    };
+    // 清单中的变量作为该文件的变量，-D 仍可覆盖
+    let mut args = cli.clone();
+    args.defines.splice(
+        0..0,
+        entry.variables.iter().map(|(k, v)| (k.clone(), v.clone())),
+    );
+    let project = new_cli::project_type::detect(&dir).and_then(|kind| config.projects.get(kind));
+    let context = CreateContext {
+        cli: &args,
+        config,
+        engine,
+        target_dir: &dir,
+        project,
+        git,
+    };
+
+    let mut target = None;
+    let result = (|| {
+        fs::create_dir_all(&dir).with_context(|| {
+            tr!(
+                "无法创建目录 {}",
+                "failed to create directory {}",
+                dir.display()
+            )
+        })?;
+        let picked = match &entry.template {
+            Some(template) => Some((engine.resolve_template(template)?, template)),
+            None => None,
+        };
+        // 后缀依次取自清单、所用模板、同名模板、项目和配置的默认值
+        let mut extension = extension.or_else(|| {
+            picked
+                .as_ref()
+                .and_then(|(_, template)| split_name_ext(template).1)
+        });
+        if let (None, Some(name)) = (&extension, &filename) {
+            extension = infer_extension(&context, name)?;
         }
-        if extension.contains(char_set) {
-            return Err(format!(
-                "错误：文件后缀 '{}' 包含无效字符 '{}'。",
-                extension, char_set
-            ));
+        let (filename, extension) = fill_target(config, project, filename, extension);
+        let name = filename.rsplit('/').next().unwrap_or_default();
+        target = Some(dir.join(format!("{}.{}", name, extension)));
+        let picked = picked.map(|(path, _)| path);
+        create_one(&context, reporter, picked, &filename, &extension)
+    })();
+    let path = match &result {
+        Ok(Some(Outcome::Created { path, .. }))
+        | Ok(Some(Outcome::Inserted { path }))
+        | Ok(Some(Outcome::Skipped { path })) => path.clone(),
+        _ => target.unwrap_or_else(|| target_dir.join(entry.path())),
+    };
+    (path, result)
+}
+
+/// 批量生成的汇总表
+fn print_batch_table(rows: &[(usize, PathBuf, Option<Status>)]) {
+    let file_width = rows
+        .iter()
+        .map(|(_, path, _)| path.display().to_string().chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+    println!(
+        "{:>5}  {:<file_width$}  {}",
+        tr!("行", "ROW"),
+        tr!("文件", "FILE"),
+        tr!("结果", "STATUS")
+    );
+    for (row, path, status) in rows {
+        let status = match status {
+            Some(status) => status.label(),
+            None => tr!("未处理", "not run"),
+        };
+        println!(
+            "{:>5}  {:<file_width$}  {}",
+            row,
+            path.display().to_string(),
+            status
+        );
+    }
+}
+
+/// 按模板清单 `dir`（见 [`new_cli::manifest`]）为每个名称创建一组文件
+fn create_from_manifest(
+    cli: &CreateArgs,
+    config: &Config,
+    engine: &TemplateEngine,
+    quiet: bool,
+    dir: &Path,
+    names: &[String],
+) -> Result<()> {
+    let manifest = Manifest::load(dir)?;
+    let git = GitCache::default();
+    let mut reporter = Reporter::new(cli.output, quiet);
+    let target_dir = resolve_target_dir(cli)?;
+    let interactive = !cli.no_input && picker::is_interactive();
+    let line_position = cli.line.map(|line| Position::line(line as usize));
+    let mut created = Vec::new();
+    let mut skipped = 0;
+    let mut failed = 0;
+    let mut total = 0;
+    for name in names {
+        // 名称作为 name 变量，-D 仍可覆盖
+        let mut args = cli.clone();
+        args.defines.insert(0, ("name".to_string(), name.clone()));
+        let mut vars = variables::builtin_variables(name, "");
+        vars.extend(variables::env_variables(&config.allow_env, cli.allow_env));
+        vars.extend(git.get(&target_dir));
+        vars.extend(config.variables.clone());
+        for path in &cli.vars_file {
+            vars.extend(vars_file::load(path)?);
+        }
+        vars.extend(args.defines.iter().cloned());
+
+        // 各文件共用的变量只询问一次，之后按普通流程创建时不再询问
+        if interactive {
+            let mut prompts: Vec<VariablePrompt> = Vec::new();
+            for file in &manifest.files {
+                let template = dir.join(&file.template);
+                if !template.is_file() {
+                    continue;
+                }
+                for prompt in engine.variable_prompts(&template, &vars)? {
+                    if !prompts.iter().any(|p| p.name == prompt.name) {
+                        prompts.push(prompt);
+                    }
+                }
+            }
+            let mut answers = Variables::new();
+            prompt::prompt_variables(&prompts, &mut answers)?;
+            vars.extend(answers.clone());
+            args.defines.extend(answers);
+        }
+
+        for (template, output) in manifest.outputs(dir, &vars)? {
+            total += 1;
+            let path = target_dir.join(&output);
+            let parent = path.parent().unwrap_or(&target_dir).to_path_buf();
+            let file_name = output
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let (Some(filename), Some(extension)) = split_name_ext(&file_name) else {
+                let e = anyhow::anyhow!(
+                    "{}",
+                    tr!(
+                        "模板清单中的输出路径 {} 没有后缀",
+                        "output path {} in the template manifest has no extension",
+                        output.display()
+                    )
+                );
+                reporter.failed(path, &e);
+                failed += 1;
+                continue;
+            };
+            fs::create_dir_all(&parent).with_context(|| {
+                tr!(
+                    "无法创建目录 {}",
+                    "failed to create directory {}",
+                    parent.display()
+                )
+            })?;
+            let context = CreateContext {
+                cli: &args,
+                config,
+                engine,
+                target_dir: &parent,
+                project: None,
+                git: &git,
+            };
+            match create_one(
+                &context,
+                &mut reporter,
+                Some(template),
+                &filename,
+                &extension,
+            ) {
+                Ok(Some(Outcome::Created { path, cursor, .. })) => {
+                    created.push((path, line_position.or(cursor)))
+                }
+                Ok(Some(Outcome::Inserted { path })) => created.push((path, line_position)),
+                Ok(Some(Outcome::Skipped { .. })) => skipped += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    reporter.failed(path, &e);
+                    failed += 1;
+                }
+            }
         }
     }
+    finish_creation(cli, config, reporter, created, skipped, failed, total)
+}
 
-    if filename.is_empty() {
-        return Err("错误：文件名不能为空。".to_string());
+/// 输出汇总，按需用编辑器打开创建的文件，并在有文件失败时按失败原因的退出码结束
+fn finish_creation(
+    cli: &CreateArgs,
+    config: &Config,
+    mut reporter: Reporter,
+    created: Vec<(PathBuf, Option<Position>)>,
+    skipped: usize,
+    failed: usize,
+    total: usize,
+) -> Result<()> {
+    // --stdout 时不输出汇总，以免混入模板内容
+    if total > 1 && !cli.stdout {
+        reporter.info(tr!(
+            "共 {} 个文件：成功 {} 个，跳过 {} 个，失败 {} 个",
+            "{} files: {} created, {} skipped, {} failed",
+            total,
+            created.len(),
+            skipped,
+            failed
+        ));
     }
 
-    if extension.is_empty() {
-        return Err("错误：文件后缀不能为空。".to_string());
+    let mut editor_status = None;
+    if !created.is_empty() && should_open(cli, config) {
+        let editor_flag = cli
+            .editor
+            .clone()
+            .or_else(|| cli.app.as_deref().map(editor::app_command));
+        // 使用编辑器打开新文件；--wait 时和终端编辑器逐个在前台运行并等待退出，
+        // 终端编辑器不等待的话 new-cli 退出后它会和 shell 争抢终端
+        for (path, position) in &created {
+            let editor = editor::resolve_editor(editor_flag.as_deref(), config, path);
+            let terminal = cli.terminal_editor || editor::is_terminal_editor(&editor);
+            if cli.wait || terminal {
+                if !terminal {
+                    reporter.info(tr!(
+                        "正在等待 {} 关闭文件...",
+                        "Waiting for {} to close the file...",
+                        editor
+                    ));
+                }
+                let status = editor::open_file_and_wait(&editor, path, *position)?;
+                reporter.opened(path, &editor);
+                // 只有 --wait 时才以编辑器的退出码结束，例如 vim 中 :cq 放弃编辑
+                if cli.wait && !status.success() {
+                    editor_status = Some(status.code().unwrap_or(1));
+                    break;
+                }
+                continue;
+            }
+            match editor::open_file(&editor, path, *position) {
+                Ok(_) => {
+                    reporter.info(tr!("已使用 {} 打开文件", "Opened file with {}", editor));
+                    reporter.opened(path, &editor);
+                }
+                Err(e) => {
+                    reporter.warn(tr!("打开文件失败: {}", "Failed to open file: {}", e));
+                    editor_status.get_or_insert(ExitCode::Editor.code());
+                }
+            }
+        }
     }
 
+    reporter.finish();
+    if let Some(code) = reporter.exit_code().filter(|_| failed > 0) {
+        std::process::exit(code.code());
+    }
+    if let Some(code) = editor_status {
+        std::process::exit(code);
+    }
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// 是否运行在 CI 中：大多数 CI 服务都会设置环境变量 `CI`，`CI=false` 或 `CI=0` 视为未设置
+fn ci_detected(var: impl Fn(&str) -> Option<String>) -> bool {
+    var("CI").is_some_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+}
 
-    // Validate filename and extension using the new function
-    if let Err(e) = validate_cli_inputs(&cli.filename, &cli.extension) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+/// `--ci` 或检测到 CI 环境时：不打开编辑器、不进行任何交互、未定义的变量报错，并输出 JSON
+///
+/// 创建文件和批量生成等所有创建类的命令都使用同一份设置。命令行中明确指定的 `--output`
+/// 保持不变；明确要求了 --stdout、--wait、--pick 或 --preview 时不自动启用 CI 模式。
+fn apply_ci_mode(
+    cli: &mut CreateArgs,
+    matches: &clap::ArgMatches,
+    var: impl Fn(&str) -> Option<String>,
+) {
+    let explicit = cli.stdout || cli.wait || cli.pick || cli.preview.is_some();
+    if !cli.ci && (explicit || !ci_detected(var)) {
+        return;
+    }
+    log::debug!("CI mode enabled");
+    cli.no_input = true;
+    cli.no_open = true;
+    cli.strict_vars = true;
+    if matches.value_source("output") != Some(clap::parser::ValueSource::CommandLine) {
+        cli.output = OutputFormat::Json;
     }
+}
 
-    // 确保模板目录存在
-    let template_dir = ensure_template_dir()?;
-
-    // 获取模板内容
-    let template_content = match find_template_file(&template_dir, &cli.filename, &cli.extension) {
-        Some(template_path) => {
-            // 找到了模板文件，读取其内容
-            fs::read_to_string(&template_path)
-                .with_context(|| format!("无法读取模板文件: {:?}", template_path))?
-        },
-        None => {
-            // 没有找到任何匹配的模板文件，使用空内容
-            println!("未找到模板 {}.{} 或任何 .{} 后缀的文件，将创建空文件", 
-                    cli.filename, cli.extension, cli.extension);
-            String::new()
+/// 创建后是否用编辑器打开文件：`--wait` 即使配置了 `open = false` 也会打开
+fn should_open(cli: &CreateArgs, config: &Config) -> bool {
+    !cli.no_open && (cli.wait || config.open != Some(false))
+}
+
+/// 确定并校验目标目录：默认是当前目录，`--dir` 可指定其他目录
+fn resolve_target_dir(cli: &CreateArgs) -> Result<PathBuf> {
+    let current_dir = std::env::current_dir().context(tr!(
+        "无法获取当前目录",
+        "failed to get the current directory"
+    ))?;
+    let target_dir = match &cli.dir {
+        Some(dir) => {
+            let dir = current_dir.join(dir);
+            if !dir.exists() {
+                if !cli.create_dirs {
+                    return Err(exit::fail(
+                        ExitCode::InvalidInput,
+                        tr!(
+                            "目录 '{}' 不存在，使用 --create-dirs 自动创建。",
+                            "directory '{}' does not exist, use --create-dirs to create it.",
+                            dir.display()
+                        ),
+                    ));
+                }
+                fs::create_dir_all(&dir).with_context(|| {
+                    tr!(
+                        "无法创建目录 {}",
+                        "failed to create directory {}",
+                        dir.display()
+                    )
+                })?;
+            } else if !dir.is_dir() {
+                return Err(exit::fail(
+                    ExitCode::InvalidInput,
+                    tr!("'{}' 不是目录。", "'{}' is not a directory.", dir.display()),
+                ));
+            }
+            dir
         }
+        None => current_dir,
     };
+    target_dir.canonicalize().context(tr!(
+        "无法规范化目标目录路径",
+        "failed to canonicalize the target directory"
+    ))
+}
+
+/// 一次创建命令中所有文件共用的参数
+#[derive(Clone, Copy)]
+struct CreateContext<'a> {
+    cli: &'a CreateArgs,
+    config: &'a Config,
+    engine: &'a TemplateEngine,
+    target_dir: &'a Path,
+    /// 目标目录所在项目类型的默认值
+    project: Option<&'a ProjectDefaults>,
+    /// 模板引用 `git.` 变量时才查询，同一目录只查询一次
+    git: &'a GitCache,
+}
 
-    // 创建目标文件名
-    let target_filename = format!("{}.{}", cli.filename, cli.extension);
-    
-    // --- Path validation for target file ---
-    let current_dir = std::env::current_dir().context("无法获取当前目录")?;
-    let canonical_current_dir = current_dir.canonicalize().context("无法规范化当前目录路径")?;
-    
-    let absolute_target_path = canonical_current_dir.join(&target_filename);
-
-    // Ensure the target path is directly within the canonical current working directory
-    if absolute_target_path.parent() != Some(canonical_current_dir.as_path()) {
-        eprintln!(
-            "错误：目标文件路径 '{:?}' 不在当前工作目录内。",
-            absolute_target_path
+/// 拆分出文件名中的模板命名空间，并给出查找模板时依次使用的命名空间
+///
+/// `web/index` 形式的文件名指定了模板命名空间，优先于 --from；
+/// 未指定命名空间时先查找项目类型的命名空间，再查找模板目录根部。
+fn lookup_namespaces<'a>(
+    from: Option<&'a str>,
+    project: Option<&'a ProjectDefaults>,
+    filename: &'a str,
+) -> (Option<&'a str>, &'a str, Vec<Option<&'a str>>) {
+    let (namespace, filename) = match filename.rsplit_once('/') {
+        Some((namespace, filename)) => (Some(namespace), filename),
+        None => (
+            from.filter(|from| !from.starts_with(remote::GITHUB_PREFIX)),
+            filename,
+        ),
+    };
+    let lookup = match project.and_then(|project| project.namespace.as_deref()) {
+        Some(project) if namespace.is_none() => vec![Some(project), None],
+        _ => vec![namespace],
+    };
+    (namespace, filename, lookup)
+}
+
+/// 交互式选择时列出的模板：指定了 --tag 时只保留带有这些标签的模板；
+/// 开启了使用统计时最近用过的模板排在前面
+fn pickable_templates(
+    engine: &TemplateEngine,
+    cli: &CreateArgs,
+    config: &Config,
+) -> Result<Vec<TemplateInfo>> {
+    let filter = Filter {
+        tags: cli.tags.iter().cloned().map(Pattern::Glob).collect(),
+        ..Filter::default()
+    };
+    let mut templates: Vec<TemplateInfo> = engine
+        .list_templates()?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .collect();
+    if config.track_usage {
+        // 统计只影响排列顺序，读取失败时保持按名称排列
+        let events = usage::usage_path().and_then(|path| usage::load(&path));
+        let summary = usage::summarize(&events.unwrap_or_default());
+        templates.sort_by_key(|t| {
+            std::cmp::Reverse(summary.get(&t.qualified_name()).map(|u| u.last_used))
+        });
+    }
+    Ok(templates)
+}
+
+/// 未指定后缀时根据已有的同名模板推断后缀：只有一个时直接使用，有多个时在终端中让用户选择；
+/// 没有同名模板或未做选择时返回 None，使用默认后缀
+fn infer_extension(context: &CreateContext, filename: &str) -> Result<Option<String>> {
+    let (_, name, namespaces) =
+        lookup_namespaces(context.cli.from.as_deref(), context.project, filename);
+    let Ok(name) = pattern::expand(name) else {
+        return Ok(None);
+    };
+    let mut templates = context.engine.templates_named(&namespaces, &name)?;
+    if templates.len() == 1 {
+        let template = templates.remove(0);
+        log::debug!(
+            "inferred .{} from {}",
+            template.extension,
+            template.qualified_name()
         );
-        std::process::exit(1);
+        return Ok(Some(template.extension));
     }
-    // --- End of path validation ---
+    if templates.is_empty() || context.cli.no_input || !picker::is_interactive() {
+        return Ok(None);
+    }
+    let prompt = tr!(
+        "有多个名为 {} 的模板，请选择 (Esc 使用默认后缀)",
+        "Several templates are named {}, select one (Esc for the default extension)",
+        name
+    );
+    Ok(picker::pick_template(&templates, &prompt)?.map(|t| t.extension.clone()))
+}
 
-    // 写入新文件
-    fs::write(&absolute_target_path, template_content)
-        .with_context(|| format!("无法创建文件 {}", target_filename))?;
+/// 根据模板创建单个文件；`--stdout` 时只输出渲染结果并返回 None
+fn create_one(
+    context: &CreateContext,
+    reporter: &mut Reporter,
+    picked: Option<PathBuf>,
+    filename: &str,
+    extension: &str,
+) -> Result<Option<Outcome>> {
+    let CreateContext {
+        cli,
+        config,
+        engine,
+        target_dir,
+        project,
+        git,
+    } = *context;
+    let (namespace, filename, lookup_namespaces) =
+        lookup_namespaces(cli.from.as_deref(), project, filename);
+    if let Some(namespace) = namespace {
+        new_cli::validate::validate_namespace(namespace)?;
+    }
+
+    // 替换文件名中的日期占位符；序号要等确定目标目录后才能计算
+    // --auto-number 时仍按原名查找模板，`note` 的文件都使用 note.<后缀> 模板
+    let mut filename = pattern::expand(filename)?;
+    let lookup_name = filename.clone();
+    if cli.auto_number && !pattern::has_number(&filename) {
+        filename.push_str("-{n}");
+    }
+    let numbered = pattern::has_number(&filename);
 
-    println!("成功创建文件: {}", target_filename);
+    // 校验文件名和后缀
+    new_cli::validate::validate_cli_inputs(&filename, extension)?;
 
-    // 使用默认编辑器打开新文件
-    let editor = get_default_editor();
-    match Command::new(editor)
-        .arg(&absolute_target_path) // Use the validated absolute_target_path
-        .spawn()
-        .with_context(|| format!("无法使用 {} 打开文件", editor))
+    let template_label = match namespace {
+        Some(namespace) => format!("{}/{}.{}", namespace, filename, extension),
+        None => format!("{}.{}", filename, extension),
+    };
+    let interactive = !cli.no_input && picker::is_interactive();
+
+    // --explain 只解释自动查找，--pick 或 --empty 时不需要
+    if cli.explain && picked.is_none() && !cli.empty {
+        let (lookup, candidates) =
+            engine.explain_among(&lookup_namespaces, &lookup_name, extension);
+        template::write_explanation(
+            &mut std::io::stderr(),
+            &template_label,
+            &lookup,
+            &candidates,
+        )?;
+    }
+    // 查找模板：同名模板 > 子目录中的同名模板 > default.<后缀> > 配置的后备模板，都没有时在终端中提供交互式选择；
+    // `fallback` 说明没有使用同名模板时用的是哪个模板
+    // --tag 只筛选交互式选择的模板，没有出现选择界面时提醒用户
+    let mut tags_unused = !cli.tags.is_empty() && !cli.pick;
+    let (template_path, fallback) = match picked {
+        Some(path) => (Some(path), None),
+        None if cli.empty => (None, None),
+        None => match engine.lookup_among(&lookup_namespaces, &lookup_name, extension) {
+            Lookup::Exact(path) | Lookup::Nested(path) => (Some(path), None),
+            Lookup::Default(path) => {
+                let what = tr!(
+                    " .{} 的默认模板 {}",
+                    "the default .{} template {}",
+                    extension,
+                    path.display()
+                );
+                (Some(path), Some(what))
+            }
+            Lookup::Fallback(path) => {
+                let what = tr!(
+                    "配置的后备模板 {}",
+                    "the configured fallback template {}",
+                    path.display()
+                );
+                (Some(path), Some(what))
+            }
+            Lookup::Empty if interactive => {
+                let templates = pickable_templates(engine, cli, config)?;
+                let prompt = tr!(
+                    "未找到模板 {}，请选择模板 (Esc 创建空文件)",
+                    "Template {} not found, select a template (Esc for an empty file)",
+                    template_label
+                );
+                tags_unused = false;
+                let path = picker::pick_template(&templates, &prompt)?.map(|t| t.path.clone());
+                (path, None)
+            }
+            Lookup::Empty => (None, None),
+        },
+    };
+    if tags_unused {
+        reporter.warn(tr!(
+            "警告: 没有交互式选择模板，已忽略 --tag",
+            "Warning: --tag was ignored because no template picker was shown"
+        ));
+    }
+    // 默认模板和后备模板都是用户自己准备的，直接使用并给出提示；配置了 confirm_fallback 时
+    // 先征得同意：终端中询问（拒绝时创建空文件），否则需要 --allow-fallback
+    let (template_path, notice) = match (template_path, fallback) {
+        (Some(path), Some(what)) if cli.allow_fallback || !config.confirm_fallback => (
+            Some(path),
+            Some(tr!(
+                "未找到模板 {}，使用{}",
+                "Template {} not found, using {}",
+                template_label,
+                what
+            )),
+        ),
+        (Some(path), Some(what)) => {
+            // 标准输出另有用途时不能在上面提问
+            if !interactive || cli.stdout || reporter.is_json() {
+                return Err(exit::fail(
+                    ExitCode::TemplateNotFound,
+                    tr!(
+                        "未找到模板 {}，不会自动使用{}；加上 --allow-fallback 使用它，或加上 --empty 创建空文件",
+                        "template {} not found; refusing to use {} without --allow-fallback (or pass --empty for a blank file)",
+                        template_label,
+                        what
+                    ),
+                ));
+            }
+            let question = tr!(
+                "未找到模板 {}，是否使用{}？",
+                "Template {} not found, use {} instead?",
+                template_label,
+                what
+            );
+            (prompt::confirm(&question)?.then_some(path), None)
+        }
+        (path, _) => (path, None),
+    };
+    let notice = notice.or_else(|| {
+        (template_path.is_none() && !cli.empty).then(|| {
+            tr!(
+                "未找到模板 {} 或 default.{}，将创建空文件",
+                "No template {} or default.{} found, creating an empty file",
+                template_label,
+                extension
+            )
+        })
+    });
+    if let Some(message) = notice {
+        // --stdout 时标准输出只用于模板内容
+        if cli.stdout {
+            eprintln!("{}", message);
+        } else if reporter.is_json() {
+            reporter.warn(message);
+        } else {
+            reporter.info(message);
+        }
+    }
+
+    // 未指定 --dir 时，使用模板 front matter 中建议的输出目录
+    let front_matter = match &template_path {
+        Some(path) => engine.front_matter(path)?,
+        None => None,
+    };
+    let hinted_dir = match (&cli.dir, &front_matter) {
+        (None, Some(front_matter)) if !cli.stdout => front_matter.output.dir_under(target_dir)?,
+        _ => None,
+    };
+    if let Some(dir) = &hinted_dir {
+        fs::create_dir_all(dir).with_context(|| {
+            tr!(
+                "无法创建目录 {}",
+                "failed to create directory {}",
+                dir.display()
+            )
+        })?;
+    }
+    let target_dir = hinted_dir.as_deref().unwrap_or(target_dir);
+    if numbered {
+        filename = pattern::next_free(target_dir, &filename, extension)?;
+        new_cli::validate::validate_cli_inputs(&filename, extension)?;
+    }
+    let filename = filename.as_str();
+
+    // 渲染模板变量
+    // 环境变量与内置变量一样不写入历史记录
+    let mut builtins = variables::builtin_variables(filename, extension);
+    builtins.extend(variables::env_variables(&config.allow_env, cli.allow_env));
+    if template_path
+        .as_deref()
+        .is_some_and(variables::references_git)
     {
-        Ok(_) => println!("已使用 {} 打开文件", editor),
-        Err(e) => println!("打开文件失败: {}", e),
+        builtins.extend(git.get(target_dir));
+    }
+    let mut variables = builtins.clone();
+    variables.extend(config.variables.clone());
+    for path in &cli.vars_file {
+        variables.extend(vars_file::load(path)?);
     }
+    variables.extend(cli.defines.iter().cloned());
 
-    Ok(())
+    // 询问模板声明的变量和未定义的占位符；非交互时交由渲染时的默认值和校验处理
+    if let (Some(path), true) = (&template_path, interactive) {
+        let prompts = engine.variable_prompts(path, &variables)?;
+        prompt::prompt_variables(&prompts, &mut variables)?;
+    }
+
+    // --stdout：只输出渲染结果，不创建文件、不执行钩子
+    if cli.stdout {
+        let mut content =
+            engine.render_template(template_path.as_deref(), &variables, cli.strict_vars)?;
+        if let Some(marker) = &cli.cursor_marker {
+            content = cursor::take(content, &[marker]).0;
+        }
+        if let Some(eol) = cli.eol.or(config.eol) {
+            content = eol.normalize(content);
+        }
+        // 标准输出只用于模板内容，复制失败的警告输出到标准错误
+        if cli.to_clipboard {
+            if let Err(e) = copy_to_clipboard(&content) {
+                eprintln!("{}", tr!("警告: {:#}", "Warning: {:#}", e));
+            }
+        }
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&content)?;
+        stdout.flush()?;
+        return Ok(None);
+    }
+
+    let overwrite = if cli.force {
+        Overwrite::Force
+    } else if cli.skip {
+        Overwrite::Skip
+    } else if cli.backup {
+        Overwrite::Backup
+    } else {
+        Overwrite::Refuse
+    };
+    let options = CreateOptions {
+        target_dir: target_dir.to_path_buf(),
+        variables,
+        strict_vars: cli.strict_vars,
+        overwrite,
+        insert: if cli.append {
+            Some(Insertion::Append)
+        } else {
+            cli.insert_at.clone()
+        },
+        ignore_editorconfig: cli.no_editorconfig,
+        eol: cli.eol.or(config.eol),
+        executable: cli.exec,
+        cursor_marker: cli.cursor_marker.clone(),
+    };
+
+    let target_filename = format!("{}.{}", filename, extension);
+    if let Some(lines) = cli.preview {
+        // 与使用其他模板时一样，标准输出另有用途时不能在上面提问
+        if !interactive || reporter.is_json() {
+            return Err(exit::fail(
+                ExitCode::InvalidInput,
+                tr!(
+                    "--preview 需要在终端中确认，不能用于非交互环境或 JSON 输出",
+                    "--preview needs a terminal to confirm and cannot be used non-interactively or with JSON output"
+                ),
+            ));
+        }
+        // 与写入时一样经过换行和 .editorconfig 的处理，预览的就是最终的文件内容
+        let content =
+            engine.output_content(filename, extension, template_path.as_deref(), &options)?;
+        let target = target_dir.join(&target_filename);
+        if !confirm_preview(&content, &target, template_path.as_deref(), lines as usize)? {
+            reporter.info(tr!(
+                "已取消创建 {}",
+                "Cancelled creating {}",
+                target_filename
+            ));
+            return Ok(None);
+        }
+    }
+    let outcome = match engine.create(filename, extension, template_path.as_deref(), &options) {
+        Ok(outcome) => outcome,
+        Err(Error::TargetExists(name)) => {
+            return Err(exit::fail(
+                ExitCode::TargetExists,
+                tr!(
+                    "错误：文件 {} 已存在。使用 --force 覆盖、--skip 跳过或 --backup 备份后覆盖。",
+                    "Error: file {} already exists. Use --force to overwrite, --skip to skip or --backup to back it up first.",
+                    name
+                ),
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let (path, status, backup) = match &outcome {
+        Outcome::Created { path, backup, .. } => (path, Status::Created, backup.clone()),
+        Outcome::Inserted { path } => (path, Status::Inserted, None),
+        Outcome::Skipped { path } => (path, Status::Skipped, None),
+    };
+    // 钩子执行成功后才记录结果，钩子失败时该文件记为失败
+    let report = FileReport {
+        template: template_path.clone(),
+        bytes: (status != Status::Skipped)
+            .then(|| fs::metadata(path).map(|m| m.len()).ok())
+            .flatten(),
+        backup,
+        ..FileReport::new(path.clone(), status)
+    };
+
+    match &outcome {
+        Outcome::Skipped { .. } => reporter.info(tr!(
+            "文件 {} 已存在，已跳过",
+            "File {} already exists, skipped",
+            target_filename
+        )),
+        Outcome::Inserted { .. } => reporter.info(tr!(
+            "已将内容插入文件: {}",
+            "Inserted content into: {}",
+            target_filename
+        )),
+        Outcome::Created { path, backup, .. } => {
+            if backup.is_some() {
+                reporter.info(tr!(
+                    "已将原文件备份为: {}.bak",
+                    "Backed up the existing file as: {}.bak",
+                    target_filename
+                ));
+            }
+            if !should_open(cli, config) {
+                reporter.info(tr!("成功创建文件: {}", "Created file: {}", path.display()));
+            } else {
+                reporter.info(tr!("成功创建文件: {}", "Created file: {}", target_filename));
+            }
+
+            if cli.add_mod || (config.add_mod && !cli.no_add_mod) {
+                declare_module(path, reporter);
+            }
+
+            // 先执行模板中的钩子，再执行配置文件中的全局钩子
+            if !cli.no_hooks {
+                let mut commands = front_matter
+                    .map(|front_matter| front_matter.hooks.post_create)
+                    .unwrap_or_default();
+                commands.extend(config.hooks.post_create.iter().cloned());
+
+                let mut hook_vars = options.variables.clone();
+                hook_vars.insert("file".to_string(), path.display().to_string());
+                hook_vars.insert("dir".to_string(), target_dir.display().to_string());
+                for command in &commands {
+                    reporter.info(tr!("执行钩子: {}", "Running hook: {}", command));
+                    // JSON 输出时标准输出只用于结果，钩子的输出转到标准错误
+                    let stdout = if reporter.is_json() {
+                        Stdio::from(std::io::stderr())
+                    } else {
+                        Stdio::inherit()
+                    };
+                    hooks::run_with_stdout(command, &hook_vars, target_dir, stdout)?;
+                }
+            }
+
+            if cli.to_clipboard {
+                let copied = fs::read(path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| copy_to_clipboard(&content));
+                match copied {
+                    Ok(()) => reporter.info(tr!("已复制到剪贴板", "Copied to the clipboard")),
+                    Err(e) => reporter.warn(tr!("警告: {:#}", "Warning: {:#}", e)),
+                }
+            }
+            if cli.git_add || (config.git_add && !cli.no_git_add) {
+                stage(path, reporter);
+            }
+        }
+    }
+
+    reporter.file(report);
+
+    let recorded = match &outcome {
+        Outcome::Created { path, backup, .. } => {
+            Some((history::Action::Created, path, backup.clone()))
+        }
+        Outcome::Inserted { path } => Some((history::Action::Inserted, path, None)),
+        Outcome::Skipped { .. } => None,
+    };
+    if let Some((action, path, backup)) = recorded {
+        let used = template_path
+            .as_deref()
+            .filter(|_| config.track_usage)
+            .and_then(|template| engine.qualified_name(template));
+        if let Some(template) = used {
+            let event = usage::Event {
+                timestamp: chrono::Local::now(),
+                template,
+            };
+            if let Err(e) = usage::usage_path().and_then(|file| usage::record(&file, &event)) {
+                reporter.warn(tr!("警告: {}", "Warning: {}", e));
+            }
+        }
+        let entry = history::Entry {
+            timestamp: chrono::Local::now(),
+            action,
+            template: template_path,
+            path: path.clone(),
+            backup,
+            variables: options
+                .variables
+                .iter()
+                .filter(|(key, value)| builtins.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            undone: false,
+        };
+        // 记录失败不影响已创建的文件
+        if let Err(e) = history::history_path().and_then(|file| history::append(&file, &entry)) {
+            reporter.warn(tr!("警告: {}", "Warning: {}", e));
+        }
+    }
+
+    Ok(Some(outcome))
+}
+
+/// `--preview`：输出渲染结果的前 `lines` 行（终端中按目标文件的后缀语法高亮），询问是否创建
+fn confirm_preview(
+    content: &[u8],
+    target: &Path,
+    template: Option<&Path>,
+    lines: usize,
+) -> Result<bool> {
+    match template {
+        Some(template) => println!(
+            "{}",
+            tr!(
+                "{} (模板 {}):",
+                "{} (template {}):",
+                target.display(),
+                template.display()
+            )
+        ),
+        None => println!(
+            "{}",
+            tr!("{} (空文件)", "{} (empty file)", target.display())
+        ),
+    }
+    match std::str::from_utf8(content) {
+        Ok(text) => {
+            let head: String = text.split_inclusive('\n').take(lines).collect();
+            let newline = !head.is_empty() && !head.ends_with('\n');
+            let head = if std::io::stdout().is_terminal() {
+                let extension = target
+                    .extension()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                cli::highlight::highlight(&head, &extension)
+            } else {
+                head
+            };
+            print!("{}", head);
+            if newline {
+                println!();
+            }
+            let total = text.lines().count();
+            if total > lines {
+                println!(
+                    "{}",
+                    tr!("... (还有 {} 行)", "... ({} more lines)", total - lines)
+                );
+            }
+        }
+        Err(_) => println!(
+            "{}",
+            tr!(
+                "二进制内容 ({})，不显示",
+                "Binary content ({}), not shown",
+                template::format_size(content.len() as u64)
+            )
+        ),
+    }
+    prompt::confirm(&tr!(
+        "创建 {}？",
+        "Create {}?",
+        target.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+/// 与远程模板一样，将剪贴板或标准输入中的模板先保存到缓存目录，再作为普通模板使用
+fn save_input_template(source: &str, content: &[u8]) -> Result<PathBuf> {
+    let dir = remote::cache_dir()?.join(source);
+    fs::create_dir_all(&dir)
+        .with_context(|| tr!("无法创建目录 {:?}", "failed to create directory {:?}", dir))?;
+    let path = dir.join("template");
+    fs::write(&path, content)
+        .with_context(|| tr!("无法写入 {:?}", "failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// 将渲染结果复制到剪贴板，二进制内容不复制
+fn copy_to_clipboard(content: &[u8]) -> Result<()> {
+    let text = std::str::from_utf8(content).map_err(|_| {
+        anyhow::anyhow!(
+            "{}",
+            tr!(
+                "内容不是文本，没有复制到剪贴板",
+                "the content is not text, not copied to the clipboard"
+            )
+        )
+    })?;
+    clipboard::write(text)
+}
+
+/// 在父模块中声明新建的 Rust 源文件；失败时只给出警告，不影响已创建的文件
+fn declare_module(path: &Path, reporter: &mut Reporter) {
+    match rust_mod::declare(path) {
+        Ok(None) => {}
+        Ok(Some(rust_mod::Declaration::Added { module, parent })) => reporter.info(tr!(
+            "已在 {} 中添加 mod {};",
+            "Added mod {1}; to {0}",
+            parent.display(),
+            module
+        )),
+        Ok(Some(rust_mod::Declaration::Exists { module, parent })) => log::debug!(
+            "module {} is already declared in {}",
+            module,
+            parent.display()
+        ),
+        Ok(Some(rust_mod::Declaration::NoParent { module })) => reporter.warn(tr!(
+            "警告: 找不到声明模块 {} 的 lib.rs、main.rs 或 mod.rs，未添加 mod 声明",
+            "Warning: no lib.rs, main.rs or mod.rs to declare module {} in, skipped the mod declaration",
+            module
+        )),
+        Ok(Some(rust_mod::Declaration::InvalidName { module })) => reporter.warn(tr!(
+            "警告: {} 不是合法的模块名，未添加 mod 声明",
+            "Warning: {} is not a valid module name, skipped the mod declaration",
+            module
+        )),
+        Err(e) => reporter.warn(tr!("警告: {}", "Warning: {}", e)),
+    }
+}
+
+/// `git add` 新建的文件；失败时只给出警告，不影响已创建的文件
+fn stage(path: &Path, reporter: &mut Reporter) {
+    match git::add(path) {
+        Ok(git::Added::Staged) => reporter.info(tr!(
+            "已将 {} 添加到 git 暂存区",
+            "Staged {} in git",
+            path.display()
+        )),
+        Ok(git::Added::NotInRepository) => reporter.warn(tr!(
+            "警告: {} 不在 git 仓库中，未执行 git add",
+            "Warning: {} is not inside a git repository, skipped git add",
+            path.display()
+        )),
+        Ok(git::Added::Ignored) => reporter.warn(tr!(
+            "警告: {} 被 .gitignore 忽略，未添加到暂存区",
+            "Warning: {} is ignored by .gitignore, not staged",
+            path.display()
+        )),
+        Err(e) => reporter.warn(tr!("警告: {:#}", "Warning: {:#}", e)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn target(name: Option<&str>, ext: Option<&str>) -> Target {
+        (name.map(str::to_string), ext.map(str::to_string))
+    }
+
     #[test]
-    fn test_validate_cli_inputs_valid() {
-        assert!(validate_cli_inputs("index", "html").is_ok());
-        assert!(validate_cli_inputs("my_file-123", "txt").is_ok());
+    fn test_parse_targets_legacy_form() {
+        assert_eq!(parse_targets(&[], None), Ok(vec![target(None, None)]));
+        assert_eq!(
+            parse_targets(&strings(&["main"]), None),
+            Ok(vec![target(Some("main"), None)])
+        );
+        assert_eq!(
+            parse_targets(&strings(&["main", "js"]), None),
+            Ok(vec![target(Some("main"), Some("js"))])
+        );
     }
 
     #[test]
-    fn test_validate_filename_empty() {
-        let result = validate_cli_inputs("", "html");
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "错误：文件名不能为空。");
+    fn test_parse_targets_combined_form() {
+        assert_eq!(
+            parse_targets(&strings(&["article.md"]), None),
+            Ok(vec![target(Some("article"), Some("md"))])
+        );
+        assert_eq!(
+            parse_targets(&strings(&["app.config.js"]), None),
+            Ok(vec![target(Some("app.config"), Some("js"))])
+        );
+        // 旧写法中文件名本身带 `.` 时仍按旧写法处理
+        assert_eq!(
+            parse_targets(&strings(&["jquery.min", "js"]), None),
+            Ok(vec![target(Some("jquery.min"), Some("js"))])
+        );
     }
 
     #[test]
-    fn test_validate_filename_invalid_chars() {
-        let chars = ["/", "\\", ".."];
-        for &char_set in &chars {
-            let filename = format!("file{}", char_set);
-            let result = validate_cli_inputs(&filename, "html");
-            assert!(result.is_err());
-            assert_eq!(
-                result.unwrap_err(),
-                format!(
-                    "错误：文件名 '{}' 包含无效字符 '{}'。",
-                    filename, char_set
-                )
-            );
-        }
+    fn test_split_name_ext() {
+        assert_eq!(split_name_ext("main.rs"), target(Some("main"), Some("rs")));
+        assert_eq!(split_name_ext("main"), target(Some("main"), None));
+        assert_eq!(
+            split_name_ext(".gitignore"),
+            target(Some(".gitignore"), None)
+        );
+        assert_eq!(split_name_ext("notes."), target(Some("notes."), None));
     }
 
     #[test]
-    fn test_validate_extension_empty() {
-        let result = validate_cli_inputs("index", "");
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "错误：文件后缀不能为空。");
+    fn test_parse_targets_with_ext() {
+        assert_eq!(
+            parse_targets(&strings(&["header", "footer", "nav.htm"]), Some("html")),
+            Ok(vec![
+                target(Some("header"), Some("html")),
+                target(Some("footer"), Some("html")),
+                target(Some("nav"), Some("htm")),
+            ])
+        );
+        assert_eq!(
+            parse_targets(&[], Some("md")),
+            Ok(vec![target(None, Some("md"))])
+        );
     }
 
     #[test]
-    fn test_validate_extension_invalid_chars() {
-        let chars = ["/", "\\", ".."];
-        for &char_set in &chars {
-            let extension = format!("ext{}", char_set);
-            let result = validate_cli_inputs("index", &extension);
-            assert!(result.is_err());
-            assert_eq!(
-                result.unwrap_err(),
-                format!(
-                    "错误：文件后缀 '{}' 包含无效字符 '{}'。",
-                    extension, char_set
-                )
-            );
-        }
+    fn test_parse_targets_name_ext_form() {
+        assert_eq!(
+            parse_targets(&strings(&["a.md", "b.txt", "c.rs"]), None),
+            Ok(vec![
+                target(Some("a"), Some("md")),
+                target(Some("b"), Some("txt")),
+                target(Some("c"), Some("rs")),
+            ])
+        );
+        assert!(parse_targets(&strings(&["a", "b", "c"]), None).is_err());
+        assert!(parse_targets(&strings(&["a.md", "b.txt", "c"]), None).is_err());
     }
 
     #[test]
-    fn test_validate_both_invalid_filename_takes_precedence() {
-        // Test that filename error is reported first if both are invalid (due to order of checks)
-        let result = validate_cli_inputs("file/", "ext/");
-        assert!(result.is_err());
+    fn test_ci_detected() {
+        let env = |value: &'static str| move |key: &str| (key == "CI").then(|| value.to_string());
+        assert!(ci_detected(env("true")));
+        assert!(ci_detected(env("1")));
+        assert!(!ci_detected(env("false")));
+        assert!(!ci_detected(env("")));
+        assert!(!ci_detected(|_: &str| None));
+    }
+
+    #[test]
+    fn test_preview_takes_lines_only_after_equals() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().create;
+        let create = parse(&["new-cli", "--preview", "report", "md"]);
+        assert_eq!(create.preview, Some(20));
+        assert_eq!(create.targets, ["report", "md"]);
+        let create = parse(&["new-cli", "--preview=5", "report", "md"]);
+        assert_eq!(create.preview, Some(5));
+        assert_eq!(parse(&["new-cli", "report", "md"]).preview, None);
+        assert!(Cli::try_parse_from(["new-cli", "--preview=0", "report"]).is_err());
+        assert!(Cli::try_parse_from(["new-cli", "--preview", "--stdout", "report"]).is_err());
+    }
+
+    #[test]
+    fn test_check_remote_options() {
+        let check = |args: &[&str]| check_remote_options(&Cli::parse_from(args).create).is_ok();
+        assert!(check(&["new-cli", "a", "txt"]));
+        assert!(check(&[
+            "new-cli",
+            "--from-url",
+            "https://example.com/a.txt",
+            "--cache",
+            "a"
+        ]));
+        assert!(check(&[
+            "new-cli",
+            "--from",
+            "gh:acme/t#a.txt",
+            "--sha256",
+            "00",
+            "a"
+        ]));
+        assert!(!check(&["new-cli", "--sha256", "00", "a", "txt"]));
+        assert!(!check(&["new-cli", "--from", "web", "--cache", "a", "txt"]));
+    }
+
+    #[test]
+    fn test_apply_ci_mode() {
+        let ci = |args: &[&str], var: Option<&str>| {
+            let matches = Cli::command().try_get_matches_from(args).unwrap();
+            let mut cli = Cli::from_arg_matches(&matches).unwrap();
+            apply_ci_mode(&mut cli.create, &matches, |key| {
+                (key == "CI").then_some(var?.to_string())
+            });
+            cli.create
+        };
+        let create = ci(&["new-cli", "a", "txt"], Some("true"));
+        assert!(create.no_input && create.no_open && create.strict_vars);
+        assert_eq!(create.output, OutputFormat::Json);
+        // 明确指定的 --output 保持不变
+        let create = ci(&["new-cli", "--output", "text", "a", "txt"], Some("true"));
+        assert!(create.no_input);
+        assert_eq!(create.output, OutputFormat::Text);
+        // 批量生成同样适用
+        let create = ci(&["new-cli", "--ci", "batch", "spec.csv"], None);
+        assert!(create.no_input && create.no_open);
+        let create = ci(&["new-cli", "--wait", "a", "txt"], Some("true"));
+        assert!(!create.no_input);
+        assert_eq!(
+            ci(&["new-cli", "a", "txt"], None).output,
+            OutputFormat::Text
+        );
+    }
+
+    #[test]
+    fn test_resolve_alias() {
+        let aliases = HashMap::from([("rc".to_string(), "react-component.tsx".to_string())]);
+        let template = "react-component.tsx".to_string();
+
+        assert_eq!(
+            resolve_alias(Some("rc"), &strings(&["Button"]), None, &aliases),
+            Ok(Some((template.clone(), strings(&["Button"]))))
+        );
+        assert_eq!(
+            resolve_alias(None, &strings(&["rc", "Button", "Card"]), None, &aliases),
+            Ok(Some((template.clone(), strings(&["Button", "Card"]))))
+        );
+        assert_eq!(
+            resolve_alias(None, &strings(&["rc"]), Some("tsx"), &aliases),
+            Ok(None)
+        );
         assert_eq!(
-            result.unwrap_err(),
-            "错误：文件名 'file/' 包含无效字符 '/'。"
+            resolve_alias(None, &strings(&["main", "rs"]), None, &aliases),
+            Ok(None)
         );
+        assert!(resolve_alias(Some("missing"), &[], None, &aliases).is_err());
     }
 }
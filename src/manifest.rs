@@ -0,0 +1,279 @@
+//! 多文件模板清单 (`new-cli component Button`)
+//!
+//! 模板目录下含有 `template.toml` 的子目录是一个模板清单，列出一次要创建的多个文件：
+//!
+//! ```toml
+//! description = "React 组件"
+//!
+//! [[files]]
+//! template = "component.tsx"
+//! path = "{{name}}/{{name}}.tsx"
+//!
+//! [[files]]
+//! template = "component.test.tsx"
+//! path = "{{name}}/{{name}}.test.tsx"
+//! ```
+//!
+//! `template` 是清单目录中的模板文件，`path` 是相对于目标目录的输出路径，其中可以使用模板变量，
+//! `{{name}}` 为命令行中给出的名称。省略 `path` 时，输出路径为名称加上模板文件名中第一个 `.`
+//! 之后的部分，例如 `component.test.tsx` 输出为 `{{name}}.test.tsx`。
+//!
+//! 文件可以用 `when = "use_tests"` 指定条件，条件不成立时不生成该文件，条件语法与
+//! `{% if %}` 相同（`not x`、`x == "value"`、`x != "value"`）。项目模板（`new-cli project`）
+//! 根目录中的 `template.toml` 可以用 `[when]` 表为其中的文件或目录指定条件：
+//!
+//! ```toml
+//! [when]
+//! "tests" = "use_tests"
+//! ".github/workflows/ci.yml" = "ci"
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::blocks;
+use crate::error::{Error, IoContext, Result};
+use crate::render::{self, Variables};
+use crate::tr;
+use crate::validate::{self, validate_qualified_name};
+
+/// 清单文件名
+pub const FILE_NAME: &str = "template.toml";
+
+/// 模板清单
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    /// 清单说明
+    pub description: Option<String>,
+
+    /// 要创建的文件
+    pub files: Vec<ManifestFile>,
+
+    /// 项目模板中文件或目录（相对于模板根目录）的生成条件
+    pub when: BTreeMap<String, String>,
+}
+
+/// 清单中的一个文件
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestFile {
+    /// 清单目录中的模板文件
+    pub template: String,
+
+    /// 输出路径，相对于目标目录，可以使用模板变量
+    pub path: Option<String>,
+
+    /// 生成条件，不成立时跳过该文件
+    pub when: Option<String>,
+}
+
+impl Manifest {
+    /// 读取目录 `dir` 中的 `template.toml`
+    pub fn load(dir: &Path) -> Result<Manifest> {
+        let manifest = Manifest::read(&dir.join(FILE_NAME))?;
+        if manifest.files.is_empty() {
+            return Err(Error::EmptyManifest(dir.to_path_buf()));
+        }
+        Ok(manifest)
+    }
+
+    /// 读取项目模板 `dir` 的 `[when]` 条件；没有 `template.toml` 时为空
+    ///
+    /// 所有条件都会先检查语法，避免生成到一半才报错。
+    pub fn load_conditions(dir: &Path) -> Result<BTreeMap<String, String>> {
+        let path = dir.join(FILE_NAME);
+        if !path.is_file() {
+            return Ok(BTreeMap::new());
+        }
+        let manifest = Manifest::read(&path)?;
+        for condition in manifest.when.values() {
+            blocks::condition(condition)?;
+        }
+        Ok(manifest.when)
+    }
+
+    fn read(path: &Path) -> Result<Manifest> {
+        let content = fs::read_to_string(path).io_context(|| {
+            tr!(
+                "无法读取模板清单 {:?}",
+                "failed to read template manifest {:?}",
+                path
+            )
+        })?;
+        toml::from_str(&content).map_err(|source| Error::Manifest {
+            path: path.to_path_buf(),
+            source: Box::new(source),
+        })
+    }
+
+    /// 用 `vars` 渲染每个文件的输出路径，返回模板路径和相对于目标目录的输出路径
+    ///
+    /// 跳过 `when` 条件不成立的文件。模板必须存在；输出路径必须是不含 `..` 的相对路径，
+    /// 其中的变量必须都已定义。
+    pub fn outputs(&self, dir: &Path, vars: &Variables) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let mut wanted = Vec::new();
+        for file in &self.files {
+            let when = file.when.as_deref().map(blocks::condition).transpose()?;
+            if when.is_none_or(|when| holds(&when, vars)) {
+                wanted.push(file);
+            }
+        }
+        wanted
+            .into_iter()
+            .map(|file| {
+                validate_qualified_name(&file.template)?;
+                let template = dir.join(&file.template);
+                if !template.is_file() {
+                    return Err(Error::TemplateNotFound(template.display().to_string()));
+                }
+                let pattern = match &file.path {
+                    Some(path) => path.clone(),
+                    None => default_path(&file.template),
+                };
+                let rendered = render::render(&pattern, vars, true)?;
+                match validate::rendered_path(&rendered) {
+                    Some(output) => Ok((template, output)),
+                    None => Err(Error::InvalidManifestPath(PathBuf::from(rendered))),
+                }
+            })
+            .collect()
+    }
+}
+
+/// 判断条件在 `vars` 下是否成立
+pub fn holds(condition: &blocks::Condition, vars: &Variables) -> bool {
+    condition.holds(|name| vars.get(name).map(String::as_str))
+}
+
+/// 省略 `path` 时的输出路径：`component.test.tsx` -> `{{name}}.test.tsx`
+fn default_path(template: &str) -> String {
+    let file_name = template.rsplit('/').next().unwrap_or(template);
+    match file_name.split_once('.') {
+        Some((_, rest)) => format!("{{{{name}}}}.{}", rest),
+        None => "{{name}}".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(name: &str) -> Variables {
+        [("name".to_string(), name.to_string())].into()
+    }
+
+    #[test]
+    fn test_outputs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in ["component.tsx", "component.test.tsx", "style.css"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        fs::write(
+            dir.path().join(FILE_NAME),
+            "[[files]]\ntemplate = \"component.tsx\"\npath = \"{{name}}/index.tsx\"\n\
+             [[files]]\ntemplate = \"component.test.tsx\"\n\
+             [[files]]\ntemplate = \"style.css\"\npath = \"styles/{{name | kebab}}.css\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::load(dir.path()).unwrap();
+        let outputs: Vec<PathBuf> = manifest
+            .outputs(dir.path(), &vars("NavBar"))
+            .unwrap()
+            .into_iter()
+            .map(|(_, output)| output)
+            .collect();
+        assert_eq!(
+            outputs,
+            [
+                PathBuf::from("NavBar/index.tsx"),
+                PathBuf::from("NavBar.test.tsx"),
+                PathBuf::from("styles/nav-bar.css"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditional_outputs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in ["lib.rs", "tests.rs"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        fs::write(
+            dir.path().join(FILE_NAME),
+            "[[files]]\ntemplate = \"lib.rs\"\n\
+             [[files]]\ntemplate = \"tests.rs\"\nwhen = \"use_tests\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::load(dir.path()).unwrap();
+        let count = |value: Option<&str>| {
+            let mut vars = vars("x");
+            if let Some(value) = value {
+                vars.insert("use_tests".to_string(), value.to_string());
+            }
+            manifest.outputs(dir.path(), &vars).unwrap().len()
+        };
+        assert_eq!(count(None), 1);
+        assert_eq!(count(Some("false")), 1);
+        assert_eq!(count(Some("true")), 2);
+
+        let invalid = Manifest {
+            files: vec![ManifestFile {
+                template: "lib.rs".to_string(),
+                path: None,
+                when: Some("a b".to_string()),
+            }],
+            ..Manifest::default()
+        };
+        assert!(matches!(
+            invalid.outputs(dir.path(), &vars("x")),
+            Err(Error::TemplateSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+        let manifest = |path: &str| Manifest {
+            description: None,
+            files: vec![ManifestFile {
+                template: "a.txt".to_string(),
+                path: Some(path.to_string()),
+                when: None,
+            }],
+            ..Manifest::default()
+        };
+        for path in ["../{{name}}.txt", "/tmp/{{name}}.txt", "{{missing}}"] {
+            assert!(
+                manifest(path).outputs(dir.path(), &vars("x")).is_err(),
+                "{path}"
+            );
+        }
+        let missing = Manifest {
+            files: vec![ManifestFile {
+                template: "b.txt".to_string(),
+                path: None,
+                when: None,
+            }],
+            ..Manifest::default()
+        };
+        assert!(matches!(
+            missing.outputs(dir.path(), &vars("x")),
+            Err(Error::TemplateNotFound(_))
+        ));
+
+        fs::write(dir.path().join(FILE_NAME), "description = \"empty\"\n").unwrap();
+        assert!(matches!(
+            Manifest::load(dir.path()),
+            Err(Error::EmptyManifest(_))
+        ));
+        fs::write(dir.path().join(FILE_NAME), "[[files]]\ntemplat = \"a\"\n").unwrap();
+        assert!(matches!(
+            Manifest::load(dir.path()),
+            Err(Error::Manifest { .. })
+        ));
+    }
+}
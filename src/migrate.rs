@@ -0,0 +1,194 @@
+//! 从旧版本使用的 `~/.new-cli` 迁移到配置、数据和缓存目录
+//!
+//! 启动时检查旧目录，把其中的每一项移动到新的位置：
+//!
+//! | 旧位置 | 新位置 |
+//! | --- | --- |
+//! | `config.toml`、`trusted-keys/` | 配置目录 |
+//! | `template/` | 数据目录中的 `templates/` |
+//! | `plugins/`、`history.jsonl`、`templates.lock` | 数据目录 |
+//! | `cache/` 中的内容、`detected-editor` | 缓存目录 |
+//!
+//! 新位置已有同名文件时保留旧文件，不覆盖；全部移走后删除旧目录，之后的启动只需检查一次
+//! 目录是否存在。设置了 `NEW_CLI_HOME` 时不迁移。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, home_override};
+use crate::error::{IoContext, Result};
+use crate::tr;
+
+/// 迁移的目标目录
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub config: PathBuf,
+    pub data: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl Layout {
+    /// 当前平台上的目录
+    pub fn current() -> Result<Layout> {
+        Ok(Layout {
+            config: config::config_dir()?,
+            data: config::data_dir()?,
+            cache: config::cache_dir()?,
+        })
+    }
+
+    /// 旧目录中的各项及其新位置
+    fn targets(&self) -> [(&'static str, PathBuf); 8] {
+        [
+            ("config.toml", self.config.join("config.toml")),
+            ("trusted-keys", self.config.join("trusted-keys")),
+            ("template", self.data.join("templates")),
+            ("plugins", self.data.join("plugins")),
+            ("history.jsonl", self.data.join("history.jsonl")),
+            ("templates.lock", self.data.join("templates.lock")),
+            ("cache", self.cache.clone()),
+            ("detected-editor", self.cache.join("detected-editor")),
+        ]
+    }
+}
+
+/// 一项已完成的移动
+#[derive(Debug, PartialEq, Eq)]
+pub struct Moved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// 把 `legacy` 中的内容移动到 `layout` 中，返回移动过的项
+///
+/// 不认识的文件留在旧目录中；旧目录最终为空时将其删除。
+pub fn migrate(legacy: &Path, layout: &Layout) -> Result<Vec<Moved>> {
+    let mut moved = Vec::new();
+    if !legacy.is_dir() {
+        return Ok(moved);
+    }
+    for (name, target) in layout.targets() {
+        let source = legacy.join(name);
+        if source.exists() {
+            move_into(&source, &target, &mut moved)?;
+        }
+    }
+    // 只删除空目录，留下的文件需要用户自己处理
+    let _ = fs::remove_dir(legacy);
+    Ok(moved)
+}
+
+/// 迁移当前用户的 `~/.new-cli`，设置了 `NEW_CLI_HOME` 时什么也不做
+pub fn migrate_legacy() -> Result<Vec<Moved>> {
+    if home_override().is_some() {
+        return Ok(Vec::new());
+    }
+    migrate(&config::legacy_dir()?, &Layout::current()?)
+}
+
+/// 移动 `source` 到 `target`；两者都是目录时逐项合并，已存在的文件保持不动
+fn move_into(source: &Path, target: &Path, moved: &mut Vec<Moved>) -> Result<()> {
+    if source == target {
+        return Ok(());
+    }
+    if !target.exists() {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).io_context(|| {
+                tr!(
+                    "无法创建目录 {:?}",
+                    "failed to create directory {:?}",
+                    parent
+                )
+            })?;
+        }
+        fs::rename(source, target).io_context(|| {
+            tr!(
+                "无法将 {:?} 移动到 {:?}",
+                "failed to move {:?} to {:?}",
+                source,
+                target
+            )
+        })?;
+        moved.push(Moved {
+            from: source.to_path_buf(),
+            to: target.to_path_buf(),
+        });
+        return Ok(());
+    }
+    if source.is_dir() && target.is_dir() {
+        let entries = fs::read_dir(source)
+            .io_context(|| tr!("无法读取目录 {:?}", "failed to read directory {:?}", source))?;
+        for entry in entries.filter_map(std::result::Result::ok) {
+            move_into(&entry.path(), &target.join(entry.file_name()), moved)?;
+        }
+        let _ = fs::remove_dir(source);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn layout(root: &Path) -> Layout {
+        Layout {
+            config: root.join("config/new-cli"),
+            data: root.join("data/new-cli"),
+            cache: root.join("cache/new-cli"),
+        }
+    }
+
+    #[test]
+    fn test_migrate_moves_legacy_layout() {
+        let root = TempDir::new().unwrap();
+        let legacy = root.path().join(".new-cli");
+        fs::create_dir_all(legacy.join("template/rust")).unwrap();
+        fs::create_dir_all(legacy.join("cache/ab")).unwrap();
+        fs::write(legacy.join("config.toml"), "open = false").unwrap();
+        fs::write(legacy.join("template/rust/main.rs"), "fn main() {}").unwrap();
+        fs::write(legacy.join("history.jsonl"), "").unwrap();
+        fs::write(legacy.join("cache/ab/a.md"), "# a").unwrap();
+        fs::write(legacy.join("detected-editor"), "code").unwrap();
+
+        let layout = layout(root.path());
+        let moved = migrate(&legacy, &layout).unwrap();
+        assert_eq!(moved.len(), 5);
+        assert!(!legacy.exists());
+        assert!(layout.config.join("config.toml").is_file());
+        assert!(layout.data.join("templates/rust/main.rs").is_file());
+        assert!(layout.data.join("history.jsonl").is_file());
+        assert!(layout.cache.join("ab/a.md").is_file());
+        assert!(layout.cache.join("detected-editor").is_file());
+
+        // 再次运行时没有可迁移的内容
+        assert!(migrate(&legacy, &layout).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_files() {
+        let root = TempDir::new().unwrap();
+        let legacy = root.path().join(".new-cli");
+        let layout = layout(root.path());
+        fs::create_dir_all(legacy.join("template")).unwrap();
+        fs::create_dir_all(layout.data.join("templates")).unwrap();
+        fs::write(legacy.join("template/a.md"), "old").unwrap();
+        fs::write(legacy.join("template/b.md"), "b").unwrap();
+        fs::write(layout.data.join("templates/a.md"), "new").unwrap();
+        fs::write(legacy.join("notes.txt"), "").unwrap();
+
+        let moved = migrate(&legacy, &layout).unwrap();
+        assert_eq!(
+            moved,
+            [Moved {
+                from: legacy.join("template/b.md"),
+                to: layout.data.join("templates/b.md"),
+            }]
+        );
+        let a = layout.data.join("templates/a.md");
+        assert_eq!(fs::read_to_string(a).unwrap(), "new");
+        // 冲突的文件和不认识的文件都留在旧目录中
+        assert!(legacy.join("template/a.md").is_file());
+        assert!(legacy.join("notes.txt").is_file());
+    }
+}
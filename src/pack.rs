@@ -0,0 +1,461 @@
+//! 模板包：把一组模板打包为单个 `.tar.gz` 或 `.zip` 文件，便于分享
+//!
+//! 包内的路径就是模板相对于模板目录的路径（包括命名空间子目录），
+//! 导出和导入时都会保留文件的修改时间和 Unix 权限。
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, IoContext, Result};
+use crate::template::{self, TemplateInfo};
+use crate::tr;
+use crate::validate::{validate_namespace, validate_qualified_name};
+
+/// 通过 HTTP(S) 导入模板包时允许下载的最大字节数，也是导入时解压后单个文件和所有文件的总大小上限
+pub const MAX_DOWNLOAD_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 模板包的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFormat {
+    TarGz,
+    Zip,
+}
+
+impl PackFormat {
+    /// 根据文件名或地址的后缀确定格式：`.tar.gz`、`.tgz` 或 `.zip`
+    pub fn detect(name: &str) -> Result<PackFormat> {
+        let lower = name.to_lowercase();
+        let lower = lower.split(['?', '#']).next().unwrap_or_default();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Ok(PackFormat::TarGz)
+        } else if lower.ends_with(".zip") {
+            Ok(PackFormat::Zip)
+        } else {
+            Err(Error::UnsupportedPack(name.to_string()))
+        }
+    }
+}
+
+/// 包中的一个模板文件
+struct PackEntry {
+    /// 相对于模板目录的路径，例如 `web/index.html`
+    name: String,
+    content: Vec<u8>,
+    mode: Option<u32>,
+    modified: Option<SystemTime>,
+}
+
+/// 将模板目录（或其中的命名空间 `namespace`）中的所有模板导出到 `output`
+///
+/// 返回导出的模板数量。
+pub fn export(
+    template_dir: &Path,
+    namespace: Option<&str>,
+    output: &Path,
+    format: PackFormat,
+) -> Result<usize> {
+    if let Some(namespace) = namespace {
+        validate_namespace(namespace)?;
+    }
+    let templates: Vec<TemplateInfo> = template::list_templates(template_dir)?
+        .into_iter()
+        .filter(|t| match namespace {
+            Some(namespace) => t
+                .namespace
+                .as_deref()
+                .is_some_and(|ns| ns == namespace || ns.starts_with(&format!("{}/", namespace))),
+            None => true,
+        })
+        .collect();
+
+    let write_context = || {
+        tr!(
+            "无法写入模板包 {:?}",
+            "failed to write template pack {:?}",
+            output
+        )
+    };
+    let file = File::create(output).io_context(write_context)?;
+    match format {
+        PackFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            for template in &templates {
+                builder
+                    .append_path_with_name(&template.path, template.qualified_name())
+                    .io_context(write_context)?;
+            }
+            builder
+                .into_inner()
+                .and_then(|encoder| encoder.finish())
+                .io_context(write_context)?;
+        }
+        PackFormat::Zip => {
+            let mut writer = zip::ZipWriter::new(file);
+            for template in &templates {
+                let content = fs::read(&template.path).io_context(|| {
+                    tr!(
+                        "无法读取模板文件: {:?}",
+                        "failed to read template file: {:?}",
+                        template.path
+                    )
+                })?;
+                let metadata = fs::metadata(&template.path).ok();
+                let mut options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                if let Some(mode) = metadata.as_ref().and_then(unix_mode) {
+                    options = options.unix_permissions(mode);
+                }
+                if let Some(time) = metadata.and_then(|m| m.modified().ok()).and_then(zip_time) {
+                    options = options.last_modified_time(time);
+                }
+                writer
+                    .start_file(template.qualified_name(), options)
+                    .map_err(|e| zip_error(output, e))?;
+                writer.write_all(&content).io_context(write_context)?;
+            }
+            writer.finish().map_err(|e| zip_error(output, e))?;
+        }
+    }
+    Ok(templates.len())
+}
+
+/// 将模板包 `data` 中的模板导入 `template_dir`，返回导入的模板名
+///
+/// 所有路径都会先经过校验；除非指定 `force`，已存在的模板会导致整个导入失败，
+/// 不会写入任何文件。`source` 只用于错误信息。
+pub fn import(
+    data: &[u8],
+    format: PackFormat,
+    template_dir: &Path,
+    force: bool,
+    source: &str,
+) -> Result<Vec<String>> {
+    let entries = match format {
+        PackFormat::TarGz => read_tar(data, source)?,
+        PackFormat::Zip => read_zip(data, source)?,
+    };
+
+    for entry in &entries {
+        validate_qualified_name(&entry.name)?;
+        if !force && template_dir.join(&entry.name).exists() {
+            return Err(Error::PackConflict(entry.name.clone()));
+        }
+    }
+
+    let mut imported = Vec::new();
+    for entry in entries {
+        let path = template_dir.join(&entry.name);
+        let context = || {
+            tr!(
+                "无法写入模板文件: {:?}",
+                "failed to write template file: {:?}",
+                path
+            )
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).io_context(context)?;
+        }
+        fs::write(&path, &entry.content).io_context(context)?;
+        restore_metadata(&path, entry.mode, entry.modified);
+        imported.push(entry.name);
+    }
+    Ok(imported)
+}
+
+fn read_tar(data: &[u8], source: &str) -> Result<Vec<PackEntry>> {
+    let context = || {
+        tr!(
+            "无法读取模板包 {}",
+            "failed to read template pack {}",
+            source
+        )
+    };
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    let mut entries = Vec::new();
+    let mut unpacked = 0;
+    for entry in archive.entries().io_context(context)? {
+        let mut entry = entry.io_context(context)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .io_context(context)?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        let mode = entry.header().mode().ok();
+        let modified = entry
+            .header()
+            .mtime()
+            .ok()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+        let content = read_entry(&mut entry, &mut unpacked, source)?;
+        entries.push(PackEntry {
+            name,
+            content,
+            mode,
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_zip(data: &[u8], source: &str) -> Result<Vec<PackEntry>> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|e| zip_error(Path::new(source), e))?;
+    let mut entries = Vec::new();
+    let mut unpacked = 0;
+    for index in 0..archive.len() {
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| zip_error(Path::new(source), e))?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().replace('\\', "/");
+        let mode = file.unix_mode();
+        let modified = file.last_modified().and_then(system_time);
+        let content = read_entry(&mut file, &mut unpacked, source)?;
+        entries.push(PackEntry {
+            name,
+            content,
+            mode,
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+/// 解压一个文件的内容，`unpacked` 累计已解压的字节数
+///
+/// 单个文件和所有文件的总大小都不能超过 [`MAX_DOWNLOAD_SIZE`]，防止很小的包解压后占满磁盘。
+fn read_entry(entry: impl Read, unpacked: &mut u64, source: &str) -> Result<Vec<u8>> {
+    let remaining = MAX_DOWNLOAD_SIZE - *unpacked;
+    // 多读一个字节，用来判断是否超过了大小限制
+    let mut content = Vec::new();
+    entry
+        .take(remaining + 1)
+        .read_to_end(&mut content)
+        .io_context(|| {
+            tr!(
+                "无法读取模板包 {}",
+                "failed to read template pack {}",
+                source
+            )
+        })?;
+    if content.len() as u64 > remaining {
+        return Err(Error::PackTooLarge {
+            pack: source.to_string(),
+            limit: MAX_DOWNLOAD_SIZE,
+        });
+    }
+    *unpacked += content.len() as u64;
+    Ok(content)
+}
+
+fn zip_error(path: &Path, error: zip::result::ZipError) -> Error {
+    Error::Pack {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+/// 尽量恢复文件的权限和修改时间，失败时忽略
+fn restore_metadata(path: &Path, mode: Option<u32>, modified: Option<SystemTime>) {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777));
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    if let Some(modified) = modified {
+        if let Ok(file) = File::options().write(true).open(path) {
+            let _ = file.set_modified(modified);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+fn zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    let local = DateTime::<Local>::from(time);
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).ok()?,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .ok()
+}
+
+fn system_time(time: zip::DateTime) -> Option<SystemTime> {
+    let local = chrono::NaiveDate::from_ymd_opt(
+        time.year().into(),
+        time.month().into(),
+        time.day().into(),
+    )?
+    .and_hms_opt(
+        time.hour().into(),
+        time.minute().into(),
+        time.second().into(),
+    )?
+    .and_local_timezone(Local)
+    .earliest()?;
+    Some(local.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn templates() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("web/react")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("index.html"), "root").unwrap();
+        fs::write(dir.path().join("web/index.html"), "web").unwrap();
+        fs::write(dir.path().join("web/react/app.tsx"), "app").unwrap();
+        fs::write(dir.path().join(".git/config"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(PackFormat::detect("a.tar.gz").unwrap(), PackFormat::TarGz);
+        assert_eq!(PackFormat::detect("a.TGZ").unwrap(), PackFormat::TarGz);
+        assert_eq!(
+            PackFormat::detect("https://x/a.zip?dl=1").unwrap(),
+            PackFormat::Zip
+        );
+        assert!(matches!(
+            PackFormat::detect("a.rar"),
+            Err(Error::UnsupportedPack(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let source = templates();
+        for (file, format) in [("p.tar.gz", PackFormat::TarGz), ("p.zip", PackFormat::Zip)] {
+            let out = TempDir::new().unwrap();
+            let pack = out.path().join(file);
+            assert_eq!(export(source.path(), None, &pack, format).unwrap(), 3);
+
+            let target = TempDir::new().unwrap();
+            let data = fs::read(&pack).unwrap();
+            let mut imported = import(&data, format, target.path(), false, file).unwrap();
+            imported.sort();
+            assert_eq!(
+                imported,
+                ["index.html", "web/index.html", "web/react/app.tsx"]
+            );
+            assert_eq!(
+                fs::read_to_string(target.path().join("web/react/app.tsx")).unwrap(),
+                "app"
+            );
+
+            // 已存在时整个导入失败
+            assert!(matches!(
+                import(&data, format, target.path(), false, file),
+                Err(Error::PackConflict(_))
+            ));
+            assert!(import(&data, format, target.path(), true, file).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_export_namespace() {
+        let source = templates();
+        let out = TempDir::new().unwrap();
+        let pack = out.path().join("web.zip");
+        assert_eq!(
+            export(source.path(), Some("web"), &pack, PackFormat::Zip).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_paths() {
+        let out = TempDir::new().unwrap();
+        let pack = out.path().join("evil.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&pack).unwrap());
+        writer
+            .start_file("../evil.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"evil").unwrap();
+        writer.finish().unwrap();
+
+        let target = TempDir::new().unwrap();
+        let data = fs::read(&pack).unwrap();
+        assert!(import(&data, PackFormat::Zip, target.path(), false, "evil.zip").is_err());
+        assert!(!out.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_import_limits_unpacked_size() {
+        let out = TempDir::new().unwrap();
+        let pack = out.path().join("bomb.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&pack).unwrap());
+        let half = vec![0; (MAX_DOWNLOAD_SIZE / 2) as usize + 1];
+        for name in ["a.txt", "b.txt"] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&half).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // 每个文件都没有超过上限，但总大小超过了
+        let target = TempDir::new().unwrap();
+        let data = fs::read(&pack).unwrap();
+        assert!(matches!(
+            import(&data, PackFormat::Zip, target.path(), false, "bomb.zip"),
+            Err(Error::PackTooLarge { .. })
+        ));
+        assert!(!target.path().join("a.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_import_drops_special_mode_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let out = TempDir::new().unwrap();
+        let pack = out.path().join("suid.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&pack).unwrap());
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(0o4755);
+        writer.start_file("run.sh", options).unwrap();
+        writer.write_all(b"#!/bin/sh").unwrap();
+        writer.finish().unwrap();
+
+        let target = TempDir::new().unwrap();
+        let data = fs::read(&pack).unwrap();
+        import(&data, PackFormat::Zip, target.path(), false, "suid.zip").unwrap();
+        let mode = fs::metadata(target.path().join("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+}
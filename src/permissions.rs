@@ -0,0 +1,121 @@
+//! 新建文件的权限
+//!
+//! 模板 front matter 中的 `mode = "755"` 或命令行的 `--exec` 可以让生成的脚本
+//! 直接可执行。权限只在 Unix 上生效，其他平台上静默忽略。
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::tr;
+
+/// 八进制表示的文件权限，例如 `755`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Mode(u32);
+
+impl Mode {
+    /// 解析 `755`、`0755` 或 `0o755` 形式的八进制权限
+    pub fn parse(value: &str) -> std::result::Result<Mode, String> {
+        let digits = value.trim();
+        let digits = digits.strip_prefix("0o").unwrap_or(digits);
+        match u32::from_str_radix(digits, 8) {
+            Ok(bits) if !digits.is_empty() && bits <= 0o7777 => Ok(Mode(bits)),
+            _ => Err(tr!(
+                "无效的文件权限 '{}'，应为八进制数字，例如 755",
+                "invalid file mode '{}', expected octal digits such as 755",
+                value
+            )),
+        }
+    }
+
+    /// 权限位
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<String> for Mode {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Mode, String> {
+        Mode::parse(&value)
+    }
+}
+
+/// 给有读权限的用户加上执行权限，相当于 `chmod +x`
+fn with_exec(bits: u32) -> u32 {
+    bits | ((bits & 0o444) >> 2)
+}
+
+/// 设置 `path` 的权限：`mode` 为 None 时保留当前权限，`executable` 为 true 时再加上执行权限
+#[cfg(unix)]
+pub fn apply(path: &Path, mode: Option<Mode>, executable: bool) -> Result<()> {
+    use crate::error::IoContext;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    if mode.is_none() && !executable {
+        return Ok(());
+    }
+    let context = || {
+        tr!(
+            "无法设置文件权限 {}",
+            "failed to set permissions of {}",
+            path.display()
+        )
+    };
+    let current = fs::metadata(path).io_context(context)?.permissions().mode() & 0o7777;
+    let mut bits = mode.map_or(current, Mode::bits);
+    if executable {
+        bits = with_exec(bits);
+    }
+    if bits != current {
+        fs::set_permissions(path, fs::Permissions::from_mode(bits)).io_context(context)?;
+    }
+    Ok(())
+}
+
+/// 非 Unix 平台没有对应的权限位，不做任何处理
+#[cfg(not(unix))]
+pub fn apply(_path: &Path, _mode: Option<Mode>, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Mode::parse("755").unwrap().bits(), 0o755);
+        assert_eq!(Mode::parse("0644").unwrap().bits(), 0o644);
+        assert_eq!(Mode::parse("0o700").unwrap().bits(), 0o700);
+        assert!(Mode::parse("").is_err());
+        assert!(Mode::parse("789").is_err());
+        assert!(Mode::parse("17777").is_err());
+    }
+
+    #[test]
+    fn test_with_exec() {
+        assert_eq!(with_exec(0o644), 0o755);
+        assert_eq!(with_exec(0o600), 0o700);
+        assert_eq!(with_exec(0o755), 0o755);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("run.sh");
+        std::fs::write(&path, "").unwrap();
+        let mode = || std::fs::metadata(&path).unwrap().permissions().mode() & 0o7777;
+
+        apply(&path, Some(Mode(0o640)), false).unwrap();
+        assert_eq!(mode(), 0o640);
+        apply(&path, None, true).unwrap();
+        assert_eq!(mode(), 0o750);
+    }
+}
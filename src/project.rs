@@ -0,0 +1,215 @@
+//! 项目脚手架 (`new-cli project <name> --template <dir>`)
+//!
+//! 将模板目录下的整个子目录复制为新项目，文件内容和文件/目录名中的
+//! 模板变量都会被渲染。二进制文件按字节原样复制。根目录中的 `template.toml` 不会被复制，
+//! 其中的 `[when]` 表可以为文件或目录指定生成条件，见 [`manifest`](crate::manifest)。
+//! 含有 `cookiecutter.json` 的模板按 cookiecutter 的方式处理，见 [`cookiecutter`](crate::cookiecutter)。
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::blocks;
+use crate::cookiecutter::{self, Cookiecutter};
+use crate::error::{Error, IoContext, Result};
+use crate::manifest::{self, Manifest};
+use crate::render::{self, Variables};
+use crate::tr;
+use crate::validate::{self, validate_template_name};
+
+/// 将模板目录下的项目模板 `template` 复制到 `target`，返回创建的文件数
+///
+/// `target` 必须尚不存在。
+pub fn create_project(
+    template_dir: &Path,
+    target: &Path,
+    template: &str,
+    vars: &Variables,
+    strict: bool,
+) -> Result<usize> {
+    validate_template_name(template)?;
+
+    let source = template_dir.join(template);
+    if !source.is_dir() {
+        return Err(Error::ProjectTemplateNotFound(template.to_string()));
+    }
+
+    if target.exists() {
+        return Err(Error::DirectoryExists(target.display().to_string()));
+    }
+
+    let (source, vars, cookiecutter) = match Cookiecutter::detect(&source)? {
+        Some(template) => (template.root.clone(), template.variables(vars)?, true),
+        None => (source, vars.clone(), false),
+    };
+    let tree = Tree {
+        when: Manifest::load_conditions(&source)?,
+        vars: &vars,
+        strict,
+        cookiecutter,
+    };
+    let mut count = 0;
+    tree.copy(&source, target, "", &mut count)?;
+    Ok(count)
+}
+
+/// 复制项目模板时不变的参数
+struct Tree<'a> {
+    when: BTreeMap<String, String>,
+    vars: &'a Variables,
+    strict: bool,
+    /// 渲染前先把 cookiecutter 的写法转换为 new-cli 的模板语法
+    cookiecutter: bool,
+}
+
+impl Tree<'_> {
+    /// 复制目录 `source`（相对于模板根目录为 `relative`，根目录为空）
+    fn copy(&self, source: &Path, target: &Path, relative: &str, count: &mut usize) -> Result<()> {
+        fs::create_dir_all(target).io_context(|| {
+            tr!(
+                "无法创建目录: {:?}",
+                "failed to create directory: {:?}",
+                target
+            )
+        })?;
+
+        let mut entries: Vec<_> = fs::read_dir(source)
+            .io_context(|| {
+                tr!(
+                    "无法读取目录: {:?}",
+                    "failed to read directory: {:?}",
+                    source
+                )
+            })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_name == ".git" || (relative.is_empty() && file_name == manifest::FILE_NAME) {
+                continue;
+            }
+            let relative = if relative.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{}/{}", relative, file_name)
+            };
+            if !self.wanted(&relative)? {
+                continue;
+            }
+
+            let source_path = entry.path();
+            let target_path = target.join(render_file_name(
+                &self.translate(&file_name),
+                self.vars,
+                self.strict,
+            )?);
+
+            if source_path.is_dir() {
+                self.copy(&source_path, &target_path, &relative, count)?;
+                continue;
+            }
+
+            let bytes = fs::read(&source_path).io_context(|| {
+                tr!(
+                    "无法读取模板文件: {:?}",
+                    "failed to read template file: {:?}",
+                    source_path
+                )
+            })?;
+            let bytes = match String::from_utf8(bytes) {
+                Ok(text) if self.cookiecutter && !render::is_binary(text.as_bytes()) => {
+                    cookiecutter::translate(&text, self.vars).into_bytes()
+                }
+                Ok(text) => text.into_bytes(),
+                Err(err) => err.into_bytes(),
+            };
+            let content =
+                render::render_bytes(bytes, self.vars, self.strict).map_err(|source| {
+                    Error::Render {
+                        name: source_path.display().to_string(),
+                        source: Box::new(source),
+                    }
+                })?;
+            if let Some(parent) = target_path.parent() {
+                fs::create_dir_all(parent).io_context(|| {
+                    tr!(
+                        "无法创建目录: {:?}",
+                        "failed to create directory: {:?}",
+                        parent
+                    )
+                })?;
+            }
+            fs::write(&target_path, content).io_context(|| {
+                tr!(
+                    "无法写入文件: {:?}",
+                    "failed to write file: {:?}",
+                    target_path
+                )
+            })?;
+            *count += 1;
+        }
+
+        Ok(())
+    }
+
+    fn translate<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.cookiecutter {
+            Cow::Owned(cookiecutter::translate(text, self.vars))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// 相对路径为 `relative` 的文件或目录是否需要生成
+    fn wanted(&self, relative: &str) -> Result<bool> {
+        match self.when.get(relative) {
+            Some(condition) => Ok(manifest::holds(&blocks::condition(condition)?, self.vars)),
+            None => Ok(true),
+        }
+    }
+}
+
+/// 渲染文件/目录名，返回相对于所在目录的路径
+///
+/// 变量的值可以包含 `/`（例如 `{{module}}` 为 `auth/oauth`），此时生成多级目录；
+/// 渲染结果必须是安全的相对路径，见 [`validate::rendered_path`]。
+fn render_file_name(name: &str, vars: &Variables, strict: bool) -> Result<PathBuf> {
+    let rendered = render::render(name, vars, strict).map_err(|source| Error::Render {
+        name: name.to_string(),
+        source: Box::new(source),
+    })?;
+    validate::rendered_path(&rendered).ok_or_else(|| Error::InvalidRenderedName {
+        name: name.to_string(),
+        rendered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_file_name() {
+        let mut vars = Variables::new();
+        vars.insert("name".to_string(), "my-app".to_string());
+        vars.insert("evil".to_string(), "../etc".to_string());
+        vars.insert("root".to_string(), "/etc".to_string());
+        vars.insert("module".to_string(), "auth/oauth".to_string());
+
+        assert_eq!(
+            render_file_name("{{name}}.toml", &vars, false).unwrap(),
+            PathBuf::from("my-app.toml")
+        );
+        assert_eq!(
+            render_file_name("{{module}}", &vars, false).unwrap(),
+            PathBuf::from("auth/oauth")
+        );
+        assert!(render_file_name("{{evil}}", &vars, false).is_err());
+        assert!(render_file_name("{{root}}.conf", &vars, false).is_err());
+        assert!(render_file_name("{{missing}}", &vars, false).is_err());
+    }
+}
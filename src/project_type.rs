@@ -0,0 +1,76 @@
+//! 项目类型检测：在 Rust 仓库中 `new-cli util` 默认创建 `util.rs`
+//!
+//! 从目标目录开始逐级向上查找标记文件，最近的标记决定项目类型：
+//!
+//! | 标记文件 | 项目类型 |
+//! | --- | --- |
+//! | `Cargo.toml` | `rust` |
+//! | `package.json` | `node` |
+//! | `go.mod` | `go` |
+//! | `pyproject.toml` | `python` |
+//!
+//! 配置文件中的 `[projects.<类型>]` 为该类型的项目指定默认后缀和模板命名空间：
+//!
+//! ```toml
+//! [projects.rust]
+//! extension = "rs"
+//! namespace = "rust"
+//! ```
+
+use serde::Deserialize;
+use std::path::Path;
+
+/// 标记文件和对应的项目类型，同一目录中有多个标记时以靠前的为准
+pub const MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "rust"),
+    ("package.json", "node"),
+    ("go.mod", "go"),
+    ("pyproject.toml", "python"),
+];
+
+/// 某一类型项目中的默认值，命令行参数优先
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectDefaults {
+    /// 未指定后缀时使用的后缀，优先于配置中的 `default_extension`
+    pub extension: Option<String>,
+
+    /// 未指定命名空间时先在该命名空间中查找模板，找不到再查找模板目录根部
+    pub namespace: Option<String>,
+}
+
+/// `dir` 所在项目的类型，从 `dir` 开始逐级向上查找标记文件
+pub fn detect(dir: &Path) -> Option<&'static str> {
+    dir.ancestors().find_map(|dir| {
+        MARKERS
+            .iter()
+            .find(|(marker, _)| dir.join(marker).is_file())
+            .map(|&(_, kind)| kind)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let web = dir.path().join("web");
+        let src = dir.path().join("src/bin");
+        fs::create_dir_all(&web).unwrap();
+        fs::create_dir_all(&src).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        fs::write(web.join("package.json"), "{}").unwrap();
+
+        assert_eq!(detect(&src), Some("rust"));
+        // 最近的标记优先
+        assert_eq!(detect(&web), Some("node"));
+        // 目标目录可以还不存在
+        assert_eq!(detect(&web.join("components/new")), Some("node"));
+
+        fs::write(web.join("pyproject.toml"), "").unwrap();
+        assert_eq!(detect(&web), Some("node"));
+    }
+}
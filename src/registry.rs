@@ -0,0 +1,175 @@
+//! 模板注册表：列出社区模板的 JSON 清单
+//!
+//! 清单地址由配置文件中的 `registry` 指定，可以是 HTTP(S) 地址或本地文件，格式为：
+//!
+//! ```json
+//! {
+//!   "templates": [
+//!     {
+//!       "name": "react-kit",
+//!       "description": "React 组件、hooks 和测试模板",
+//!       "tags": ["frontend", "react"],
+//!       "url": "https://github.com/acme/react-kit.git"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `url` 是模板集的 git 仓库地址，或 `.tar.gz`/`.zip` 模板包的地址，通过 `template install <name>`
+//! 安装。模板包的分离签名默认为 `url` 加上 `.minisig`、`.asc` 或 `.sig`，也可以用 `signature` 指定。
+
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoContext, Result};
+use crate::remote::{self, FetchOptions};
+use crate::template::Pattern;
+use crate::tr;
+
+/// 注册表清单
+#[derive(Debug, Default, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub templates: Vec<RegistryEntry>,
+}
+
+/// 注册表中的一个模板集
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RegistryEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 分类标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 模板集的 git 仓库地址或模板包地址
+    pub url: String,
+    /// 模板包的分离签名地址
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl Registry {
+    /// 读取注册表：HTTP(S) 地址会被下载，其他值视为本地文件路径
+    pub fn load(location: &str) -> Result<Registry> {
+        let content = if location.starts_with("https://") || location.starts_with("http://") {
+            remote::download(location, &FetchOptions::default())?
+        } else {
+            fs::read(Path::new(location)).io_context(|| {
+                tr!(
+                    "无法读取模板注册表 {}",
+                    "failed to read template registry {}",
+                    location
+                )
+            })?
+        };
+        Registry::parse(&content).map_err(|source| Error::Registry {
+            location: location.to_string(),
+            source,
+        })
+    }
+
+    fn parse(content: &[u8]) -> std::result::Result<Registry, serde_json::Error> {
+        serde_json::from_slice(content)
+    }
+
+    /// 名称、说明或标签中包含关键字（不区分大小写），并且 `tags` 中的每个模式都匹配某个标签的
+    /// 模板集，按名称排序
+    pub fn search(&self, keyword: &str, tags: &[Pattern]) -> Vec<&RegistryEntry> {
+        let keyword = keyword.to_lowercase();
+        let mut found: Vec<&RegistryEntry> = self
+            .templates
+            .iter()
+            .filter(|entry| {
+                entry.name.to_lowercase().contains(&keyword)
+                    || entry
+                        .description
+                        .as_ref()
+                        .is_some_and(|d| d.to_lowercase().contains(&keyword))
+                    || entry
+                        .tags
+                        .iter()
+                        .any(|tag| tag.to_lowercase().contains(&keyword))
+            })
+            .filter(|entry| {
+                tags.iter()
+                    .all(|pattern| entry.tags.iter().any(|tag| pattern.matches(tag)))
+            })
+            .collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        found
+    }
+
+    /// 按名称查找模板集
+    pub fn find(&self, name: &str) -> Result<&RegistryEntry> {
+        self.templates
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| Error::RegistryEntryNotFound(name.to_string()))
+    }
+}
+
+/// 参数是注册表中的名称而不是仓库地址或路径（不含 `/` 和 `:`）
+pub fn is_registry_name(value: &str) -> bool {
+    !value.is_empty() && !value.contains(['/', ':', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = r#"{
+        "templates": [
+            {"name": "vue-kit", "url": "https://example.com/vue-kit.git"},
+            {
+                "name": "react-kit",
+                "description": "React components and hooks",
+                "tags": ["frontend", "react"],
+                "url": "https://example.com/react-kit.git"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_search() {
+        let registry = Registry::parse(INDEX.as_bytes()).unwrap();
+        let names = |keyword, tags: &[&str]| -> Vec<String> {
+            let tags: Vec<Pattern> = tags.iter().map(|t| Pattern::Glob(t.to_string())).collect();
+            registry
+                .search(keyword, &tags)
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect()
+        };
+        assert_eq!(names("kit", &[]), ["react-kit", "vue-kit"]);
+        assert_eq!(names("HOOKS", &[]), ["react-kit"]);
+        assert_eq!(names("frontend", &[]), ["react-kit"]);
+        assert_eq!(names("", &["front*"]), ["react-kit"]);
+        assert!(names("vue", &["frontend"]).is_empty());
+        assert!(names("svelte", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_find() {
+        let registry = Registry::parse(INDEX.as_bytes()).unwrap();
+        assert_eq!(
+            registry.find("vue-kit").unwrap().url,
+            "https://example.com/vue-kit.git"
+        );
+        assert!(matches!(
+            registry.find("svelte-kit"),
+            Err(Error::RegistryEntryNotFound(_))
+        ));
+        assert!(Registry::parse(b"{\"templates\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_is_registry_name() {
+        assert!(is_registry_name("react-kit"));
+        assert!(!is_registry_name("https://example.com/react-kit.git"));
+        assert!(!is_registry_name("git@github.com:acme/react-kit.git"));
+        assert!(!is_registry_name("../react-kit"));
+        assert!(!is_registry_name(""));
+    }
+}
@@ -0,0 +1,478 @@
+//! 通过 HTTP(S) 下载远程模板
+//!
+//! 下载的模板保存在缓存目录中，之后就和模板目录中的模板一样使用。
+//! 下载有超时和大小限制，并可以用 SHA-256 校验内容。离线模式（[`set_offline`]）下
+//! 不进行任何网络访问，远程模板只能使用缓存中已下载的副本。
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::config;
+use crate::error::{Error, IoContext, Result};
+use crate::tr;
+use crate::validate::validate_template_name;
+
+/// 缓存目录中记录下载地址的文件名，与模板放在同一个子目录中
+const SOURCE_FILE: &str = ".url";
+
+/// 是否处于离线模式
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// 开启或关闭离线模式：开启后所有下载都返回 [`Error::Offline`]
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// 是否处于离线模式
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// 默认的下载超时
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 默认允许下载的最大字节数
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// 下载远程模板的选项
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// 连接和读取的总超时
+    pub timeout: Duration,
+    /// 允许下载的最大字节数
+    pub max_size: u64,
+    /// 期望的内容 SHA-256（十六进制），不一致时拒绝使用
+    pub sha256: Option<String>,
+    /// 缓存中已有该地址的模板时直接使用，不再下载
+    pub use_cache: bool,
+    /// 附加的请求头，例如私有仓库的认证信息
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> FetchOptions {
+        FetchOptions {
+            timeout: DEFAULT_TIMEOUT,
+            max_size: DEFAULT_MAX_SIZE,
+            sha256: None,
+            use_cache: false,
+            headers: Vec::new(),
+        }
+    }
+}
+
+/// GitHub 简写来源的前缀，例如 `gh:acme/templates#react/component.tsx@main`
+pub const GITHUB_PREFIX: &str = "gh:";
+
+/// 私有仓库认证使用的环境变量
+pub const GITHUB_TOKEN_VAR: &str = "GITHUB_TOKEN";
+
+/// GitHub 仓库中的一个模板文件
+#[derive(Debug, PartialEq, Eq)]
+pub struct GithubSource {
+    pub owner: String,
+    pub repo: String,
+    /// 仓库中的文件路径
+    pub path: String,
+    /// 分支、标签或提交，未指定时为默认分支 (`HEAD`)
+    pub reference: String,
+}
+
+impl GithubSource {
+    /// 解析 `gh:owner/repo#path/to/file@ref`，`@ref` 可以省略
+    pub fn parse(spec: &str) -> Result<GithubSource> {
+        let invalid = || Error::InvalidGithubSource(spec.to_string());
+        let rest = spec.strip_prefix(GITHUB_PREFIX).ok_or_else(invalid)?;
+        let (repository, path) = rest.split_once('#').ok_or_else(invalid)?;
+        let (owner, repo) = repository.split_once('/').ok_or_else(invalid)?;
+        let (path, reference) = match path.rsplit_once('@') {
+            Some((path, reference)) => (path, reference),
+            None => (path, "HEAD"),
+        };
+
+        let valid_segment = |segment: &str| {
+            !segment.is_empty() && segment != "." && segment != ".." && !segment.contains('\\')
+        };
+        if !valid_segment(owner)
+            || !valid_segment(repo)
+            || repo.contains('/')
+            || reference.is_empty()
+            || !path.split('/').all(valid_segment)
+        {
+            return Err(invalid());
+        }
+        Ok(GithubSource {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            path: path.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+
+    /// 文件在 raw.githubusercontent.com 上的下载地址
+    pub fn raw_url(&self) -> String {
+        format!(
+            "https://raw.githubusercontent.com/{}/{}/{}/{}",
+            self.owner, self.repo, self.reference, self.path
+        )
+    }
+
+    /// 设置了 `GITHUB_TOKEN` 时用于访问私有仓库的请求头
+    pub fn auth_headers(&self) -> Vec<(String, String)> {
+        github_auth_headers()
+    }
+}
+
+/// 设置了 `GITHUB_TOKEN` 时访问 GitHub 使用的认证请求头
+pub fn github_auth_headers() -> Vec<(String, String)> {
+    match std::env::var(GITHUB_TOKEN_VAR) {
+        Ok(token) if !token.is_empty() => {
+            vec![("Authorization".to_string(), format!("token {}", token))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// 下载缓存目录，即 new-cli 的[缓存目录](config::cache_dir)
+pub fn cache_dir() -> Result<PathBuf> {
+    config::cache_dir()
+}
+
+/// 下载 `url` 指向的模板到 `cache_dir` 中，返回本地文件路径
+///
+/// 本地文件名取自地址的最后一段（例如 `invoice.md`），因此模板的后缀
+/// 和按后缀进行的处理都与本地模板一致。离线模式下总是使用缓存。
+pub fn fetch(url: &str, cache_dir: &Path, options: &FetchOptions) -> Result<PathBuf> {
+    let path = cache_path(url, cache_dir)?;
+
+    if (options.use_cache || is_offline()) && path.is_file() {
+        log::debug!("using cached copy of {}: {}", url, path.display());
+        let bytes = fs::read(&path).io_context(|| {
+            tr!(
+                "无法读取缓存的模板 {:?}",
+                "failed to read cached template {:?}",
+                path
+            )
+        })?;
+        verify(url, &bytes, options.sha256.as_deref())?;
+        return Ok(path);
+    }
+
+    log::debug!("downloading {}", url);
+    let bytes = download(url, options)?;
+    verify(url, &bytes, options.sha256.as_deref())?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).io_context(|| {
+            tr!(
+                "无法创建缓存目录 {:?}",
+                "failed to create cache directory {:?}",
+                parent
+            )
+        })?;
+    }
+    fs::write(&path, &bytes).io_context(|| {
+        tr!(
+            "无法写入缓存的模板 {:?}",
+            "failed to write cached template {:?}",
+            path
+        )
+    })?;
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::write(parent.join(SOURCE_FILE), url) {
+            log::debug!("failed to record the source of {}: {}", path.display(), e);
+        }
+    }
+    Ok(path)
+}
+
+/// 缓存中的一个文件
+#[derive(Debug, PartialEq, Eq)]
+pub struct CachedFile {
+    pub path: PathBuf,
+    /// 下载地址，从剪贴板或标准输入保存的模板没有地址
+    pub url: Option<String>,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// 列出缓存目录中的所有文件，按路径排序；缓存目录不存在时为空
+pub fn list_cache(cache_dir: &Path) -> Result<Vec<CachedFile>> {
+    let read_dir = |dir: &Path| {
+        fs::read_dir(dir).io_context(|| {
+            tr!(
+                "无法读取缓存目录 {:?}",
+                "failed to read cache directory {:?}",
+                dir
+            )
+        })
+    };
+    if !cache_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in read_dir(cache_dir)?.filter_map(std::result::Result::ok) {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let url = fs::read_to_string(dir.join(SOURCE_FILE)).ok();
+        for file in read_dir(&dir)?.filter_map(std::result::Result::ok) {
+            if file.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = file.metadata() else {
+                continue;
+            };
+            if metadata.is_file() {
+                files.push(CachedFile {
+                    path: file.path(),
+                    url: url.clone(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// 删除缓存目录中的所有内容，返回删除的文件数和总字节数
+pub fn clean_cache(cache_dir: &Path) -> Result<(usize, u64)> {
+    let files = list_cache(cache_dir)?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(cache_dir).io_context(|| {
+            tr!(
+                "无法删除缓存目录 {:?}",
+                "failed to remove cache directory {:?}",
+                cache_dir
+            )
+        })?;
+    }
+    Ok((files.len(), files.iter().map(|file| file.size).sum()))
+}
+
+/// 远程文件的文件名：地址路径的最后一段，不含查询参数和片段
+pub fn file_name_from_url(url: &str) -> Option<&str> {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = without_scheme.split(['?', '#']).next()?;
+    let (_, path) = path.split_once('/')?;
+    path.rsplit('/').next().filter(|name| !name.is_empty())
+}
+
+/// `url` 在缓存中的位置：`<cache_dir>/<地址哈希>/<文件名>`
+fn cache_path(url: &str, cache_dir: &Path) -> Result<PathBuf> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(Error::InvalidUrl(url.to_string()));
+    }
+    let file_name = file_name_from_url(url).ok_or_else(|| Error::InvalidUrl(url.to_string()))?;
+    validate_template_name(file_name).map_err(|_| Error::InvalidUrl(url.to_string()))?;
+
+    let digest = hex(&Sha256::digest(url.as_bytes()));
+    Ok(cache_dir.join(&digest[..16]).join(file_name))
+}
+
+/// 下载 `url` 的内容，遵守 `options` 中的超时、大小限制和请求头，不使用缓存
+pub fn download(url: &str, options: &FetchOptions) -> Result<Vec<u8>> {
+    if is_offline() {
+        return Err(Error::Offline(url.to_string()));
+    }
+    let agent = ureq::AgentBuilder::new().timeout(options.timeout).build();
+    let mut request = agent.get(url);
+    for (name, value) in &options.headers {
+        request = request.set(name, value);
+    }
+    let response = request.call().map_err(|e| Error::Download {
+        url: url.to_string(),
+        message: match e {
+            ureq::Error::Status(code, _) => format!("HTTP {}", code),
+            ureq::Error::Transport(transport) => match transport.message() {
+                Some(message) => format!("{}: {}", transport.kind(), message),
+                None => transport.kind().to_string(),
+            },
+        },
+    })?;
+
+    // 多读一个字节，用来判断是否超过了大小限制
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(options.max_size + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| Error::Download {
+            url: url.to_string(),
+            message: e.to_string(),
+        })?;
+    if bytes.len() as u64 > options.max_size {
+        return Err(Error::DownloadTooLarge {
+            url: url.to_string(),
+            limit: options.max_size,
+        });
+    }
+    Ok(bytes)
+}
+
+/// 校验 `bytes` 的 SHA-256 是否为 `expected`，`expected` 为 `None` 时不校验
+pub(crate) fn verify(url: &str, bytes: &[u8], expected: Option<&str>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = hex(&Sha256::digest(bytes));
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(Error::ChecksumMismatch {
+            url: url.to_string(),
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// `bytes` 的 SHA-256（小写十六进制）
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_name_from_url() {
+        assert_eq!(
+            file_name_from_url("https://example.com/t/invoice.md"),
+            Some("invoice.md")
+        );
+        assert_eq!(
+            file_name_from_url("https://example.com/t/invoice.md?raw=1#top"),
+            Some("invoice.md")
+        );
+        assert_eq!(file_name_from_url("https://example.com/"), None);
+        assert_eq!(file_name_from_url("https://example.com"), None);
+    }
+
+    #[test]
+    fn test_cache_path() {
+        let dir = Path::new("/cache");
+        let path = cache_path("https://example.com/t/invoice.md", dir).unwrap();
+        assert_eq!(path.file_name().unwrap(), "invoice.md");
+        assert_ne!(
+            path,
+            cache_path("https://example.org/t/invoice.md", dir).unwrap()
+        );
+        assert!(matches!(
+            cache_path("ftp://example.com/a.md", dir),
+            Err(Error::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            cache_path("https://example.com/..", dir),
+            Err(Error::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_github_source() {
+        let source = GithubSource::parse("gh:acme/templates#react/component.tsx@main").unwrap();
+        assert_eq!(
+            source.raw_url(),
+            "https://raw.githubusercontent.com/acme/templates/main/react/component.tsx"
+        );
+
+        let source = GithubSource::parse("gh:acme/templates#README.md").unwrap();
+        assert_eq!(source.reference, "HEAD");
+        assert_eq!(source.path, "README.md");
+
+        for spec in [
+            "acme/templates#a.md",
+            "gh:acme#a.md",
+            "gh:acme/templates",
+            "gh:acme/templates#",
+            "gh:acme/templates#../a.md",
+            "gh:acme/templates#a.md@",
+            "gh:acme/x/y#a.md",
+        ] {
+            assert!(
+                matches!(
+                    GithubSource::parse(spec),
+                    Err(Error::InvalidGithubSource(_))
+                ),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        // "hello" 的 SHA-256
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify("u", b"hello", None).is_ok());
+        assert!(verify("u", b"hello", Some(digest)).is_ok());
+        assert!(verify("u", b"hello", Some(&digest.to_uppercase())).is_ok());
+        assert!(matches!(
+            verify("u", b"hello!", Some(digest)),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_and_clean_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = dir.path().join("cache");
+        assert!(list_cache(&cache).unwrap().is_empty());
+
+        let url = "https://example.com/t/invoice.md";
+        let cached = cache_path(url, &cache).unwrap();
+        fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        fs::write(&cached, "hello").unwrap();
+        fs::write(cached.parent().unwrap().join(SOURCE_FILE), url).unwrap();
+        fs::create_dir_all(cache.join("stdin")).unwrap();
+        fs::write(cache.join("stdin/template"), "hi").unwrap();
+
+        let files = list_cache(&cache).unwrap();
+        assert_eq!(files.len(), 2);
+        let invoice = files.iter().find(|file| file.path == cached).unwrap();
+        assert_eq!(invoice.url.as_deref(), Some(url));
+        assert_eq!(invoice.size, 5);
+
+        assert_eq!(clean_cache(&cache).unwrap(), (2, 7));
+        assert!(!cache.exists());
+        assert_eq!(clean_cache(&cache).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_fetch_uses_cache() {
+        let dir = TempDir::new().unwrap();
+        // 该地址无法访问，只有使用缓存时才能成功
+        let url = "http://127.0.0.1:9/t/cached.md";
+        let cached = cache_path(url, dir.path()).unwrap();
+        fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        fs::write(&cached, "hello").unwrap();
+
+        let options = FetchOptions {
+            use_cache: true,
+            ..FetchOptions::default()
+        };
+        assert_eq!(fetch(url, dir.path(), &options).unwrap(), cached);
+
+        let options = FetchOptions {
+            timeout: Duration::from_secs(2),
+            ..FetchOptions::default()
+        };
+        assert!(matches!(
+            fetch(url, dir.path(), &options),
+            Err(Error::Download { .. })
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+//! 模板变量替换
+//!
+//! 模板中的 `{{name}}` 会被替换为对应变量的值，占位符两侧允许有空白
+//! (`{{ name }}`)。未知变量默认替换为空字符串，严格模式下则报错。
+//! 变量名后可以接过滤器转换大小写，例如 `{{name | pascal}}`，见 [`filters`]。
+//! 需要输出字面量 `{{` 时写作 `\{{`。`{{cursor}}` 标记打开文件后的光标位置，
+//! 渲染为空，见 [`cursor`]。
+//!
+//! 模板还可以使用 `{% if %}`/`{% for %}` 块输出可选内容或遍历列表变量，见 [`blocks`]。
+//!
+//! 二进制模板（图片、表格等）不做变量替换，按字节原样输出。
+
+use std::collections::HashMap;
+
+use crate::blocks::{self, Node};
+use crate::cursor;
+use crate::error::{Error, Result};
+use crate::filters;
+use crate::tr;
+use crate::variables;
+
+/// 模板渲染时可用的变量
+pub type Variables = HashMap<String, String>;
+
+/// 解析 `-D key=value` 形式的变量定义
+pub fn parse_define(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s.split_once('=').ok_or_else(|| {
+        tr!(
+            "变量定义 '{}' 缺少 '='，应为 key=value 形式",
+            "variable definition '{}' is missing '=', expected key=value",
+            s
+        )
+    })?;
+    let key = key.trim();
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err(tr!("变量名 '{}' 无效", "invalid variable name '{}'", key));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// 判断模板内容是否为二进制：前 8000 字节中含有 NUL，或不是合法的 UTF-8
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0) || std::str::from_utf8(bytes).is_err()
+}
+
+/// 渲染以字节形式读取的模板，二进制内容原样返回
+pub fn render_bytes(bytes: Vec<u8>, vars: &Variables, strict: bool) -> Result<Vec<u8>> {
+    if is_binary(&bytes) {
+        return Ok(bytes);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(render(&text, vars, strict)?.into_bytes()),
+        Err(err) => Ok(err.into_bytes()),
+    }
+}
+
+/// 渲染模板内容
+///
+/// `strict` 为 true 时，模板中出现任何未定义的变量都会返回错误；
+/// 否则未定义的变量替换为空字符串。条件和循环块见 [`blocks`]。
+pub fn render(template: &str, vars: &Variables, strict: bool) -> Result<String> {
+    let nodes = blocks::parse(template)?;
+    let mut output = String::with_capacity(template.len());
+    let mut unknown: Vec<String> = Vec::new();
+    render_nodes(&nodes, vars, &mut output, &mut unknown)?;
+
+    if strict && !unknown.is_empty() {
+        return Err(Error::UndefinedVariables(unknown));
+    }
+    Ok(output)
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    vars: &Variables,
+    output: &mut String,
+    unknown: &mut Vec<String>,
+) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Placeholder(inner) => match substitute(inner, vars)? {
+                Some(value) => output.push_str(&value),
+                None => {
+                    let key = inner.split('|').next().unwrap_or_default().trim();
+                    if !unknown.iter().any(|k| k == key) {
+                        unknown.push(key.to_string());
+                    }
+                }
+            },
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                let lookup = |name: &str| vars.get(name).map(String::as_str);
+                let body = branches
+                    .iter()
+                    .find(|(condition, _)| condition.holds(lookup))
+                    .map_or(otherwise, |(_, body)| body);
+                render_nodes(body, vars, output, unknown)?;
+            }
+            Node::For { item, list, body } => {
+                let items = vars
+                    .get(*list)
+                    .map(|v| blocks::split_list(v))
+                    .unwrap_or_default();
+                let mut scope = vars.clone();
+                for (index, value) in items.iter().enumerate() {
+                    scope.insert(item.to_string(), value.to_string());
+                    scope.insert("loop.index".to_string(), (index + 1).to_string());
+                    scope.insert("loop.first".to_string(), (index == 0).to_string());
+                    scope.insert(
+                        "loop.last".to_string(),
+                        (index + 1 == items.len()).to_string(),
+                    );
+                    render_nodes(body, &scope, output, unknown)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 求出占位符 `{{ ... }}` 的值；变量未定义时返回 None
+///
+/// `{{name | snake | upper}}`：变量名之后是依次应用的过滤器。
+fn substitute(inner: &str, vars: &Variables) -> Result<Option<String>> {
+    let mut parts = inner.split('|').map(str::trim);
+    let key = parts.next().unwrap_or_default();
+    let filters: Vec<&str> = parts.collect();
+    for filter in &filters {
+        filters::check(filter)?;
+    }
+    let value = match vars.get(key) {
+        Some(value) => value.clone(),
+        None => match variables::resolve_formatted(key)? {
+            Some(value) => value,
+            None if key == cursor::PLACEHOLDER => String::new(),
+            None => return Ok(None),
+        },
+    };
+    filters
+        .iter()
+        .try_fold(value, |value, filter| filters::apply(filter, &value))
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{set_lang, Lang};
+
+    fn vars(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_variables() {
+        let vars = vars(&[("filename", "index"), ("year", "2025")]);
+        assert_eq!(
+            render("<title>{{filename}}</title> © {{ year }}", &vars, false).unwrap(),
+            "<title>index</title> © 2025"
+        );
+    }
+
+    #[test]
+    fn test_render_unknown_placeholders_become_empty() {
+        let vars = vars(&[("filename", "index")]);
+        assert_eq!(
+            render("[{{ message }}] {{filename}}", &vars, false).unwrap(),
+            "[] index"
+        );
+    }
+
+    #[test]
+    fn test_render_strict_rejects_unknown_placeholders() {
+        set_lang(Lang::Zh);
+        let vars = vars(&[("filename", "index")]);
+        let err = render("{{title}} {{owner}} {{title}}", &vars, true).unwrap_err();
+        assert_eq!(err.to_string(), "模板中存在未定义的变量: title, owner");
+    }
+
+    #[test]
+    fn test_render_formatted_date() {
+        let vars = vars(&[]);
+        let rendered = render("{{ date:%Y }}", &vars, true).unwrap();
+        assert_eq!(rendered.len(), 4);
+        assert!(render("{{date:%Q}}", &vars, false).is_err());
+    }
+
+    #[test]
+    fn test_render_filters() {
+        let vars = vars(&[("name", "my widget")]);
+        assert_eq!(
+            render(
+                "class {{name | pascal}} {} // {{ name|kebab }}.ts {{name | snake | upper}}",
+                &vars,
+                true
+            )
+            .unwrap(),
+            "class MyWidget {} // my-widget.ts MY_WIDGET"
+        );
+        assert!(matches!(
+            render("{{name | shout}}", &vars, false),
+            Err(Error::UnknownFilter(_))
+        ));
+
+        // 未定义的变量只报告变量名
+        let err = render("{{title | pascal}}", &vars, true).unwrap_err();
+        assert!(matches!(err, Error::UndefinedVariables(names) if names == ["title"]));
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let template = "\
+{% if license %}
+// License: {{license}}
+{% endif %}
+struct {{name | pascal}} {
+{% for field in fields %}
+    {{field}}: String,{% if loop.last %} // last{% endif %}
+{% endfor %}
+}
+{% if kind == \"enum\" %}enum{% elif kind %}{{kind}}{% else %}none{% endif %}
+";
+        let rendered = render(
+            template,
+            &vars(&[("name", "user"), ("fields", "id, email")]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "struct User {\n    id: String,\n    email: String, // last\n}\nnone\n"
+        );
+
+        let rendered = render(
+            template,
+            &vars(&[("name", "user"), ("license", "MIT"), ("kind", "enum")]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(rendered, "// License: MIT\nstruct User {\n}\nenum\n");
+
+        // 未进入的分支中的变量不算未定义
+        assert!(render("{% if x %}{{y}}{% endif %}", &vars(&[]), true).is_ok());
+        assert!(matches!(
+            render("{% for x in y %}", &vars(&[]), false),
+            Err(Error::TemplateSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_cursor_is_always_defined() {
+        assert_eq!(render("a{{ cursor }}b", &vars(&[]), true).unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_render_escaped_braces() {
+        let vars = vars(&[("filename", "index")]);
+        assert_eq!(
+            render(r"\{{filename}}", &vars, true).unwrap(),
+            "{{filename}}"
+        );
+    }
+
+    #[test]
+    fn test_render_unclosed_placeholder() {
+        let vars = vars(&[("filename", "index")]);
+        assert_eq!(
+            render("{{filename}} {{oops", &vars, true).unwrap(),
+            "index {{oops"
+        );
+    }
+
+    #[test]
+    fn test_parse_define() {
+        assert_eq!(
+            parse_define("title=Q3 Review"),
+            Ok(("title".to_string(), "Q3 Review".to_string()))
+        );
+        assert_eq!(
+            parse_define("expr=a=b"),
+            Ok(("expr".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            parse_define("empty="),
+            Ok(("empty".to_string(), String::new()))
+        );
+        assert!(parse_define("novalue").is_err());
+        assert!(parse_define("=value").is_err());
+        assert!(parse_define("two words=x").is_err());
+    }
+
+    #[test]
+    fn test_render_bytes_skips_binary() {
+        let v = vars(&[("name", "x")]);
+        assert_eq!(render_bytes(b"{{name}}".to_vec(), &v, false).unwrap(), b"x");
+
+        let png = b"\x89PNG\r\n\x1a\n\0\0{{name}}".to_vec();
+        assert!(is_binary(&png));
+        assert_eq!(render_bytes(png.clone(), &v, true).unwrap(), png);
+
+        let latin1 = vec![b'c', b'a', b'f', 0xe9];
+        assert!(is_binary(&latin1));
+    }
+}
@@ -0,0 +1,267 @@
+//! 为新建的 Rust 源文件添加 `mod` 声明 (`--add-mod`)
+//!
+//! 在 `src/` 中创建 `foo.rs` 后，在声明它的父模块中加入 `mod foo;`，新模块立即参与编译：
+//!
+//! | 新文件 | 父模块 |
+//! | --- | --- |
+//! | `src/foo.rs` | `src/lib.rs`，没有时为 `src/main.rs` |
+//! | `src/net/foo.rs` | `src/net/mod.rs`，没有时为 `src/net.rs` |
+//! | `src/net/mod.rs` | 与 `src/net.rs` 相同 |
+//!
+//! `src/bin/` 中的文件是独立的程序，`lib.rs`、`main.rs` 本身是 crate 根，都不需要声明。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{IoContext, Result};
+use crate::tr;
+
+/// [`declare`] 的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Declaration {
+    /// 已在父模块中添加声明
+    Added { module: String, parent: PathBuf },
+    /// 父模块中已经声明了该模块
+    Exists { module: String, parent: PathBuf },
+    /// 找不到声明该模块的 `lib.rs`、`main.rs` 或 `mod.rs`
+    NoParent { module: String },
+    /// 文件名不是合法的模块名，例如 `my-util.rs` 或关键字 `type.rs`
+    InvalidName { module: String },
+}
+
+/// 在父模块中声明新建的源文件 `path`；`path` 不是 `src/` 中的模块文件时返回 `None`
+pub fn declare(path: &Path) -> Result<Option<Declaration>> {
+    let Some((module, dir)) = module_of(path) else {
+        return Ok(None);
+    };
+    if !is_identifier(&module) {
+        return Ok(Some(Declaration::InvalidName { module }));
+    }
+    let Some(parent) = parent_candidates(&dir)
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+    else {
+        return Ok(Some(Declaration::NoParent { module }));
+    };
+
+    let content = fs::read_to_string(&parent).io_context(|| {
+        tr!(
+            "无法读取父模块 {:?}",
+            "failed to read parent module {:?}",
+            parent
+        )
+    })?;
+    let Some(updated) = insert_declaration(&content, &module) else {
+        return Ok(Some(Declaration::Exists { module, parent }));
+    };
+    fs::write(&parent, updated).io_context(|| {
+        tr!(
+            "无法写入父模块 {:?}",
+            "failed to write parent module {:?}",
+            parent
+        )
+    })?;
+    Ok(Some(Declaration::Added { module, parent }))
+}
+
+/// 模块名和父模块所在的目录
+fn module_of(path: &Path) -> Option<(String, PathBuf)> {
+    if path.extension()? != "rs" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path.parent()?;
+    let (module, dir) = match stem {
+        "lib" | "main" => return None,
+        "mod" => (dir.file_name()?.to_str()?, dir.parent()?),
+        _ => (stem, dir),
+    };
+    // 必须在 `src` 中，`src/bin` 中的文件各自是独立的 crate
+    let src = dir
+        .ancestors()
+        .find(|dir| dir.file_name() == Some("src".as_ref()))?;
+    if dir.strip_prefix(src).ok()?.starts_with("bin") {
+        return None;
+    }
+    Some((module.to_string(), dir.to_path_buf()))
+}
+
+fn parent_candidates(dir: &Path) -> Vec<PathBuf> {
+    if dir.file_name() == Some("src".as_ref()) {
+        vec![dir.join("lib.rs"), dir.join("main.rs")]
+    } else {
+        vec![dir.join("mod.rs"), dir.with_extension("rs")]
+    }
+}
+
+/// Rust 的严格关键字和保留关键字，不能直接用作模块名
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+/// 在 `content` 中加入 `mod <module>;`，已经声明时返回 `None`
+///
+/// 声明放在最后一个顶层 `mod` 声明之后；没有时放在开头的 `//!` 文档和 `#![...]` 属性之后。
+/// 只看没有缩进的行，内联模块 (`mod tests { ... }`) 中的声明不算。
+fn insert_declaration(content: &str, module: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let top_level = |line: &str| !line.starts_with(char::is_whitespace);
+    if lines
+        .iter()
+        .any(|line| top_level(line) && declared_module(line) == Some(module))
+    {
+        return None;
+    }
+    let eol = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let declaration = format!("mod {};", module);
+
+    let mut out: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    match lines.iter().rposition(|line| {
+        top_level(line) && declared_module(line).is_some() && line.trim_end().ends_with(';')
+    }) {
+        Some(last) => out.insert(last + 1, declaration),
+        None => {
+            let header = lines
+                .iter()
+                .take_while(|line| {
+                    let line = line.trim_start();
+                    line.starts_with("//!") || line.starts_with("#![")
+                })
+                .count();
+            let mut inserted = vec![declaration];
+            if header > 0 {
+                inserted.insert(0, String::new());
+            }
+            if lines
+                .get(header)
+                .is_some_and(|line| !line.trim().is_empty())
+            {
+                inserted.push(String::new());
+            }
+            out.splice(header..header, inserted);
+        }
+    }
+    Some(out.join(eol) + eol)
+}
+
+/// `mod foo;`、`pub(crate) mod foo {` 这类声明中的模块名
+fn declared_module(line: &str) -> Option<&str> {
+    let mut rest = line.trim_start();
+    if let Some(after) = rest.strip_prefix("pub") {
+        rest = match after.strip_prefix('(') {
+            Some(scope) => scope.split_once(')')?.1,
+            None => after,
+        }
+        .trim_start();
+    }
+    let rest = rest.strip_prefix("mod ")?.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_declaration() {
+        assert_eq!(
+            insert_declaration("mod a;\npub mod b;\n\nfn main() {}\n", "c").unwrap(),
+            "mod a;\npub mod b;\nmod c;\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            insert_declaration("//! Crate docs\n#![deny(unsafe_code)]\nuse std::fs;\n", "c")
+                .unwrap(),
+            "//! Crate docs\n#![deny(unsafe_code)]\n\nmod c;\n\nuse std::fs;\n"
+        );
+        assert_eq!(insert_declaration("", "c").unwrap(), "mod c;\n");
+        assert_eq!(
+            insert_declaration("mod a;\r\nfn f() {}\r\n", "c").unwrap(),
+            "mod a;\r\nmod c;\r\nfn f() {}\r\n"
+        );
+        for existing in ["mod c;", "pub mod c;", "pub(crate) mod c;", "mod c {}"] {
+            assert_eq!(insert_declaration(existing, "c"), None, "{existing}");
+        }
+        assert!(insert_declaration("mod cc;\n", "c").is_some());
+        // 内联模块中的声明既不算已声明，也不作为插入位置
+        assert_eq!(
+            insert_declaration("mod a;\nmod inner {\n    mod c;\n}\n", "c").unwrap(),
+            "mod a;\nmod c;\nmod inner {\n    mod c;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_declare() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("net")).unwrap();
+        fs::create_dir_all(src.join("bin")).unwrap();
+        fs::write(src.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(src.join("lib.rs"), "pub mod net;\n").unwrap();
+        fs::write(src.join("net.rs"), "").unwrap();
+
+        assert_eq!(
+            declare(&src.join("util.rs")).unwrap(),
+            Some(Declaration::Added {
+                module: "util".to_string(),
+                parent: src.join("lib.rs"),
+            })
+        );
+        assert_eq!(
+            fs::read_to_string(src.join("lib.rs")).unwrap(),
+            "pub mod net;\nmod util;\n"
+        );
+        assert!(matches!(
+            declare(&src.join("util.rs")).unwrap(),
+            Some(Declaration::Exists { .. })
+        ));
+        assert!(matches!(
+            declare(&src.join("net/mod.rs")).unwrap(),
+            Some(Declaration::Exists { .. })
+        ));
+        assert_eq!(
+            declare(&src.join("net/tcp.rs")).unwrap(),
+            Some(Declaration::Added {
+                module: "tcp".to_string(),
+                parent: src.join("net.rs"),
+            })
+        );
+        assert!(matches!(
+            declare(&src.join("db/pool.rs")).unwrap(),
+            Some(Declaration::NoParent { .. })
+        ));
+        for invalid in ["my-util.rs", "type.rs", "net/self.rs"] {
+            assert!(
+                matches!(
+                    declare(&src.join(invalid)).unwrap(),
+                    Some(Declaration::InvalidName { .. })
+                ),
+                "{invalid}"
+            );
+        }
+        for ignored in ["bin/tool.rs", "main.rs", "notes.md"] {
+            assert_eq!(declare(&src.join(ignored)).unwrap(), None, "{ignored}");
+        }
+        assert_eq!(declare(&dir.path().join("build.rs")).unwrap(), None);
+    }
+}
@@ -0,0 +1,693 @@
+//! 模板包的签名校验和受信任的公钥
+//!
+//! 支持两种分离签名：
+//!
+//! - minisign：`<模板包>.minisig`，由内置实现校验，公钥保存为
+//!   `<配置目录>/trusted-keys/<密钥 ID>.pub`
+//! - GPG：`<模板包>.asc` 或 `<模板包>.sig`，通过系统的 `gpg` 命令校验，公钥导入到
+//!   `<配置目录>/trusted-keys/gpg.kbx`，不影响用户自己的 GPG 密钥环
+//!
+//! 签名必须由受信任的公钥之一签署才算有效；公钥通过 `new-cli trust add` 添加。
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::config::config_dir;
+use crate::error::{Error, IoContext, Result};
+use crate::tr;
+
+/// minisign 签名文件的后缀
+pub const MINISIGN_SUFFIX: &str = ".minisig";
+
+/// GPG 分离签名文件的后缀，依次尝试
+pub const GPG_SUFFIXES: &[&str] = &[".asc", ".sig"];
+
+/// GPG 密钥环在受信任公钥目录中的文件名
+const GPG_KEYRING: &str = "gpg.kbx";
+
+/// 受信任公钥目录 `<配置目录>/trusted-keys`
+pub fn trust_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join("trusted-keys"))
+}
+
+/// 一个分离签名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signature {
+    /// minisign 签名文件的内容
+    Minisign(String),
+    /// GPG 签名（ASCII 或二进制格式）
+    Gpg(Vec<u8>),
+}
+
+impl Signature {
+    /// 按签名文件名的后缀确定签名类型
+    pub fn from_file_name(name: &str, bytes: Vec<u8>) -> Result<Signature> {
+        if name.ends_with(MINISIGN_SUFFIX) {
+            let text = String::from_utf8(bytes).map_err(|_| {
+                Error::Signature(tr!(
+                    "{} 不是 minisign 签名文件",
+                    "{} is not a minisign signature file",
+                    name
+                ))
+            })?;
+            Ok(Signature::Minisign(text))
+        } else {
+            Ok(Signature::Gpg(bytes))
+        }
+    }
+}
+
+/// 一个受信任的公钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustedKey {
+    /// minisign 公钥，`id` 为十六进制的密钥 ID
+    Minisign { id: String },
+    /// GPG 公钥
+    Gpg { fingerprint: String, user: String },
+}
+
+impl TrustedKey {
+    /// 用于 `trust remove` 的标识
+    pub fn id(&self) -> &str {
+        match self {
+            TrustedKey::Minisign { id } => id,
+            TrustedKey::Gpg { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+impl std::fmt::Display for TrustedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrustedKey::Minisign { id } => write!(f, "minisign {}", id),
+            TrustedKey::Gpg { fingerprint, user } => write!(f, "gpg {} {}", fingerprint, user),
+        }
+    }
+}
+
+/// minisign 公钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinisignKey {
+    id: [u8; 8],
+    key: [u8; 32],
+}
+
+impl MinisignKey {
+    /// 解析 minisign 公钥：`.pub` 文件的内容，或其中 base64 编码的那一行
+    pub fn parse(text: &str) -> Result<MinisignKey> {
+        let invalid =
+            || Error::Signature(tr!("无效的 minisign 公钥", "invalid minisign public key"));
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or_else(invalid)?;
+        let bytes = STANDARD.decode(line).map_err(|_| invalid())?;
+        if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+            return Err(invalid());
+        }
+        let mut key = MinisignKey {
+            id: [0; 8],
+            key: [0; 32],
+        };
+        key.id.copy_from_slice(&bytes[2..10]);
+        key.key.copy_from_slice(&bytes[10..]);
+        Ok(key)
+    }
+
+    /// 与 minisign 显示方式相同的密钥 ID（大写十六进制）
+    pub fn id(&self) -> String {
+        key_id(&self.id)
+    }
+
+    /// `.pub` 文件的内容
+    pub fn to_file(&self) -> String {
+        let mut bytes = b"Ed".to_vec();
+        bytes.extend_from_slice(&self.id);
+        bytes.extend_from_slice(&self.key);
+        format!(
+            "untrusted comment: minisign public key {}\n{}\n",
+            self.id(),
+            STANDARD.encode(bytes)
+        )
+    }
+
+    /// 校验 `data` 的 minisign 签名，包括签名中的可信注释
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<()> {
+        let malformed =
+            || Error::Signature(tr!("无效的 minisign 签名", "malformed minisign signature"));
+
+        let mut lines = signature.lines().map(str::trim).filter(|l| !l.is_empty());
+        let mut line = lines.next().ok_or_else(malformed)?;
+        if line.starts_with("untrusted comment:") {
+            line = lines.next().ok_or_else(malformed)?;
+        }
+        let sig = STANDARD.decode(line).map_err(|_| malformed())?;
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix("trusted comment: "))
+            .ok_or_else(malformed)?;
+        let global = lines
+            .next()
+            .and_then(|line| STANDARD.decode(line).ok())
+            .ok_or_else(malformed)?;
+        if sig.len() != 74 || global.len() != 64 {
+            return Err(malformed());
+        }
+
+        if sig[2..10] != self.id {
+            return Err(Error::Signature(tr!(
+                "签名使用的密钥 {} 与公钥 {} 不符",
+                "the signature was made with key {}, not {}",
+                key_id(&sig[2..10]),
+                self.id()
+            )));
+        }
+        // "ED" 签名的是内容的 BLAKE2b-512 摘要，旧版的 "Ed" 直接签名内容
+        let message = match &sig[..2] {
+            b"ED" => blake2b_512(data).to_vec(),
+            b"Ed" => data.to_vec(),
+            _ => return Err(malformed()),
+        };
+        let key = UnparsedPublicKey::new(&ED25519, &self.key);
+        key.verify(&message, &sig[10..]).map_err(|_| {
+            Error::Signature(tr!(
+                "签名与内容不符",
+                "the signature does not match the content"
+            ))
+        })?;
+
+        let mut signed_comment = sig[10..].to_vec();
+        signed_comment.extend_from_slice(trusted_comment.as_bytes());
+        key.verify(&signed_comment, &global).map_err(|_| {
+            Error::Signature(tr!(
+                "签名中的可信注释已被篡改",
+                "the trusted comment of the signature was tampered with"
+            ))
+        })
+    }
+}
+
+fn key_id(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .rev()
+        .map(|byte| format!("{:02X}", byte))
+        .collect()
+}
+
+/// 保存在目录中的受信任公钥
+pub struct TrustStore {
+    dir: PathBuf,
+}
+
+impl TrustStore {
+    /// 使用 `dir` 中的公钥，目录不存在时没有任何受信任的公钥
+    pub fn new(dir: impl Into<PathBuf>) -> TrustStore {
+        TrustStore { dir: dir.into() }
+    }
+
+    /// 添加公钥：minisign 公钥（base64 或 `.pub` 文件），或 GPG 公钥文件
+    pub fn add(&self, key: &str) -> Result<Vec<TrustedKey>> {
+        let path = Path::new(key);
+        let text = if path.is_file() {
+            let bytes = fs::read(path)
+                .io_context(|| tr!("无法读取公钥 {:?}", "failed to read public key {:?}", path))?;
+            String::from_utf8(bytes).ok()
+        } else {
+            Some(key.to_string())
+        };
+
+        fs::create_dir_all(&self.dir).io_context(|| {
+            tr!(
+                "无法创建目录 {:?}",
+                "failed to create directory {:?}",
+                self.dir
+            )
+        })?;
+        if let Some(minisign) = text.as_deref().and_then(|t| MinisignKey::parse(t).ok()) {
+            let id = minisign.id();
+            let file = self.dir.join(format!("{}.pub", id));
+            fs::write(&file, minisign.to_file())
+                .io_context(|| tr!("无法写入公钥 {:?}", "failed to write public key {:?}", file))?;
+            return Ok(vec![TrustedKey::Minisign { id }]);
+        }
+        if !path.is_file() {
+            return Err(Error::Signature(tr!(
+                "{} 既不是 minisign 公钥也不是公钥文件",
+                "{} is neither a minisign public key nor a key file",
+                key
+            )));
+        }
+
+        let before = self.gpg_keys()?;
+        let output = self.gpg(&["--import".as_ref(), path.as_os_str()])?;
+        if !output.status.success() {
+            return Err(Error::Signature(tr!(
+                "gpg 无法导入公钥 {:?}: {}",
+                "gpg could not import key {:?}: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(self
+            .gpg_keys()?
+            .into_iter()
+            .filter(|key| !before.contains(key))
+            .collect())
+    }
+
+    /// 所有受信任的公钥
+    pub fn list(&self) -> Result<Vec<TrustedKey>> {
+        let mut list: Vec<TrustedKey> = self
+            .minisign_keys()?
+            .into_iter()
+            .map(|key| TrustedKey::Minisign { id: key.id() })
+            .collect();
+        list.sort_by(|a, b| a.id().cmp(b.id()));
+        list.extend(self.gpg_keys()?);
+        Ok(list)
+    }
+
+    /// 删除标识为 `id` 的公钥（不区分大小写），没有该公钥时返回 false
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let id = id.to_uppercase();
+        let file = self.dir.join(format!("{}.pub", id));
+        if file.is_file() {
+            fs::remove_file(&file).io_context(|| {
+                tr!(
+                    "无法删除公钥 {:?}",
+                    "failed to remove public key {:?}",
+                    file
+                )
+            })?;
+            return Ok(true);
+        }
+        if !self.gpg_keys()?.iter().any(|key| key.id() == id) {
+            return Ok(false);
+        }
+        let output = self.gpg(&["--yes".as_ref(), "--delete-keys".as_ref(), id.as_ref()])?;
+        Ok(output.status.success())
+    }
+
+    /// 校验 `data` 的签名，返回签署它的受信任公钥
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> Result<TrustedKey> {
+        match signature {
+            Signature::Minisign(text) => {
+                let keys = self.minisign_keys()?;
+                if keys.is_empty() {
+                    return Err(Error::Signature(tr!(
+                        "没有受信任的 minisign 公钥，请先使用 new-cli trust add 添加",
+                        "no trusted minisign keys, add one with new-cli trust add"
+                    )));
+                }
+                let mut last_error = None;
+                for key in keys {
+                    match key.verify(data, text) {
+                        Ok(()) => return Ok(TrustedKey::Minisign { id: key.id() }),
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                Err(last_error.expect("at least one key"))
+            }
+            Signature::Gpg(bytes) => self.verify_gpg(data, bytes),
+        }
+    }
+
+    fn minisign_keys(&self) -> Result<Vec<MinisignKey>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let entries = fs::read_dir(&self.dir).io_context(|| {
+            tr!(
+                "无法读取目录 {:?}",
+                "failed to read directory {:?}",
+                self.dir
+            )
+        })?;
+        let mut keys = Vec::new();
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().is_some_and(|ext| ext == "pub") {
+                match fs::read_to_string(&path).map(|text| MinisignKey::parse(&text)) {
+                    Ok(Ok(key)) => keys.push(key),
+                    _ => log::warn!("ignoring invalid public key {}", path.display()),
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    fn gpg_keys(&self) -> Result<Vec<TrustedKey>> {
+        if !self.dir.join(GPG_KEYRING).is_file() {
+            return Ok(Vec::new());
+        }
+        let output = self.gpg(&["--with-colons".as_ref(), "--list-keys".as_ref()])?;
+        Ok(parse_gpg_keys(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn verify_gpg(&self, data: &[u8], signature: &[u8]) -> Result<TrustedKey> {
+        let keys = self.gpg_keys()?;
+        if keys.is_empty() {
+            return Err(Error::Signature(tr!(
+                "没有受信任的 GPG 公钥，请先使用 new-cli trust add 添加",
+                "no trusted GPG keys, add one with new-cli trust add"
+            )));
+        }
+
+        // gpg 只能从文件读取分离签名和被签名的内容
+        let work = std::env::temp_dir().join(format!("new-cli-verify-{}", uuid::Uuid::new_v4()));
+        let context = || {
+            tr!(
+                "无法写入临时文件 {:?}",
+                "failed to write temporary file {:?}",
+                work
+            )
+        };
+        fs::create_dir_all(&work).io_context(context)?;
+        let (sig_path, data_path) = (work.join("signature"), work.join("data"));
+        let result = fs::write(&sig_path, signature)
+            .and_then(|_| fs::write(&data_path, data))
+            .io_context(context)
+            .and_then(|_| {
+                self.gpg(&[
+                    "--trust-model".as_ref(),
+                    "always".as_ref(),
+                    "--status-fd".as_ref(),
+                    "1".as_ref(),
+                    "--verify".as_ref(),
+                    sig_path.as_os_str(),
+                    data_path.as_os_str(),
+                ])
+            });
+        let _ = fs::remove_dir_all(&work);
+        let output = result?;
+
+        let status = String::from_utf8_lossy(&output.stdout);
+        let signer = status.lines().find_map(|line| {
+            let rest = line.strip_prefix("[GNUPG:] VALIDSIG ")?;
+            let mut fields = rest.split_whitespace();
+            let signing = fields.next()?;
+            // 最后一个字段是主密钥的指纹（签名可能来自子密钥）
+            let primary = fields.nth(8).unwrap_or(signing);
+            keys.iter()
+                .find(|key| key.id() == primary || key.id() == signing)
+                .cloned()
+        });
+        match signer {
+            Some(key) if output.status.success() => Ok(key),
+            _ => Err(Error::Signature(tr!(
+                "GPG 签名无效或不是由受信任的公钥签署: {}",
+                "the GPG signature is invalid or not made by a trusted key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))),
+        }
+    }
+
+    /// 使用独立密钥环执行 gpg
+    fn gpg(&self, args: &[&std::ffi::OsStr]) -> Result<Output> {
+        Command::new("gpg")
+            .args(["--batch", "--no-default-keyring", "--keyring"])
+            .arg(self.dir.join(GPG_KEYRING))
+            .args(args)
+            .output()
+            .io_context(|| {
+                tr!(
+                    "无法执行 gpg，请确认已安装 GnuPG 并在 PATH 中",
+                    "failed to run gpg, make sure GnuPG is installed and on PATH"
+                )
+            })
+    }
+}
+
+/// 解析 `gpg --with-colons --list-keys` 的输出
+fn parse_gpg_keys(output: &str) -> Vec<TrustedKey> {
+    let mut keys = Vec::new();
+    // 每个 pub 记录之后的第一条 fpr 是主密钥的指纹，第一条 uid 是用户
+    let mut current: Option<(Option<String>, Option<String>)> = None;
+    let mut finish = |current: Option<(Option<String>, Option<String>)>| {
+        if let Some((Some(fingerprint), user)) = current {
+            keys.push(TrustedKey::Gpg {
+                fingerprint,
+                user: user.unwrap_or_default(),
+            });
+        }
+    };
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first() {
+            Some(&"pub") => finish(current.replace((None, None))),
+            Some(&"fpr") => {
+                if let Some((fingerprint @ None, _)) = current.as_mut() {
+                    *fingerprint = fields.get(9).map(|f| f.to_string());
+                }
+            }
+            Some(&"uid") => {
+                if let Some((_, user @ None)) = current.as_mut() {
+                    *user = fields.get(9).map(|u| u.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    finish(current);
+    keys
+}
+
+/// BLAKE2b-512 摘要 (RFC 7693)，minisign 的预哈希签名使用
+fn blake2b_512(data: &[u8]) -> [u8; 64] {
+    const IV: [u64; 8] = [
+        0x6a09e667f3bcc908,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+    const SIGMA: [[usize; 16]; 10] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+        [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+        [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+        [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+        [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+        [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+        [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+        [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+        [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    ];
+
+    fn compress(h: &mut [u64; 8], block: &[u8; 128], counter: u128, last: bool) {
+        let mut m = [0u64; 16];
+        for (word, bytes) in m.iter_mut().zip(block.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(h);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= counter as u64;
+        v[13] ^= (counter >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+        let mut g = |a: usize, b: usize, c: usize, d: usize, x: u64, y: u64| {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        };
+        for round in 0..12 {
+            let s = &SIGMA[round % 10];
+            g(0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+        for i in 0..8 {
+            h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+
+    let mut h = IV;
+    // 不使用密钥，输出 64 字节
+    h[0] ^= 0x0101_0040;
+    let mut counter = 0u128;
+    let mut chunks = data.chunks(128).peekable();
+    let mut block = [0u8; 128];
+    if chunks.peek().is_none() {
+        compress(&mut h, &block, 0, true);
+    }
+    while let Some(chunk) = chunks.next() {
+        block = [0; 128];
+        block[..chunk.len()].copy_from_slice(chunk);
+        counter += chunk.len() as u128;
+        compress(&mut h, &block, counter, chunks.peek().is_none());
+    }
+
+    let mut out = [0u8; 64];
+    for (bytes, word) in out.chunks_exact_mut(8).zip(h) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use tempfile::TempDir;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// 生成密钥对，返回公钥和签名函数（与 `minisign -S` 的输出格式相同）
+    fn keypair() -> (MinisignKey, impl Fn(&[u8]) -> String) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let mut key = MinisignKey {
+            id: [1, 2, 3, 4, 5, 6, 7, 8],
+            key: [0; 32],
+        };
+        key.key.copy_from_slice(pair.public_key().as_ref());
+        let id = key.id;
+        let sign = move |data: &[u8]| {
+            let mut sig = b"ED".to_vec();
+            sig.extend_from_slice(&id);
+            sig.extend_from_slice(pair.sign(&blake2b_512(data)).as_ref());
+            let comment = "timestamp:1700000000\tfile:pack.tar.gz";
+            let mut global = sig[10..].to_vec();
+            global.extend_from_slice(comment.as_bytes());
+            format!(
+                "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {}\n{}\n",
+                STANDARD.encode(&sig),
+                comment,
+                STANDARD.encode(pair.sign(&global))
+            )
+        };
+        (key, sign)
+    }
+
+    #[test]
+    fn test_blake2b_512() {
+        assert_eq!(
+            hex(&blake2b_512(b"")),
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+        );
+        assert_eq!(
+            hex(&blake2b_512(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        // BLAKE2 官方测试向量（未加密钥，输入为 00 01 02 …），覆盖恰好填满块和跨块的情况
+        for (len, expected) in [
+            (
+                128,
+                "2319e3789c47e2daa5fe807f61bec2a1a6537fa03f19ff32e87eecbfd64b7e0e\
+                 8ccff439ac333b040f19b0c4ddd11a61e24ac1fe0f10a039806c5dcc0da3d115",
+            ),
+            (
+                129,
+                "f59711d44a031d5f97a9413c065d1e614c417ede998590325f49bad2fd444d3e\
+                 4418be19aec4e11449ac1a57207898bc57d76a1bcf3566292c20c683a5c4648f",
+            ),
+            (
+                255,
+                "5b21c5fd8868367612474fa2e70e9cfa2201ffeee8fafab5797ad58fefa17c9b\
+                 5b107da4a3db6320baaf2c8617d5a51df914ae88da3867c2d41f0cc14fa67928",
+            ),
+            (
+                256,
+                "1ecc896f34d3f9cac484c73f75f6a5fb58ee6784be41b35f46067b9c65c63a67\
+                 94d3d744112c653f73dd7deb6666204c5a9bfa5b46081fc10fdbe7884fa5cbf8",
+            ),
+        ] {
+            let input: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            assert_eq!(hex(&blake2b_512(&input)), expected, "{} 字节", len);
+        }
+    }
+
+    #[test]
+    fn test_minisign_key_round_trip() {
+        let (key, _) = keypair();
+        assert_eq!(key.id(), "0807060504030201");
+        assert_eq!(MinisignKey::parse(&key.to_file()).unwrap(), key);
+        let line = key.to_file().lines().nth(1).unwrap().to_string();
+        assert_eq!(MinisignKey::parse(&line).unwrap(), key);
+        assert!(MinisignKey::parse("not a key").is_err());
+    }
+
+    #[test]
+    fn test_minisign_verify() {
+        let (key, sign) = keypair();
+        let signature = sign(b"pack");
+        assert!(key.verify(b"pack", &signature).is_ok());
+        assert!(matches!(
+            key.verify(b"tampered", &signature),
+            Err(Error::Signature(_))
+        ));
+        let forged = signature.replace("file:pack.tar.gz", "file:other.tar.gz");
+        assert!(key.verify(b"pack", &forged).is_err());
+
+        let (other, _) = keypair();
+        assert!(other.verify(b"pack", &signature).is_err());
+    }
+
+    #[test]
+    fn test_trust_store() {
+        let dir = TempDir::new().unwrap();
+        let store = TrustStore::new(dir.path().join("keys"));
+        let (key, sign) = keypair();
+        let signature = Signature::Minisign(sign(b"pack"));
+        assert!(store.verify(b"pack", &signature).is_err());
+
+        let added = store.add(&key.to_file()).unwrap();
+        assert_eq!(added, [TrustedKey::Minisign { id: key.id() }]);
+        assert_eq!(store.list().unwrap(), added);
+        assert_eq!(store.verify(b"pack", &signature).unwrap(), added[0]);
+
+        assert!(store.remove(&key.id().to_lowercase()).unwrap());
+        assert!(!store.remove(&key.id()).unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_gpg_keys() {
+        let output = "tru::1:1700000000:0:3:1:5\n\
+            pub:-:255:22:AAAA:1700000000:::-:::scESC::::::ed25519:::0:\n\
+            fpr:::::::::1111AAAA:\n\
+            uid:-::::1700000000::HASH::Acme Release <release@acme.test>::::::::::0:\n\
+            sub:-:255:18:BBBB:1700000000::::::e::::::cv25519::\n\
+            fpr:::::::::2222BBBB:\n\
+            pub:-:255:22:CCCC:1700000000:::-:::scESC::::::ed25519:::0:\n\
+            fpr:::::::::3333CCCC:\n";
+        assert_eq!(
+            parse_gpg_keys(output),
+            [
+                TrustedKey::Gpg {
+                    fingerprint: "1111AAAA".to_string(),
+                    user: "Acme Release <release@acme.test>".to_string()
+                },
+                TrustedKey::Gpg {
+                    fingerprint: "3333CCCC".to_string(),
+                    user: String::new()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,534 @@
+//! 由模板生成编辑器代码片段 (`new-cli template export-vscode`)
+//!
+//! 与 [`vscode`](crate::vscode) 的转换方向相反，让模板目录成为唯一的来源。支持两种格式：
+//!
+//! - VS Code 的 `.code-snippets` JSON：用户变量转换为 `${1:默认值}` 占位符，重复出现时引用同一个
+//!   序号，`upper`/`lower`/`pascal`/`camel` 过滤器转换为 `${1/(.*)/${1:/upcase}/}` 这类变换；
+//!   `{{filename}}`、`{{date}}`、`{{uuid}}` 等内置变量转换为 `$TM_FILENAME_BASE`、`$CURRENT_YEAR` 等
+//! - JetBrains 的 Live Templates XML：用户变量转换为 `$name$`，内置变量和过滤器转换为
+//!   `fileNameWithoutExtension()`、`date("yyyy-MM-dd")`、`camelCase(name)` 等表达式
+//!
+//! `{{cursor}}` 转换为 `$0`/`$END$`，`{{extension}}` 直接写为模板的后缀。代码片段中无法表达条件和
+//! 循环，使用了 `{% if %}`/`{% for %}` 的模板不会被导出。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::blocks::{self, Node};
+use crate::cursor;
+use crate::error::Result;
+use crate::front_matter::FrontMatter;
+use crate::vscode;
+
+/// 模板正文中的元素
+#[derive(Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    Cursor,
+    /// 变量，以及最后一个过滤器
+    Variable {
+        name: String,
+        filter: Option<String>,
+    },
+}
+
+/// 由一个模板生成的代码片段
+#[derive(Debug)]
+pub struct Exported {
+    /// 模板的完整名称，例如 `web/page.html`
+    pub name: String,
+
+    /// 触发前缀：模板名去掉后缀，命名空间用 `-` 连接，例如 `web-page`
+    pub prefix: String,
+
+    pub description: Option<String>,
+
+    extension: String,
+    defaults: BTreeMap<String, String>,
+    pieces: Vec<Piece>,
+}
+
+impl Exported {
+    /// 由模板的 front matter 和正文生成代码片段；模板使用了条件或循环时返回 None
+    pub fn new(
+        name: &str,
+        extension: &str,
+        front_matter: Option<&FrontMatter>,
+        body: &str,
+    ) -> Result<Option<Exported>> {
+        let mut pieces = Vec::new();
+        for node in blocks::parse(body)? {
+            match node {
+                Node::Text(text) => pieces.push(Piece::Text(text)),
+                Node::Placeholder(inner) => {
+                    let mut parts = inner.split('|').map(str::trim);
+                    let key = parts.next().unwrap_or_default();
+                    let filter = parts.next_back().map(String::from);
+                    pieces.push(if key == cursor::PLACEHOLDER {
+                        Piece::Cursor
+                    } else {
+                        Piece::Variable {
+                            name: key.to_string(),
+                            filter,
+                        }
+                    });
+                }
+                Node::If { .. } | Node::For { .. } => return Ok(None),
+            }
+        }
+
+        let stem = name
+            .strip_suffix(extension)
+            .and_then(|stem| stem.strip_suffix('.'))
+            .unwrap_or(name);
+        let defaults = front_matter
+            .map(|front_matter| {
+                front_matter
+                    .variables
+                    .iter()
+                    .filter_map(|(name, spec)| Some((name.clone(), spec.default.clone()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(Exported {
+            name: name.to_string(),
+            prefix: stem.replace('/', "-"),
+            description: front_matter.and_then(|front_matter| front_matter.description.clone()),
+            extension: extension.to_string(),
+            defaults,
+            pieces,
+        }))
+    }
+
+    fn default(&self, name: &str) -> String {
+        self.defaults
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// VS Code 片段正文
+    fn vscode_body(&self) -> String {
+        let mut inputs: Vec<&str> = Vec::new();
+        let mut output = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => output.push_str(&escape_vscode(text)),
+                Piece::Cursor => output.push_str("$0"),
+                Piece::Variable { name, filter } => {
+                    if let Some(text) = self.vscode_builtin(name, filter.as_deref()) {
+                        output.push_str(&text);
+                        continue;
+                    }
+                    let number = match inputs.iter().position(|input| input == name) {
+                        Some(index) => index + 1,
+                        None => {
+                            inputs.push(name);
+                            let default = escape_vscode(&self.default(name)).replace('}', "\\}");
+                            output.push_str(&format!("${{{}:{}}}", inputs.len(), default));
+                            continue;
+                        }
+                    };
+                    match filter.as_deref().and_then(vscode_case) {
+                        Some(case) => {
+                            output.push_str(&format!("${{{}/(.*)/${{1:/{}}}/}}", number, case))
+                        }
+                        None => output.push_str(&format!("${{{}}}", number)),
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    /// 内置变量对应的 VS Code 变量；不是内置变量时返回 None
+    fn vscode_builtin(&self, name: &str, filter: Option<&str>) -> Option<String> {
+        match name {
+            "filename" => Some(match filter.and_then(vscode_case) {
+                Some(case) => format!("${{TM_FILENAME_BASE/(.*)/${{1:/{}}}/}}", case),
+                None => "${TM_FILENAME_BASE}".to_string(),
+            }),
+            "extension" => Some(escape_vscode(&self.extension)),
+            "uuid" => Some("${UUID}".to_string()),
+            "timestamp" => Some("${CURRENT_SECONDS_UNIX}".to_string()),
+            _ => date_format(name).and_then(vscode_date),
+        }
+    }
+
+    /// JetBrains 模板内容和其中的变量
+    ///
+    /// 用户变量第一次出现时总是需要输入的变量，之后带过滤器的引用转换为派生变量。
+    fn jetbrains_template(&self) -> (String, Vec<JetBrainsVariable>) {
+        let mut variables: Vec<JetBrainsVariable> = Vec::new();
+        let mut output = String::new();
+        for piece in &self.pieces {
+            let (name, filter) = match piece {
+                Piece::Text(text) => {
+                    output.push_str(&text.replace('$', "$$"));
+                    continue;
+                }
+                Piece::Cursor => {
+                    output.push_str("$END$");
+                    continue;
+                }
+                Piece::Variable { name, .. } if name == "extension" => {
+                    output.push_str(&self.extension.replace('$', "$$"));
+                    continue;
+                }
+                Piece::Variable { name, filter } => (name, filter.as_deref()),
+            };
+            let base = jetbrains_name(name);
+            let filtered = |expression: &str| {
+                let filter = filter?;
+                Some((
+                    format!("{}_{}", base, filter),
+                    jetbrains_filter(filter, expression)?,
+                ))
+            };
+            let declared = variables.iter().any(|v| v.name == base);
+            let variable = match jetbrains_builtin(name) {
+                Some(expression) => {
+                    let (name, expression) = filtered(&expression).unwrap_or((base, expression));
+                    JetBrainsVariable::derived(name, expression)
+                }
+                None => match filtered(&base) {
+                    Some((name, expression)) if declared => {
+                        JetBrainsVariable::derived(name, expression)
+                    }
+                    _ => JetBrainsVariable {
+                        name: base,
+                        expression: String::new(),
+                        default: format!("\"{}\"", self.default(name).replace('"', "\\\"")),
+                        stop: true,
+                    },
+                },
+            };
+            output.push_str(&format!("${}$", variable.name));
+            if !variables.iter().any(|v| v.name == variable.name) {
+                variables.push(variable);
+            }
+        }
+        // 需要输入的变量排在前面，按出现顺序依次停留
+        variables.sort_by_key(|variable| !variable.stop);
+        (output, variables)
+    }
+}
+
+/// JetBrains 模板中的变量
+#[derive(Debug)]
+struct JetBrainsVariable {
+    name: String,
+    expression: String,
+    default: String,
+    /// 是否停留在该变量上等待输入
+    stop: bool,
+}
+
+impl JetBrainsVariable {
+    /// 由表达式求值、不需要输入的变量
+    fn derived(name: String, expression: String) -> Self {
+        JetBrainsVariable {
+            name,
+            expression,
+            default: String::new(),
+            stop: false,
+        }
+    }
+}
+
+/// 生成 VS Code 的 `.code-snippets` 文件内容
+pub fn to_vscode(snippets: &[Exported]) -> String {
+    #[derive(Serialize)]
+    struct Snippet {
+        prefix: String,
+        body: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        scope: Option<&'static str>,
+    }
+
+    let document: BTreeMap<&str, Snippet> = snippets
+        .iter()
+        .map(|snippet| {
+            let body = snippet.vscode_body();
+            let body = body.strip_suffix('\n').unwrap_or(&body);
+            (
+                snippet.name.as_str(),
+                Snippet {
+                    prefix: snippet.prefix.clone(),
+                    body: body.split('\n').map(String::from).collect(),
+                    description: snippet.description.clone(),
+                    scope: vscode::language(&snippet.extension),
+                },
+            )
+        })
+        .collect();
+    let mut json = serde_json::to_string_pretty(&document).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+/// 生成 JetBrains 的 Live Templates XML（放入 IDE 配置目录的 `templates/` 中）
+///
+/// 所有模板都启用在“其他”上下文中，可以在 IDE 中调整适用的语言。
+pub fn to_jetbrains(snippets: &[Exported], group: &str) -> String {
+    let mut xml = format!("<templateSet group=\"{}\">\n", escape_xml(group));
+    for snippet in snippets {
+        let (value, variables) = snippet.jetbrains_template();
+        xml.push_str(&format!(
+            "  <template name=\"{}\" value=\"{}\" description=\"{}\" toReformat=\"false\" toShortenFQNames=\"true\">\n",
+            escape_xml(&snippet.prefix),
+            escape_xml(&value),
+            escape_xml(snippet.description.as_deref().unwrap_or(&snippet.name)),
+        ));
+        for variable in variables {
+            xml.push_str(&format!(
+                "    <variable name=\"{}\" expression=\"{}\" defaultValue=\"{}\" alwaysStopAt=\"{}\" />\n",
+                escape_xml(&variable.name),
+                escape_xml(&variable.expression),
+                escape_xml(&variable.default),
+                variable.stop
+            ));
+        }
+        xml.push_str(
+            "    <context>\n      <option name=\"OTHER\" value=\"true\" />\n    </context>\n",
+        );
+        xml.push_str("  </template>\n");
+    }
+    xml.push_str("</templateSet>\n");
+    xml
+}
+
+/// 转义 VS Code 片段中的 `$` 和 `\`
+fn escape_vscode(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('$', "\\$")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+        .replace('\t', "&#9;")
+}
+
+/// 过滤器对应的 VS Code 大小写变换
+fn vscode_case(filter: &str) -> Option<&'static str> {
+    Some(match filter {
+        "upper" => "upcase",
+        "lower" => "downcase",
+        "pascal" => "pascalcase",
+        "camel" => "camelcase",
+        _ => return None,
+    })
+}
+
+/// 日期类内置变量的 strftime 格式，例如 `date` -> `%Y-%m-%d`、`date:%Y` -> `%Y`
+fn date_format(name: &str) -> Option<&str> {
+    match name {
+        "date" => Some("%Y-%m-%d"),
+        "time" => Some("%H:%M:%S"),
+        "datetime" => Some("%Y-%m-%d %H:%M:%S"),
+        "year" => Some("%Y"),
+        _ => name.strip_prefix("date:"),
+    }
+}
+
+/// 把 strftime 格式转换为 VS Code 日期变量；含有不支持的格式时返回 None
+fn vscode_date(format: &str) -> Option<String> {
+    convert_date(
+        format,
+        |code| {
+            Some(match code {
+                'Y' => "${CURRENT_YEAR}",
+                'y' => "${CURRENT_YEAR_SHORT}",
+                'm' => "${CURRENT_MONTH}",
+                'B' => "${CURRENT_MONTH_NAME}",
+                'b' => "${CURRENT_MONTH_NAME_SHORT}",
+                'd' => "${CURRENT_DATE}",
+                'A' => "${CURRENT_DAY_NAME}",
+                'a' => "${CURRENT_DAY_NAME_SHORT}",
+                'H' => "${CURRENT_HOUR}",
+                'M' => "${CURRENT_MINUTE}",
+                'S' => "${CURRENT_SECOND}",
+                _ => return None,
+            })
+        },
+        escape_vscode,
+    )
+}
+
+/// 把 strftime 格式转换为 Java 的 `SimpleDateFormat` 格式；含有不支持的格式时返回 None
+fn java_date(format: &str) -> Option<String> {
+    convert_date(
+        format,
+        |code| {
+            Some(match code {
+                'Y' => "yyyy",
+                'y' => "yy",
+                'm' => "MM",
+                'B' => "MMMM",
+                'b' => "MMM",
+                'd' => "dd",
+                'A' => "EEEE",
+                'a' => "EEE",
+                'H' => "HH",
+                'M' => "mm",
+                'S' => "ss",
+                _ => return None,
+            })
+        },
+        |text| {
+            if text.chars().any(|c| c.is_ascii_alphabetic()) {
+                format!("'{}'", text.replace('\'', "''"))
+            } else {
+                text.to_string()
+            }
+        },
+    )
+}
+
+fn convert_date(
+    format: &str,
+    code: impl Fn(char) -> Option<&'static str>,
+    literal: impl Fn(&str) -> String,
+) -> Option<String> {
+    let mut output = String::new();
+    let mut text = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '%' => text.push('%'),
+            c => {
+                output.push_str(&literal(&std::mem::take(&mut text)));
+                output.push_str(code(c)?);
+            }
+        }
+    }
+    output.push_str(&literal(&text));
+    Some(output)
+}
+
+/// 内置变量对应的 JetBrains 表达式
+fn jetbrains_builtin(name: &str) -> Option<String> {
+    match name {
+        "filename" => Some("fileNameWithoutExtension()".to_string()),
+        "uuid" => Some("groovyScript(\"UUID.randomUUID().toString()\")".to_string()),
+        "timestamp" => Some(
+            "groovyScript(\"String.valueOf(System.currentTimeMillis().intdiv(1000))\")".to_string(),
+        ),
+        _ => date_format(name)
+            .and_then(java_date)
+            .map(|format| format!("date(\"{}\")", format.replace('"', "\\\""))),
+    }
+}
+
+/// 过滤器对应的 JetBrains 表达式，`variable` 为被转换的变量名
+fn jetbrains_filter(filter: &str, variable: &str) -> Option<String> {
+    Some(match filter {
+        "camel" => format!("camelCase({})", variable),
+        "pascal" => format!("capitalize(camelCase({}))", variable),
+        "snake" => format!("snakeCase({})", variable),
+        "kebab" => format!("lowercaseAndDash({})", variable),
+        "constant" => format!("capitalizeAndUnderscore({})", variable),
+        "upper" => format!("groovyScript(\"_1.toUpperCase()\", {})", variable),
+        "lower" => format!("groovyScript(\"_1.toLowerCase()\", {})", variable),
+        _ => return None,
+    })
+}
+
+/// JetBrains 变量名只能包含字母、数字和 `_`，例如 `date:%Y` -> `date_Y`
+fn jetbrains_name(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(body: &str) -> Exported {
+        let front_matter = FrontMatter {
+            description: Some("Component".to_string()),
+            variables: [(
+                "name".to_string(),
+                crate::front_matter::VariableSpec {
+                    default: Some("Widget".to_string()),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+        Exported::new("web/component.tsx", "tsx", Some(&front_matter), body)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_vscode() {
+        let snippet = exported(
+            "// {{filename | pascal}}.{{extension}} {{date}}\n\
+             export class {{name}} {\n  {{cursor}}\n}\nexport default {{name | camel}}; // ${{title}}\n",
+        );
+        assert_eq!(snippet.prefix, "web-component");
+        assert_eq!(
+            snippet.vscode_body(),
+            "// ${TM_FILENAME_BASE/(.*)/${1:/pascalcase}/}.tsx ${CURRENT_YEAR}-${CURRENT_MONTH}-${CURRENT_DATE}\n\
+             export class ${1:Widget} {\n  $0\n}\nexport default ${1/(.*)/${1:/camelcase}/}; // \\$${2:title}\n"
+        );
+        let json = to_vscode(&[snippet]);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let snippet = &value["web/component.tsx"];
+        assert_eq!(snippet["prefix"], "web-component");
+        assert_eq!(snippet["scope"], "typescriptreact");
+        assert_eq!(snippet["description"], "Component");
+        assert_eq!(snippet["body"].as_array().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_jetbrains() {
+        let snippet = exported("class {{name}} {} // {{name | snake}} {{date:%Y}} ${{cursor}}");
+        let (value, variables) = snippet.jetbrains_template();
+        assert_eq!(value, "class $name$ {} // $name_snake$ $date_Y$ $$$END$");
+        let names: Vec<(&str, &str, bool)> = variables
+            .iter()
+            .map(|v| (v.name.as_str(), v.expression.as_str(), v.stop))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("name", "", true),
+                ("name_snake", "snakeCase(name)", false),
+                ("date_Y", "date(\"yyyy\")", false),
+            ]
+        );
+        let xml = to_jetbrains(&[snippet], "new-cli");
+        assert!(
+            xml.starts_with("<templateSet group=\"new-cli\">\n  <template name=\"web-component\"")
+        );
+        assert!(xml.contains("defaultValue=\"&quot;Widget&quot;\""));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(
+            Exported::new("a.txt", "txt", None, "{% if x %}y{% endif %}")
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            java_date("%d %B, week"),
+            Some("dd MMMM', week'".to_string())
+        );
+        assert_eq!(vscode_date("%j"), None);
+    }
+}
@@ -0,0 +1,232 @@
+//! 模板目录中的模板文件
+//!
+//! 模板可以按子目录组织（命名空间），例如 `web/index.html`、`rust/main.rs`，
+//! 以 `.` 开头的子目录（如 `.git`）会被忽略。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::{Error, IoContext, Result};
+use crate::glob;
+use crate::index::Index;
+use crate::tr;
+use crate::validate::validate_qualified_name;
+
+/// 模板目录中的一个模板文件
+#[derive(Debug, Clone)]
+pub struct TemplateInfo {
+    pub path: PathBuf,
+    /// 模板所在的子目录（命名空间），例如 `web` 或 `web/react`；位于模板目录根部时为 None
+    pub namespace: Option<String>,
+    pub name: String,
+    pub extension: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// front matter 中的模板说明
+    pub description: Option<String>,
+    /// front matter 中的分类标签
+    pub tags: Vec<String>,
+}
+
+impl TemplateInfo {
+    /// 模板的完整文件名，例如 `index.html`
+    pub fn file_name(&self) -> String {
+        if self.extension.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.name, self.extension)
+        }
+    }
+
+    /// 带命名空间的模板名，例如 `web/index.html`；没有命名空间时与 [`file_name`](Self::file_name) 相同
+    pub fn qualified_name(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}/{}", namespace, self.file_name()),
+            None => self.file_name(),
+        }
+    }
+}
+
+/// 匹配模板名或标签的模式
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// 通配模式，规则同 [`glob::matches`]
+    Glob(String),
+    /// 正则表达式，匹配其中任意一部分即可
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// `regex` 为 true 时按正则表达式解析，否则为通配模式
+    pub fn new(pattern: &str, regex: bool) -> Result<Pattern> {
+        if !regex {
+            return Ok(Pattern::Glob(pattern.to_string()));
+        }
+        regex::Regex::new(pattern)
+            .map(Pattern::Regex)
+            .map_err(|e| Error::InvalidRegex {
+                pattern: pattern.to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    pub fn matches(&self, subject: &str) -> bool {
+        match self {
+            Pattern::Glob(pattern) => glob::matches(pattern, subject),
+            Pattern::Regex(regex) => regex.is_match(subject),
+        }
+    }
+}
+
+/// 筛选模板的条件，各条件都满足的模板才会保留
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// 匹配带命名空间的模板名，带或不带后缀均可（例如 `react/*` 或 `web/index`）
+    pub pattern: Option<Pattern>,
+    /// 后缀，匹配其中任意一个即可；为空时不限制
+    pub extensions: Vec<String>,
+    /// 每个模式都必须匹配模板的某个标签
+    pub tags: Vec<Pattern>,
+}
+
+impl Filter {
+    pub fn matches(&self, template: &TemplateInfo) -> bool {
+        let name_matches = self.pattern.as_ref().is_none_or(|pattern| {
+            let qualified = template.qualified_name();
+            let stem = match &template.namespace {
+                Some(namespace) => format!("{}/{}", namespace, template.name),
+                None => template.name.clone(),
+            };
+            pattern.matches(&qualified) || pattern.matches(&stem)
+        });
+        let extension_matches = self.extensions.is_empty()
+            || self.extensions.iter().any(|extension| {
+                extension
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(&template.extension)
+            });
+        let tags_match = self
+            .tags
+            .iter()
+            .all(|pattern| template.tags.iter().any(|tag| pattern.matches(tag)));
+        name_matches && extension_matches && tags_match
+    }
+}
+
+/// 扫描模板目录及其子目录，返回按带命名空间的模板名排序的模板列表
+///
+/// 不使用缓存的索引，需要缓存时使用 [`Index::list`]。
+pub fn list_templates(template_dir: &Path) -> Result<Vec<TemplateInfo>> {
+    Index::default().list(template_dir)
+}
+
+/// 查找已存在的模板文件，并确认其规范化路径位于模板目录内
+///
+/// `name` 可以带命名空间，例如 `web/index.html`。
+pub fn resolve_existing_template(template_dir: &Path, name: &str) -> Result<PathBuf> {
+    validate_qualified_name(name)?;
+
+    let path = template_dir.join(name);
+    if !path.is_file() {
+        return Err(Error::TemplateNotFound(name.to_string()));
+    }
+
+    let canonical_dir = fs::canonicalize(template_dir).io_context(|| {
+        tr!(
+            "无法规范化模板目录路径",
+            "failed to canonicalize the template directory"
+        )
+    })?;
+    let canonical_path = fs::canonicalize(&path).io_context(|| {
+        tr!(
+            "无法规范化模板路径: {:?}",
+            "failed to canonicalize template path: {:?}",
+            path
+        )
+    })?;
+    if !canonical_path.starts_with(&canonical_dir) {
+        return Err(Error::OutsideTemplateDir(name.to_string()));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_info_file_name() {
+        let mut info = TemplateInfo {
+            path: PathBuf::from("index.html"),
+            namespace: None,
+            name: "index".to_string(),
+            extension: "html".to_string(),
+            size: 0,
+            modified: None,
+            description: None,
+            tags: Vec::new(),
+        };
+        assert_eq!(info.file_name(), "index.html");
+        assert_eq!(info.qualified_name(), "index.html");
+
+        info.namespace = Some("web".to_string());
+        assert_eq!(info.file_name(), "index.html");
+        assert_eq!(info.qualified_name(), "web/index.html");
+    }
+
+    #[test]
+    fn test_filter() {
+        let template = TemplateInfo {
+            path: PathBuf::from("react/button.tsx"),
+            namespace: Some("react".to_string()),
+            name: "button".to_string(),
+            extension: "tsx".to_string(),
+            size: 0,
+            modified: None,
+            description: None,
+            tags: vec!["frontend".to_string(), "ui".to_string()],
+        };
+        let glob = |pattern: &str| Pattern::new(pattern, false).unwrap();
+        let filter = |pattern: Option<Pattern>, extensions: &[&str], tags: Vec<Pattern>| Filter {
+            pattern,
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            tags,
+        };
+        assert!(filter(None, &[], Vec::new()).matches(&template));
+        assert!(filter(Some(glob("react/*")), &["tsx"], vec![glob("frontend")]).matches(&template));
+        assert!(filter(Some(glob("button")), &[".TSX", "jsx"], Vec::new()).matches(&template));
+        assert!(
+            filter(Some(glob("*.tsx")), &[], vec![glob("front*"), glob("ui")]).matches(&template)
+        );
+        assert!(!filter(Some(glob("vue/*")), &[], Vec::new()).matches(&template));
+        assert!(!filter(None, &["ts"], Vec::new()).matches(&template));
+        assert!(!filter(None, &[], vec![glob("frontend"), glob("backend")]).matches(&template));
+
+        let regex = Pattern::new("^react/(button|input)", true).unwrap();
+        assert!(filter(Some(regex), &[], Vec::new()).matches(&template));
+        assert!(matches!(
+            Pattern::new("(", true),
+            Err(Error::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_templates_with_namespaces() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("web/react")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("index.html"), "").unwrap();
+        fs::write(dir.path().join("web/index.html"), "").unwrap();
+        fs::write(dir.path().join("web/react/app.tsx"), "").unwrap();
+        fs::write(dir.path().join(".git/config"), "").unwrap();
+
+        let names: Vec<String> = list_templates(dir.path())
+            .unwrap()
+            .iter()
+            .map(TemplateInfo::qualified_name)
+            .collect();
+        assert_eq!(names, ["index.html", "web/index.html", "web/react/app.tsx"]);
+    }
+}
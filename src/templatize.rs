@@ -0,0 +1,158 @@
+//! 把已有文件转换为模板 (`template init --from-current`)
+//!
+//! 文件中出现的变量值替换为占位符，例如 `--var name=MyButton` 会把 `MyButton` 替换为
+//! `{{name}}`。`--case-variants` 时还会把 `myButton`、`my-button`、`MY_BUTTON` 等大小写形式
+//! 替换为带对应过滤器的占位符（`{{name | camel}}`、`{{name | kebab}}`、`{{name | constant}}`）；
+//! 这些形式只在完整的标识符处替换，但 `Button` 的小写形式仍会命中 `<button>` 这样的普通单词，
+//! 因此默认不替换。原文中的 `{{` 和 `{%` 会被转义，渲染时按原样输出。
+
+use crate::filters;
+use crate::front_matter;
+
+/// 依次尝试的过滤器；结果与原值相同或与前面的结果重复时跳过
+const FILTERS: &[&str] = &[
+    "lower", "upper", "pascal", "camel", "snake", "kebab", "constant",
+];
+
+/// 转换结果
+#[derive(Debug, PartialEq, Eq)]
+pub struct Templatized {
+    /// 模板内容
+    pub content: String,
+    /// 替换的次数
+    pub replaced: usize,
+}
+
+/// 将 `content` 中的变量值替换为占位符，并在 front matter 中把这些变量声明为必填
+///
+/// 内容已有 front matter 时不再添加。空值会被忽略。`case_variants` 时同时替换值的各种大小写形式。
+pub fn templatize(content: &str, vars: &[(String, String)], case_variants: bool) -> Templatized {
+    let vars: Vec<_> = vars.iter().filter(|(_, value)| !value.is_empty()).collect();
+    // (要替换的文本, 占位符, 是否只在标识符边界处替换)
+    let mut replacements: Vec<(String, String, bool)> = Vec::new();
+    for (name, value) in &vars {
+        replacements.push((value.clone(), format!("{{{{{}}}}}", name), false));
+    }
+    for (name, value) in vars.iter().filter(|_| case_variants) {
+        for filter in FILTERS {
+            let Ok(form) = filters::apply(filter, value) else {
+                continue;
+            };
+            if !form.is_empty() && !replacements.iter().any(|(f, _, _)| *f == form) {
+                replacements.push((form, format!("{{{{{} | {}}}}}", name, filter), true));
+            }
+        }
+    }
+    // 较长的值优先，避免 `MyButton` 中的 `Button` 先被替换
+    replacements.sort_by_key(|(form, _, _)| std::cmp::Reverse(form.len()));
+
+    let has_front_matter = matches!(front_matter::split(content), Ok((Some(_), _)));
+    let mut output = String::with_capacity(content.len());
+    if !has_front_matter && !vars.is_empty() {
+        output.push_str("+++\n[variables]\n");
+        for (name, _) in &vars {
+            output.push_str(&format!("{} = {{ required = true }}\n", name));
+        }
+        output.push_str("+++\n");
+    }
+
+    let mut replaced = 0;
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        let previous = content[..content.len() - rest.len()].chars().next_back();
+        let at = |form: &str, boundary: bool| {
+            rest.starts_with(form)
+                && (!boundary
+                    || (!previous.is_some_and(is_identifier_char)
+                        && !rest[form.len()..]
+                            .chars()
+                            .next()
+                            .is_some_and(is_identifier_char)))
+        };
+        if rest.starts_with("{{") || rest.starts_with("{%") {
+            output.push('\\');
+            output.push_str(&rest[..2]);
+            rest = &rest[2..];
+        } else if let Some((form, placeholder, _)) = replacements
+            .iter()
+            .find(|(form, _, boundary)| at(form, *boundary))
+        {
+            output.push_str(placeholder);
+            rest = &rest[form.len()..];
+            replaced += 1;
+        } else {
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Templatized {
+        content: output,
+        replaced,
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{render, Variables};
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_templatize_case_variants() {
+        let source = "export const MY_BUTTON = 'my-button';\nfunction MyButton() { return {{ myButton }}; }\n";
+        let result = templatize(source, &vars(&[("name", "MyButton")]), true);
+        assert_eq!(result.replaced, 4);
+        assert_eq!(
+            result.content,
+            "+++\n[variables]\nname = { required = true }\n+++\n\
+             export const {{name | constant}} = '{{name | kebab}}';\n\
+             function {{name}}() { return \\{{ {{name | camel}} }}; }\n"
+        );
+
+        // 用原来的值渲染得到原文
+        let body = result.content.split_once("+++\n").unwrap().1;
+        let body = body.split_once("+++\n").unwrap().1;
+        let variables: Variables = [("name".to_string(), "MyButton".to_string())].into();
+        assert_eq!(render(body, &variables, true).unwrap(), source);
+    }
+
+    #[test]
+    fn test_templatize_keeps_existing_front_matter() {
+        let source = "+++\ndescription = \"x\"\n+++\nHello World";
+        let result = templatize(source, &vars(&[("who", "World"), ("empty", "")]), true);
+        assert_eq!(
+            result.content,
+            "+++\ndescription = \"x\"\n+++\nHello {{who}}"
+        );
+        assert_eq!(result.replaced, 1);
+    }
+
+    #[test]
+    fn test_templatize_leaves_colliding_words() {
+        let source = "<button class=\"Button\">button</button>\n";
+        // 默认只替换原值
+        let result = templatize(source, &vars(&[("name", "Button")]), false);
+        assert_eq!(result.replaced, 1);
+        assert!(result
+            .content
+            .ends_with("<button class=\"{{name}}\">button</button>\n"));
+
+        // 大小写形式只在完整的标识符处替换
+        let source = "const buttonSize = 1;\nlet x = button;\n";
+        let result = templatize(source, &vars(&[("name", "Button")]), true);
+        assert_eq!(result.replaced, 1);
+        assert!(result
+            .content
+            .ends_with("const buttonSize = 1;\nlet x = {{name | lower}};\n"));
+    }
+}
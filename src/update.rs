@@ -0,0 +1,388 @@
+//! 从 GitHub Releases 更新 new-cli 自身 (`new-cli self-update`)
+//!
+//! 每个发布版本的附件中包含各平台的可执行文件压缩包和 `SHA256SUMS`：
+//!
+//! ```text
+//! new-cli-x86_64-linux.tar.gz
+//! new-cli-aarch64-macos.tar.gz
+//! new-cli-x86_64-windows.zip
+//! SHA256SUMS
+//! ```
+//!
+//! 压缩包名中的平台为 `<架构>-<系统>`（即 Rust 的 `std::env::consts::ARCH` 和 `OS`），
+//! `SHA256SUMS` 采用 `sha256sum` 的输出格式。下载的压缩包必须与其中记录的 SHA-256 一致。
+
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::{Error, IoContext, Result};
+use crate::remote::{self, FetchOptions};
+use crate::tr;
+
+/// 发布版本所在的 GitHub 仓库
+pub const REPOSITORY: &str = "jaffliang/new-cli";
+
+/// 当前运行的版本
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 记录附件 SHA-256 的附件名
+pub const CHECKSUMS: &str = "SHA256SUMS";
+
+/// 可执行文件压缩包的最大字节数
+const MAX_BINARY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 下载可执行文件的超时
+const BINARY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 一个发布版本
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Release {
+    /// 版本标签，例如 `v0.2.0`
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+
+    /// 附件
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// 发布版本的一个附件
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Asset {
+    pub name: String,
+
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+}
+
+impl Release {
+    /// 解析 GitHub API 返回的发布信息
+    pub fn parse(json: &[u8]) -> Result<Release> {
+        serde_json::from_slice(json).map_err(|e| {
+            Error::SelfUpdate(tr!(
+                "无法解析发布信息: {}",
+                "failed to parse the release information: {}",
+                e
+            ))
+        })
+    }
+
+    /// 去掉 `v` 前缀的版本号
+    pub fn version(&self) -> &str {
+        self.tag.strip_prefix('v').unwrap_or(&self.tag)
+    }
+
+    /// 平台 `platform` 的可执行文件压缩包
+    pub fn binary_asset(&self, platform: &str) -> Option<&Asset> {
+        let prefix = format!("new-cli-{}.", platform);
+        self.assets.iter().find(|asset| {
+            asset
+                .name
+                .strip_prefix(&prefix)
+                .is_some_and(|rest| rest == "tar.gz" || rest == "zip")
+        })
+    }
+
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// 当前平台，例如 `x86_64-linux`
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// 查询最新的发布版本
+pub fn latest_release() -> Result<Release> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        REPOSITORY
+    );
+    Release::parse(&remote::download(&url, &api_options())?)
+}
+
+/// `latest` 是否比 `current` 新
+///
+/// 版本号按 `.` 分隔的数字逐段比较；带预发布后缀（`1.0.0-rc.1`）的版本低于对应的正式版本。
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// 解析为 (数字段, 是否为正式版本)，以便直接比较
+fn parse_version(version: &str) -> Option<(Vec<u64>, bool)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let version = version.split('+').next()?;
+    let (numbers, release) = match version.split_once('-') {
+        Some((numbers, _)) => (numbers, false),
+        None => (version, true),
+    };
+    let mut numbers = numbers
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    while numbers.len() > 1 && numbers.last() == Some(&0) {
+        numbers.pop();
+    }
+    Some((numbers, release))
+}
+
+/// 下载 `asset`，按 `SHA256SUMS` 校验后从压缩包中取出可执行文件的内容
+pub fn download_binary(release: &Release, asset: &Asset) -> Result<Vec<u8>> {
+    let sums = release.asset(CHECKSUMS).ok_or_else(|| {
+        Error::SelfUpdate(tr!(
+            "版本 {} 没有提供 {}，无法校验下载的文件",
+            "release {} does not provide {}, cannot verify the download",
+            release.tag,
+            CHECKSUMS
+        ))
+    })?;
+    let sums = remote::download(&sums.url, &download_options(remote::DEFAULT_MAX_SIZE))?;
+    let sums = String::from_utf8_lossy(&sums);
+    let expected = checksum(&sums, &asset.name).ok_or_else(|| {
+        Error::SelfUpdate(tr!(
+            "{} 中没有 {} 的校验和",
+            "{} has no checksum for {}",
+            CHECKSUMS,
+            asset.name
+        ))
+    })?;
+
+    let archive = remote::download(&asset.url, &download_options(MAX_BINARY_SIZE))?;
+    remote::verify(&asset.url, &archive, Some(expected))?;
+    extract_binary(&asset.name, &archive)
+}
+
+/// 在 `sha256sum` 格式的 `sums` 中查找 `name` 的校验和
+pub fn checksum<'a>(sums: &'a str, name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (digest, file) = line.trim().split_once(char::is_whitespace)?;
+        // 二进制模式的输出在文件名前带有 `*`
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then_some(digest)
+    })
+}
+
+/// 从 `.tar.gz` 或 `.zip` 压缩包中取出名为 `new-cli`（Windows 上为 `new-cli.exe`）的文件
+pub fn extract_binary(archive_name: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let invalid = |message: String| {
+        Error::SelfUpdate(tr!(
+            "无法解压 {}: {}",
+            "failed to extract {}: {}",
+            archive_name,
+            message
+        ))
+    };
+    let binary_name = format!("new-cli{}", std::env::consts::EXE_SUFFIX);
+    let is_binary = |path: &str| path.rsplit(['/', '\\']).next() == Some(binary_name.as_str());
+
+    if archive_name.ends_with(".zip") {
+        let mut archive =
+            zip::ZipArchive::new(Cursor::new(data)).map_err(|e| invalid(e.to_string()))?;
+        for index in 0..archive.len() {
+            let mut file = archive
+                .by_index(index)
+                .map_err(|e| invalid(e.to_string()))?;
+            if file.is_file() && is_binary(file.name()) {
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)
+                    .map_err(|e| invalid(e.to_string()))?;
+                return Ok(content);
+            }
+        }
+    } else {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+        for entry in archive.entries().map_err(|e| invalid(e.to_string()))? {
+            let mut entry = entry.map_err(|e| invalid(e.to_string()))?;
+            let path = entry.path().map_err(|e| invalid(e.to_string()))?;
+            if entry.header().entry_type().is_file() && is_binary(&path.to_string_lossy()) {
+                let mut content = Vec::new();
+                entry
+                    .read_to_end(&mut content)
+                    .map_err(|e| invalid(e.to_string()))?;
+                return Ok(content);
+            }
+        }
+    }
+    Err(invalid(tr!(
+        "压缩包中没有 {}",
+        "the archive does not contain {}",
+        binary_name
+    )))
+}
+
+/// 用 `binary` 替换可执行文件 `exe`
+///
+/// 新文件先写到同一目录下的 `<exe>.new`，再通过重命名替换，中途失败不会留下损坏的可执行文件。
+/// Windows 不能覆盖正在运行的程序，因此先把它重命名为 `<exe>.old`，下次更新时删除。
+pub fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let staged = sibling(exe, "new");
+    fs::write(&staged, binary).io_context(|| {
+        tr!(
+            "无法写入新版本到 {:?}",
+            "failed to write the new version to {:?}",
+            staged
+        )
+    })?;
+    let result = install(exe, &staged);
+    if result.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    result
+}
+
+fn install(exe: &Path, staged: &Path) -> Result<()> {
+    let context = || {
+        tr!(
+            "无法替换可执行文件 {:?}",
+            "failed to replace the executable {:?}",
+            exe
+        )
+    };
+    let permissions = fs::metadata(exe).io_context(context)?.permissions();
+    fs::set_permissions(staged, permissions).io_context(context)?;
+
+    if cfg!(windows) {
+        let old = sibling(exe, "old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old).io_context(context)?;
+        if let Err(e) = fs::rename(staged, exe) {
+            let _ = fs::rename(&old, exe);
+            return Err(e).io_context(context);
+        }
+        return Ok(());
+    }
+    fs::rename(staged, exe).io_context(context)
+}
+
+/// `<path>.<suffix>`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn api_options() -> FetchOptions {
+    let mut headers = remote::github_auth_headers();
+    headers.push((
+        "Accept".to_string(),
+        "application/vnd.github+json".to_string(),
+    ));
+    FetchOptions {
+        headers,
+        ..FetchOptions::default()
+    }
+}
+
+fn download_options(max_size: u64) -> FetchOptions {
+    FetchOptions {
+        timeout: BINARY_TIMEOUT,
+        max_size,
+        headers: remote::github_auth_headers(),
+        ..FetchOptions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.2.0", "0.1.3"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(is_newer("1.0.0", "1.0.0-rc.1"));
+        assert!(!is_newer("1.0", "1.0.0"));
+        assert!(!is_newer("0.1.3", "0.1.3"));
+        assert!(!is_newer("0.1.2", "0.1.3"));
+        assert!(!is_newer("1.0.0-rc.1", "1.0.0"));
+        assert!(!is_newer("nightly", "0.1.3"));
+    }
+
+    #[test]
+    fn test_release() {
+        let release = Release::parse(
+            br#"{
+                "tag_name": "v0.2.0",
+                "assets": [
+                    {"name": "SHA256SUMS", "browser_download_url": "https://example.com/SHA256SUMS"},
+                    {"name": "new-cli-x86_64-linux.tar.gz", "browser_download_url": "https://example.com/a"},
+                    {"name": "new-cli-x86_64-linux.tar.gz.sig", "browser_download_url": "https://example.com/b"},
+                    {"name": "new-cli-x86_64-windows.zip", "browser_download_url": "https://example.com/c"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(release.version(), "0.2.0");
+        assert_eq!(
+            release.binary_asset("x86_64-linux").unwrap().url,
+            "https://example.com/a"
+        );
+        assert_eq!(
+            release.binary_asset("x86_64-windows").unwrap().url,
+            "https://example.com/c"
+        );
+        assert!(release.binary_asset("aarch64-macos").is_none());
+        assert!(matches!(Release::parse(b"{}"), Err(Error::SelfUpdate(_))));
+    }
+
+    #[test]
+    fn test_checksum() {
+        let sums = "aaa  new-cli-x86_64-linux.tar.gz\nbbb *new-cli-x86_64-windows.zip\n";
+        assert_eq!(checksum(sums, "new-cli-x86_64-linux.tar.gz"), Some("aaa"));
+        assert_eq!(checksum(sums, "new-cli-x86_64-windows.zip"), Some("bbb"));
+        assert_eq!(checksum(sums, "new-cli-aarch64-macos.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_extract_binary() {
+        let binary_name = format!("new-cli{}", std::env::consts::EXE_SUFFIX);
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, content) in [
+            ("new-cli-0.2.0/README.md", "readme"),
+            (&*format!("new-cli-0.2.0/{}", binary_name), "binary"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        assert_eq!(extract_binary("a.tar.gz", &archive).unwrap(), b"binary");
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("README.md", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+        assert!(matches!(
+            extract_binary("a.zip", &archive),
+            Err(Error::SelfUpdate(_))
+        ));
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = TempDir::new().unwrap();
+        let exe = dir.path().join("new-cli");
+        fs::write(&exe, "old").unwrap();
+        replace_executable(&exe, b"new").unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"new");
+        assert!(!sibling(&exe, "new").exists());
+    }
+}
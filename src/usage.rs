@@ -0,0 +1,138 @@
+//! 模板使用统计 `<数据目录>/usage.jsonl`
+//!
+//! 配置 `track_usage = true` 后，每次用模板创建（或插入）文件都追加一行 JSON，只记录
+//! 带命名空间的模板名和时间，不记录目标路径和变量。`new-cli stats` 据此列出最常用、最近使用
+//! 和从未使用的模板，交互式选择模板时也会把最近用过的模板排在前面。
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::data_dir;
+use crate::error::{IoContext, Result};
+use crate::tr;
+
+/// 一次模板使用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: DateTime<Local>,
+    /// 带命名空间的模板名，例如 `web/index.html`
+    pub template: String,
+}
+
+/// 一个模板的使用情况
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub count: usize,
+    pub last_used: DateTime<Local>,
+}
+
+/// 统计文件路径 `<数据目录>/usage.jsonl`
+pub fn usage_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("usage.jsonl"))
+}
+
+/// 在统计文件末尾追加一次使用
+pub fn record(path: &Path, event: &Event) -> Result<()> {
+    let context = || {
+        tr!(
+            "无法写入使用统计 {}",
+            "failed to write usage statistics {}",
+            path.display()
+        )
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).io_context(context)?;
+    }
+    let mut line = serde_json::to_string(event)
+        .map_err(io::Error::from)
+        .io_context(context)?;
+    // 与创建记录一样一次写入整行，并行创建文件时各行不会交错
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .io_context(context)?;
+    file.write_all(line.as_bytes()).io_context(context)
+}
+
+/// 读取所有使用记录，按写入顺序排列
+///
+/// 统计文件不存在时返回空列表；无法解析的行会被忽略。
+pub fn load(path: &Path) -> Result<Vec<Event>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "无法读取使用统计 {}",
+            "failed to read usage statistics {}",
+            path.display()
+        )
+    })?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// 按模板汇总使用次数和最近使用时间
+pub fn summarize(events: &[Event]) -> BTreeMap<String, Usage> {
+    let mut usage: BTreeMap<String, Usage> = BTreeMap::new();
+    for event in events {
+        usage
+            .entry(event.template.clone())
+            .and_modify(|usage| {
+                usage.count += 1;
+                usage.last_used = usage.last_used.max(event.timestamp);
+            })
+            .or_insert(Usage {
+                count: 1,
+                last_used: event.timestamp,
+            });
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn event(template: &str, day: u32) -> Event {
+        Event {
+            timestamp: Local.with_ymd_and_hms(2024, 5, day, 9, 0, 0).unwrap(),
+            template: template.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_summarize() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data/usage.jsonl");
+        for event in [
+            event("web/page.html", 3),
+            event("README.md", 1),
+            event("web/page.html", 2),
+        ] {
+            record(&path, &event).unwrap();
+        }
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("not json\n");
+        fs::write(&path, content).unwrap();
+
+        let events = load(&path).unwrap();
+        assert_eq!(events.len(), 3);
+        let usage = summarize(&events);
+        assert_eq!(usage["web/page.html"].count, 2);
+        assert_eq!(usage["web/page.html"].last_used, event("", 3).timestamp);
+        assert_eq!(usage["README.md"].count, 1);
+        assert!(load(&dir.path().join("missing.jsonl")).unwrap().is_empty());
+    }
+}
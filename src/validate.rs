@@ -0,0 +1,216 @@
+//! 文件名、后缀与模板名的校验
+
+use std::path::{Component, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// 可能导致路径逃出目标目录的字符
+const INVALID_CHARS: [&str; 3] = ["/", "\\", ".."];
+
+/// 校验要创建的文件名和后缀
+pub fn validate_cli_inputs(filename: &str, extension: &str) -> Result<()> {
+    for invalid in INVALID_CHARS {
+        if filename.contains(invalid) {
+            return Err(Error::InvalidFilename {
+                name: filename.to_string(),
+                invalid,
+            });
+        }
+        if extension.contains(invalid) {
+            return Err(Error::InvalidExtension {
+                extension: extension.to_string(),
+                invalid,
+            });
+        }
+    }
+
+    if filename.is_empty() {
+        return Err(Error::EmptyFilename);
+    }
+
+    if extension.is_empty() {
+        return Err(Error::EmptyExtension);
+    }
+
+    Ok(())
+}
+
+/// 校验 `name.ext` 形式的模板文件名，拒绝任何可能逃出模板目录的写法
+pub fn validate_template_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::EmptyTemplateName);
+    }
+    for invalid in INVALID_CHARS {
+        if name.contains(invalid) {
+            return Err(Error::InvalidTemplateName {
+                name: name.to_string(),
+                invalid,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 校验可能带命名空间的模板名，例如 `web/index.html`
+///
+/// 以 `/` 分隔的每一段都必须是合法的模板名，命名空间（子目录）不能以 `.` 开头。
+pub fn validate_qualified_name(name: &str) -> Result<()> {
+    let mut segments: Vec<&str> = name.split('/').collect();
+    let file_name = segments.pop().unwrap_or_default();
+    for namespace in segments {
+        validate_namespace(namespace)?;
+    }
+    validate_template_name(file_name)
+}
+
+/// 校验单级或多级命名空间，例如 `web` 或 `web/react`
+pub fn validate_namespace(namespace: &str) -> Result<()> {
+    for segment in namespace.split('/') {
+        validate_template_name(segment)?;
+        if segment.starts_with('.') {
+            return Err(Error::InvalidTemplateName {
+                name: namespace.to_string(),
+                invalid: ".",
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 把模板变量渲染出的路径（例如 `src/{{module}}/mod.rs` 渲染为 `src/auth/mod.rs`）
+/// 转换为相对路径，不安全时返回 None
+///
+/// `/` 和 `\` 都视为分隔符；每一段都不能为空、`.` 或包含 `..`，结果也不能带有盘符等前缀，
+/// 因此不会是绝对路径，也不会逃出目标目录。
+pub fn rendered_path(rendered: &str) -> Option<PathBuf> {
+    let segments: Vec<&str> = rendered.split(['/', '\\']).collect();
+    let valid = segments
+        .iter()
+        .all(|segment| !segment.is_empty() && *segment != "." && !segment.contains(".."));
+    let path: PathBuf = segments.iter().collect();
+    let normal = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (valid && normal).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{set_lang, Lang};
+
+    #[test]
+    fn test_validate_cli_inputs_valid() {
+        assert!(validate_cli_inputs("index", "html").is_ok());
+        assert!(validate_cli_inputs("my_file-123", "txt").is_ok());
+    }
+
+    #[test]
+    fn test_validate_filename_empty() {
+        set_lang(Lang::Zh);
+        let result = validate_cli_inputs("", "html");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().to_string(), "错误：文件名不能为空。");
+    }
+
+    #[test]
+    fn test_validate_filename_invalid_chars() {
+        set_lang(Lang::Zh);
+        let chars = ["/", "\\", ".."];
+        for &char_set in &chars {
+            let filename = format!("file{}", char_set);
+            let result = validate_cli_inputs(&filename, "html");
+            assert!(result.is_err());
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                format!("错误：文件名 '{}' 包含无效字符 '{}'。", filename, char_set)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_extension_empty() {
+        set_lang(Lang::Zh);
+        let result = validate_cli_inputs("index", "");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().to_string(), "错误：文件后缀不能为空。");
+    }
+
+    #[test]
+    fn test_validate_extension_invalid_chars() {
+        set_lang(Lang::Zh);
+        let chars = ["/", "\\", ".."];
+        for &char_set in &chars {
+            let extension = format!("ext{}", char_set);
+            let result = validate_cli_inputs("index", &extension);
+            assert!(result.is_err());
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                format!(
+                    "错误：文件后缀 '{}' 包含无效字符 '{}'。",
+                    extension, char_set
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_both_invalid_filename_takes_precedence() {
+        set_lang(Lang::Zh);
+        // Test that filename error is reported first if both are invalid (due to order of checks)
+        let result = validate_cli_inputs("file/", "ext/");
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "错误：文件名 'file/' 包含无效字符 '/'。"
+        );
+    }
+
+    #[test]
+    fn test_validate_template_name() {
+        assert!(validate_template_name("index.html").is_ok());
+        assert!(validate_template_name("").is_err());
+        assert!(validate_template_name("../index.html").is_err());
+        assert!(validate_template_name("web/index.html").is_err());
+        assert!(validate_template_name("web\\index.html").is_err());
+    }
+
+    #[test]
+    fn test_validate_qualified_name() {
+        assert!(validate_qualified_name("index.html").is_ok());
+        assert!(validate_qualified_name("web/index.html").is_ok());
+        assert!(validate_qualified_name("web/react/app.tsx").is_ok());
+        assert!(validate_qualified_name("web/").is_err());
+        assert!(validate_qualified_name("/index.html").is_err());
+        assert!(validate_qualified_name("web/../index.html").is_err());
+        assert!(validate_qualified_name(".git/config").is_err());
+        assert!(validate_qualified_name("web\\index.html").is_err());
+    }
+
+    #[test]
+    fn test_rendered_path() {
+        assert_eq!(
+            rendered_path("src/auth/mod.rs"),
+            Some(PathBuf::from("src/auth/mod.rs"))
+        );
+        assert_eq!(
+            rendered_path("src\\auth.rs"),
+            Some(PathBuf::from("src").join("auth.rs"))
+        );
+        assert_eq!(
+            rendered_path("user.service.ts"),
+            Some("user.service.ts".into())
+        );
+        for invalid in [
+            "",
+            "/etc/passwd",
+            "src//mod.rs",
+            "src/",
+            "./a",
+            "a/../b",
+            "..",
+        ] {
+            assert_eq!(rendered_path(invalid), None, "{invalid}");
+        }
+    }
+}
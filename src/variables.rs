@@ -0,0 +1,290 @@
+//! 内置模板变量
+//!
+//! 除固定的内置变量外，还支持带格式的日期变量：`{{date:%Y/%m/%d}}`、
+//! `{{datetime:%H:%M}}`，格式语法与 strftime 相同。
+//!
+//! 环境变量以 `{{env.NAME}}` 引用，出于安全考虑只有显式允许的变量才会被读取，
+//! 见 [`env_variables`]。
+//!
+//! 目标目录所在 git 仓库的信息以 `{{git.user_name}}`、`{{git.user_email}}`、
+//! `{{git.repo_name}}` 和 `{{git.branch}}` 引用，见 [`git_variables`]；一次运行中创建多个文件时
+//! 用 [`GitCache`] 避免重复查询。
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, PoisonError};
+
+use crate::error::{Error, Result};
+use crate::render::Variables;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const TIME_FORMAT: &str = "%H:%M:%S";
+
+/// 根据目标文件生成内置变量
+///
+/// `filename`、`extension`、`author`，以及 `date`、`datetime`、`time`、
+/// `timestamp`、`year`、`uuid`。
+pub fn builtin_variables(filename: &str, extension: &str) -> Variables {
+    builtin_variables_at(filename, extension, Local::now())
+}
+
+fn builtin_variables_at(filename: &str, extension: &str, now: DateTime<Local>) -> Variables {
+    let author = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+
+    let mut vars = Variables::new();
+    vars.insert("filename".to_string(), filename.to_string());
+    vars.insert("extension".to_string(), extension.to_string());
+    vars.insert("author".to_string(), author);
+    vars.insert("date".to_string(), now.format(DATE_FORMAT).to_string());
+    vars.insert(
+        "datetime".to_string(),
+        now.format(DATETIME_FORMAT).to_string(),
+    );
+    vars.insert("time".to_string(), now.format(TIME_FORMAT).to_string());
+    vars.insert("timestamp".to_string(), now.timestamp().to_string());
+    vars.insert("year".to_string(), now.year().to_string());
+    vars.insert("uuid".to_string(), uuid::Uuid::new_v4().to_string());
+    vars
+}
+
+/// 模板中以 `env.NAME` 引用的环境变量
+///
+/// 只读取 `allowlist` 中列出的变量，支持 `CI_*` 形式的前缀匹配；
+/// `allow_all` 为 true 时读取全部环境变量。名称或值不是 UTF-8 的变量会被跳过。
+pub fn env_variables(allowlist: &[String], allow_all: bool) -> Variables {
+    if allowlist.is_empty() && !allow_all {
+        return Variables::new();
+    }
+    // std::env::vars() 遇到不是 UTF-8 的变量会 panic
+    let env = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    env_variables_from(env, allowlist, allow_all)
+}
+
+fn env_variables_from(
+    env: impl Iterator<Item = (String, String)>,
+    allowlist: &[String],
+    allow_all: bool,
+) -> Variables {
+    let allowed = |name: &str| {
+        allow_all
+            || allowlist
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == pattern,
+                })
+    };
+    env.filter(|(name, _)| allowed(name))
+        .map(|(name, value)| (format!("env.{name}"), value))
+        .collect()
+}
+
+/// `dir` 所在 git 仓库的信息，以 `git.NAME` 引用
+///
+/// `git.user_name`、`git.user_email` 来自 `git config`，`git.repo_name` 是仓库根目录的名称，
+/// `git.branch` 是当前分支。`dir` 还不存在时从最近的已存在的上级目录查询；没有安装 git、
+/// 不在仓库中或没有设置的值不会出现，按未定义的变量处理。
+pub fn git_variables(dir: &Path) -> Variables {
+    let Some(dir) = dir.ancestors().find(|dir| dir.is_dir()) else {
+        return Variables::new();
+    };
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !value.is_empty()).then_some(value)
+    };
+    let repo_name = git(&["rev-parse", "--show-toplevel"]).and_then(|root| {
+        Path::new(&root)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    });
+
+    [
+        ("user_name", git(&["config", "user.name"])),
+        ("user_email", git(&["config", "user.email"])),
+        ("repo_name", repo_name),
+        // 分离 HEAD 时没有分支
+        ("branch", git(&["symbolic-ref", "--short", "-q", "HEAD"])),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((format!("git.{name}"), value?)))
+    .collect()
+}
+
+/// 模板是否引用了 `git.` 变量；项目模板目录中任一文件的路径或内容引用了即可，读取失败时按未引用处理
+pub fn references_git(template: &Path) -> bool {
+    if template.is_dir() {
+        return fs::read_dir(template)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_name() != ".git")
+            .any(|entry| {
+                entry.file_name().to_string_lossy().contains("git.")
+                    || references_git(&entry.path())
+            });
+    }
+    fs::read(template).is_ok_and(|content| content.windows(4).any(|w| w == b"git."))
+}
+
+/// 按目录缓存的 [`git_variables`]：同一次运行中每个目录只启动一次 git
+#[derive(Debug, Default)]
+pub struct GitCache(Mutex<HashMap<PathBuf, Variables>>);
+
+impl GitCache {
+    pub fn get(&self, dir: &Path) -> Variables {
+        let mut cache = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        cache
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| git_variables(dir))
+            .clone()
+    }
+}
+
+/// 解析带参数的内置变量，例如 `date:%Y/%m/%d`；不是此类变量时返回 None
+pub fn resolve_formatted(key: &str) -> Result<Option<String>> {
+    resolve_formatted_at(key, Local::now())
+}
+
+pub(crate) fn resolve_formatted_at(key: &str, now: DateTime<Local>) -> Result<Option<String>> {
+    let Some((name, format)) = key.split_once(':') else {
+        return Ok(None);
+    };
+
+    match name.trim() {
+        "date" | "datetime" | "time" => {
+            let items: Vec<Item> = StrftimeItems::new(format).collect();
+            if items.iter().any(|item| matches!(item, Item::Error)) {
+                return Err(Error::InvalidDateFormat(format.to_string()));
+            }
+            Ok(Some(now.format_with_items(items.into_iter()).to_string()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_references_git() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let project = dir.path().join("lib");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("src/lib.rs"), "// {{name}}").unwrap();
+        assert!(!references_git(&project));
+        assert!(!references_git(&project.join("src/lib.rs")));
+
+        std::fs::write(project.join("README.md"), "by {{git.user_name}}").unwrap();
+        assert!(references_git(&project));
+        assert!(references_git(&project.join("README.md")));
+        assert!(!references_git(&dir.path().join("missing.md")));
+    }
+
+    #[test]
+    fn test_git_variables() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = dir.path().join("billing");
+        std::fs::create_dir(&repo).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(&repo)
+                .status()
+                .is_ok_and(|status| status.success())
+        };
+        assert!(!git_variables(&repo).contains_key("git.repo_name"));
+        if !git(&["init", "-q", "-b", "main"]) {
+            return; // 没有安装 git
+        }
+        assert!(git(&["config", "user.name", "Ada Lovelace"]));
+        assert!(git(&["config", "user.email", "ada@example.com"]));
+
+        // 目标目录还不存在时使用已存在的上级目录
+        let vars = git_variables(&repo.join("src/new"));
+        assert_eq!(vars["git.user_name"], "Ada Lovelace");
+        assert_eq!(vars["git.user_email"], "ada@example.com");
+        assert_eq!(vars["git.repo_name"], "billing");
+        assert_eq!(vars["git.branch"], "main");
+
+        // 缓存的结果在同一次运行中不再变化
+        let cache = GitCache::default();
+        assert_eq!(cache.get(&repo)["git.user_name"], "Ada Lovelace");
+        assert!(git(&["config", "user.name", "Grace Hopper"]));
+        assert_eq!(cache.get(&repo)["git.user_name"], "Ada Lovelace");
+    }
+
+    fn fixed_now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 7, 9, 5, 30).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_variables() {
+        let vars = builtin_variables_at("main", "rs", fixed_now());
+        assert_eq!(vars["filename"], "main");
+        assert_eq!(vars["extension"], "rs");
+        assert_eq!(vars["date"], "2025-03-07");
+        assert_eq!(vars["datetime"], "2025-03-07 09:05:30");
+        assert_eq!(vars["time"], "09:05:30");
+        assert_eq!(vars["year"], "2025");
+        assert_eq!(vars["timestamp"], fixed_now().timestamp().to_string());
+        assert_eq!(vars["uuid"].len(), 36);
+        assert!(vars.contains_key("author"));
+    }
+
+    #[test]
+    fn test_env_variables() {
+        let env = || {
+            [
+                ("USER", "alice"),
+                ("CI_COMMIT", "abc123"),
+                ("TOKEN", "secret"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+        };
+        let allowlist = vec!["USER".to_string(), "CI_*".to_string()];
+        let vars = env_variables_from(env(), &allowlist, false);
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars["env.USER"], "alice");
+        assert_eq!(vars["env.CI_COMMIT"], "abc123");
+
+        assert!(env_variables_from(env(), &[], false).is_empty());
+        assert_eq!(env_variables_from(env(), &[], true)["env.TOKEN"], "secret");
+    }
+
+    #[test]
+    fn test_resolve_formatted() {
+        let now = fixed_now();
+        assert_eq!(
+            resolve_formatted_at("date:%Y/%m/%d", now)
+                .unwrap()
+                .as_deref(),
+            Some("2025/03/07")
+        );
+        assert_eq!(
+            resolve_formatted_at("datetime:%d.%m.%Y %H:%M", now)
+                .unwrap()
+                .as_deref(),
+            Some("07.03.2025 09:05")
+        );
+        assert_eq!(resolve_formatted_at("date", now).unwrap(), None);
+        assert_eq!(resolve_formatted_at("title:x", now).unwrap(), None);
+        assert!(resolve_formatted_at("date:%Q", now).is_err());
+    }
+}
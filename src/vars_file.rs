@@ -0,0 +1,160 @@
+//! 从 JSON、TOML 或 YAML 文件读取模板变量
+//!
+//! 文件格式按后缀确定（`.json`、`.toml`、`.yaml`/`.yml`），顶层必须是表。
+//! 嵌套的表展开为以 `.` 连接的变量名（`company.name`），数组按逗号连接，
+//! 可以直接用于 `{% for %}`；数字和布尔值转换为文本，null 为空字符串。
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoContext, Result};
+use crate::render::Variables;
+use crate::tr;
+
+/// 读取变量文件
+pub fn load(path: &Path) -> Result<Variables> {
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "无法读取变量文件 {:?}",
+            "failed to read variables file {:?}",
+            path
+        )
+    })?;
+    let invalid = |message: String| Error::VarsFile {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let value: Value = match extension.as_str() {
+        "json" => serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?,
+        "toml" => {
+            let table: toml::Table =
+                toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+            serde_json::to_value(table).map_err(|e| invalid(e.to_string()))?
+        }
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?,
+        _ => {
+            return Err(invalid(tr!(
+                "不支持的格式，需要 .json、.toml、.yaml 或 .yml",
+                "unsupported format, expected .json, .toml, .yaml or .yml"
+            )))
+        }
+    };
+
+    let Value::Object(table) = value else {
+        return Err(invalid(tr!(
+            "顶层必须是键值表",
+            "the top level must be a table"
+        )));
+    };
+    let mut vars = Variables::new();
+    for (key, value) in table {
+        flatten(&key, value, &mut vars).map_err(invalid)?;
+    }
+    Ok(vars)
+}
+
+/// 把 `value` 展开为以 `key` 开头的变量，批量生成清单中的 JSON 也使用这一规则
+pub(crate) fn flatten(
+    key: &str,
+    value: Value,
+    vars: &mut Variables,
+) -> std::result::Result<(), String> {
+    match value {
+        Value::Object(table) => {
+            for (child, value) in table {
+                flatten(&format!("{}.{}", key, child), value, vars)?;
+            }
+        }
+        Value::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| {
+                    scalar(item).ok_or_else(|| {
+                        tr!(
+                            "{} 中的数组只能包含文本、数字或布尔值",
+                            "arrays in {} may only contain strings, numbers or booleans",
+                            key
+                        )
+                    })
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            vars.insert(key.to_string(), items.join(","));
+        }
+        value => {
+            vars.insert(key.to_string(), scalar(value).unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+fn scalar(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => Some(String::new()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn load_str(name: &str, content: &str) -> Result<Variables> {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        load(&path)
+    }
+
+    #[test]
+    fn test_load_formats() {
+        let toml = load_str(
+            "vars.toml",
+            "project = \"demo\"\nyear = 2025\nfields = [\"id\", \"name\"]\n[company]\nname = \"Acme\"\n",
+        )
+        .unwrap();
+        let json = load_str(
+            "vars.json",
+            r#"{"project": "demo", "year": 2025, "fields": ["id", "name"], "company": {"name": "Acme"}}"#,
+        )
+        .unwrap();
+        let yaml = load_str(
+            "vars.YML",
+            "project: demo\nyear: 2025\nfields: [id, name]\ncompany:\n  name: Acme\n",
+        )
+        .unwrap();
+
+        for vars in [&toml, &json, &yaml] {
+            assert_eq!(vars["project"], "demo");
+            assert_eq!(vars["year"], "2025");
+            assert_eq!(vars["fields"], "id,name");
+            assert_eq!(vars["company.name"], "Acme");
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        for (name, content) in [
+            ("vars.ini", "a = 1"),
+            ("vars.json", "[1, 2]"),
+            ("vars.json", "{"),
+            ("vars.toml", "a = [[1]]"),
+        ] {
+            assert!(
+                matches!(load_str(name, content), Err(Error::VarsFile { .. })),
+                "{} {}",
+                name,
+                content
+            );
+        }
+    }
+}
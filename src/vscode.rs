@@ -0,0 +1,708 @@
+//! VS Code 代码片段转换 (`new-cli template import-vscode snippets.json`)
+//!
+//! 片段文件是一个 JSON 对象（允许注释和末尾逗号），每一项包含 `prefix`、`body`
+//! （字符串或按行的数组）、可选的 `description` 和 `scope`。转换规则：
+//!
+//! - `$0` 转换为 `{{cursor}}`；没有 `$0` 时第一个不带默认值的 `$1` 等视为光标位置，其余去掉
+//! - 带默认值的 `${1:ComponentName}` 以及选项 `${1|a,b|}` 转换为变量，在 front matter
+//!   中声明默认值；默认值是合法变量名时直接用作变量名，否则为 `tabstop1`，`$1` 的重复引用
+//!   指向同一个变量
+//! - `$TM_FILENAME_BASE`、`$CURRENT_YEAR`、`$UUID` 等转换为对应的内置变量，
+//!   `${TM_FILENAME_BASE/(.*)/${1:/pascalcase}/}` 这类整体大小写转换转换为过滤器；
+//!   其他变量使用其默认值
+//! - 正文中原有的 `{{` 和 `{%` 会被转义
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, IoContext, Result};
+use crate::tr;
+
+/// 一个代码片段
+#[derive(Debug, PartialEq)]
+pub struct Snippet {
+    /// 片段在文件中的名称
+    pub name: String,
+
+    /// 触发前缀，有多个时取第一个
+    pub prefix: String,
+
+    pub description: Option<String>,
+
+    /// 片段正文，多行已用换行连接
+    pub body: String,
+
+    /// 适用的语言，例如 `typescript`、`javascriptreact`
+    pub scope: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSnippet {
+    prefix: Option<OneOrMany>,
+    body: OneOrMany,
+    description: Option<String>,
+    scope: Option<String>,
+}
+
+/// 读取片段文件
+pub fn load(path: &Path) -> Result<Vec<Snippet>> {
+    let content = fs::read_to_string(path).io_context(|| {
+        tr!(
+            "无法读取代码片段文件 {:?}",
+            "failed to read snippets file {:?}",
+            path
+        )
+    })?;
+    parse(&content).map_err(|message| Error::Snippets {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// 解析片段文件内容，按名称排序
+fn parse(content: &str) -> std::result::Result<Vec<Snippet>, String> {
+    let raw: BTreeMap<String, RawSnippet> =
+        serde_json::from_str(&strip_jsonc(content)).map_err(|e| e.to_string())?;
+    Ok(raw
+        .into_iter()
+        .map(|(name, raw)| Snippet {
+            prefix: raw
+                .prefix
+                .and_then(|prefix| prefix.into_vec().into_iter().next())
+                .unwrap_or_else(|| name.clone()),
+            name,
+            description: raw.description,
+            body: raw.body.into_vec().join("\n"),
+            scope: raw
+                .scope
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+        })
+        .collect())
+}
+
+/// 去掉 JSONC 中的注释和末尾逗号
+fn strip_jsonc(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            (']' | '}', _) => {
+                let trimmed = output.trim_end().len();
+                if output[..trimmed].ends_with(',') {
+                    output.truncate(trimmed - 1);
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// VS Code 语言标识和对应的文件后缀
+const LANGUAGES: &[(&str, &str)] = &[
+    ("typescript", "ts"),
+    ("typescriptreact", "tsx"),
+    ("javascript", "js"),
+    ("javascriptreact", "jsx"),
+    ("python", "py"),
+    ("rust", "rs"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kotlin", "kt"),
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("csharp", "cs"),
+    ("ruby", "rb"),
+    ("php", "php"),
+    ("swift", "swift"),
+    ("html", "html"),
+    ("css", "css"),
+    ("scss", "scss"),
+    ("less", "less"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("markdown", "md"),
+    ("json", "json"),
+    ("jsonc", "json"),
+    ("yaml", "yaml"),
+    ("toml", "toml"),
+    ("shellscript", "sh"),
+    ("powershell", "ps1"),
+    ("sql", "sql"),
+    ("lua", "lua"),
+    ("dart", "dart"),
+];
+
+/// VS Code 语言标识对应的文件后缀
+pub fn extension(language: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(id, _)| *id == language)
+        .map(|(_, extension)| *extension)
+}
+
+/// 文件后缀对应的 VS Code 语言标识，例如 `tsx` -> `typescriptreact`
+pub fn language(extension: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(_, ext)| ext.eq_ignore_ascii_case(extension))
+        .map(|(id, _)| *id)
+}
+
+/// 由触发前缀得到模板名：只保留字母、数字、`-` 和 `_`，其他字符替换为 `-`
+pub fn template_name(prefix: &str) -> Option<String> {
+    let name: String = prefix
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('-');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// 片段正文中的元素
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Tabstop(u32),
+    Placeholder(u32, Vec<Node>),
+    Choice(u32, Vec<String>),
+    Variable {
+        name: String,
+        default: Vec<Node>,
+        filter: Option<&'static str>,
+    },
+}
+
+/// 把片段转换为模板内容（含 front matter）
+pub fn to_template(snippet: &Snippet) -> String {
+    let nodes = Parser::new(&snippet.body).parse(false);
+    let mut converter = Converter::default();
+    converter.collect(&nodes);
+
+    let body = converter.emit(&nodes);
+    let mut front_matter = String::new();
+    if let Some(description) = &snippet.description {
+        front_matter.push_str(&format!("description = {}\n", toml_string(description)));
+    }
+    if !converter.variables.is_empty() {
+        if !front_matter.is_empty() {
+            front_matter.push('\n');
+        }
+        front_matter.push_str("[variables]\n");
+        for (name, default) in &converter.variables {
+            front_matter.push_str(&format!(
+                "{} = {{ default = {} }}\n",
+                name,
+                toml_string(default)
+            ));
+        }
+    }
+    if front_matter.is_empty() {
+        body
+    } else {
+        format!("+++\n{}+++\n{}", front_matter, body)
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// 片段语法的解析器
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    /// 解析到文本结束；`nested` 为 true 时解析到未转义的 `}` 为止（并消耗它）
+    fn parse(&mut self, nested: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => match self.chars.peek() {
+                    Some('$' | '}' | '\\') => text.extend(self.chars.next()),
+                    _ => text.push(c),
+                },
+                '}' if nested => break,
+                '$' => match self.element() {
+                    Some(node) => {
+                        if !text.is_empty() {
+                            nodes.push(Node::Text(std::mem::take(&mut text)));
+                        }
+                        nodes.push(node);
+                    }
+                    None => text.push('$'),
+                },
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        nodes
+    }
+
+    /// 解析 `$` 之后的元素；不是合法元素时返回 None，`$` 按文本处理
+    fn element(&mut self) -> Option<Node> {
+        match self.chars.peek()? {
+            c if c.is_ascii_digit() => Some(Node::Tabstop(self.number())),
+            c if c.is_ascii_alphabetic() || *c == '_' => Some(Node::Variable {
+                name: self.identifier(),
+                default: Vec::new(),
+                filter: None,
+            }),
+            '{' => {
+                self.chars.next();
+                self.braced()
+            }
+            _ => None,
+        }
+    }
+
+    /// 解析 `${...}` 的内容（`{` 已消耗）
+    fn braced(&mut self) -> Option<Node> {
+        if self.chars.peek()?.is_ascii_digit() {
+            let number = self.number();
+            return match self.chars.next()? {
+                '}' => Some(Node::Tabstop(number)),
+                ':' => Some(Node::Placeholder(number, self.parse(true))),
+                '|' => {
+                    let choices = self.choices();
+                    Some(Node::Choice(number, choices))
+                }
+                '/' => {
+                    self.transform();
+                    Some(Node::Tabstop(number))
+                }
+                _ => None,
+            };
+        }
+        let name = self.identifier();
+        if name.is_empty() {
+            return None;
+        }
+        match self.chars.next()? {
+            '}' => Some(Node::Variable {
+                name,
+                default: Vec::new(),
+                filter: None,
+            }),
+            ':' => Some(Node::Variable {
+                name,
+                default: self.parse(true),
+                filter: None,
+            }),
+            '/' => Some(Node::Variable {
+                name,
+                default: Vec::new(),
+                filter: self.transform(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> u32 {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(*c);
+            self.chars.next();
+        }
+        digits.parse().unwrap_or(0)
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self
+            .chars
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+        {
+            name.push(*c);
+            self.chars.next();
+        }
+        name
+    }
+
+    /// 解析 `a,b,c|}`（`|` 已消耗）
+    fn choices(&mut self) -> Vec<String> {
+        let mut choices = vec![String::new()];
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => choices.last_mut().unwrap().extend(self.chars.next()),
+                ',' => choices.push(String::new()),
+                '|' => {
+                    if self.chars.peek() == Some(&'}') {
+                        self.chars.next();
+                    }
+                    break;
+                }
+                _ => choices.last_mut().unwrap().push(c),
+            }
+        }
+        choices
+    }
+
+    /// 解析 `regex/format/options}`（第一个 `/` 已消耗），整体大小写转换时返回对应的过滤器
+    fn transform(&mut self) -> Option<&'static str> {
+        let mut parts = vec![String::new()];
+        let mut depth = 0;
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => {
+                    let part = parts.last_mut().unwrap();
+                    part.push(c);
+                    part.extend(self.chars.next());
+                }
+                '/' if depth == 0 && parts.len() < 3 => parts.push(String::new()),
+                '{' => {
+                    depth += 1;
+                    parts.last_mut().unwrap().push(c);
+                }
+                '}' if depth == 0 => break,
+                '}' => {
+                    depth -= 1;
+                    parts.last_mut().unwrap().push(c);
+                }
+                _ => parts.last_mut().unwrap().push(c),
+            }
+        }
+        let [regex, format, ..] = parts.as_slice() else {
+            return None;
+        };
+        if !["(.*)", "(.+)", "^(.*)$", "^(.+)$"].contains(&regex.as_str()) {
+            return None;
+        }
+        match format.as_str() {
+            "${1:/upcase}" => Some("upper"),
+            "${1:/downcase}" => Some("lower"),
+            "${1:/pascalcase}" => Some("pascal"),
+            "${1:/camelcase}" => Some("camel"),
+            _ => None,
+        }
+    }
+}
+
+/// 把解析后的元素转换为模板文本
+#[derive(Default)]
+struct Converter {
+    /// 序号对应的模板内容，例如 `{{Widget}}`
+    values: BTreeMap<u32, String>,
+    /// front matter 中声明的变量及其默认值
+    variables: BTreeMap<String, String>,
+    /// 作为光标位置的序号
+    cursor: Option<u32>,
+}
+
+impl Converter {
+    /// 第一遍：为带默认值的序号确定变量名，并选出光标位置
+    fn collect(&mut self, nodes: &[Node]) {
+        let mut bare = Vec::new();
+        self.collect_into(nodes, &mut bare);
+        self.cursor = if bare.contains(&0) {
+            Some(0)
+        } else {
+            bare.into_iter()
+                .filter(|n| !self.values.contains_key(n))
+                .min()
+        };
+    }
+
+    fn collect_into(&mut self, nodes: &[Node], bare: &mut Vec<u32>) {
+        for node in nodes {
+            match node {
+                Node::Tabstop(number) => bare.push(*number),
+                // 默认值中使用了内置变量时（`${1:$TM_FILENAME_BASE}`）直接使用这些变量
+                Node::Placeholder(number, default) if uses_builtin(default) => {
+                    if *number != 0 && !self.values.contains_key(number) {
+                        let value = self.emit(default);
+                        self.values.insert(*number, value);
+                    }
+                }
+                Node::Placeholder(number, default) => {
+                    let text = plain_text(default);
+                    self.declare(*number, &text);
+                    self.collect_into(default, bare);
+                }
+                Node::Choice(number, choices) => {
+                    let first = choices.first().cloned().unwrap_or_default();
+                    self.declare(*number, &first);
+                }
+                Node::Variable { default, .. } => self.collect_into(default, bare),
+                Node::Text(_) => {}
+            }
+        }
+    }
+
+    fn declare(&mut self, number: u32, default: &str) {
+        if number == 0 || self.values.contains_key(&number) {
+            return;
+        }
+        let is_name = default
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && default
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let name = if is_name && !self.variables.contains_key(default) {
+            default.to_string()
+        } else {
+            format!("tabstop{}", number)
+        };
+        self.values.insert(number, format!("{{{{{}}}}}", name));
+        self.variables.insert(name, default.to_string());
+    }
+
+    /// 第二遍：输出模板文本
+    fn emit(&self, nodes: &[Node]) -> String {
+        let mut output = String::new();
+        for node in nodes {
+            match node {
+                Node::Text(text) => {
+                    output.push_str(&text.replace("{{", "\\{{").replace("{%", "\\{%"))
+                }
+                Node::Tabstop(number) | Node::Placeholder(number, _) | Node::Choice(number, _) => {
+                    match self.values.get(number) {
+                        Some(value) => output.push_str(value),
+                        None if self.cursor == Some(*number) => output.push_str("{{cursor}}"),
+                        None => {
+                            // `${0:text}`：保留默认文本
+                            if let Node::Placeholder(_, default) = node {
+                                output.push_str(&self.emit(default));
+                            }
+                        }
+                    }
+                }
+                Node::Variable {
+                    name,
+                    default,
+                    filter,
+                } => match (builtin(name), filter) {
+                    (Some(expr), Some(filter)) if !expr.contains(['.', ':']) => {
+                        output.push_str(&format!("{{{{{} | {}}}}}", expr, filter))
+                    }
+                    (Some(expr), _) => output.push_str(&builtin_placeholders(expr)),
+                    (None, _) => output.push_str(&self.emit(default)),
+                },
+            }
+        }
+        output
+    }
+}
+
+/// VS Code 变量对应的 new-cli 变量
+fn builtin(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "TM_FILENAME" => "filename.extension",
+        "TM_FILENAME_BASE" => "filename",
+        "CURRENT_YEAR" => "year",
+        "CURRENT_YEAR_SHORT" => "date:%y",
+        "CURRENT_MONTH" => "date:%m",
+        "CURRENT_MONTH_NAME" => "date:%B",
+        "CURRENT_MONTH_NAME_SHORT" => "date:%b",
+        "CURRENT_DATE" => "date:%d",
+        "CURRENT_DAY_NAME" => "date:%A",
+        "CURRENT_DAY_NAME_SHORT" => "date:%a",
+        "CURRENT_HOUR" => "date:%H",
+        "CURRENT_MINUTE" => "date:%M",
+        "CURRENT_SECOND" => "date:%S",
+        "CURRENT_SECONDS_UNIX" => "timestamp",
+        "UUID" => "uuid",
+        _ => return None,
+    })
+}
+
+/// `filename.extension` -> `{{filename}}.{{extension}}`
+fn builtin_placeholders(expr: &str) -> String {
+    if expr.contains(':') {
+        return format!("{{{{{}}}}}", expr);
+    }
+    expr.split('.')
+        .map(|name| format!("{{{{{}}}}}", name))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// 元素中是否使用了有对应内置变量的 VS Code 变量
+fn uses_builtin(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Variable { name, default, .. } => builtin(name).is_some() || uses_builtin(default),
+        Node::Placeholder(_, default) => uses_builtin(default),
+        _ => false,
+    })
+}
+
+/// 元素的纯文本形式，用作变量的默认值
+fn plain_text(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Text(text) => text.clone(),
+            Node::Placeholder(_, default) => plain_text(default),
+            Node::Choice(_, choices) => choices.first().cloned().unwrap_or_default(),
+            Node::Variable { default, .. } => plain_text(default),
+            Node::Tabstop(_) => String::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(body: &str) -> Snippet {
+        Snippet {
+            name: "test".to_string(),
+            prefix: "test".to_string(),
+            description: None,
+            body: body.to_string(),
+            scope: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let snippets = parse(
+            r#"{
+                // React 组件
+                "Function component": {
+                    "prefix": ["rfc", "component"],
+                    "body": ["export function $1() {", "\treturn null;", "}",],
+                    "description": "React function component", /* 说明 */
+                    "scope": "typescriptreact, javascriptreact",
+                },
+                "url": { "body": "https://example.com" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].prefix, "rfc");
+        assert_eq!(
+            snippets[0].body,
+            "export function $1() {\n\treturn null;\n}"
+        );
+        assert_eq!(snippets[0].scope, ["typescriptreact", "javascriptreact"]);
+        assert_eq!(snippets[1].prefix, "url");
+        assert_eq!(snippets[1].body, "https://example.com");
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_to_template() {
+        assert_eq!(
+            to_template(&snippet(
+                "class ${1:Widget} {\n\t$0\n}\nexport default $1;\n// ${2:todo note}"
+            )),
+            "+++\n[variables]\nWidget = { default = \"Widget\" }\ntabstop2 = { default = \"todo note\" }\n+++\n\
+             class {{Widget}} {\n\t{{cursor}}\n}\nexport default {{Widget}};\n// {{tabstop2}}"
+        );
+        assert_eq!(
+            to_template(&snippet("fn $1() {\n    $2\n}")),
+            "fn {{cursor}}() {\n    \n}"
+        );
+        assert_eq!(
+            to_template(&snippet("${1|let,const|} x")),
+            "+++\n[variables]\nlet = { default = \"let\" }\n+++\n{{let}} x"
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(
+            to_template(&snippet(
+                "// $TM_FILENAME (${CURRENT_YEAR}-$CURRENT_MONTH) ${TM_SELECTED_TEXT:none}\n\
+                 class ${TM_FILENAME_BASE/(.*)/${1:/pascalcase}/} {} \\$5 {{x}}"
+            )),
+            "// {{filename}}.{{extension}} ({{year}}-{{date:%m}}) none\n\
+             class {{filename | pascal}} {} $5 \\{{x}}"
+        );
+        assert_eq!(
+            to_template(&snippet("export ${1:${TM_FILENAME_BASE}}; // $1")),
+            "export {{filename}}; // {{filename}}"
+        );
+    }
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(extension("typescriptreact"), Some("tsx"));
+        assert_eq!(extension("plaintext"), None);
+        assert_eq!(language("json"), Some("json"));
+        assert_eq!(language("RS"), Some("rust"));
+    }
+
+    #[test]
+    fn test_template_name() {
+        assert_eq!(template_name("rfc").as_deref(), Some("rfc"));
+        assert_eq!(template_name("!html:5").as_deref(), Some("html-5"));
+        assert_eq!(template_name("@@"), None);
+    }
+}
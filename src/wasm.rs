@@ -0,0 +1,274 @@
+//! WASM 过滤器插件 (`{{ name | company_header }}`)
+//!
+//! `<数据目录>/plugins/` 中的每个 `.wasm` 模块可以提供任意多个过滤器，模块需要导出：
+//!
+//! - `memory`：模块的线性内存
+//! - `alloc(len: i32) -> i32`：分配 `len` 字节，返回其地址，用于传入变量值
+//! - `filter_<名称>(ptr: i32, len: i32) -> i64`：过滤器本身，参数为 UTF-8 编码的输入，
+//!   返回值的高 32 位是输出的地址、低 32 位是输出的长度
+//!
+//! 例如导出 `filter_company_header` 的模块提供过滤器 `company_header`。内置过滤器优先于同名的
+//! 插件过滤器。
+//!
+//! 模块在沙箱中运行：不提供任何导入（包括 WASI），因此无法访问文件系统、网络、环境变量或时钟；
+//! 每次调用都使用新的实例，并限制可执行的指令数和内存大小，死循环或失控的插件会报错而不会卡住。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use wasmtime::{Engine, ExternType, Instance, Linker, Module, Store, StoreLimits};
+
+use crate::config::data_dir;
+use crate::error::{Error, IoContext, Result};
+use crate::tr;
+
+/// 导出的过滤器函数名的前缀
+pub const FILTER_PREFIX: &str = "filter_";
+
+/// 每次调用允许消耗的燃料（约等于执行的 WASM 指令数）
+const FUEL: u64 = 10_000_000;
+
+/// 每个实例的最大内存字节数
+const MAX_MEMORY: usize = 16 * 1024 * 1024;
+
+/// 已加载的插件
+pub struct Plugins {
+    engine: Engine,
+    modules: Vec<(PathBuf, Module)>,
+    /// 过滤器名 -> `modules` 中的下标
+    filters: BTreeMap<String, usize>,
+}
+
+impl Plugins {
+    /// 加载 `dir` 中的所有 `.wasm` 模块；目录不存在时没有插件
+    ///
+    /// 无法编译或缺少必要导出的模块会报错，以免模板在使用时才发现过滤器不可用。
+    pub fn load(dir: &Path) -> Result<Plugins> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| plugin_error(dir, e))?;
+        let mut plugins = Plugins {
+            engine,
+            modules: Vec::new(),
+            filters: BTreeMap::new(),
+        };
+        if !dir.is_dir() {
+            return Ok(plugins);
+        }
+
+        let context = || {
+            tr!(
+                "无法读取插件目录 {:?}",
+                "failed to read plugin directory {:?}",
+                dir
+            )
+        };
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).io_context(context)? {
+            let path = entry.io_context(context)?.path();
+            if path.extension().is_some_and(|ext| ext == "wasm") && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        for path in paths {
+            plugins.add(path)?;
+        }
+        Ok(plugins)
+    }
+
+    fn add(&mut self, path: PathBuf) -> Result<()> {
+        let bytes = fs::read(&path)
+            .io_context(|| tr!("无法读取插件 {:?}", "failed to read plugin {:?}", path))?;
+        let module = Module::new(&self.engine, bytes).map_err(|e| plugin_error(&path, e))?;
+        let exports: Vec<_> = module
+            .exports()
+            .map(|export| (export.name().to_string(), export.ty()))
+            .collect();
+        for required in ["memory", "alloc"] {
+            if !exports.iter().any(|(name, _)| name == required) {
+                return Err(plugin_error(
+                    &path,
+                    tr!("缺少导出 {}", "missing export {}", required),
+                ));
+            }
+        }
+
+        let index = self.modules.len();
+        for (name, ty) in &exports {
+            let Some(filter) = name.strip_prefix(FILTER_PREFIX) else {
+                continue;
+            };
+            if !matches!(ty, ExternType::Func(_)) || filter.is_empty() {
+                continue;
+            }
+            if let Some(&other) = self.filters.get(filter) {
+                log::warn!(
+                    "{}",
+                    tr!(
+                        "插件 {} 和 {} 都提供了过滤器 '{}'，使用前者",
+                        "plugins {} and {} both provide filter '{}', using the former",
+                        self.modules[other].0.display(),
+                        path.display(),
+                        filter
+                    )
+                );
+                continue;
+            }
+            self.filters.insert(filter.to_string(), index);
+        }
+        self.modules.push((path, module));
+        Ok(())
+    }
+
+    /// 插件提供的过滤器名和所在的模块
+    pub fn filters(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.filters
+            .iter()
+            .map(|(name, &index)| (name.as_str(), self.modules[index].0.as_path()))
+    }
+
+    /// 是否有名为 `filter` 的过滤器
+    pub fn has_filter(&self, filter: &str) -> bool {
+        self.filters.contains_key(filter)
+    }
+
+    /// 对 `value` 应用插件过滤器 `filter`，没有该过滤器时返回 `None`
+    pub fn apply(&self, filter: &str, value: &str) -> Option<Result<String>> {
+        let &index = self.filters.get(filter)?;
+        let (path, module) = &self.modules[index];
+        Some(
+            self.call(module, filter, value)
+                .map_err(|e| plugin_error(path, e)),
+        )
+    }
+
+    fn call(&self, module: &Module, filter: &str, value: &str) -> wasmtime::Result<String> {
+        let limits = wasmtime::StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL)?;
+        // 不定义任何导入：需要导入的模块无法实例化
+        let instance: Instance = Linker::new(&self.engine).instantiate(&mut store, module)?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("missing memory export"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let function = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, &format!("{FILTER_PREFIX}{filter}"))?;
+
+        let len = i32::try_from(value.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, value.as_bytes())?;
+        let packed = function.call(&mut store, (ptr, len))? as u64;
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+
+        let mut output = vec![0; len];
+        memory.read(&store, ptr, &mut output)?;
+        Ok(String::from_utf8(output)?)
+    }
+}
+
+/// 插件目录 `<数据目录>/plugins`
+pub fn plugin_dir() -> Result<PathBuf> {
+    Ok(data_dir()?.join("plugins"))
+}
+
+/// 插件目录中的插件，第一次使用时加载；加载失败时给出警告并视为没有插件
+pub fn installed() -> &'static Plugins {
+    static PLUGINS: OnceLock<Plugins> = OnceLock::new();
+    PLUGINS.get_or_init(|| {
+        let dir = plugin_dir().unwrap_or_default();
+        Plugins::load(&dir).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            Plugins::load(Path::new("")).expect("an empty plugin set")
+        })
+    })
+}
+
+fn plugin_error(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::WasmPlugin {
+        path: path.to_path_buf(),
+        message: format!("{:#}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 把输入原样写回，并在前面加上 `// ` 的过滤器；`spin` 是死循环
+    const COMMENT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) i32.const 16)
+          (func (export "filter_comment") (param $ptr i32) (param $len i32) (result i64)
+            (i32.store16 (i32.const 1024) (i32.const 0x2f2f))
+            (i32.store8 (i32.const 1026) (i32.const 0x20))
+            (memory.copy (i32.const 1027) (local.get $ptr) (local.get $len))
+            (i64.or
+              (i64.shl (i64.const 1024) (i64.const 32))
+              (i64.extend_i32_u (i32.add (local.get $len) (i32.const 3)))))
+          (func (export "filter_spin") (param i32 i32) (result i64)
+            (loop $l (br $l))
+            i64.const 0))
+    "#;
+
+    fn write(dir: &Path, name: &str, wat: &str) {
+        fs::write(dir.join(name), wat::parse_str(wat).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_filters() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "comment.wasm", COMMENT);
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        let plugins = Plugins::load(dir.path()).unwrap();
+
+        assert_eq!(
+            plugins.filters().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["comment", "spin"]
+        );
+        assert_eq!(
+            plugins.apply("comment", "Acme 公司").unwrap().unwrap(),
+            "// Acme 公司"
+        );
+        assert!(plugins.apply("upper", "x").is_none());
+        assert!(matches!(
+            plugins.apply("spin", "x"),
+            Some(Err(Error::WasmPlugin { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let dir = TempDir::new().unwrap();
+        // 需要导入 WASI 函数的模块无法实例化
+        write(
+            dir.path(),
+            "wasi.wasm",
+            r#"(module
+                 (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) i32.const 0)
+                 (func (export "filter_exit") (param i32 i32) (result i64) i64.const 0))"#,
+        );
+        let plugins = Plugins::load(dir.path()).unwrap();
+        assert!(matches!(
+            plugins.apply("exit", "x"),
+            Some(Err(Error::WasmPlugin { .. }))
+        ));
+
+        write(dir.path(), "broken.wasm", "(module)");
+        assert!(matches!(
+            Plugins::load(dir.path()),
+            Err(Error::WasmPlugin { .. })
+        ));
+    }
+}
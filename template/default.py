@@ -0,0 +1,10 @@
+#!/usr/bin/env python3
+"""{{filename}}.py"""
+
+
+def main():
+    {{cursor}}pass
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,7 @@
++++
+mode = "755"
++++
+#!/usr/bin/env bash
+set -euo pipefail
+
+{{cursor}}
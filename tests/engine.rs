@@ -0,0 +1,1075 @@
+use new_cli::cursor::Position;
+use new_cli::engine::{Step, Verdict};
+use new_cli::eol::Eol;
+use new_cli::insert::Insertion;
+use new_cli::project;
+use new_cli::render::Variables;
+use new_cli::{CreateOptions, Error, Lookup, Outcome, Overwrite, TemplateEngine, VariablePrompt};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn engine_with(templates: &[(&str, &str)]) -> (TempDir, TemplateEngine) {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in templates {
+        fs::write(dir.path().join(name), content).unwrap();
+    }
+    let engine = TemplateEngine::new(dir.path());
+    (dir, engine)
+}
+
+fn options(target_dir: &Path, overwrite: Overwrite) -> CreateOptions {
+    let mut variables = Variables::new();
+    variables.insert("filename".to_string(), "index".to_string());
+    CreateOptions {
+        target_dir: target_dir.to_path_buf(),
+        variables,
+        strict_vars: false,
+        overwrite,
+        insert: None,
+        ignore_editorconfig: false,
+        eol: None,
+        executable: false,
+        cursor_marker: None,
+    }
+}
+
+#[test]
+fn ensure_template_dir_writes_default_templates() {
+    let dir = TempDir::new().unwrap();
+    let engine = TemplateEngine::new(dir.path().join("template"));
+    engine.ensure_template_dir(true).unwrap();
+
+    let names: Vec<_> = engine
+        .list_templates()
+        .unwrap()
+        .iter()
+        .map(|t| t.file_name().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "default.css",
+            "default.html",
+            "default.js",
+            "default.md",
+            "default.py",
+            "default.rs",
+            "default.sh"
+        ]
+    );
+    for template in engine.list_templates().unwrap() {
+        engine.check_template(&template.path).unwrap();
+    }
+
+    let empty = TemplateEngine::new(dir.path().join("empty"));
+    empty.ensure_template_dir(false).unwrap();
+    assert!(empty.template_dir().is_dir());
+    assert!(empty.list_templates().unwrap().is_empty());
+}
+
+#[test]
+fn fresh_install_uses_starter_templates_for_any_name() {
+    let dir = TempDir::new().unwrap();
+    let engine = TemplateEngine::new(dir.path().join("template"));
+    engine.ensure_template_dir(true).unwrap();
+
+    let template = match engine.lookup_among(&[None], "page", "html") {
+        Lookup::Default(path) => path,
+        other => panic!("expected the starter default.html, got {:?}", other),
+    };
+    let target = TempDir::new().unwrap();
+    engine
+        .create(
+            "page",
+            "html",
+            Some(&template),
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap();
+    let content = fs::read_to_string(target.path().join("page.html")).unwrap();
+    assert!(content.starts_with("<!DOCTYPE html>"));
+    for extension in ["css", "js", "md", "py", "rs", "sh"] {
+        assert!(
+            matches!(
+                engine.lookup_among(&[None], "anything", extension),
+                Lookup::Default(_)
+            ),
+            "{extension}"
+        );
+    }
+}
+
+#[test]
+fn lookup_prefers_exact_name_then_default_then_fallback() {
+    let (dir, engine) = engine_with(&[
+        ("index.html", "exact"),
+        ("other.md", "md"),
+        ("default.md", "default"),
+        ("main.rs", "rs"),
+    ]);
+    fs::create_dir_all(dir.path().join("notes")).unwrap();
+    fs::write(dir.path().join("notes/page.txt"), "fallback").unwrap();
+    let engine = engine.with_fallbacks(HashMap::from([
+        ("txt".to_string(), "notes/page.txt".to_string()),
+        ("css".to_string(), "missing.css".to_string()),
+    ]));
+
+    assert_eq!(
+        engine.lookup_among(&[None], "index", "html"),
+        Lookup::Exact(dir.path().join("index.html"))
+    );
+    assert_eq!(
+        engine.lookup_among(&[None], "notes", "md"),
+        Lookup::Default(dir.path().join("default.md"))
+    );
+    assert_eq!(
+        engine.lookup_among(&[None], "todo", "txt"),
+        Lookup::Fallback(dir.path().join("notes/page.txt"))
+    );
+    // 不再随便使用相同后缀的其他模板；不存在的后备模板被忽略
+    assert_eq!(engine.lookup_among(&[None], "lib", "rs"), Lookup::Empty);
+    assert_eq!(engine.lookup_among(&[None], "site", "css"), Lookup::Empty);
+    assert!(engine.find_template("lib", "rs").is_none());
+}
+
+#[test]
+fn explain_lists_every_candidate_and_verdict() {
+    let (dir, engine) = engine_with(&[("report.md", "global"), ("report.txt", "txt")]);
+    let extra = TempDir::new().unwrap();
+    fs::write(extra.path().join("report.md"), "extra").unwrap();
+    fs::write(extra.path().join("default.md"), "default").unwrap();
+    let engine = engine.with_extra_dir(extra.path());
+
+    let (lookup, candidates) = engine.explain_among(&[None], "report", "md");
+    assert_eq!(lookup, Lookup::Exact(dir.path().join("report.md")));
+    let verdicts: Vec<_> = candidates
+        .iter()
+        .map(|c| (c.step, c.path.clone(), c.verdict.clone()))
+        .collect();
+    assert_eq!(
+        verdicts,
+        [
+            (Step::Exact, dir.path().join("report.md"), Verdict::Used),
+            (
+                Step::Exact,
+                dir.path().join("report.txt"),
+                Verdict::WrongExtension
+            ),
+            (
+                Step::Exact,
+                extra.path().join("report.md"),
+                Verdict::Shadowed
+            ),
+            (
+                Step::Default,
+                dir.path().join("default.md"),
+                Verdict::Missing
+            ),
+            (
+                Step::Default,
+                extra.path().join("default.md"),
+                Verdict::Shadowed
+            ),
+            (Step::Fallback, Default::default(), Verdict::NotConfigured),
+        ]
+    );
+    // 解释不改变查找结果
+    assert_eq!(engine.lookup_among(&[None], "report", "md"), lookup);
+}
+
+#[cfg(unix)]
+#[test]
+fn explain_reports_symlinks_leaving_the_template_dir() {
+    let (dir, engine) = engine_with(&[]);
+    let outside = TempDir::new().unwrap();
+    fs::write(outside.path().join("secret.md"), "").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret.md"), dir.path().join("a.md")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("gone.md"), dir.path().join("b.md")).unwrap();
+
+    let (lookup, candidates) = engine.explain_among(&[None], "a", "md");
+    assert_eq!(lookup, Lookup::Empty);
+    assert!(matches!(
+        &candidates[0].verdict,
+        Verdict::OutsideDirectory(target) if target.ends_with("secret.md")
+    ));
+    let (_, candidates) = engine.explain_among(&[None], "b", "md");
+    assert_eq!(candidates[0].verdict, Verdict::Unresolvable);
+}
+
+#[test]
+fn create_renders_template_into_target_dir() {
+    let (dir, engine) = engine_with(&[("index.html", "<title>{{filename}}</title>")]);
+    let target = TempDir::new().unwrap();
+
+    let template = engine.find_template("index", "html");
+    let outcome = engine
+        .create(
+            "index",
+            "html",
+            template.as_deref(),
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap();
+
+    let path = target.path().canonicalize().unwrap().join("index.html");
+    assert_eq!(
+        outcome,
+        Outcome::Created {
+            path: path.clone(),
+            backup: None,
+            cursor: None
+        }
+    );
+    assert_eq!(fs::read_to_string(path).unwrap(), "<title>index</title>");
+    drop(dir);
+}
+
+#[test]
+fn create_without_template_writes_empty_file() {
+    let (_dir, engine) = engine_with(&[]);
+    let target = TempDir::new().unwrap();
+
+    engine
+        .create(
+            "notes",
+            "txt",
+            None,
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(target.path().join("notes.txt")).unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn create_respects_overwrite_policy() {
+    let (_dir, engine) = engine_with(&[("a.txt", "new")]);
+    let target = TempDir::new().unwrap();
+    let existing = target.path().join("a.txt");
+    fs::write(&existing, "old").unwrap();
+    let template = engine.find_template("a", "txt");
+
+    let err = engine
+        .create(
+            "a",
+            "txt",
+            template.as_deref(),
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::TargetExists(name) if name == "a.txt"));
+
+    let outcome = engine
+        .create(
+            "a",
+            "txt",
+            template.as_deref(),
+            &options(target.path(), Overwrite::Skip),
+        )
+        .unwrap();
+    assert!(matches!(outcome, Outcome::Skipped { .. }));
+    assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+
+    let outcome = engine
+        .create(
+            "a",
+            "txt",
+            template.as_deref(),
+            &options(target.path(), Overwrite::Backup),
+        )
+        .unwrap();
+    assert!(matches!(
+        outcome,
+        Outcome::Created {
+            backup: Some(_),
+            ..
+        }
+    ));
+    assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+    assert_eq!(
+        fs::read_to_string(target.path().join("a.txt.bak")).unwrap(),
+        "old"
+    );
+}
+
+#[test]
+fn create_inserts_into_existing_file() {
+    let (_dir, engine) = engine_with(&[("route.rs", "    route(\"{{filename}}\");")]);
+    let target = TempDir::new().unwrap();
+    let existing = target.path().join("index.rs");
+    fs::write(&existing, "fn routes() {\n    // routes\n}\n").unwrap();
+    let template = engine.find_template("route", "rs");
+
+    let mut insert = options(target.path(), Overwrite::Refuse);
+    insert.insert = Some(Insertion::Marker("// routes".to_string()));
+    let outcome = engine
+        .create("index", "rs", template.as_deref(), &insert)
+        .unwrap();
+    assert!(matches!(outcome, Outcome::Inserted { .. }));
+
+    insert.insert = Some(Insertion::Append);
+    engine
+        .create("index", "rs", template.as_deref(), &insert)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&existing).unwrap(),
+        "fn routes() {\n    route(\"index\");\n    // routes\n}\n    route(\"index\");\n"
+    );
+
+    // 插入模式下目标文件必须已存在
+    let err = engine
+        .create("missing", "rs", template.as_deref(), &insert)
+        .unwrap_err();
+    assert!(matches!(err, Error::InsertTargetMissing(name) if name == "missing.rs"));
+    assert!(!target.path().join("missing.rs").exists());
+}
+
+#[test]
+fn create_applies_editorconfig() {
+    let (_dir, engine) = engine_with(&[("main.py", "def main():\n    pass")]);
+    let target = TempDir::new().unwrap();
+    fs::write(
+        target.path().join(".editorconfig"),
+        "root = true\n\n[*.py]\nindent_style = tab\ninsert_final_newline = true\n",
+    )
+    .unwrap();
+    let template = engine.find_template("main", "py");
+
+    let mut options = options(target.path(), Overwrite::Force);
+    engine
+        .create("main", "py", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(target.path().join("main.py")).unwrap(),
+        "def main():\n\tpass\n"
+    );
+
+    options.ignore_editorconfig = true;
+    engine
+        .create("main", "py", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(target.path().join("main.py")).unwrap(),
+        "def main():\n    pass"
+    );
+
+    // 换行先统一，.editorconfig 补上的结尾换行也使用同一风格
+    options.ignore_editorconfig = false;
+    options.eol = Some(Eol::Crlf);
+    engine
+        .create("main", "py", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(target.path().join("main.py")).unwrap(),
+        "def main():\r\n\tpass\r\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn create_sets_permissions_from_front_matter_and_exec() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_dir, engine) = engine_with(&[
+        ("run.sh", "+++\nmode = \"750\"\n+++\n#!/bin/sh\n"),
+        ("tool.py", "print()\n"),
+    ]);
+    let target = TempDir::new().unwrap();
+    let mode = |name: &str| {
+        fs::metadata(target.path().join(name))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777
+    };
+
+    let mut options = options(target.path(), Overwrite::Refuse);
+    let template = engine.find_template("run", "sh");
+    engine
+        .create("run", "sh", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(mode("run.sh"), 0o750);
+
+    options.executable = true;
+    let template = engine.find_template("tool", "py");
+    engine
+        .create("tool", "py", template.as_deref(), &options)
+        .unwrap();
+    // 只给有读权限的用户加上执行权限，与 umask 无关的部分是属主可执行
+    assert_ne!(mode("tool.py") & 0o100, 0);
+}
+
+#[test]
+fn create_reports_and_strips_cursor_marker() {
+    let (_dir, engine) = engine_with(&[
+        ("main.rs", "fn main() {\n    {{cursor}}\n}\n"),
+        ("note.md", "# {{filename}}\n\n$0\n"),
+    ]);
+    let target = TempDir::new().unwrap();
+    let mut options = options(target.path(), Overwrite::Refuse);
+
+    let template = engine.find_template("main", "rs");
+    let outcome = engine
+        .create("main", "rs", template.as_deref(), &options)
+        .unwrap();
+    assert!(matches!(
+        outcome,
+        Outcome::Created {
+            cursor: Some(Position { line: 2, column: 5 }),
+            ..
+        }
+    ));
+    assert_eq!(
+        fs::read_to_string(target.path().join("main.rs")).unwrap(),
+        "fn main() {\n    \n}\n"
+    );
+
+    options.cursor_marker = Some("$0".to_string());
+    let template = engine.find_template("note", "md");
+    let outcome = engine
+        .create("note", "md", template.as_deref(), &options)
+        .unwrap();
+    assert!(matches!(
+        outcome,
+        Outcome::Created {
+            cursor: Some(Position { line: 3, column: 1 }),
+            ..
+        }
+    ));
+    assert_eq!(
+        fs::read_to_string(target.path().join("note.md")).unwrap(),
+        "# index\n\n\n"
+    );
+}
+
+#[test]
+fn cursor_is_located_after_editorconfig_and_eol() {
+    let (_dir, engine) = engine_with(&[("main.py", "def main():\n        {{cursor}}pass\n")]);
+    let target = TempDir::new().unwrap();
+    fs::write(
+        target.path().join(".editorconfig"),
+        "root = true\n\n[*.py]\nindent_style = tab\nindent_size = 4\ncharset = utf-8-bom\n",
+    )
+    .unwrap();
+    let mut options = options(target.path(), Overwrite::Refuse);
+    options.eol = Some(Eol::Crlf);
+    let template = engine.find_template("main", "py");
+    let preview = engine
+        .output_content("main", "py", template.as_deref(), &options)
+        .unwrap();
+    let outcome = engine
+        .create("main", "py", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(
+        fs::read(target.path().join("main.py")).unwrap(),
+        b"\xEF\xBB\xBFdef main():\r\n\tpass\r\n"
+    );
+    // 预览的内容与写入的文件一致
+    assert_eq!(preview, fs::read(target.path().join("main.py")).unwrap());
+    // 在转换后的制表符之后，而不是原来的八个空格之后
+    assert!(matches!(
+        outcome,
+        Outcome::Created {
+            cursor: Some(Position { line: 2, column: 2 }),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn check_template_reports_syntax_errors_only() {
+    let (dir, engine) = engine_with(&[
+        (
+            "ok.md",
+            "+++\n[variables.title]\nrequired = true\n+++\n# {{title | upper}}\n",
+        ),
+        ("filter.md", "{{name | shout}}"),
+        ("block.md", "{% if x %}open"),
+        ("front.md", "+++\nmode = 1\n+++\nbody"),
+    ]);
+
+    assert!(engine.check_template(&dir.path().join("ok.md")).is_ok());
+    assert!(matches!(
+        engine.check_template(&dir.path().join("filter.md")),
+        Err(Error::Render { source, .. }) if matches!(*source, Error::UnknownFilter(_))
+    ));
+    assert!(matches!(
+        engine.check_template(&dir.path().join("block.md")),
+        Err(Error::Render { source, .. }) if matches!(*source, Error::TemplateSyntax(_))
+    ));
+    assert!(matches!(
+        engine.check_template(&dir.path().join("front.md")),
+        Err(Error::Render { source, .. }) if matches!(*source, Error::FrontMatter(_))
+    ));
+}
+
+#[test]
+fn create_rejects_invalid_names() {
+    let (_dir, engine) = engine_with(&[]);
+    let target = TempDir::new().unwrap();
+
+    let err = engine
+        .create(
+            "../escape",
+            "txt",
+            None,
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidFilename { .. }));
+}
+
+#[test]
+fn create_strict_vars_reports_undefined_variables() {
+    let (_dir, engine) = engine_with(&[("report.md", "# {{title}}")]);
+    let target = TempDir::new().unwrap();
+    let template = engine.find_template("report", "md");
+
+    let mut options = options(target.path(), Overwrite::Refuse);
+    options.strict_vars = true;
+    let err = engine
+        .create("report", "md", template.as_deref(), &options)
+        .unwrap_err();
+    match err {
+        Error::Render { source, .. } => {
+            assert!(matches!(*source, Error::UndefinedVariables(ref vars) if vars == &["title"]))
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn project_layer_takes_precedence_over_global() {
+    let (_global, engine) = engine_with(&[("index.html", "global"), ("main.rs", "global")]);
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join("index.html"), "project").unwrap();
+    fs::write(project.path().join("other.md"), "project").unwrap();
+    fs::write(project.path().join("default.rs"), "project").unwrap();
+    let engine = engine.with_project_dir(project.path());
+
+    let index = engine.find_template("index", "html").unwrap();
+    assert!(index.starts_with(project.path()));
+
+    // 全局层的精确匹配优先于项目层的默认模板
+    let main = engine.find_template("main", "rs").unwrap();
+    assert_eq!(fs::read_to_string(main).unwrap(), "global");
+    let util = engine.find_template("util", "rs").unwrap();
+    assert!(util.starts_with(project.path()));
+
+    let names: Vec<String> = engine
+        .list_templates()
+        .unwrap()
+        .iter()
+        .map(|t| t.file_name())
+        .collect();
+    assert_eq!(names, ["default.rs", "index.html", "main.rs", "other.md"]);
+    let listed = engine.list_templates().unwrap();
+    assert_eq!(
+        engine.layer_of(&listed[0].path).unwrap().source,
+        new_cli::layers::LayerSource::Project
+    );
+}
+
+#[test]
+fn extra_and_system_layers_rank_below_global() {
+    use new_cli::layers::LayerSource;
+
+    let (global, engine) = engine_with(&[("index.html", "global")]);
+    let team = TempDir::new().unwrap();
+    let system = TempDir::new().unwrap();
+    fs::write(team.path().join("index.html"), "team").unwrap();
+    fs::write(team.path().join("notes.md"), "team").unwrap();
+    fs::write(system.path().join("notes.md"), "system").unwrap();
+    fs::write(system.path().join("main.rs"), "system").unwrap();
+    let project = TempDir::new().unwrap();
+    let engine = engine
+        .with_system_dir(system.path())
+        .with_extra_dir(team.path())
+        .with_extra_dir(global.path())
+        .with_project_dir(project.path());
+
+    let sources: Vec<LayerSource> = engine.layers().iter().map(|l| l.source).collect();
+    assert_eq!(
+        sources,
+        [
+            LayerSource::Project,
+            LayerSource::Global,
+            LayerSource::Extra,
+            LayerSource::System
+        ]
+    );
+    assert_eq!(engine.template_dir(), global.path());
+
+    let notes = engine.resolve_template("notes.md").unwrap();
+    assert_eq!(fs::read_to_string(notes).unwrap(), "team");
+    let main = engine.resolve_template("main.rs").unwrap();
+    assert_eq!(fs::read_to_string(main).unwrap(), "system");
+
+    let shadowing: Vec<LayerSource> = engine
+        .layers_with("index.html")
+        .iter()
+        .map(|l| l.source)
+        .collect();
+    assert_eq!(shadowing, [LayerSource::Global, LayerSource::Extra]);
+}
+
+#[test]
+fn create_copies_binary_template_byte_for_byte() {
+    let icon: &[u8] = &[0, 0, 1, 0, 1, 0, 0x10, 0x10, b'{', b'{', 0xff, 0xfe];
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("favicon.ico"), icon).unwrap();
+    let engine = TemplateEngine::new(dir.path());
+    let target = TempDir::new().unwrap();
+
+    let template = engine.find_template("favicon", "ico");
+    let mut options = options(target.path(), Overwrite::Refuse);
+    options.strict_vars = true;
+    engine
+        .create("favicon", "ico", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(fs::read(target.path().join("favicon.ico")).unwrap(), icon);
+}
+
+#[test]
+fn create_applies_front_matter_and_strips_it() {
+    let (_dir, engine) = engine_with(&[(
+        "page.html",
+        "+++\ndescription = \"页面\"\n[variables]\ntitle = { default = \"Home\" }\nowner = { required = true }\n+++\n<h1>{{title}}</h1>{{owner}}\n",
+    )]);
+    let target = TempDir::new().unwrap();
+    let template = engine.find_template("page", "html");
+
+    let err = engine
+        .create(
+            "page",
+            "html",
+            template.as_deref(),
+            &options(target.path(), Overwrite::Refuse),
+        )
+        .unwrap_err();
+    match err {
+        Error::Render { source, .. } => {
+            assert!(matches!(*source, Error::MissingVariables(ref vars) if vars == &["owner"]))
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+
+    let mut options = options(target.path(), Overwrite::Refuse);
+    options
+        .variables
+        .insert("owner".to_string(), "me".to_string());
+    engine
+        .create("page", "html", template.as_deref(), &options)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(target.path().join("page.html")).unwrap(),
+        "<h1>Home</h1>me\n"
+    );
+}
+
+#[test]
+fn variable_prompts_cover_declared_and_unresolved_variables() {
+    let (_dir, engine) = engine_with(&[(
+        "page.html",
+        "+++\n[variables]\ntitle = { description = \"标题\", default = \"Home\" }\nowner = { required = true }\n+++\n{{title}} {{owner}} {{filename}} {{extra}} {{date:%Y}}\n",
+    )]);
+    let template = engine.find_template("page", "html").unwrap();
+
+    let mut variables = Variables::new();
+    variables.insert("filename".to_string(), "page".to_string());
+    variables.insert("owner".to_string(), "me".to_string());
+    let prompts = engine.variable_prompts(&template, &variables).unwrap();
+
+    assert_eq!(
+        prompts,
+        [
+            VariablePrompt {
+                name: "title".to_string(),
+                description: Some("标题".to_string()),
+                default: Some("Home".to_string()),
+                required: false,
+            },
+            VariablePrompt {
+                name: "extra".to_string(),
+                description: None,
+                default: None,
+                required: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn resolve_template_searches_all_layers() {
+    let (_global, engine) = engine_with(&[("index.html", "global"), ("main.rs", "global")]);
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join("index.html"), "project").unwrap();
+    let engine = engine.with_project_dir(project.path());
+
+    let index = engine.resolve_template("index.html").unwrap();
+    assert_eq!(fs::read_to_string(index).unwrap(), "project");
+    let main = engine.resolve_template("main.rs").unwrap();
+    assert_eq!(fs::read_to_string(main).unwrap(), "global");
+
+    assert!(matches!(
+        engine.resolve_template("missing.md"),
+        Err(Error::TemplateNotFound(_))
+    ));
+    assert!(matches!(
+        engine.resolve_template("../index.html"),
+        Err(Error::InvalidTemplateName { .. })
+    ));
+}
+
+#[test]
+fn nested_templates_are_found_within_the_depth_limit() {
+    let (dir, engine) = engine_with(&[("default.tsx", "default")]);
+    for (name, content) in [
+        ("web/react/app.tsx", "react"),
+        ("web/vue/app.tsx", "vue"),
+        ("a/b/c/d/deep.tsx", "deep"),
+        (".git/hooks/hook.sh", "hook"),
+        ("web/.drafts/draft.tsx", "draft"),
+        ("docs/report.md", "md"),
+        ("docs/report.txt", "txt"),
+    ] {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    // 深度相同时按名称排序，其余候选被遮蔽
+    let (lookup, candidates) = engine.explain_among(&[None], "app", "tsx");
+    assert_eq!(lookup, Lookup::Nested(dir.path().join("web/react/app.tsx")));
+    let nested: Vec<_> = candidates
+        .iter()
+        .filter(|c| c.step == Step::Nested)
+        .map(|c| (c.path.clone(), c.verdict.clone()))
+        .collect();
+    assert_eq!(
+        nested,
+        [
+            (dir.path().join("web/react/app.tsx"), Verdict::Used),
+            (dir.path().join("web/vue/app.tsx"), Verdict::Shadowed),
+        ]
+    );
+    assert_eq!(
+        engine.find_template_in(Some("web/vue"), "app", "tsx"),
+        Some(dir.path().join("web/vue/app.tsx"))
+    );
+
+    // 超过深度限制或位于隐藏目录中的模板不会被找到
+    assert_eq!(
+        engine.lookup_among(&[None], "deep", "tsx"),
+        Lookup::Default(dir.path().join("default.tsx"))
+    );
+    assert!(engine.find_template("hook", "sh").is_none());
+    assert!(engine
+        .find_template_in(Some("web"), "draft", "tsx")
+        .is_none());
+    let deep = TemplateEngine::new(dir.path()).with_depth(4);
+    assert_eq!(
+        deep.find_template("deep", "tsx"),
+        Some(dir.path().join("a/b/c/d/deep.tsx"))
+    );
+    let flat = TemplateEngine::new(dir.path()).with_depth(0);
+    assert_eq!(
+        flat.lookup_among(&[None], "app", "tsx"),
+        Lookup::Default(dir.path().join("default.tsx"))
+    );
+
+    // 推断后缀时同样会查找子目录
+    let extensions: Vec<String> = engine
+        .templates_named(&[None], "report")
+        .unwrap()
+        .into_iter()
+        .map(|t| t.extension)
+        .collect();
+    assert_eq!(extensions, ["md", "txt"]);
+    assert!(flat.templates_named(&[None], "report").unwrap().is_empty());
+}
+
+#[test]
+fn namespaces_are_listed_and_searched_separately() {
+    let (dir, engine) = engine_with(&[("index.html", "root")]);
+    fs::create_dir_all(dir.path().join("web/react")).unwrap();
+    fs::write(dir.path().join("web/index.html"), "web").unwrap();
+    fs::write(dir.path().join("web/react/app.tsx"), "app").unwrap();
+
+    let names: Vec<String> = engine
+        .list_templates()
+        .unwrap()
+        .iter()
+        .map(|t| t.qualified_name())
+        .collect();
+    assert_eq!(names, ["index.html", "web/index.html", "web/react/app.tsx"]);
+
+    // 根部的同名模板优先于子目录中的，命名空间中也不会回退到根部
+    assert_eq!(
+        engine.find_template("index", "html").unwrap(),
+        dir.path().join("index.html")
+    );
+    assert_eq!(
+        engine
+            .find_template_in(Some("web"), "index", "html")
+            .unwrap(),
+        dir.path().join("web/index.html")
+    );
+    assert!(engine
+        .find_template_in(Some("web/react"), "page", "tsx")
+        .is_none());
+    assert!(engine.find_template_in(Some("web"), "main", "rs").is_none());
+    assert!(engine
+        .find_template_in(Some(".."), "index", "html")
+        .is_none());
+
+    assert_eq!(
+        engine.resolve_template("web/index.html").unwrap(),
+        dir.path().join("web/index.html")
+    );
+    assert!(matches!(
+        engine.resolve_template("web/../index.html"),
+        Err(Error::InvalidTemplateName { .. })
+    ));
+}
+
+#[test]
+fn templates_named_finds_candidate_extensions() {
+    let (dir, engine) = engine_with(&[("report.md", ""), ("report.html", ""), ("memo.txt", "")]);
+    fs::create_dir_all(dir.path().join("web")).unwrap();
+    fs::write(dir.path().join("web/memo.vue"), "").unwrap();
+
+    let extensions = |namespaces: &[Option<&str>], name: &str| -> Vec<String> {
+        engine
+            .templates_named(namespaces, name)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.extension)
+            .collect()
+    };
+    assert_eq!(extensions(&[None], "report"), ["html", "md"]);
+    assert_eq!(extensions(&[None], "memo"), ["txt"]);
+    // 第一个有同名模板的命名空间为准
+    assert_eq!(extensions(&[Some("web"), None], "memo"), ["vue"]);
+    assert_eq!(extensions(&[Some("web"), None], "report"), ["html", "md"]);
+    assert!(extensions(&[None], "missing").is_empty());
+}
+
+#[test]
+fn find_template_among_prefers_exact_names_across_namespaces() {
+    let (dir, engine) = engine_with(&[("main.rs", "root main"), ("default.rs", "root default")]);
+    fs::create_dir_all(dir.path().join("rust")).unwrap();
+    fs::write(dir.path().join("rust/util.rs"), "util").unwrap();
+    fs::write(dir.path().join("rust/default.rs"), "rust default").unwrap();
+
+    let find = |name| engine.find_template_among(&[Some("rust"), None], name, "rs");
+    assert_eq!(find("util").unwrap(), dir.path().join("rust/util.rs"));
+    // 同名模板优先于前一个命名空间中的默认模板
+    assert_eq!(find("main").unwrap(), dir.path().join("main.rs"));
+    assert_eq!(find("other").unwrap(), dir.path().join("rust/default.rs"));
+    assert!(engine
+        .find_template_among(&[Some(".."), None], "x", "toml")
+        .is_none());
+}
+
+#[test]
+fn templates_compose_partials_and_base_layouts() {
+    let (dir, engine) = engine_with(&[
+        (
+            "page.html",
+            "+++\n[variables]\ntitle = { required = true }\n+++\n{% extends \"layouts/base.html\" %}\n{% block body %}<h1>{{title}}</h1>\n{% endblock %}\n",
+        ),
+        ("loop.txt", "{% include \"loop.txt\" %}"),
+    ]);
+    fs::create_dir_all(dir.path().join("layouts")).unwrap();
+    fs::create_dir_all(dir.path().join("partials")).unwrap();
+    fs::write(
+        dir.path().join("layouts/base.html"),
+        "+++\n[variables]\nlang = { default = \"en\" }\n+++\n<html lang=\"{{lang}}\">\n{% include \"partials/meta.html\" %}\n{% block body %}{% endblock %}\n</html>\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("partials/meta.html"),
+        "<meta charset=\"utf-8\">\n",
+    )
+    .unwrap();
+
+    // 基础模板中声明的变量同样需要询问，并使用其默认值
+    let page = dir.path().join("page.html");
+    let names: Vec<String> = engine
+        .variable_prompts(&page, &Variables::new())
+        .unwrap()
+        .into_iter()
+        .map(|prompt| prompt.name)
+        .collect();
+    assert_eq!(names, ["lang", "title"]);
+
+    let variables: Variables = [("title".to_string(), "Hi".to_string())].into();
+    let rendered = engine
+        .render_template(Some(&page), &variables, true)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(rendered).unwrap(),
+        "<html lang=\"en\">\n<meta charset=\"utf-8\">\n<h1>Hi</h1>\n</html>\n"
+    );
+
+    let err = engine
+        .render_template(Some(&dir.path().join("loop.txt")), &Variables::new(), false)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Render { source, .. } if matches!(*source, Error::IncludeCycle(_)))
+    );
+}
+
+#[test]
+fn manifests_are_found_by_directory_and_hidden_from_template_list() {
+    let (dir, engine) = engine_with(&[("index.html", "")]);
+    fs::create_dir_all(dir.path().join("component")).unwrap();
+    fs::write(dir.path().join("component/component.tsx"), "").unwrap();
+    fs::write(
+        dir.path().join("component/template.toml"),
+        "[[files]]\ntemplate = \"component.tsx\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        engine.find_manifest("component").unwrap(),
+        dir.path().join("component")
+    );
+    assert!(engine.find_manifest("index").is_none());
+    assert!(engine.find_manifest("../component").is_none());
+    let names: Vec<String> = engine
+        .list_templates()
+        .unwrap()
+        .iter()
+        .map(|t| t.qualified_name())
+        .collect();
+    assert_eq!(names, ["component/component.tsx", "index.html"]);
+}
+
+#[test]
+fn project_templates_skip_files_whose_condition_fails() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("templates/lib");
+    fs::create_dir_all(source.join("tests")).unwrap();
+    fs::create_dir_all(source.join("src")).unwrap();
+    fs::write(source.join("src/lib.rs"), "").unwrap();
+    fs::write(source.join("src/bench.rs"), "").unwrap();
+    fs::write(source.join("tests/smoke.rs"), "").unwrap();
+    fs::write(
+        source.join("template.toml"),
+        "[when]\n\"tests\" = \"use_tests\"\n\"src/bench.rs\" = \"not no_bench\"\n",
+    )
+    .unwrap();
+
+    let create = |target: &str, pairs: &[(&str, &str)]| {
+        let vars: Variables = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let target = dir.path().join(target);
+        let count =
+            project::create_project(&dir.path().join("templates"), &target, "lib", &vars, false)
+                .unwrap();
+        (target, count)
+    };
+
+    let (target, count) = create("plain", &[("no_bench", "true")]);
+    assert_eq!(count, 1);
+    assert!(target.join("src/lib.rs").is_file());
+    assert!(!target.join("src/bench.rs").exists());
+    assert!(!target.join("tests").exists());
+    assert!(!target.join("template.toml").exists());
+
+    let (target, count) = create("full", &[("use_tests", "true")]);
+    assert_eq!(count, 3);
+    assert!(target.join("tests/smoke.rs").is_file());
+}
+
+#[test]
+fn lockfile_rejects_drifted_templates() {
+    let (dir, engine) = engine_with(&[("page.html", "<p>{{filename}}</p>")]);
+    let mut lockfile = new_cli::lock::Lockfile::default();
+    lockfile.pin(dir.path(), "page.html").unwrap();
+    let engine = engine.with_lockfile(lockfile);
+    let template = dir.path().join("page.html");
+    let target = TempDir::new().unwrap();
+    let options = options(target.path(), Overwrite::Refuse);
+
+    assert_eq!(
+        engine
+            .render_template(Some(&template), &options.variables, false)
+            .unwrap(),
+        b"<p>index</p>"
+    );
+    fs::write(&template, "<p>changed</p>").unwrap();
+    assert!(matches!(
+        engine.create("index", "html", Some(&template), &options),
+        Err(Error::TemplateDrifted { .. })
+    ));
+}
+
+#[test]
+fn ensure_template_dir_is_safe_to_run_concurrently() {
+    let dir = TempDir::new().unwrap();
+    let template_dir = dir.path().join("template");
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                TemplateEngine::new(&template_dir)
+                    .ensure_template_dir(true)
+                    .unwrap()
+            });
+        }
+    });
+
+    let expected =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("template/default.html"))
+            .unwrap();
+    assert_eq!(
+        fs::read_to_string(template_dir.join("default.html")).unwrap(),
+        expected
+    );
+    // 临时目录在重命名后不会残留
+    let leftovers: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().contains(".tmp-"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+#[test]
+fn create_leaves_no_temporary_files() {
+    let (dir, engine) = engine_with(&[("index.html", "<p>{{filename}}</p>")]);
+    let template = dir.path().join("index.html");
+    let target = TempDir::new().unwrap();
+    fs::write(target.path().join("index.html"), "old").unwrap();
+
+    engine
+        .create(
+            "index",
+            "html",
+            Some(&template),
+            &options(target.path(), Overwrite::Backup),
+        )
+        .unwrap();
+    let mut names: Vec<String> = fs::read_dir(target.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["index.html", "index.html.bak"]);
+    assert_eq!(
+        fs::read_to_string(target.path().join("index.html")).unwrap(),
+        "<p>index</p>"
+    );
+    assert_eq!(
+        fs::read_to_string(target.path().join("index.html.bak")).unwrap(),
+        "old"
+    );
+}